@@ -1,14 +1,21 @@
-//! JWT authentication middleware and utilities
+//! Request authentication: customer JWTs and merchant API keys
 
+use ::entity::prelude::MerchantApiKey;
 use axum::{
     async_trait,
     extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
-    RequestPartsExt,
+    http::request::Parts,
 };
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use chrono::Utc;
+use commercerack_merchant::{ApiKeyService, ApiScope};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error::ApiError;
+use crate::AppState;
+
+/// Header carrying a merchant API key
+pub const API_KEY_HEADER: &str = "x-api-key";
 
 /// JWT claims structure
 #[derive(Debug, Serialize, Deserialize, Clone, utoipa::ToSchema)]
@@ -17,76 +24,82 @@ pub struct Claims {
     pub mid: i32,         // Merchant ID
     pub exp: i64,         // Expiration time
     pub iat: i64,         // Issued at
+    #[serde(default)]
+    pub iss: String,      // Issuer
+    #[serde(default)]
+    pub aud: String,      // Audience
 }
 
 impl Claims {
-    /// Create new claims with 24h expiration
-    pub fn new(customer_id: i32, mid: i32) -> Self {
-        let now = Utc::now();
+    /// Create new claims expiring after `ttl`
+    pub fn new(customer_id: i32, mid: i32, ttl: Duration) -> Self {
+        let now = Utc::now().timestamp();
         Self {
             sub: customer_id.to_string(),
             mid,
-            iat: now.timestamp(),
-            exp: (now + Duration::hours(24)).timestamp(),
+            iat: now,
+            exp: now.saturating_add(ttl.as_secs() as i64),
+            iss: String::new(),
+            aud: String::new(),
         }
     }
 
-    /// Encode claims into JWT token
-    pub fn encode(&self, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
-        encode(
-            &Header::default(),
-            self,
-            &EncodingKey::from_secret(secret.as_bytes()),
-        )
-    }
-
-    /// Decode JWT token into claims
-    pub fn decode(token: &str, secret: &str) -> Result<Self, jsonwebtoken::errors::Error> {
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(secret.as_bytes()),
-            &Validation::default(),
-        )?;
-        Ok(token_data.claims)
+    /// Customer ID from `sub`
+    pub fn customer_id(&self) -> Option<i32> {
+        self.sub.parse().ok()
     }
 }
 
 /// Axum extractor for JWT authentication
 #[async_trait]
-impl<S> FromRequestParts<S> for Claims
-where
-    S: Send + Sync,
-{
-    type Rejection = (StatusCode, String);
+impl FromRequestParts<AppState> for Claims {
+    type Rejection = ApiError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
         // Extract Authorization header
         let auth_header = parts
             .headers
             .get("Authorization")
             .and_then(|h| h.to_str().ok())
-            .ok_or((
-                StatusCode::UNAUTHORIZED,
-                "Missing Authorization header".to_string(),
-            ))?;
+            .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".to_string()))?;
 
         // Parse Bearer token
         let token = auth_header
             .strip_prefix("Bearer ")
-            .ok_or((
-                StatusCode::UNAUTHORIZED,
-                "Invalid Authorization header format".to_string(),
-            ))?;
+            .ok_or_else(|| {
+                ApiError::Unauthorized("Invalid Authorization header format".to_string())
+            })?;
 
         // Decode and validate JWT
-        // TODO: Get secret from config
-        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "dev-secret-key".to_string());
+        state
+            .jwt
+            .decode(token)
+            .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))
+    }
+}
+
+/// Merchant API key from the `X-Api-Key` header
+#[derive(Debug, Clone)]
+pub struct ApiKey(pub MerchantApiKey);
+
+impl ApiKey {
+    pub fn scopes(&self) -> Vec<ApiScope> {
+        ApiScope::of(&self.0)
+    }
+}
+
+/// Axum extractor for merchant API keys
+#[async_trait]
+impl FromRequestParts<AppState> for ApiKey {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let key = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .ok_or_else(|| ApiError::Unauthorized("Missing X-Api-Key header".to_string()))?;
 
-        Claims::decode(token, &secret).map_err(|e| {
-            (
-                StatusCode::UNAUTHORIZED,
-                format!("Invalid token: {}", e),
-            )
-        })
+        Ok(Self(ApiKeyService::authenticate(&state.db, key).await?))
     }
 }
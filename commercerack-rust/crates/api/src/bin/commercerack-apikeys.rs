@@ -0,0 +1,103 @@
+//! Manage merchant API keys
+//!
+//! ```text
+//! commercerack-apikeys create <mid> <name> <scope,...> [ttl-days]
+//! commercerack-apikeys list <mid>
+//! commercerack-apikeys rotate <mid> <id> [grace-hours]
+//! commercerack-apikeys revoke <mid> <id>
+//! ```
+//!
+//! Uses the same configuration as the server (`DATABASE_URL`, `APP__*`).
+
+use anyhow::{bail, Context};
+use commercerack_api::config::Settings;
+use commercerack_merchant::{ApiKeyService, ApiScope, IssuedApiKey, MerchantService};
+use sea_orm::Database;
+use std::time::Duration;
+
+const USAGE: &str = "usage:
+  commercerack-apikeys create <mid> <name> <scope,...> [ttl-days]
+  commercerack-apikeys list <mid>
+  commercerack-apikeys rotate <mid> <id> [grace-hours]
+  commercerack-apikeys revoke <mid> <id>
+
+scopes: catalog:read, catalog:write, orders:read, orders:write,
+        inventory:write, customers:notes, customers:admin, merchant:settings";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let Some((&command, args)) = args.split_first() else {
+        bail!(USAGE);
+    };
+
+    let settings = Settings::load()?;
+    let db = Database::connect(settings.database.connect_options()).await?;
+
+    match (command, args) {
+        ("create", [mid, name, scopes, rest @ ..]) if rest.len() <= 1 => {
+            let mid = number(mid, "mid")?;
+            if MerchantService::find_by_id(&db, mid).await?.is_none() {
+                bail!("merchant {} does not exist", mid);
+            }
+            let scopes = ApiScope::parse_list(scopes)?;
+            let ttl = rest
+                .first()
+                .map(|days| number(days, "ttl-days").map(|d| Duration::from_secs(d as u64 * 86_400)))
+                .transpose()?;
+
+            print_issued(&ApiKeyService::create(&db, mid, name, &scopes, ttl).await?);
+        }
+        ("list", [mid]) => {
+            for key in ApiKeyService::list(&db, number(mid, "mid")?).await? {
+                println!(
+                    "{}\t{}…\t{}\t{}\texpires={}\tlast_used={}\trevoked={}\treplaced_by={}",
+                    key.id,
+                    key.key_prefix,
+                    key.name,
+                    key.scopes,
+                    key.expires_gmt,
+                    key.last_used_gmt,
+                    key.revoked_gmt,
+                    key.replaced_by
+                );
+            }
+        }
+        ("rotate", [mid, id, rest @ ..]) if rest.len() <= 1 => {
+            let grace = rest
+                .first()
+                .map(|hours| number(hours, "grace-hours"))
+                .transpose()?
+                .unwrap_or(24);
+            let grace = Duration::from_secs(grace as u64 * 3_600);
+
+            let issued = ApiKeyService::rotate(&db, number(mid, "mid")?, number(id, "id")?, grace).await?;
+            print_issued(&issued);
+        }
+        ("revoke", [mid, id]) => {
+            ApiKeyService::revoke(&db, number(mid, "mid")?, number(id, "id")?).await?;
+            println!("revoked");
+        }
+        _ => bail!(USAGE),
+    }
+
+    Ok(())
+}
+
+fn number(value: &str, name: &str) -> anyhow::Result<i32> {
+    let n: i32 = value.parse().with_context(|| format!("{} must be a number", name))?;
+    if n < 0 {
+        bail!("{} must not be negative", name);
+    }
+    Ok(n)
+}
+
+fn print_issued(issued: &IssuedApiKey) {
+    println!("id:     {}", issued.record.id);
+    println!("scopes: {}", issued.record.scopes);
+    println!("key:    {}", issued.key);
+    eprintln!("Store the key now; it cannot be shown again.");
+}
@@ -0,0 +1,401 @@
+//! Layered server configuration
+//!
+//! Settings are resolved in order, later sources overriding earlier ones:
+//! 1. Built-in defaults
+//! 2. `config/default.toml` (optional)
+//! 3. `config/{APP_ENV}.toml` (optional, `APP_ENV` defaults to `development`)
+//! 4. Environment variables prefixed with `APP__`, e.g. `APP__SERVER__PORT=9000`
+//!
+//! `DATABASE_URL` is honoured as a shortcut for `APP__DATABASE__URL` so the
+//! same `.env` works for the server, the migration CLI and sqlx tooling, and
+//! `JWT_SECRET` as a shortcut for `APP__AUTH__JWT__SECRET`.
+
+use commercerack_cart::{
+    CartError, CartRepository, InMemoryCartRepository, RedisCartRepository, SeaOrmCartRepository,
+};
+use commercerack_customer::{FileNotifier, LogNotifier, LoginPolicy, Notifier};
+use commercerack_inventory::DEFAULT_HOLD_TTL;
+use config::{Config, ConfigError, Environment, File, FileFormat};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Top-level server settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    /// `APP_ENV`, e.g. `development` or `production`
+    pub environment: String,
+    pub server: ServerSettings,
+    pub database: DatabaseSettings,
+    pub cart: CartSettings,
+    pub auth: AuthSettings,
+    pub notifier: NotifierSettings,
+}
+
+/// HTTP listener settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerSettings {
+    pub host: String,
+    pub port: u16,
+    /// Seconds to wait for in-flight requests after a shutdown signal
+    pub shutdown_grace_secs: u64,
+}
+
+/// Database connection settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatabaseSettings {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub connect_timeout_secs: u64,
+    /// Apply pending `migration::Migrator` migrations at startup
+    pub run_migrations: bool,
+}
+
+/// Where carts are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CartBackend {
+    /// Process memory; carts are lost on restart and not shared by replicas
+    Memory,
+    /// The `carts` table in the main database
+    Postgres,
+    /// Redis at `cart.redis_url`
+    Redis,
+}
+
+/// Cart storage settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct CartSettings {
+    pub backend: CartBackend,
+    /// Seconds without changes before a cart counts as abandoned
+    pub ttl_secs: u64,
+    /// Seconds between sweeps that delete abandoned carts and release
+    /// lapsed stock holds
+    pub purge_interval_secs: u64,
+    /// Seconds a cart holds stock after its last change
+    pub hold_ttl_secs: u64,
+    pub redis_url: String,
+}
+
+/// How customer notifications are delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifierBackend {
+    /// The application log; tokens end up in it, so development only
+    Log,
+    /// JSON lines appended to `notifier.path` for an external mailer
+    File,
+}
+
+/// Customer notification settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotifierSettings {
+    pub backend: NotifierBackend,
+    pub path: String,
+}
+
+/// Customer login token settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthSettings {
+    /// Seconds an access JWT is valid
+    pub access_ttl_secs: u64,
+    /// Seconds a refresh token is valid; each refresh issues a fresh one
+    pub refresh_ttl_secs: u64,
+    /// Wrong passwords in a row before an account is locked out
+    pub max_failed_logins: i16,
+    /// Seconds a lockout after failed logins lasts
+    pub lockout_secs: u64,
+    /// Seconds a password reset token is valid
+    pub reset_ttl_secs: u64,
+    pub jwt: JwtSettings,
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        Self {
+            access_ttl_secs: 15 * 60,
+            refresh_ttl_secs: 30 * 24 * 60 * 60,
+            max_failed_logins: 5,
+            lockout_secs: 15 * 60,
+            reset_ttl_secs: 60 * 60,
+            jwt: JwtSettings::default(),
+        }
+    }
+}
+
+/// Access token signing keys
+///
+/// ```toml
+/// [auth.jwt]
+/// signing_kid = "2025-11"
+///
+/// [[auth.jwt.keys]]
+/// kid = "2025-11"
+/// private_key_file = "/run/secrets/jwt-2025-11.pem"
+/// public_key_file = "/run/secrets/jwt-2025-11.pub.pem"
+///
+/// # retired: still verifies tokens issued before the rotation
+/// [[auth.jwt.keys]]
+/// kid = "2025-10"
+/// public_key_file = "/run/secrets/jwt-2025-10.pub.pem"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtSettings {
+    /// `iss` claim written and required
+    pub issuer: String,
+    /// `aud` claim written and required
+    pub audience: String,
+    /// Key that signs new tokens; defaults to the first key
+    #[serde(default)]
+    pub signing_kid: String,
+    /// HS256 secret; shortcut for a single key with kid `default`
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub keys: Vec<JwtKeySettings>,
+}
+
+impl Default for JwtSettings {
+    fn default() -> Self {
+        Self {
+            issuer: "commercerack".to_string(),
+            audience: "commercerack-api".to_string(),
+            signing_kid: String::new(),
+            secret: None,
+            keys: Vec::new(),
+        }
+    }
+}
+
+/// One signing or verification key; the algorithm follows from the key:
+/// a `secret` is HS256, an RSA key pair RS256 and an Ed25519 key pair EdDSA
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JwtKeySettings {
+    pub kid: String,
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// PKCS#8 PEM; only needed by the signing key
+    #[serde(default)]
+    pub private_key_file: Option<String>,
+    /// SPKI PEM (`BEGIN PUBLIC KEY`)
+    #[serde(default)]
+    pub public_key_file: Option<String>,
+}
+
+impl Settings {
+    /// Load settings from defaults, config files and the environment
+    pub fn load() -> Result<Self, ConfigError> {
+        let env = std::env::var("APP_ENV").unwrap_or_else(|_| "development".to_string());
+
+        let mut builder = Self::defaults()?
+            .add_source(File::with_name("config/default").required(false))
+            .add_source(File::with_name(&format!("config/{}", env)).required(false))
+            .add_source(Environment::with_prefix("APP").prefix_separator("__").separator("__"));
+
+        builder = builder.set_override("environment", env)?;
+        if let Ok(url) = std::env::var("DATABASE_URL") {
+            builder = builder.set_override("database.url", url)?;
+        }
+        if let Ok(secret) = std::env::var("JWT_SECRET") {
+            builder = builder.set_override("auth.jwt.secret", secret)?;
+        }
+
+        builder.build()?.try_deserialize()
+    }
+
+    /// Load settings from defaults overlaid with a TOML document
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        Self::defaults()?
+            .add_source(File::from_str(toml, FileFormat::Toml))
+            .build()?
+            .try_deserialize()
+    }
+
+    fn defaults() -> Result<config::ConfigBuilder<config::builder::DefaultState>, ConfigError> {
+        let jwt = JwtSettings::default();
+        Config::builder()
+            .set_default("environment", "development")?
+            .set_default("server.host", "0.0.0.0")?
+            .set_default("server.port", 8000)?
+            .set_default("server.shutdown_grace_secs", 30)?
+            .set_default("database.url", "postgres://localhost/commercerack")?
+            .set_default("database.max_connections", 50)?
+            .set_default("database.min_connections", 1)?
+            .set_default("database.connect_timeout_secs", 10)?
+            .set_default("database.run_migrations", false)?
+            .set_default("cart.backend", "memory")?
+            .set_default("cart.ttl_secs", 7 * 24 * 60 * 60)?
+            .set_default("cart.purge_interval_secs", 300)?
+            .set_default("cart.hold_ttl_secs", DEFAULT_HOLD_TTL.as_secs())?
+            .set_default("cart.redis_url", "redis://127.0.0.1/")?
+            .set_default("auth.access_ttl_secs", AuthSettings::default().access_ttl_secs)?
+            .set_default("auth.refresh_ttl_secs", AuthSettings::default().refresh_ttl_secs)?
+            .set_default("auth.max_failed_logins", AuthSettings::default().max_failed_logins)?
+            .set_default("auth.lockout_secs", AuthSettings::default().lockout_secs)?
+            .set_default("auth.reset_ttl_secs", AuthSettings::default().reset_ttl_secs)?
+            .set_default("notifier.backend", "log")?
+            .set_default("notifier.path", "notifications.jsonl")?
+            .set_default("auth.jwt.issuer", jwt.issuer)?
+            .set_default("auth.jwt.audience", jwt.audience)
+    }
+}
+
+impl Settings {
+    pub fn is_production(&self) -> bool {
+        self.environment == "production"
+    }
+}
+
+impl ServerSettings {
+    /// Socket address to bind the listener to
+    pub fn addr(&self) -> Result<SocketAddr, std::net::AddrParseError> {
+        format!("{}:{}", self.host, self.port).parse()
+    }
+}
+
+impl DatabaseSettings {
+    /// SeaORM connection options derived from these settings
+    pub fn connect_options(&self) -> sea_orm::ConnectOptions {
+        let mut opts = sea_orm::ConnectOptions::new(self.url.clone());
+        opts.max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .sqlx_logging(false);
+        opts
+    }
+}
+
+impl CartSettings {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs)
+    }
+
+    pub fn hold_ttl(&self) -> Duration {
+        Duration::from_secs(self.hold_ttl_secs)
+    }
+
+    /// Cart repository for the configured backend
+    pub async fn repository(
+        &self,
+        db: &Arc<DatabaseConnection>,
+    ) -> Result<Arc<dyn CartRepository>, CartError> {
+        Ok(match self.backend {
+            CartBackend::Memory => Arc::new(InMemoryCartRepository::new(self.ttl())),
+            CartBackend::Postgres => Arc::new(SeaOrmCartRepository::new(db.clone(), self.ttl())),
+            CartBackend::Redis => {
+                Arc::new(RedisCartRepository::connect(&self.redis_url, self.ttl()).await?)
+            }
+        })
+    }
+}
+
+impl AuthSettings {
+    pub fn access_ttl(&self) -> Duration {
+        Duration::from_secs(self.access_ttl_secs)
+    }
+
+    pub fn refresh_ttl(&self) -> Duration {
+        Duration::from_secs(self.refresh_ttl_secs)
+    }
+
+    pub fn reset_ttl(&self) -> Duration {
+        Duration::from_secs(self.reset_ttl_secs)
+    }
+
+    pub fn login_policy(&self) -> LoginPolicy {
+        LoginPolicy {
+            max_failed_attempts: self.max_failed_logins,
+            lockout: Duration::from_secs(self.lockout_secs),
+        }
+    }
+}
+
+impl NotifierSettings {
+    /// Notifier for the configured backend
+    pub fn notifier(&self) -> Arc<dyn Notifier> {
+        match self.backend {
+            NotifierBackend::Log => Arc::new(LogNotifier),
+            NotifierBackend::File => Arc::new(FileNotifier::new(&self.path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let settings = Settings::from_toml("").unwrap();
+        assert_eq!(settings.server.port, 8000);
+        assert!(!settings.database.run_migrations);
+        assert_eq!(settings.server.addr().unwrap().port(), 8000);
+        assert_eq!(settings.cart.backend, CartBackend::Memory);
+        assert_eq!(settings.cart.hold_ttl(), Duration::from_secs(15 * 60));
+        assert_eq!(settings.auth.access_ttl(), Duration::from_secs(900));
+        assert_eq!(settings.auth.jwt.issuer, "commercerack");
+        assert!(settings.auth.jwt.keys.is_empty());
+        assert!(!settings.is_production());
+        assert_eq!(settings.auth.login_policy().max_failed_attempts, 5);
+        assert_eq!(settings.notifier.backend, NotifierBackend::Log);
+    }
+
+    #[test]
+    fn test_toml_overrides_defaults() {
+        let settings = Settings::from_toml(
+            r#"
+            [server]
+            port = 9100
+
+            [database]
+            url = "postgres://db/test"
+            run_migrations = true
+
+            [cart]
+            backend = "redis"
+            ttl_secs = 3600
+            hold_ttl_secs = 600
+
+            [auth]
+            access_ttl_secs = 300
+            lockout_secs = 60
+
+            [notifier]
+            backend = "file"
+            path = "/var/spool/commercerack/notify.jsonl"
+
+            [auth.jwt]
+            audience = "storefront"
+            signing_kid = "k2"
+
+            [[auth.jwt.keys]]
+            kid = "k1"
+            public_key_file = "/keys/k1.pub.pem"
+
+            [[auth.jwt.keys]]
+            kid = "k2"
+            secret = "0123456789abcdef0123456789abcdef"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.server.port, 9100);
+        assert_eq!(settings.server.host, "0.0.0.0");
+        assert_eq!(settings.database.url, "postgres://db/test");
+        assert!(settings.database.run_migrations);
+        assert_eq!(settings.cart.backend, CartBackend::Redis);
+        assert_eq!(settings.cart.ttl(), Duration::from_secs(3600));
+        assert_eq!(settings.cart.hold_ttl(), Duration::from_secs(600));
+        assert_eq!(settings.auth.access_ttl_secs, 300);
+        assert_eq!(settings.auth.refresh_ttl_secs, 30 * 24 * 60 * 60);
+        assert_eq!(settings.auth.login_policy().lockout, Duration::from_secs(60));
+        assert_eq!(settings.notifier.backend, NotifierBackend::File);
+        assert_eq!(settings.auth.jwt.issuer, "commercerack");
+        assert_eq!(settings.auth.jwt.audience, "storefront");
+        assert_eq!(settings.auth.jwt.keys.len(), 2);
+        assert_eq!(settings.auth.jwt.keys[0].public_key_file.as_deref(), Some("/keys/k1.pub.pem"));
+    }
+}
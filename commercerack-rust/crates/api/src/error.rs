@@ -0,0 +1,504 @@
+//! API error model rendered as `application/problem+json` (RFC 9457)
+//!
+//! Handlers return [`ApiError`]; service errors and extractor rejections
+//! convert into it so every route reports failures the same way.
+
+use axum::{
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        Request,
+    },
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use commercerack_cart::CartError;
+use commercerack_checkout::CheckoutError;
+use commercerack_customer::CustomerError;
+use commercerack_inventory::InventoryError;
+use commercerack_merchant::MerchantError;
+use commercerack_order::OrderError;
+use commercerack_product::ProductError;
+use sea_orm::{DbErr, SqlErr};
+use serde::Serialize;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// A single invalid input field
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Problem details body returned for every error response
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ProblemDetails {
+    /// Problem type URI (`about:blank` when the status code says it all)
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short, human-readable summary of the status code
+    pub title: String,
+    pub status: u16,
+    /// Stable machine-readable error code, e.g. `duplicate_email`
+    pub code: String,
+    /// Human-readable explanation specific to this occurrence
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Error type returned by all API handlers
+#[derive(Debug)]
+pub enum ApiError {
+    /// 400 - request is malformed
+    BadRequest { code: &'static str, message: String },
+    /// 401 - missing or invalid credentials
+    Unauthorized(String),
+    /// 404 - resource does not exist (or is not visible to the caller)
+    NotFound(String),
+    /// 409 - request conflicts with existing state
+    Conflict { code: &'static str, message: String },
+    /// 422 - request is well-formed but fails validation
+    Validation { message: String, errors: Vec<FieldError> },
+    /// 500 - unexpected failure; details are logged, never returned
+    Internal(String),
+    /// Any other status produced by an extractor rejection
+    Status { status: StatusCode, code: &'static str, message: String },
+}
+
+impl ApiError {
+    pub fn not_found(what: &str) -> Self {
+        Self::NotFound(format!("{} not found", what))
+    }
+
+    /// Validation failure for a single field
+    pub fn invalid_field(field: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self::Validation {
+            message: format!("Invalid value for {}", field),
+            errors: vec![FieldError::new(field, message)],
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict { .. } => StatusCode::CONFLICT,
+            Self::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Status { status, .. } => *status,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::BadRequest { code, .. } | Self::Conflict { code, .. } | Self::Status { code, .. } => code,
+            Self::Unauthorized(_) => "unauthorized",
+            Self::NotFound(_) => "not_found",
+            Self::Validation { .. } => "validation_failed",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+
+    fn into_problem(self) -> ProblemDetails {
+        let status = self.status();
+        let code = self.code().to_string();
+        let (message, errors) = match self {
+            Self::Validation { message, errors } => (message, errors),
+            Self::Internal(detail) => {
+                tracing::error!(error = %detail, "internal error");
+                ("An unexpected error occurred".to_string(), Vec::new())
+            }
+            Self::BadRequest { message, .. }
+            | Self::Conflict { message, .. }
+            | Self::Status { message, .. }
+            | Self::Unauthorized(message)
+            | Self::NotFound(message) => (message, Vec::new()),
+        };
+
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            code,
+            message,
+            errors,
+            request_id: REQUEST_ID
+                .try_with(Clone::clone)
+                .ok()
+                .filter(|id| !id.is_empty()),
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.code(), self.status())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let mut response = (status, axum::Json(self.into_problem())).into_response();
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        response
+    }
+}
+
+impl From<DbErr> for ApiError {
+    fn from(err: DbErr) -> Self {
+        match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => Self::Conflict {
+                code: "conflict",
+                message: "Resource already exists".to_string(),
+            },
+            Some(SqlErr::ForeignKeyConstraintViolation(_)) => Self::Conflict {
+                code: "reference_conflict",
+                message: "Resource is referenced by or references missing data".to_string(),
+            },
+            _ => match err {
+                DbErr::RecordNotFound(_) | DbErr::RecordNotUpdated => {
+                    Self::NotFound("Resource not found".to_string())
+                }
+                other => Self::Internal(other.to_string()),
+            },
+        }
+    }
+}
+
+impl From<CustomerError> for ApiError {
+    fn from(err: CustomerError) -> Self {
+        match err {
+            CustomerError::NotFound => Self::not_found("Customer"),
+            CustomerError::DuplicateEmail(_) => Self::Conflict {
+                code: "duplicate_email",
+                message: err.to_string(),
+            },
+            CustomerError::InvalidCredentials | CustomerError::InvalidRefreshToken => {
+                Self::Unauthorized(err.to_string())
+            }
+            CustomerError::AccountLocked => Self::Status {
+                status: StatusCode::FORBIDDEN,
+                code: "account_locked",
+                message: err.to_string(),
+            },
+            CustomerError::TemporarilyLocked { .. } => Self::Status {
+                status: StatusCode::FORBIDDEN,
+                code: "account_temporarily_locked",
+                message: err.to_string(),
+            },
+            CustomerError::WeakPassword(_) => Self::invalid_field("password", err.to_string()),
+            CustomerError::InvalidResetToken => Self::BadRequest {
+                code: "invalid_reset_token",
+                message: err.to_string(),
+            },
+            CustomerError::Notify(detail) => Self::Internal(detail),
+            CustomerError::AddressNotFound => Self::not_found("Address"),
+            CustomerError::InvalidAddress { field, message } => Self::invalid_field(field, message),
+            CustomerError::NoteNotFound => Self::not_found("Note"),
+            CustomerError::InvalidNote { field, message } => Self::invalid_field(field, message),
+            CustomerError::PasswordHash(detail) => Self::Internal(detail),
+            CustomerError::InvalidCursor(e) => Self::invalid_field("cursor", e.to_string()),
+            CustomerError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<MerchantError> for ApiError {
+    fn from(err: MerchantError) -> Self {
+        match err {
+            MerchantError::NotFound => Self::not_found("Merchant"),
+            MerchantError::DuplicateUsername(_) => Self::Conflict {
+                code: "duplicate_username",
+                message: err.to_string(),
+            },
+            MerchantError::ApiKeyNotFound => Self::not_found("API key"),
+            MerchantError::InvalidApiKey => Self::Unauthorized(err.to_string()),
+            MerchantError::UnknownScope(_) => Self::invalid_field("scopes", err.to_string()),
+            MerchantError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<ProductError> for ApiError {
+    fn from(err: ProductError) -> Self {
+        match err {
+            ProductError::NotFound => Self::not_found("Product"),
+            ProductError::DuplicateProduct(_) => Self::Conflict {
+                code: "duplicate_product",
+                message: err.to_string(),
+            },
+            ProductError::SkuNotFound => Self::not_found("SKU"),
+            ProductError::DuplicateSku(_) => Self::Conflict {
+                code: "duplicate_sku",
+                message: err.to_string(),
+            },
+            ProductError::InvalidSku { field, message } => Self::invalid_field(field, message),
+            ProductError::RelationNotFound => Self::not_found("Product relation"),
+            ProductError::RelationCycle { .. } => Self::Conflict {
+                code: "relation_cycle",
+                message: err.to_string(),
+            },
+            ProductError::ComponentsTooDeep(_) => Self::Conflict {
+                code: "components_too_deep",
+                message: err.to_string(),
+            },
+            ProductError::InvalidRelation { field, message } => Self::invalid_field(field, message),
+            ProductError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<OrderError> for ApiError {
+    fn from(err: OrderError) -> Self {
+        match err {
+            OrderError::NotFound => Self::not_found("Order"),
+            OrderError::DuplicateOrder(_) => Self::Conflict {
+                code: "duplicate_order",
+                message: err.to_string(),
+            },
+            OrderError::IllegalTransition { .. } => Self::Conflict {
+                code: "illegal_transition",
+                message: err.to_string(),
+            },
+            OrderError::InvalidFormat { .. } => Self::invalid_field("format", err.to_string()),
+            OrderError::InvalidCursor(e) => Self::invalid_field("cursor", e.to_string()),
+            OrderError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<CartError> for ApiError {
+    fn from(err: CartError) -> Self {
+        match err {
+            CartError::NotFound => Self::not_found("Cart"),
+            CartError::ItemNotFound(_) => Self::not_found("Cart item"),
+            CartError::UnknownSku(_) => Self::invalid_field("sku", err.to_string()),
+            CartError::OutOfStock { .. } => Self::Conflict {
+                code: "out_of_stock",
+                message: err.to_string(),
+            },
+            CartError::Catalog(e) => e.into(),
+            CartError::VersionConflict { .. } => Self::Conflict {
+                code: "cart_version_conflict",
+                message: err.to_string(),
+            },
+            CartError::Serialization(e) => Self::Internal(e.to_string()),
+            CartError::Redis(e) => Self::Internal(e.to_string()),
+            CartError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<CheckoutError> for ApiError {
+    fn from(err: CheckoutError) -> Self {
+        match err {
+            CheckoutError::EmptyCart => Self::invalid_field("items", err.to_string()),
+            CheckoutError::AddressNotFound(_) | CheckoutError::MissingAddress => {
+                Self::invalid_field("address", err.to_string())
+            }
+            CheckoutError::PricesChanged(_) => Self::Conflict {
+                code: "cart_prices_changed",
+                message: err.to_string(),
+            },
+            CheckoutError::OutOfStock(_) => Self::Conflict {
+                code: "out_of_stock",
+                message: err.to_string(),
+            },
+            CheckoutError::Cart(e) => e.into(),
+            CheckoutError::Customer(e) => e.into(),
+            CheckoutError::Order(e) => e.into(),
+            CheckoutError::Inventory(e) => e.into(),
+            CheckoutError::Document(e) => Self::Internal(e.to_string()),
+            CheckoutError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<InventoryError> for ApiError {
+    fn from(err: InventoryError) -> Self {
+        match err {
+            InventoryError::UnknownSku(_) => Self::not_found("SKU"),
+            InventoryError::DetailNotFound => Self::not_found("Inventory record"),
+            InventoryError::DuplicateDetail(_) => Self::Conflict {
+                code: "duplicate_inventory_record",
+                message: err.to_string(),
+            },
+            InventoryError::NegativeQuantity(_) => Self::invalid_field("qty", err.to_string()),
+            InventoryError::OutOfStock(_) => Self::Conflict {
+                code: "out_of_stock",
+                message: err.to_string(),
+            },
+            InventoryError::ShelfShort { .. } => Self::Conflict {
+                code: "shelf_short",
+                message: err.to_string(),
+            },
+            InventoryError::OrderNotReady(_) => Self::Conflict {
+                code: "order_not_ready",
+                message: err.to_string(),
+            },
+            InventoryError::NothingToPick => Self::Conflict {
+                code: "nothing_to_pick",
+                message: err.to_string(),
+            },
+            InventoryError::NothingToShip(_) => Self::Conflict {
+                code: "nothing_to_ship",
+                message: err.to_string(),
+            },
+            InventoryError::PickBatchNotFound => Self::not_found("Pick batch"),
+            InventoryError::CountNotFound => Self::not_found("Inventory count"),
+            InventoryError::CountClosed(_) => Self::Conflict {
+                code: "count_closed",
+                message: err.to_string(),
+            },
+            InventoryError::SupplierNotFound => Self::not_found("Supplier"),
+            InventoryError::DuplicateSupplier(_) => Self::Conflict {
+                code: "duplicate_supplier",
+                message: err.to_string(),
+            },
+            InventoryError::SupplierInUse(_) => Self::Conflict {
+                code: "supplier_in_use",
+                message: err.to_string(),
+            },
+            InventoryError::PurchaseOrderNotFound => Self::not_found("Purchase order"),
+            InventoryError::PurchaseOrderStatus { .. } => Self::Conflict {
+                code: "purchase_order_status",
+                message: err.to_string(),
+            },
+            InventoryError::InvalidDetail { field, message } => Self::invalid_field(field, message),
+            InventoryError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::JsonDataError(e) => Self::Validation {
+                message: e.body_text(),
+                errors: Vec::new(),
+            },
+            JsonRejection::JsonSyntaxError(e) => Self::BadRequest {
+                code: "malformed_json",
+                message: e.body_text(),
+            },
+            other => Self::Status {
+                status: other.status(),
+                code: "invalid_body",
+                message: other.body_text(),
+            },
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::Validation {
+            message: rejection.body_text(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        Self::Status {
+            status: rejection.status(),
+            code: "invalid_path",
+            message: rejection.body_text(),
+        }
+    }
+}
+
+/// Make the `x-request-id` assigned by `SetRequestIdLayer` available to
+/// [`ApiError`] responses produced while handling this request
+pub async fn request_context(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    REQUEST_ID.scope(request_id, next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn problem_of(err: ApiError) -> (StatusCode, serde_json::Value) {
+        let response = err.into_response();
+        let status = response.status();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_email_is_conflict() {
+        let err: ApiError = CustomerError::DuplicateEmail("a@b.com".to_string()).into();
+        let (status, body) = problem_of(err).await;
+
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "duplicate_email");
+        assert_eq!(body["status"], 409);
+    }
+
+    #[tokio::test]
+    async fn test_record_not_found_is_404() {
+        let err: ApiError = DbErr::RecordNotFound("customers".to_string()).into();
+        let (status, body) = problem_of(err).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn test_internal_error_hides_details() {
+        let err: ApiError = DbErr::Conn(sea_orm::RuntimeErr::Internal("db down".to_string())).into();
+        let (status, body) = problem_of(err).await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(!body["message"].as_str().unwrap().contains("db down"));
+    }
+
+    #[tokio::test]
+    async fn test_field_errors_and_request_id() {
+        let err = ApiError::invalid_field("base_price", "must be a decimal number");
+        let (status, body) = REQUEST_ID
+            .scope("req-123".to_string(), problem_of(err))
+            .await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "base_price");
+        assert_eq!(body["request_id"], "req-123");
+    }
+}
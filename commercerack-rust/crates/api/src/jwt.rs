@@ -0,0 +1,374 @@
+//! Access token signing keys
+//!
+//! Every token carries the `kid` of the key that signed it. New tokens are
+//! signed with one key; any configured key verifies, so a key can be rotated
+//! out by switching `signing_kid` and keeping the old public key around until
+//! the last token it signed has expired. Public RS256/EdDSA keys are
+//! published at `/.well-known/jwks.json` for other services.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use spki::{ObjectIdentifier, SubjectPublicKeyInfoRef};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::auth::Claims;
+use crate::config::{JwtKeySettings, JwtSettings};
+
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+/// HS256 secrets shorter than the hash output are brute-forceable
+const MIN_SECRET_LEN: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum JwtConfigError {
+    #[error("no JWT keys configured; refusing to start in production (set JWT_SECRET or auth.jwt.keys)")]
+    NoKeys,
+    #[error("JWT key {0:?} is configured twice")]
+    DuplicateKid(String),
+    #[error("JWT key {kid:?}: {message}")]
+    InvalidKey { kid: String, message: String },
+    #[error("signing key {0:?} is not configured")]
+    UnknownSigningKey(String),
+}
+
+/// Keys for signing and verifying access tokens
+pub struct JwtKeys {
+    signing_kid: String,
+    signing_alg: Algorithm,
+    signing_key: EncodingKey,
+    verifying: HashMap<String, (Algorithm, DecodingKey)>,
+    jwks: JwkSet,
+    issuer: String,
+    audience: String,
+}
+
+impl JwtKeys {
+    /// Load the configured keys; outside production a missing key falls back
+    /// to [`JwtKeys::ephemeral`]
+    pub fn from_settings(settings: &JwtSettings, production: bool) -> Result<Self, JwtConfigError> {
+        let mut keys = settings.keys.clone();
+        if let Some(secret) = &settings.secret {
+            keys.push(JwtKeySettings {
+                kid: "default".to_string(),
+                secret: Some(secret.clone()),
+                ..Default::default()
+            });
+        }
+
+        if keys.is_empty() {
+            if production {
+                return Err(JwtConfigError::NoKeys);
+            }
+            tracing::warn!("No JWT keys configured; using a random key, tokens won't survive a restart");
+            return Ok(Self::ephemeral(&settings.issuer, &settings.audience));
+        }
+
+        let signing_kid = match settings.signing_kid.as_str() {
+            "" => keys[0].kid.clone(),
+            kid => kid.to_string(),
+        };
+
+        let mut verifying = HashMap::new();
+        let mut jwks = Vec::new();
+        let mut signing = None;
+        for key in &keys {
+            let invalid = |message: String| JwtConfigError::InvalidKey {
+                kid: key.kid.clone(),
+                message,
+            };
+
+            let (alg, decoding, jwk) = match &key.secret {
+                Some(secret) => {
+                    if secret.len() < MIN_SECRET_LEN {
+                        return Err(invalid(format!("secret must be at least {} bytes", MIN_SECRET_LEN)));
+                    }
+                    (Algorithm::HS256, DecodingKey::from_secret(secret.as_bytes()), None)
+                }
+                None => {
+                    let path = key
+                        .public_key_file
+                        .as_deref()
+                        .ok_or_else(|| invalid("needs a secret or public_key_file".to_string()))?;
+                    let pem = std::fs::read_to_string(path).map_err(|e| invalid(format!("{}: {}", path, e)))?;
+                    let jwk = public_jwk(&key.kid, &pem).map_err(invalid)?;
+                    let decoding = DecodingKey::from_jwk(&jwk).map_err(|e| invalid(e.to_string()))?;
+                    let alg = match jwk.algorithm {
+                        AlgorithmParameters::RSA(_) => Algorithm::RS256,
+                        _ => Algorithm::EdDSA,
+                    };
+                    (alg, decoding, Some(jwk))
+                }
+            };
+
+            if key.kid == signing_kid {
+                signing = Some((alg, encoding_key(key, alg).map_err(invalid)?));
+            }
+            if verifying.insert(key.kid.clone(), (alg, decoding)).is_some() {
+                return Err(JwtConfigError::DuplicateKid(key.kid.clone()));
+            }
+            jwks.extend(jwk);
+        }
+
+        let (signing_alg, signing_key) = signing.ok_or_else(|| JwtConfigError::UnknownSigningKey(signing_kid.clone()))?;
+        Ok(Self {
+            signing_kid,
+            signing_alg,
+            signing_key,
+            verifying,
+            jwks: JwkSet { keys: jwks },
+            issuer: settings.issuer.clone(),
+            audience: settings.audience.clone(),
+        })
+    }
+
+    /// A random HS256 key that lives as long as the process (development and tests)
+    pub fn ephemeral(issuer: &str, audience: &str) -> Self {
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let kid = "ephemeral".to_string();
+
+        Self {
+            signing_alg: Algorithm::HS256,
+            signing_key: EncodingKey::from_secret(secret.as_bytes()),
+            verifying: HashMap::from([(
+                kid.clone(),
+                (Algorithm::HS256, DecodingKey::from_secret(secret.as_bytes())),
+            )]),
+            signing_kid: kid,
+            jwks: JwkSet { keys: Vec::new() },
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+        }
+    }
+
+    /// Sign `claims` with the current signing key, stamping issuer and audience
+    pub fn encode(&self, claims: &Claims) -> Result<String, jsonwebtoken::errors::Error> {
+        let claims = Claims {
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            ..claims.clone()
+        };
+        let mut header = Header::new(self.signing_alg);
+        header.kid = Some(self.signing_kid.clone());
+
+        jsonwebtoken::encode(&header, &claims, &self.signing_key)
+    }
+
+    /// Verify a token against the key named by its `kid`
+    pub fn decode(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+        use jsonwebtoken::errors::ErrorKind;
+
+        let header = jsonwebtoken::decode_header(token)?;
+        let (alg, key) = header
+            .kid
+            .as_deref()
+            .and_then(|kid| self.verifying.get(kid))
+            .ok_or(ErrorKind::InvalidKeyFormat)?;
+
+        let mut validation = Validation::new(*alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+
+        Ok(jsonwebtoken::decode::<Claims>(token, key, &validation)?.claims)
+    }
+
+    /// Public keys other services can verify tokens with
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+fn encoding_key(key: &JwtKeySettings, alg: Algorithm) -> Result<EncodingKey, String> {
+    if let Some(secret) = &key.secret {
+        return Ok(EncodingKey::from_secret(secret.as_bytes()));
+    }
+
+    let path = key
+        .private_key_file
+        .as_deref()
+        .ok_or_else(|| "the signing key needs a private_key_file".to_string())?;
+    let pem = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    match alg {
+        Algorithm::RS256 => EncodingKey::from_rsa_pem(&pem),
+        _ => EncodingKey::from_ed_pem(&pem),
+    }
+    .map_err(|e| format!("{}: {}", path, e))
+}
+
+/// JWK for an SPKI (`BEGIN PUBLIC KEY`) PEM; the key type picks the algorithm
+fn public_jwk(kid: &str, pem: &str) -> Result<Jwk, String> {
+    let pem = pem::parse(pem).map_err(|e| e.to_string())?;
+    let spki = SubjectPublicKeyInfoRef::try_from(pem.contents()).map_err(|e| e.to_string())?;
+    let public_key = spki
+        .subject_public_key
+        .as_bytes()
+        .ok_or_else(|| "malformed public key".to_string())?;
+
+    let (key_algorithm, algorithm) = match spki.algorithm.oid {
+        RSA_ENCRYPTION => {
+            let rsa = pkcs1::RsaPublicKey::try_from(public_key).map_err(|e| e.to_string())?;
+            let params = RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: URL_SAFE_NO_PAD.encode(rsa.modulus.as_bytes()),
+                e: URL_SAFE_NO_PAD.encode(rsa.public_exponent.as_bytes()),
+            };
+            (KeyAlgorithm::RS256, AlgorithmParameters::RSA(params))
+        }
+        ED25519 => {
+            let params = OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: URL_SAFE_NO_PAD.encode(public_key),
+            };
+            (KeyAlgorithm::EdDSA, AlgorithmParameters::OctetKeyPair(params))
+        }
+        oid => return Err(format!("unsupported key type {} (use RSA or Ed25519)", oid)),
+    };
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/jwt");
+
+    fn key_pair(kid: &str, name: &str) -> JwtKeySettings {
+        JwtKeySettings {
+            kid: kid.to_string(),
+            private_key_file: Some(format!("{}/{}.pem", FIXTURES, name)),
+            public_key_file: Some(format!("{}/{}.pub.pem", FIXTURES, name)),
+            ..Default::default()
+        }
+    }
+
+    fn settings(signing_kid: &str, keys: Vec<JwtKeySettings>) -> JwtSettings {
+        JwtSettings {
+            signing_kid: signing_kid.to_string(),
+            keys,
+            ..Default::default()
+        }
+    }
+
+    fn claims() -> Claims {
+        Claims::new(7, 1, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn test_asymmetric_keys_round_trip() {
+        for (name, alg) in [("rsa", Algorithm::RS256), ("ed25519", Algorithm::EdDSA)] {
+            let keys = JwtKeys::from_settings(&settings("", vec![key_pair("k1", name)]), true).unwrap();
+
+            let token = keys.encode(&claims()).unwrap();
+            let header = jsonwebtoken::decode_header(&token).unwrap();
+            assert_eq!(header.alg, alg);
+            assert_eq!(header.kid.as_deref(), Some("k1"));
+
+            let decoded = keys.decode(&token).unwrap();
+            assert_eq!(decoded.customer_id(), Some(7));
+            assert_eq!(decoded.iss, "commercerack");
+            assert_eq!(decoded.aud, "commercerack-api");
+        }
+    }
+
+    #[test]
+    fn test_old_tokens_verify_after_rotation() {
+        let before = JwtKeys::from_settings(&settings("", vec![key_pair("2025-10", "rsa")]), true).unwrap();
+        let token = before.encode(&claims()).unwrap();
+
+        let retired = JwtKeySettings {
+            private_key_file: None,
+            ..key_pair("2025-10", "rsa")
+        };
+        let after = JwtKeys::from_settings(
+            &settings("2025-11", vec![key_pair("2025-11", "ed25519"), retired]),
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(after.decode(&token).unwrap().customer_id(), Some(7));
+        let fresh = after.encode(&claims()).unwrap();
+        assert_eq!(jsonwebtoken::decode_header(&fresh).unwrap().kid.as_deref(), Some("2025-11"));
+        assert!(before.decode(&fresh).is_err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_issuer_audience_and_kid() {
+        let shared = |issuer: &str, audience: &str| {
+            let settings = JwtSettings {
+                issuer: issuer.to_string(),
+                audience: audience.to_string(),
+                secret: Some("0123456789abcdef0123456789abcdef".to_string()),
+                ..Default::default()
+            };
+            JwtKeys::from_settings(&settings, true).unwrap()
+        };
+        let token = shared("commercerack", "commercerack-api").encode(&claims()).unwrap();
+
+        assert!(shared("commercerack", "commercerack-api").decode(&token).is_ok());
+        assert!(shared("commercerack", "billing").decode(&token).is_err());
+        assert!(shared("someone-else", "commercerack-api").decode(&token).is_err());
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("unknown".to_string());
+        let forged = jsonwebtoken::encode(
+            &header,
+            &claims(),
+            &EncodingKey::from_secret(b"0123456789abcdef0123456789abcdef"),
+        )
+        .unwrap();
+        assert!(shared("commercerack", "commercerack-api").decode(&forged).is_err());
+    }
+
+    #[test]
+    fn test_production_requires_keys() {
+        let err = JwtKeys::from_settings(&JwtSettings::default(), true).err().unwrap();
+        assert!(matches!(err, JwtConfigError::NoKeys));
+        assert!(JwtKeys::from_settings(&JwtSettings::default(), false).is_ok());
+
+        let short = JwtSettings {
+            secret: Some("hunter2".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            JwtKeys::from_settings(&short, true).err().unwrap(),
+            JwtConfigError::InvalidKey { .. }
+        ));
+    }
+
+    #[test]
+    fn test_jwks_lists_only_public_keys() {
+        let keys = JwtKeys::from_settings(
+            &JwtSettings {
+                secret: Some("0123456789abcdef0123456789abcdef".to_string()),
+                ..settings("rsa", vec![key_pair("rsa", "rsa"), key_pair("ed", "ed25519")])
+            },
+            true,
+        )
+        .unwrap();
+
+        let kids: Vec<_> = keys.jwks().keys.iter().filter_map(|k| k.common.key_id.as_deref()).collect();
+        assert_eq!(kids, ["rsa", "ed"]);
+
+        let json = serde_json::to_string(keys.jwks()).unwrap();
+        assert!(!json.contains("0123456789abcdef"));
+        assert!(!json.contains("\"d\""));
+    }
+}
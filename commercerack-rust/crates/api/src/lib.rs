@@ -1,96 +1,389 @@
 //! Axum API server for CommerceRack with SeaORM, JWT, and OpenAPI
 
+use tenant::Tenant;
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    middleware,
     routing::{get, post, put, delete},
-    Json, Router,
+    Router,
 };
-use commercerack_cart::CartStore;
+use commercerack_cart::{CartRepository, InMemoryCartRepository};
+use commercerack_customer::{LogNotifier, Notifier};
+use commercerack_inventory::DEFAULT_HOLD_TTL;
+use config::AuthSettings;
+use jwt::JwtKeys;
 use sea_orm::DatabaseConnection;
-use std::sync::{Arc, Mutex};
-use utoipa::OpenApi;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa_rapidoc::RapiDoc;
 
 pub mod auth;
+pub mod config;
+pub mod error;
+pub mod extract;
+pub mod jwt;
 pub mod routes;
+pub mod tenant;
 
 /// API Documentation
 #[derive(OpenApi)]
 #[openapi(
     paths(
+        routes::auth::login,
+        routes::auth::refresh,
+        routes::auth::logout,
+        routes::auth::request_password_reset,
+        routes::auth::confirm_password_reset,
+        routes::auth::jwks,
         routes::customers::create,
         routes::customers::get,
+        routes::customers::list,
+        routes::customers::unlock,
+        routes::customers::password_schemes,
+        routes::addresses::list,
+        routes::addresses::create,
+        routes::addresses::get,
+        routes::addresses::update,
+        routes::addresses::delete,
+        routes::notes::list,
+        routes::notes::create,
+        routes::notes::update,
+        routes::notes::delete,
+        routes::notes::timeline,
         routes::products::create,
         routes::products::get,
+        routes::skus::list,
+        routes::skus::create,
+        routes::skus::get,
+        routes::skus::update,
+        routes::skus::delete,
+        routes::skus::generate_variants,
+        routes::skus::lookup,
+        routes::relations::list,
+        routes::relations::set,
+        routes::relations::delete,
+        routes::relations::components,
+        routes::inventory::get,
+        routes::inventory::log,
+        routes::inventory::create_detail,
+        routes::inventory::set_quantity,
+        routes::inventory::adjust,
+        routes::inventory::move_detail,
+        routes::inventory::delete_detail,
+        routes::inventory::set_on_shelf,
+        routes::warehouse::bins,
+        routes::warehouse::put_away,
+        routes::warehouse::relocate,
+        routes::warehouse::create_pick_batch,
+        routes::warehouse::pick_batch,
+        routes::warehouse::confirm_picks,
+        routes::warehouse::ship,
+        routes::counts::start,
+        routes::counts::list,
+        routes::counts::get,
+        routes::counts::record,
+        routes::counts::variances,
+        routes::counts::approve,
+        routes::counts::cancel,
+        routes::purchasing::list_suppliers,
+        routes::purchasing::create_supplier,
+        routes::purchasing::get_supplier,
+        routes::purchasing::update_supplier,
+        routes::purchasing::delete_supplier,
+        routes::purchasing::create_order,
+        routes::purchasing::list_orders,
+        routes::purchasing::get_order,
+        routes::purchasing::set_lines,
+        routes::purchasing::submit,
+        routes::purchasing::confirm,
+        routes::purchasing::receive,
+        routes::purchasing::cancel,
         routes::orders::create,
         routes::orders::get,
+        routes::orders::list,
+        routes::checkout::checkout,
+        routes::merchants::set_orderid_format,
     ),
     components(
         schemas(
             auth::Claims,
+            error::ProblemDetails,
+            error::FieldError,
+            routes::auth::LoginRequest,
+            routes::auth::RefreshRequest,
+            routes::auth::PasswordResetRequest,
+            routes::auth::ConfirmPasswordResetRequest,
+            routes::auth::TokenResponse,
             routes::customers::CreateCustomerRequest,
             routes::customers::CustomerResponse,
+            routes::customers::CustomerPage,
+            routes::customers::PasswordSchemesResponse,
+            routes::addresses::AddressRequest,
+            routes::addresses::AddressResponse,
+            routes::notes::CreateNoteRequest,
+            routes::notes::UpdateNoteRequest,
+            routes::notes::NoteResponse,
+            routes::notes::TimelineKind,
+            routes::notes::TimelineEvent,
             routes::products::CreateProductRequest,
             routes::products::ProductResponse,
+            routes::skus::SkuRequest,
+            routes::skus::CreateSkuRequest,
+            routes::skus::OptionValueRequest,
+            routes::skus::OptionGroupRequest,
+            routes::skus::GenerateVariantsRequest,
+            routes::skus::SkuResponse,
+            routes::relations::RelatedProductRequest,
+            routes::relations::SetRelationsRequest,
+            routes::relations::RelationResponse,
+            routes::relations::ComponentResponse,
+            routes::inventory::DetailResponse,
+            routes::inventory::InventoryResponse,
+            routes::inventory::LogEntryResponse,
+            routes::inventory::CreateDetailRequest,
+            routes::inventory::SetQuantityRequest,
+            routes::inventory::AdjustRequest,
+            routes::inventory::MoveRequest,
+            routes::warehouse::LocationRequest,
+            routes::warehouse::BinResponse,
+            routes::warehouse::PutAwayRequest,
+            routes::warehouse::RelocateRequest,
+            routes::warehouse::CreatePickBatchRequest,
+            routes::warehouse::ConfirmPicksRequest,
+            routes::warehouse::ShipmentRequest,
+            routes::warehouse::PickLineResponse,
+            routes::warehouse::PickBatchResponse,
+            routes::counts::StartCountRequest,
+            routes::counts::CountEntryRequest,
+            routes::counts::RecordCountsRequest,
+            routes::counts::ApproveCountRequest,
+            routes::counts::ExportFormat,
+            routes::counts::CountSummaryResponse,
+            routes::counts::CountLineResponse,
+            routes::counts::CountResponse,
+            routes::counts::VarianceResponse,
+            routes::purchasing::SupplierRequest,
+            routes::purchasing::CreateSupplierRequest,
+            routes::purchasing::SupplierResponse,
+            routes::purchasing::OrderLineRequest,
+            routes::purchasing::CreatePurchaseOrderRequest,
+            routes::purchasing::SetLinesRequest,
+            routes::purchasing::ConfirmOrderRequest,
+            routes::purchasing::ReceiptRequest,
+            routes::purchasing::ReceiveRequest,
+            routes::purchasing::PurchaseOrderSummaryResponse,
+            routes::purchasing::PurchaseOrderLineResponse,
+            routes::purchasing::PurchaseOrderResponse,
             routes::orders::CreateOrderRequest,
             routes::orders::OrderResponse,
+            routes::orders::OrderPage,
+            routes::checkout::CheckoutBody,
+            routes::merchants::OrderIdFormatRequest,
+            routes::merchants::OrderIdFormatResponse,
         )
     ),
+    modifiers(&SecurityAddon),
     tags(
+        (name = "auth", description = "Customer login and token endpoints"),
         (name = "customers", description = "Customer management endpoints"),
         (name = "products", description = "Product catalog endpoints"),
+        (name = "inventory", description = "Stock ledger endpoints"),
+        (name = "warehouse", description = "Bin, picking and shipping endpoints"),
+        (name = "counts", description = "Cycle count endpoints"),
+        (name = "purchasing", description = "Supplier and purchase order endpoints"),
         (name = "orders", description = "Order management endpoints"),
         (name = "cart", description = "Shopping cart endpoints"),
+        (name = "checkout", description = "Cart checkout endpoints"),
+        (name = "merchants", description = "Merchant settings endpoints"),
     ),
     security(
-        ("bearer" = [])
+        ("bearer" = []),
+        ("api_key" = [])
     )
 )]
 pub struct ApiDoc;
 
+/// Registers the `bearer` and `api_key` schemes referenced by `security`
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+        );
+    }
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub db: Arc<DatabaseConnection>,
-    pub cart_store: Arc<Mutex<CartStore>>,
+    pub carts: Arc<dyn CartRepository>,
+    /// How long carts hold stock after each change
+    pub hold_ttl: Duration,
+    pub auth: AuthSettings,
+    pub jwt: Arc<JwtKeys>,
+    pub notifier: Arc<dyn Notifier>,
 }
 
-/// Build the Axum router with all routes and OpenAPI documentation
+impl AppState {
+    /// State with carts kept in memory (development and tests)
+    pub fn new(db: DatabaseConnection) -> Self {
+        let auth = AuthSettings::default();
+        Self {
+            db: Arc::new(db),
+            carts: Arc::new(InMemoryCartRepository::default()),
+            hold_ttl: DEFAULT_HOLD_TTL,
+            jwt: Arc::new(JwtKeys::ephemeral(&auth.jwt.issuer, &auth.jwt.audience)),
+            auth,
+            notifier: Arc::new(LogNotifier),
+        }
+    }
+}
+
+/// Build the Axum router with in-memory carts
 pub fn app(db: DatabaseConnection) -> Router {
-    let cart_store = Arc::new(Mutex::new(CartStore::new()));
-    let state = AppState {
-        db: Arc::new(db),
-        cart_store: cart_store.clone(),
-    };
+    router(AppState::new(db))
+}
 
-    Router::new()
-        // OpenAPI documentation
-        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
-        .merge(RapiDoc::new("/api-docs/openapi.json").path("/rapidoc"))
-        // Customer routes
-        .route("/api/customers", post(routes::customers::create))
+/// Build the Axum router with all routes and OpenAPI documentation
+pub fn router(state: AppState) -> Router {
+    // Customer- and merchant-scoped routes need a bearer token or API key
+    let protected = Router::new()
         .route("/api/customers/:mid/:id", get(routes::customers::get))
         .route("/api/customers", get(routes::customers::list))
-        // Product routes
+        .route("/api/customers/:id/unlock", post(routes::customers::unlock))
+        .route("/api/customers/password-schemes", get(routes::customers::password_schemes))
+        .route(
+            "/api/customers/:id/addresses",
+            get(routes::addresses::list).post(routes::addresses::create),
+        )
+        .route(
+            "/api/customers/:id/addresses/:guid",
+            get(routes::addresses::get)
+                .put(routes::addresses::update)
+                .delete(routes::addresses::delete),
+        )
+        .route(
+            "/api/customers/:id/notes",
+            get(routes::notes::list).post(routes::notes::create),
+        )
+        .route(
+            "/api/customers/:id/notes/:note_id",
+            put(routes::notes::update).delete(routes::notes::delete),
+        )
+        .route("/api/customers/:id/timeline", get(routes::notes::timeline))
         .route("/api/products", post(routes::products::create))
         .route("/api/products/:mid/:id", get(routes::products::get))
         .route("/api/products", get(routes::products::list))
-        // Order routes
+        .route(
+            "/api/products/:pid/skus",
+            get(routes::skus::list).post(routes::skus::create),
+        )
+        .route("/api/products/:pid/skus/variants", post(routes::skus::generate_variants))
+        .route(
+            "/api/products/:pid/skus/:sku",
+            get(routes::skus::get)
+                .put(routes::skus::update)
+                .delete(routes::skus::delete),
+        )
+        .route("/api/skus", get(routes::skus::lookup))
+        .route(
+            "/api/products/:pid/relations/:kind",
+            get(routes::relations::list).put(routes::relations::set),
+        )
+        .route(
+            "/api/products/:pid/relations/:kind/:child_pid",
+            delete(routes::relations::delete),
+        )
+        .route("/api/products/:pid/components", get(routes::relations::components))
+        .route("/api/inventory/:sku", get(routes::inventory::get))
+        .route("/api/inventory/:sku/log", get(routes::inventory::log))
+        .route("/api/inventory/:sku/on-shelf", put(routes::inventory::set_on_shelf))
+        .route("/api/inventory/:sku/details", post(routes::inventory::create_detail))
+        .route(
+            "/api/inventory/:sku/details/:uuid",
+            put(routes::inventory::set_quantity).delete(routes::inventory::delete_detail),
+        )
+        .route("/api/inventory/:sku/details/:uuid/adjust", post(routes::inventory::adjust))
+        .route("/api/inventory/:sku/details/:uuid/move", post(routes::inventory::move_detail))
+        .route("/api/warehouse/bins", get(routes::warehouse::bins))
+        .route("/api/warehouse/put-away", post(routes::warehouse::put_away))
+        .route("/api/warehouse/relocate", post(routes::warehouse::relocate))
+        .route("/api/warehouse/pick-batches", post(routes::warehouse::create_pick_batch))
+        .route("/api/warehouse/pick-batches/:batchid", get(routes::warehouse::pick_batch))
+        .route("/api/warehouse/pick-batches/:batchid/confirm", post(routes::warehouse::confirm_picks))
+        .route("/api/warehouse/shipments", post(routes::warehouse::ship))
+        .route("/api/inventory-counts", post(routes::counts::start).get(routes::counts::list))
+        .route("/api/inventory-counts/:id", get(routes::counts::get))
+        .route("/api/inventory-counts/:id/entries", post(routes::counts::record))
+        .route("/api/inventory-counts/:id/variances", get(routes::counts::variances))
+        .route("/api/inventory-counts/:id/approve", post(routes::counts::approve))
+        .route("/api/inventory-counts/:id/cancel", post(routes::counts::cancel))
+        .route("/api/suppliers", get(routes::purchasing::list_suppliers).post(routes::purchasing::create_supplier))
+        .route(
+            "/api/suppliers/:code",
+            get(routes::purchasing::get_supplier)
+                .put(routes::purchasing::update_supplier)
+                .delete(routes::purchasing::delete_supplier),
+        )
+        .route("/api/purchase-orders", post(routes::purchasing::create_order).get(routes::purchasing::list_orders))
+        .route("/api/purchase-orders/:id", get(routes::purchasing::get_order))
+        .route("/api/purchase-orders/:id/lines", put(routes::purchasing::set_lines))
+        .route("/api/purchase-orders/:id/submit", post(routes::purchasing::submit))
+        .route("/api/purchase-orders/:id/confirm", post(routes::purchasing::confirm))
+        .route("/api/purchase-orders/:id/receive", post(routes::purchasing::receive))
+        .route("/api/purchase-orders/:id/cancel", post(routes::purchasing::cancel))
         .route("/api/orders", post(routes::orders::create))
         .route("/api/orders/:mid/:id", get(routes::orders::get))
         .route("/api/orders", get(routes::orders::list))
-        // Cart routes
         .route("/api/carts", post(routes::cart::create_cart))
         .route("/api/carts/:cart_id", get(routes::cart::get_cart))
         .route("/api/carts/:cart_id/items", post(routes::cart::add_item))
         .route("/api/carts/:cart_id/items/:sku", put(routes::cart::update_quantity))
         .route("/api/carts/:cart_id/items/:sku", delete(routes::cart::remove_item))
         .route("/api/carts/:cart_id/clear", post(routes::cart::clear_cart))
+        .route("/api/carts/:cart_id/also-need", get(routes::cart::also_need))
         .route("/api/carts/:cart_id", delete(routes::cart::delete_cart))
+        .route("/api/carts/:cart_id/checkout", post(routes::checkout::checkout))
+        .route("/api/merchants/:mid/orderid-format", put(routes::merchants::set_orderid_format))
+        .route_layer(middleware::from_extractor_with_state::<Tenant, _>(state.clone()));
+
+    Router::new()
+        // OpenAPI documentation
+        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
+        .merge(RapiDoc::new("/api-docs/openapi.json").path("/rapidoc"))
+        // Auth routes
+        .route("/api/auth/login", post(routes::auth::login))
+        .route("/api/auth/refresh", post(routes::auth::refresh))
+        .route("/api/auth/logout", post(routes::auth::logout))
+        .route("/api/auth/password-reset", post(routes::auth::request_password_reset))
+        .route("/api/auth/password-reset/confirm", post(routes::auth::confirm_password_reset))
+        .route("/.well-known/jwks.json", get(routes::auth::jwks))
+        // Customer signup
+        .route("/api/customers", post(routes::customers::create))
+        .merge(protected)
         // Health check
         .route("/health", get(health_check))
         .with_state(state)
+        // Request ids: assign (or keep the caller's) x-request-id, expose it to
+        // problem+json error bodies, and echo it on the response
+        .layer(middleware::from_fn(error::request_context))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
 }
 
 /// Health check endpoint
@@ -145,4 +438,73 @@ mod tests {
 
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn test_scoped_routes_require_bearer_token() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<::entity::prelude::Customer>::new()])
+            .into_connection();
+        let state = AppState::new(db);
+        let app = router(state.clone());
+        let request = |token: Option<String>| {
+            let mut builder = Request::builder().uri("/api/customers/1/7");
+            if let Some(token) = token {
+                builder = builder.header("Authorization", format!("Bearer {}", token));
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.clone().oneshot(request(Some("garbage".to_string()))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let token = state
+            .jwt
+            .encode(&auth::Claims::new(7, 1, std::time::Duration::from_secs(60)))
+            .unwrap();
+        let response = app.oneshot(request(Some(token))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_api_keys_are_limited_to_their_scopes() {
+        let key = ::entity::prelude::MerchantApiKey {
+            id: 3,
+            mid: 1,
+            name: "erp".to_string(),
+            key_prefix: "crk_abcdefgh".to_string(),
+            key_hash: String::new(),
+            scopes: "catalog:read".to_string(),
+            created_gmt: 0,
+            expires_gmt: 0,
+            last_used_gmt: 0,
+            revoked_gmt: 0,
+            replaced_by: 0,
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![key.clone()]])
+            .append_exec_results([sea_orm::MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .append_query_results([Vec::<::entity::prelude::Product>::new()])
+            .append_query_results([vec![::entity::prelude::MerchantApiKey {
+                last_used_gmt: i32::MAX,
+                ..key
+            }]])
+            .into_connection();
+        let app = app(db);
+        let request = |uri: &str| {
+            Request::builder()
+                .uri(uri)
+                .header("X-Api-Key", "crk_test")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request("/api/products/1/5")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app.oneshot(request("/api/orders/1/5")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
 }
@@ -0,0 +1,138 @@
+//! CommerceRack API server binary
+
+use commercerack_api::{config::{NotifierBackend, Settings}, jwt::JwtKeys, router, AppState};
+use commercerack_cart::CartRepository;
+use commercerack_inventory::ReservationService;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, DatabaseConnection};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+/// SKUs with lapsed holds handled per sweep
+const HOLD_SWEEP_BATCH: u64 = 500;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=warn")),
+        )
+        .init();
+
+    let settings = Settings::load()?;
+    let jwt = Arc::new(JwtKeys::from_settings(&settings.auth.jwt, settings.is_production())?);
+
+    let db = Arc::new(Database::connect(settings.database.connect_options()).await?);
+    info!("✅ Database connection established");
+
+    if settings.database.run_migrations {
+        Migrator::up(db.as_ref(), None).await?;
+        info!("✅ Migrations applied");
+    }
+
+    let carts = settings.cart.repository(&db).await?;
+    info!("🛒 Cart backend: {:?}", settings.cart.backend);
+    tokio::spawn(purge_abandoned_carts(
+        carts.clone(),
+        Duration::from_secs(settings.cart.purge_interval_secs),
+    ));
+    tokio::spawn(release_lapsed_holds(
+        db.clone(),
+        Duration::from_secs(settings.cart.purge_interval_secs),
+    ));
+
+    if settings.is_production() && settings.notifier.backend == NotifierBackend::Log {
+        warn!("⚠️  Notifier backend is `log`; password reset tokens are written to the log");
+    }
+
+    let state = AppState {
+        db,
+        carts,
+        hold_ttl: settings.cart.hold_ttl(),
+        auth: settings.auth,
+        jwt,
+        notifier: settings.notifier.notifier(),
+    };
+
+    let addr = settings.server.addr()?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("🚀 Listening on {}", addr);
+
+    // 🤓 axum waits forever for open connections once shutdown starts; the
+    // watch channel lets us bound that with the configured grace period.
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    let server = axum::serve(listener, router(state)).with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(true);
+    });
+
+    let grace = Duration::from_secs(settings.server.shutdown_grace_secs);
+    tokio::select! {
+        result = server => result?,
+        _ = async {
+            let _ = shutdown_rx.wait_for(|stopping| *stopping).await;
+            tokio::time::sleep(grace).await;
+        } => warn!("Shutdown grace period of {:?} elapsed, dropping open connections", grace),
+    }
+
+    info!("👋 Server stopped");
+    Ok(())
+}
+
+/// Periodically delete carts nobody touched within the TTL
+async fn purge_abandoned_carts(carts: Arc<dyn CartRepository>, every: Duration) {
+    let mut ticker = tokio::time::interval(every);
+    loop {
+        ticker.tick().await;
+        match carts.purge_expired().await {
+            Ok(0) => {}
+            Ok(purged) => info!("🧹 Purged {} abandoned carts", purged),
+            Err(e) => warn!(error = %e, "Abandoned cart purge failed"),
+        }
+    }
+}
+
+/// Periodically give back stock held by carts that went quiet
+async fn release_lapsed_holds(db: Arc<DatabaseConnection>, every: Duration) {
+    let mut ticker = tokio::time::interval(every);
+    loop {
+        ticker.tick().await;
+        match ReservationService::release_expired(db.as_ref(), HOLD_SWEEP_BATCH).await {
+            Ok(0) => {}
+            Ok(released) => info!("🧹 Released {} lapsed stock holds", released),
+            Err(e) => warn!(error = %e, "Stock hold release failed"),
+        }
+    }
+}
+
+/// Resolve on Ctrl+C or SIGTERM (sent by Docker/Kubernetes on stop)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutdown signal received, draining connections");
+}
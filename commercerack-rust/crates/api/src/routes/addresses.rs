@@ -0,0 +1,294 @@
+use axum::{extract::State, http::StatusCode};
+use commercerack_customer::{AddressInput, AddressService, CustomerError};
+use ::entity::prelude::CustomerAddr;
+use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path};
+use crate::tenant::Tenant;
+use crate::AppState;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct AddressRequest {
+    /// Customer's name for the address, e.g. `Home`
+    #[serde(default)]
+    pub label: String,
+    pub firstname: String,
+    pub lastname: String,
+    #[serde(default)]
+    pub company: String,
+    pub address1: String,
+    #[serde(default)]
+    pub address2: String,
+    pub city: String,
+    #[serde(default)]
+    pub state: String,
+    /// Checked against the country's postal code format
+    #[serde(default)]
+    pub zip: String,
+    /// ISO 3166-1 alpha-2
+    #[schema(example = "US")]
+    pub country: String,
+    #[serde(default)]
+    pub phone: String,
+    /// Make this the default address; a customer's first address always is
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+impl From<AddressRequest> for AddressInput {
+    fn from(req: AddressRequest) -> Self {
+        Self {
+            label: req.label,
+            firstname: req.firstname,
+            lastname: req.lastname,
+            company: req.company,
+            address1: req.address1,
+            address2: req.address2,
+            city: req.city,
+            state: req.state,
+            zip: req.zip,
+            country: req.country,
+            phone: req.phone,
+            is_default: req.is_default,
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct AddressResponse {
+    /// Pass as `bill_address`/`ship_address` at checkout
+    pub id: i32,
+    pub guid: String,
+    pub cid: i32,
+    pub is_default: bool,
+    pub label: String,
+    pub firstname: String,
+    pub lastname: String,
+    pub company: String,
+    pub address1: String,
+    pub address2: String,
+    pub city: String,
+    pub state: String,
+    pub zip: String,
+    pub country: String,
+    pub phone: String,
+    pub created_gmt: i32,
+}
+
+impl From<CustomerAddr> for AddressResponse {
+    fn from(address: CustomerAddr) -> Self {
+        Self {
+            id: address.id,
+            guid: address.guid,
+            cid: address.cid,
+            is_default: address.is_default != 0,
+            label: address.label,
+            firstname: address.firstname,
+            lastname: address.lastname,
+            company: address.company,
+            address1: address.address1,
+            address2: address.address2,
+            city: address.city,
+            state: address.state,
+            zip: address.zip,
+            country: address.country,
+            phone: address.phone,
+            created_gmt: address.created_gmt,
+        }
+    }
+}
+
+/// List a customer's addresses, default first
+#[utoipa::path(
+    get,
+    path = "/api/customers/{id}/addresses",
+    params(("id" = i32, Path, description = "Customer ID")),
+    responses(
+        (status = 200, description = "The customer's addresses", body = [AddressResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 404, description = "Customer is not the caller", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn list(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(cid): Path<i32>,
+) -> Result<Json<Vec<AddressResponse>>, ApiError> {
+    tenant.require_self(cid)?;
+    let addresses = AddressService::list(&state.db, tenant.mid, cid).await?;
+    Ok(Json(addresses.into_iter().map(AddressResponse::from).collect()))
+}
+
+/// Add an address
+#[utoipa::path(
+    post,
+    path = "/api/customers/{id}/addresses",
+    params(("id" = i32, Path, description = "Customer ID")),
+    request_body = AddressRequest,
+    responses(
+        (status = 201, description = "Address created", body = AddressResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 404, description = "Customer not found", body = ProblemDetails),
+        (status = 422, description = "Invalid address", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn create(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(cid): Path<i32>,
+    Json(req): Json<AddressRequest>,
+) -> Result<(StatusCode, Json<AddressResponse>), ApiError> {
+    tenant.require_self(cid)?;
+    let address = AddressService::create(&state.db, tenant.mid, cid, req.into()).await?;
+    Ok((StatusCode::CREATED, Json(address.into())))
+}
+
+/// Get an address by its guid
+#[utoipa::path(
+    get,
+    path = "/api/customers/{id}/addresses/{guid}",
+    params(
+        ("id" = i32, Path, description = "Customer ID"),
+        ("guid" = String, Path, description = "Address guid")
+    ),
+    responses(
+        (status = 200, description = "Address found", body = AddressResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 404, description = "Address not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((cid, guid)): Path<(i32, String)>,
+) -> Result<Json<AddressResponse>, ApiError> {
+    tenant.require_self(cid)?;
+    AddressService::find_by_guid(&state.db, tenant.mid, &guid)
+        .await?
+        .filter(|address| address.cid == cid)
+        .map(|address| Json(address.into()))
+        .ok_or_else(|| CustomerError::AddressNotFound.into())
+}
+
+/// Replace an address
+#[utoipa::path(
+    put,
+    path = "/api/customers/{id}/addresses/{guid}",
+    params(
+        ("id" = i32, Path, description = "Customer ID"),
+        ("guid" = String, Path, description = "Address guid")
+    ),
+    request_body = AddressRequest,
+    responses(
+        (status = 200, description = "Address updated", body = AddressResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 404, description = "Address not found", body = ProblemDetails),
+        (status = 422, description = "Invalid address", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn update(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((cid, guid)): Path<(i32, String)>,
+    Json(req): Json<AddressRequest>,
+) -> Result<Json<AddressResponse>, ApiError> {
+    tenant.require_self(cid)?;
+    let address = AddressService::update(&state.db, tenant.mid, cid, &guid, req.into()).await?;
+    Ok(Json(address.into()))
+}
+
+/// Delete an address
+#[utoipa::path(
+    delete,
+    path = "/api/customers/{id}/addresses/{guid}",
+    params(
+        ("id" = i32, Path, description = "Customer ID"),
+        ("guid" = String, Path, description = "Address guid")
+    ),
+    responses(
+        (status = 204, description = "Address deleted"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 404, description = "Address not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn delete(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((cid, guid)): Path<(i32, String)>,
+) -> Result<StatusCode, ApiError> {
+    tenant.require_self(cid)?;
+    AddressService::delete(&state.db, tenant.mid, cid, &guid).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenant::Caller;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    const TENANT: Tenant = Tenant { mid: 1, caller: Caller::Customer(7) };
+
+    fn request(zip: &str) -> AddressRequest {
+        AddressRequest {
+            label: "Home".to_string(),
+            firstname: "Ada".to_string(),
+            lastname: "Lovelace".to_string(),
+            company: String::new(),
+            address1: "1 Main St".to_string(),
+            address2: String::new(),
+            city: "Springfield".to_string(),
+            state: "IL".to_string(),
+            zip: zip.to_string(),
+            country: "US".to_string(),
+            phone: String::new(),
+            is_default: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_postal_code_is_422() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+        let err = create(State(AppState::new(db)), TENANT, Path(7), Json(request("ABCDE")))
+            .await
+            .err()
+            .expect("letters are not a US ZIP code");
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_other_customers_addresses_are_404() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let state = AppState::new(db);
+
+        let err = list(State(state.clone()), TENANT, Path(8))
+            .await
+            .err()
+            .expect("customer 7 can't list customer 8's addresses");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let err = delete(State(state.clone()), TENANT, Path((8, "guid-1".to_string())))
+            .await
+            .expect_err("customer 7 can't delete customer 8's addresses");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let db = std::sync::Arc::try_unwrap(state.db).ok().unwrap();
+        assert!(db.into_transaction_log().is_empty());
+    }
+}
@@ -0,0 +1,321 @@
+use axum::{extract::State, http::StatusCode};
+use commercerack_customer::{CustomerError, CustomerService, IssuedToken, PasswordResetService, SessionService};
+use jsonwebtoken::jwk::JwkSet;
+use serde::{Deserialize, Serialize};
+use crate::auth::Claims;
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::Json;
+use crate::AppState;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct LoginRequest {
+    pub mid: i32,
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct PasswordResetRequest {
+    pub mid: i32,
+    pub email: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ConfirmPasswordResetRequest {
+    /// Token from the reset message
+    pub token: String,
+    pub password: String,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct TokenResponse {
+    /// JWT to send as `Authorization: Bearer <token>`
+    pub access_token: String,
+    pub token_type: &'static str,
+    /// Seconds until `access_token` expires
+    pub expires_in: u64,
+    /// Single-use token for `POST /api/auth/refresh`
+    pub refresh_token: String,
+    /// Seconds until `refresh_token` expires
+    pub refresh_expires_in: u64,
+}
+
+impl TokenResponse {
+    fn issue(state: &AppState, refresh: IssuedToken) -> Result<Self, ApiError> {
+        let session = &refresh.session;
+        let access_token = state
+            .jwt
+            .encode(&Claims::new(session.cid, session.mid, state.auth.access_ttl()))
+            .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+        Ok(Self {
+            access_token,
+            token_type: "Bearer",
+            expires_in: state.auth.access_ttl_secs,
+            refresh_token: refresh.token,
+            refresh_expires_in: state.auth.refresh_ttl_secs,
+        })
+    }
+}
+
+/// Log a customer in
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = TokenResponse),
+        (status = 401, description = "Invalid email or password", body = ProblemDetails),
+        (status = 403, description = "Account is locked, possibly after too many failed logins", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    security(()),
+    tag = "auth"
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    Json(req): Json<LoginRequest>,
+) -> Result<Json<TokenResponse>, ApiError> {
+    let customer = CustomerService::authenticate(
+        &state.db,
+        req.mid,
+        &req.email,
+        &req.password,
+        &state.auth.login_policy(),
+    )
+    .await?;
+    let refresh = SessionService::start(&state.db, customer.mid, customer.cid, state.auth.refresh_ttl()).await?;
+
+    Ok(Json(TokenResponse::issue(&state, refresh)?))
+}
+
+/// Exchange a refresh token for new tokens
+///
+/// The presented refresh token is used up. Presenting it again ends the
+/// whole session.
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "Tokens refreshed", body = TokenResponse),
+        (status = 401, description = "Refresh token is invalid, expired or revoked", body = ProblemDetails),
+        (status = 403, description = "Account is locked", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    security(()),
+    tag = "auth"
+)]
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(req): Json<RefreshRequest>,
+) -> Result<Json<TokenResponse>, ApiError> {
+    let refresh = SessionService::refresh(&state.db, &req.refresh_token, state.auth.refresh_ttl()).await?;
+
+    // 🤓 accounts can be locked or deleted while a session is open
+    let session = &refresh.session;
+    let customer = CustomerService::find_by_id(&state.db, session.mid, session.cid).await?;
+    match customer {
+        Some(customer) if customer.is_locked == 0 => {}
+        Some(_) => {
+            SessionService::end_all(&state.db, session.mid, session.cid).await?;
+            return Err(CustomerError::AccountLocked.into());
+        }
+        None => {
+            SessionService::end_all(&state.db, session.mid, session.cid).await?;
+            return Err(CustomerError::InvalidRefreshToken.into());
+        }
+    }
+
+    Ok(Json(TokenResponse::issue(&state, refresh)?))
+}
+
+/// End the session a refresh token belongs to
+///
+/// Access tokens already issued stay valid until they expire.
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    request_body = RefreshRequest,
+    responses(
+        (status = 204, description = "Logged out"),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    security(()),
+    tag = "auth"
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    Json(req): Json<RefreshRequest>,
+) -> Result<StatusCode, ApiError> {
+    SessionService::end(&state.db, &req.refresh_token).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Send a password reset token to a customer
+///
+/// Always accepted, whether or not the email belongs to an account.
+#[utoipa::path(
+    post,
+    path = "/api/auth/password-reset",
+    request_body = PasswordResetRequest,
+    responses(
+        (status = 202, description = "Reset token sent if the account exists"),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    security(()),
+    tag = "auth"
+)]
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    Json(req): Json<PasswordResetRequest>,
+) -> Result<StatusCode, ApiError> {
+    PasswordResetService::request(
+        &state.db,
+        state.notifier.as_ref(),
+        req.mid,
+        &req.email,
+        state.auth.reset_ttl(),
+    )
+    .await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Set a new password with a reset token
+///
+/// Ends all of the customer's sessions.
+#[utoipa::path(
+    post,
+    path = "/api/auth/password-reset/confirm",
+    request_body = ConfirmPasswordResetRequest,
+    responses(
+        (status = 204, description = "Password changed"),
+        (status = 400, description = "Token is invalid, expired or already used", body = ProblemDetails),
+        (status = 403, description = "Account is locked", body = ProblemDetails),
+        (status = 422, description = "Password is too short", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    security(()),
+    tag = "auth"
+)]
+pub async fn confirm_password_reset(
+    State(state): State<AppState>,
+    Json(req): Json<ConfirmPasswordResetRequest>,
+) -> Result<StatusCode, ApiError> {
+    PasswordResetService::reset(&state.db, &req.token, &req.password).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Public keys for verifying access tokens
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    responses(
+        (status = 200, description = "JSON Web Key Set", content_type = "application/json")
+    ),
+    security(()),
+    tag = "auth"
+)]
+pub async fn jwks(State(state): State<AppState>) -> Json<JwkSet> {
+    Json(state.jwt.jwks().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::entity::prelude::{Customer, CustomerSession};
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn session() -> CustomerSession {
+        CustomerSession {
+            id: 1,
+            mid: 1,
+            cid: 7,
+            family: "family-1".to_string(),
+            token_hash: String::new(),
+            created_gmt: 0,
+            expires_gmt: i32::MAX,
+            rotated_gmt: 0,
+            revoked_gmt: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_email_is_401() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Customer>::new()])
+            .into_connection();
+        let req = LoginRequest {
+            mid: 1,
+            email: "nobody@example.com".to_string(),
+            password: "secret".to_string(),
+        };
+
+        let err = login(State(AppState::new(db)), Json(req))
+            .await
+            .err()
+            .expect("unknown email should be rejected");
+        assert_eq!(err.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_for_deleted_customer_is_401() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![session()]])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .append_query_results([vec![CustomerSession { id: 2, ..session() }]])
+            .append_query_results([Vec::<Customer>::new()])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .into_connection();
+        let req = RefreshRequest { refresh_token: "token".to_string() };
+
+        let err = refresh(State(AppState::new(db)), Json(req))
+            .await
+            .err()
+            .expect("refresh for a deleted customer should be rejected");
+        assert_eq!(err.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_logout_is_idempotent() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<CustomerSession>::new()])
+            .into_connection();
+        let req = RefreshRequest { refresh_token: "gone".to_string() };
+
+        let status = logout(State(AppState::new(db)), Json(req)).await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_for_unknown_email_is_accepted() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Customer>::new()])
+            .into_connection();
+        let req = PasswordResetRequest { mid: 1, email: "nobody@example.com".to_string() };
+
+        let status = request_password_reset(State(AppState::new(db)), Json(req)).await.unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_reset_token_is_400() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<::entity::prelude::CustomerPasswordReset>::new()])
+            .into_connection();
+        let req = ConfirmPasswordResetRequest {
+            token: "nope".to_string(),
+            password: "correct horse".to_string(),
+        };
+
+        let err = confirm_password_reset(State(AppState::new(db)), Json(req))
+            .await
+            .expect_err("unknown token should be rejected");
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+    }
+}
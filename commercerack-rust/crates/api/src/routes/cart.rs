@@ -1,19 +1,30 @@
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
-};
-use commercerack_cart::{Cart, CartItem};
+use axum::{extract::State, http::StatusCode};
+use commercerack_cart::{update_cart, Cart, CartError, CartItem, CartPricing};
+use commercerack_inventory::{InventoryError, ReservationService};
+use commercerack_merchant::ApiScope;
+use commercerack_product::{ProductRelationService, SkuQty};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use crate::error::ApiError;
+use crate::routes::products::ProductResponse;
+use crate::extract::{Json, Path};
+use crate::tenant::Tenant;
 use crate::AppState;
 
+/// Most suggestions returned for one cart
+const ALSO_NEED_LIMIT: usize = 12;
+
+#[derive(Deserialize)]
+pub struct CreateCartRequest {
+    /// Merchant ID; defaults to the caller's
+    pub mid: Option<i32>,
+}
+
+/// Name and price come from the catalog, never from the client
 #[derive(Deserialize)]
 pub struct AddItemRequest {
     pub sku: String,
-    pub product_name: String,
     pub quantity: i32,
-    pub unit_price: String, // Decimal as string from JSON
 }
 
 #[derive(Deserialize)]
@@ -21,127 +32,787 @@ pub struct UpdateQuantityRequest {
     pub quantity: i32,
 }
 
+#[derive(Serialize)]
+pub struct CartLineResponse {
+    pub sku: String,
+    pub product_name: String,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub subtotal: Decimal,
+    /// The catalog price changed since this line was added
+    pub price_changed: bool,
+    /// Price the line was added at, when `price_changed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_unit_price: Option<Decimal>,
+}
+
+impl From<CartItem> for CartLineResponse {
+    fn from(item: CartItem) -> Self {
+        Self {
+            subtotal: item.subtotal(),
+            price_changed: item.price_changed(),
+            sku: item.sku,
+            product_name: item.product_name,
+            quantity: item.quantity,
+            unit_price: item.unit_price,
+            previous_unit_price: item.previous_unit_price,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct CartResponse {
     pub cart_id: String,
-    pub items: Vec<CartItem>,
+    pub mid: i32,
+    pub items: Vec<CartLineResponse>,
     pub subtotal: Decimal,
     pub item_count: i32,
+    /// Incremented on every change
+    pub version: i64,
+    pub modified_gmt: i32,
 }
 
-impl From<&Cart> for CartResponse {
-    fn from(cart: &Cart) -> Self {
+impl From<Cart> for CartResponse {
+    fn from(cart: Cart) -> Self {
         Self {
-            cart_id: cart.cart_id.clone(),
-            items: cart.items.clone(),
             subtotal: cart.subtotal(),
             item_count: cart.item_count(),
+            cart_id: cart.cart_id,
+            mid: cart.mid,
+            items: cart.items.into_iter().map(Into::into).collect(),
+            version: cart.version,
+            modified_gmt: cart.modified_gmt,
         }
     }
 }
 
+/// The caller's merchant's cart; any other merchant's is not found
+async fn load(state: &AppState, tenant: &Tenant, cart_id: &str) -> Result<Cart, ApiError> {
+    state
+        .carts
+        .get(cart_id)
+        .await?
+        .filter(|cart| cart.mid == tenant.mid)
+        .ok_or_else(|| ApiError::not_found("Cart"))
+}
+
+/// Bring line prices up to date with the catalog, saving the cart if any changed
+async fn reprice(state: &AppState, cart: Cart) -> Result<Cart, ApiError> {
+    let prices = CartPricing::current_prices(&state.db, &cart).await?;
+    if !cart.clone().apply_prices(&prices) {
+        return Ok(cart);
+    }
+
+    let cart = update_cart(state.carts.as_ref(), &cart.cart_id, |cart| {
+        cart.apply_prices(&prices);
+        Ok(())
+    })
+    .await?;
+    Ok(cart)
+}
+
+/// Hold `quantity` units of `sku` for the cart, all of its line; zero gives the hold back
+async fn hold(state: &AppState, cart: &Cart, sku: &str, quantity: i32) -> Result<(), ApiError> {
+    ReservationService::hold(&state.db, cart.mid, &cart.cart_id, sku, quantity, state.hold_ttl)
+        .await
+        .map_err(|err| match err {
+            InventoryError::UnknownSku(sku) => ApiError::from(CartError::UnknownSku(sku)),
+            err => ApiError::from(err),
+        })?;
+    Ok(())
+}
+
+/// What one unit of `sku` takes from stock: the components of a bundle or
+/// assembly, otherwise the SKU itself
+async fn components(state: &AppState, mid: i32, sku: &str) -> Result<Vec<SkuQty>, ApiError> {
+    let unit = [SkuQty { sku: sku.to_string(), qty: 1 }];
+    Ok(ProductRelationService::expand(&state.db, mid, &unit).await?)
+}
+
+fn is_kit(components: &[SkuQty], sku: &str) -> bool {
+    !matches!(components, [only] if only.sku == sku && only.qty == 1)
+}
+
+/// The cart's lines with `sku` at `quantity`, as stock to be held
+fn lines_with(cart: &Cart, sku: &str, quantity: i32) -> Vec<SkuQty> {
+    let mut lines: Vec<SkuQty> = cart
+        .items
+        .iter()
+        .filter(|item| item.sku != sku)
+        .map(|item| SkuQty { sku: item.sku.clone(), qty: item.quantity })
+        .collect();
+    if quantity > 0 {
+        lines.push(SkuQty { sku: sku.to_string(), qty: quantity });
+    }
+    lines
+}
+
+/// Hold each of `components` at what all of `lines` need of it, so a
+/// component shared with another line's bundle keeps that line's units too
+async fn hold_components(
+    state: &AppState,
+    cart: &Cart,
+    components: &[SkuQty],
+    lines: &[SkuQty],
+) -> Result<(), ApiError> {
+    let needed = ProductRelationService::expand(&state.db, cart.mid, lines).await?;
+    for component in components {
+        let quantity = needed.iter().find(|n| n.sku == component.sku).map_or(0, |n| n.qty);
+        hold(state, cart, &component.sku, quantity).await?;
+    }
+    Ok(())
+}
+
+/// Put the holds back to what `lines` need after the cart change they were
+/// for failed; a failure here is only logged, the original error is what the
+/// caller sees
+async fn restore_holds(state: &AppState, cart: &Cart, components: &[SkuQty], lines: &[SkuQty]) {
+    if let Err(undo) = hold_components(state, cart, components, lines).await {
+        tracing::warn!(cart_id = %cart.cart_id, error = ?undo, "could not roll back holds");
+    }
+}
+
+fn check_quantity(quantity: i32) -> Result<(), ApiError> {
+    if quantity <= 0 {
+        return Err(ApiError::invalid_field("quantity", "must be greater than zero"));
+    }
+    Ok(())
+}
+
 /// Create a new cart
 pub async fn create_cart(
     State(state): State<AppState>,
-) -> Result<Json<CartResponse>, StatusCode> {
-    let mut store = state.cart_store.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let cart_id = store.create_cart();
-    let cart = store
-        .get_cart(&cart_id)
-        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Json(CartResponse::from(cart)))
+    tenant: Tenant,
+    Json(req): Json<CreateCartRequest>,
+) -> Result<Json<CartResponse>, ApiError> {
+    tenant.require_storefront(ApiScope::OrdersWrite)?;
+    let cart = state.carts.create(tenant.scope(req.mid)?).await?;
+    Ok(Json(cart.into()))
 }
 
-/// Get cart by ID
+/// Get cart by ID, re-priced at current catalog prices
 pub async fn get_cart(
     State(state): State<AppState>,
+    tenant: Tenant,
     Path(cart_id): Path<String>,
-) -> Result<Json<CartResponse>, StatusCode> {
-    let store = state.cart_store.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let cart = store.get_cart(&cart_id).ok_or(StatusCode::NOT_FOUND)?;
-    Ok(Json(CartResponse::from(cart)))
+) -> Result<Json<CartResponse>, ApiError> {
+    tenant.require_storefront(ApiScope::OrdersWrite)?;
+    let cart = load(&state, &tenant, &cart_id).await?;
+    let cart = reprice(&state, cart).await?;
+    Ok(Json(cart.into()))
 }
 
-/// Add item to cart
+/// Add item to cart at its catalog price, holding the stock for it
 pub async fn add_item(
     State(state): State<AppState>,
+    tenant: Tenant,
     Path(cart_id): Path<String>,
     Json(req): Json<AddItemRequest>,
-) -> Result<Json<CartResponse>, StatusCode> {
-    let unit_price = req
-        .unit_price
-        .parse::<Decimal>()
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+) -> Result<Json<CartResponse>, ApiError> {
+    tenant.require_storefront(ApiScope::OrdersWrite)?;
+    check_quantity(req.quantity)?;
 
-    let mut store = state.cart_store.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let cart = store
-        .get_cart_mut(&cart_id)
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let cart = load(&state, &tenant, &cart_id).await?;
+    let in_cart = cart.get_item(&req.sku).map_or(0, |i| i.quantity);
+    let quantity = in_cart + req.quantity;
+    let components = components(&state, cart.mid, &req.sku).await?;
+    let line = if is_kit(&components, &req.sku) {
+        CartPricing::quote_kit(&state.db, cart.mid, &req.sku, quantity).await?
+    } else {
+        CartPricing::quote(&state.db, cart.mid, &req.sku, quantity, in_cart).await?
+    };
+    hold_components(&state, &cart, &components, &lines_with(&cart, &line.sku, quantity)).await?;
 
-    cart.add_item(req.sku, req.product_name, req.quantity, unit_price);
+    let added = async {
+        let mut prices = CartPricing::current_prices(&state.db, &cart).await?;
+        prices.insert(line.sku.clone(), line.unit_price);
 
-    Ok(Json(CartResponse::from(&*cart)))
+        let cart = update_cart(state.carts.as_ref(), &cart_id, |cart| {
+            cart.add_item(line.sku.clone(), line.product_name.clone(), req.quantity, line.unit_price);
+            cart.apply_prices(&prices);
+            Ok(())
+        })
+        .await?;
+        Ok::<_, ApiError>(cart)
+    }
+    .await;
+    match added {
+        Ok(cart) => Ok(Json(cart.into())),
+        Err(err) => {
+            // The line never reached the cart, so put the holds back to what it has
+            restore_holds(&state, &cart, &components, &lines_with(&cart, &line.sku, in_cart)).await;
+            Err(err)
+        }
+    }
 }
 
-/// Update item quantity
+/// Update item quantity and the stock held for it
 pub async fn update_quantity(
     State(state): State<AppState>,
+    tenant: Tenant,
     Path((cart_id, sku)): Path<(String, String)>,
     Json(req): Json<UpdateQuantityRequest>,
-) -> Result<Json<CartResponse>, StatusCode> {
-    let mut store = state.cart_store.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let cart = store
-        .get_cart_mut(&cart_id)
-        .ok_or(StatusCode::NOT_FOUND)?;
+) -> Result<Json<CartResponse>, ApiError> {
+    tenant.require_storefront(ApiScope::OrdersWrite)?;
+    let cart = load(&state, &tenant, &cart_id).await?;
+    let mut held = None;
+    if let Some(item) = cart.get_item(&sku).filter(|_| req.quantity > 0) {
+        let components = components(&state, cart.mid, &sku).await?;
+        if is_kit(&components, &sku) {
+            CartPricing::quote_kit(&state.db, cart.mid, &sku, req.quantity).await?;
+        } else {
+            CartPricing::quote(&state.db, cart.mid, &sku, req.quantity, item.quantity).await?;
+        }
+        hold_components(&state, &cart, &components, &lines_with(&cart, &sku, req.quantity)).await?;
+        held = Some((components, item.quantity));
+    }
 
-    if !cart.update_quantity(&sku, req.quantity) {
-        return Err(StatusCode::NOT_FOUND);
+    let updated = update_cart(state.carts.as_ref(), &cart_id, |cart| {
+        if cart.update_quantity(&sku, req.quantity) {
+            Ok(())
+        } else {
+            Err(CartError::ItemNotFound(sku.clone()))
+        }
+    })
+    .await;
+    let cart = match (updated, held) {
+        (Ok(cart), _) => cart,
+        (Err(err), Some((components, held))) => {
+            // The line kept its old quantity, so the holds go back to it
+            restore_holds(&state, &cart, &components, &lines_with(&cart, &sku, held)).await;
+            return Err(err.into());
+        }
+        (Err(err), None) => return Err(err.into()),
+    };
+    if req.quantity <= 0 {
+        let components = components(&state, cart.mid, &sku).await?;
+        hold_components(&state, &cart, &components, &lines_with(&cart, &sku, 0)).await?;
     }
 
-    Ok(Json(CartResponse::from(&*cart)))
+    Ok(Json(cart.into()))
 }
 
-/// Remove item from cart
+/// Remove item from cart, giving back its stock
 pub async fn remove_item(
     State(state): State<AppState>,
+    tenant: Tenant,
     Path((cart_id, sku)): Path<(String, String)>,
-) -> Result<Json<CartResponse>, StatusCode> {
-    let mut store = state.cart_store.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let cart = store
-        .get_cart_mut(&cart_id)
-        .ok_or(StatusCode::NOT_FOUND)?;
-
-    if !cart.remove_item(&sku) {
-        return Err(StatusCode::NOT_FOUND);
-    }
+) -> Result<Json<CartResponse>, ApiError> {
+    tenant.require_storefront(ApiScope::OrdersWrite)?;
+    load(&state, &tenant, &cart_id).await?;
+    let cart = update_cart(state.carts.as_ref(), &cart_id, |cart| {
+        if cart.remove_item(&sku) {
+            Ok(())
+        } else {
+            Err(CartError::ItemNotFound(sku.clone()))
+        }
+    })
+    .await?;
+    let components = components(&state, cart.mid, &sku).await?;
+    hold_components(&state, &cart, &components, &lines_with(&cart, &sku, 0)).await?;
 
-    Ok(Json(CartResponse::from(&*cart)))
+    Ok(Json(cart.into()))
 }
 
-/// Clear all items from cart
+/// Clear all items from cart, giving back its stock
 pub async fn clear_cart(
     State(state): State<AppState>,
+    tenant: Tenant,
     Path(cart_id): Path<String>,
-) -> Result<Json<CartResponse>, StatusCode> {
-    let mut store = state.cart_store.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let cart = store
-        .get_cart_mut(&cart_id)
-        .ok_or(StatusCode::NOT_FOUND)?;
+) -> Result<Json<CartResponse>, ApiError> {
+    tenant.require_storefront(ApiScope::OrdersWrite)?;
+    load(&state, &tenant, &cart_id).await?;
+    let cart = update_cart(state.carts.as_ref(), &cart_id, |cart| {
+        cart.clear();
+        Ok(())
+    })
+    .await?;
+    ReservationService::release(&state.db, cart.mid, &cart.cart_id).await?;
 
-    cart.clear();
-    Ok(Json(CartResponse::from(&*cart)))
+    Ok(Json(cart.into()))
 }
 
-/// Delete cart
+/// Accessories of the products in the cart ("customers also need")
+pub async fn also_need(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(cart_id): Path<String>,
+) -> Result<Json<Vec<ProductResponse>>, ApiError> {
+    tenant.require_storefront(ApiScope::OrdersWrite)?;
+    let cart = load(&state, &tenant, &cart_id).await?;
+    let mut pids: Vec<&str> = Vec::new();
+    for item in &cart.items {
+        let pid = item.sku.split(':').next().unwrap_or_default();
+        if !pids.contains(&pid) {
+            pids.push(pid);
+        }
+    }
+
+    let products = ProductRelationService::also_need(&state.db, cart.mid, &pids, ALSO_NEED_LIMIT).await?;
+    Ok(Json(products.into_iter().map(ProductResponse::from).collect()))
+}
+
+/// Delete cart, giving back its stock
 pub async fn delete_cart(
     State(state): State<AppState>,
+    tenant: Tenant,
     Path(cart_id): Path<String>,
-) -> Result<StatusCode, StatusCode> {
-    let mut store = state.cart_store.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> Result<StatusCode, ApiError> {
+    tenant.require_storefront(ApiScope::OrdersWrite)?;
+    let cart = load(&state, &tenant, &cart_id).await?;
+    if !state.carts.delete(&cart_id).await? {
+        return Err(ApiError::not_found("Cart"));
+    }
+    ReservationService::release(&state.db, cart.mid, &cart.cart_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    if store.delete_cart(&cart_id) {
-        Ok(StatusCode::NO_CONTENT)
-    } else {
-        Err(StatusCode::NOT_FOUND)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenant::Caller;
+    use ::entity::prelude::{InventoryDetail, ProductRelation, Sku};
+    use commercerack_product::RelationKind;
+    use commercerack_cart::{CartRepository, InMemoryCartRepository};
+    use commercerack_inventory::InventoryBasetype;
+    use sea_orm::{DatabaseBackend, DbErr, MockDatabase, MockExecResult, Value};
+    use std::collections::BTreeMap;
+
+    const TENANT: Tenant = Tenant { mid: 1, caller: Caller::Customer(7) };
+
+    fn state() -> AppState {
+        AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection())
+    }
+
+    fn widget() -> Sku {
+        Sku {
+            id: 4,
+            mid: 1,
+            pid: "WIDGET".to_string(),
+            invopts: ":C001".to_string(),
+            grp_parent: String::new(),
+            sku: "WIDGET:C001".to_string(),
+            title: "Blue Widget".to_string(),
+            cost: Decimal::ZERO,
+            price: Decimal::new(999, 2),
+            upc: String::new(),
+            mfgid: String::new(),
+            supplierid: String::new(),
+            prodasm: None,
+            assembly: None,
+            inv_available: 5,
+            qty_onshelf: 5,
+            qty_onorder: 0,
+            qty_needship: 0,
+            qty_markets: 0,
+            qty_legacy: 0,
+            qty_reserved: 0,
+            amz_asin: String::new(),
+            amz_feeds_done: 0,
+            amz_feeds_todo: 0,
+            amz_feeds_sent: 0,
+            amz_feeds_wait: 0,
+            amz_feeds_warn: 0,
+            amz_feeds_error: 0,
+            amz_productdb_gmt: 0,
+            amz_error: String::new(),
+            inv_on_shelf: 5,
+            inv_on_order: 0,
+            inv_is_bo: 0,
+            inv_reorder: 0,
+            inv_is_rsvp: 0,
+            dss_agent: String::new(),
+            dss_run: None,
+            dss_mood: None,
+            dss_config: None,
+        }
+    }
+
+    /// KIT, a bundle of two WIDGET:C001s with no stock of its own
+    fn kit() -> (Sku, ProductRelation) {
+        let sku = Sku {
+            pid: "KIT".to_string(),
+            invopts: String::new(),
+            sku: "KIT".to_string(),
+            title: "Widget Pair".to_string(),
+            price: Decimal::new(1799, 2),
+            inv_available: 0,
+            qty_onshelf: 0,
+            inv_on_shelf: 0,
+            ..widget()
+        };
+        let component = ProductRelation {
+            mid: 1,
+            pid: "KIT".to_string(),
+            child_pid: "WIDGET:C001".to_string(),
+            relation: RelationKind::Bundle.code().to_string(),
+            qty: 2,
+            is_active: 1,
+            list_pos: 0,
+            created_gmt: 0,
+        };
+        (sku, component)
+    }
+
+    fn cart_hold(cart_id: &str, qty: i32) -> InventoryDetail {
+        InventoryDetail {
+            id: 11,
+            uuid: cart_id.to_string(),
+            mid: 1,
+            pid: "WIDGET".to_string(),
+            sku: "WIDGET:C001".to_string(),
+            wms_geo: None,
+            wms_zone: None,
+            wms_pos: None,
+            qty,
+            cost_i: 0,
+            note: String::new(),
+            container: String::new(),
+            origin: String::new(),
+            basetype: Some(InventoryBasetype::Hold),
+            supplier_id: None,
+            supplier_sku: String::new(),
+            market_dst: None,
+            market_refid: String::new(),
+            market_ends_ts: None,
+            market_sold_qty: 0,
+            market_sale_ts: None,
+            preference: 0,
+            created_ts: None,
+            modified_ts: None,
+            modified_by: String::new(),
+            modified_inc: 0,
+            modified_qty_was: 0,
+            verify_ts: None,
+            verify_inc: 0,
+            our_orderid: String::new(),
+            pick_batchid: String::new(),
+            pick_route: None,
+            pick_done_ts: None,
+            grpasm_ref: None,
+            description: String::new(),
+            vendor_status: None,
+            vendor: String::new(),
+            vendor_order_dbid: 0,
+            vendor_sku: String::new(),
+            hold_expires_ts: None,
+        }
+    }
+
+    fn total(basetype: &str, qty: i64) -> BTreeMap<String, Value> {
+        BTreeMap::from([
+            ("basetype".to_string(), Value::from(basetype)),
+            ("qty".to_string(), Value::from(qty)),
+        ])
+    }
+
+    fn ok() -> MockExecResult {
+        MockExecResult { last_insert_id: 0, rows_affected: 1 }
+    }
+
+    /// Every statement run against the mock, in order
+    fn statements(state: AppState) -> Vec<String> {
+        let db = std::sync::Arc::try_unwrap(state.db).ok().unwrap();
+        db.into_transaction_log()
+            .iter()
+            .flat_map(|t| t.statements().to_vec())
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    /// The last statement starting with `prefix`
+    fn last<'a>(statements: &'a [String], prefix: &str) -> &'a str {
+        statements
+            .iter()
+            .rfind(|s| s.starts_with(prefix))
+            .unwrap_or_else(|| panic!("no {} in {:#?}", prefix, statements))
+    }
+
+    async fn new_cart(state: &AppState) -> CartResponse {
+        let Json(cart) = create_cart(State(state.clone()), TENANT, Json(CreateCartRequest { mid: None }))
+            .await
+            .unwrap();
+        cart
+    }
+
+    #[tokio::test]
+    async fn test_cart_changes_bump_version() {
+        // Clearing gives back the cart's holds; it has none
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<InventoryDetail>::new()])
+            .into_connection();
+        let state = AppState::new(db);
+        let cart = new_cart(&state).await;
+        assert_eq!(cart.mid, 1);
+
+        let Json(cleared) = clear_cart(State(state), TENANT, Path(cart.cart_id))
+            .await
+            .unwrap();
+        assert_eq!(cleared.version, cart.version + 1);
+    }
+
+    #[tokio::test]
+    async fn test_add_item_rejects_unknown_sku() {
+        // NOPE has no components, nor a catalog entry
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<ProductRelation>::new()])
+            .append_query_results([Vec::<Sku>::new()])
+            .into_connection();
+        let state = AppState::new(db);
+        let cart = new_cart(&state).await;
+
+        let req = AddItemRequest {
+            sku: "NOPE".to_string(),
+            quantity: 1,
+        };
+        let err = add_item(State(state), TENANT, Path(cart.cart_id), Json(req))
+            .await
+            .err()
+            .expect("unknown SKU should be rejected");
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_add_item_holds_what_the_cart_has() {
+        let state = state();
+        let cart = new_cart(&state).await;
+        // Quote, then hold: lock the SKU, total its stock, find older holds,
+        // insert this cart's hold and extend the rest. An empty cart has
+        // nothing to reprice
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![widget()], vec![widget()]])
+            .append_query_results([vec![total("SIMPLE", 5)]])
+            .append_query_results([Vec::<InventoryDetail>::new(), vec![cart_hold(&cart.cart_id, 2)]])
+            .append_exec_results([ok(), ok(), ok()])
+            .into_connection();
+        let state = AppState { db: db.into(), ..state };
+
+        let req = AddItemRequest {
+            sku: "WIDGET:C001".to_string(),
+            quantity: 2,
+        };
+        let Json(added) = add_item(State(state.clone()), TENANT, Path(cart.cart_id.clone()), Json(req))
+            .await
+            .unwrap();
+        assert_eq!(added.items.len(), 1);
+        assert_eq!(added.items[0].sku, "WIDGET:C001");
+        assert_eq!(added.items[0].quantity, 2);
+        assert_eq!(added.items[0].unit_price, Decimal::new(999, 2));
+
+        let statements = statements(state);
+        let insert = last(&statements, r#"INSERT INTO "inventory_detail""#);
+        assert!(insert.contains(&format!("'{}'", cart.cart_id)), "{}", insert);
+        let counters = last(&statements, r#"UPDATE "sku_lookup""#);
+        assert!(counters.contains(r#""qty_reserved" = 2"#), "{}", counters);
+    }
+
+    #[tokio::test]
+    async fn test_failed_add_gives_back_the_hold() {
+        let state = state();
+        let cart = new_cart(&state).await;
+        update_cart(state.carts.as_ref(), &cart.cart_id, |cart| {
+            cart.add_item("GADGET".to_string(), "Gadget".to_string(), 1, Decimal::ONE);
+            Ok(())
+        })
+        .await
+        .unwrap();
+        // The hold goes through, repricing the cart fails, and the hold is
+        // given back. GADGET has no components each time the cart's lines
+        // are expanded
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![widget()]])
+            .append_query_results([Vec::<ProductRelation>::new()])
+            .append_query_results([vec![widget()]])
+            .append_query_results([vec![total("SIMPLE", 5)]])
+            .append_query_results([Vec::<InventoryDetail>::new(), vec![cart_hold(&cart.cart_id, 2)]])
+            .append_exec_results([ok(), ok(), ok()])
+            .append_query_errors([DbErr::Custom("connection reset".to_string())])
+            .append_query_results([Vec::<ProductRelation>::new()])
+            .append_query_results([vec![widget()]])
+            .append_query_results([vec![total("SIMPLE", 5), total("HOLD", 2)]])
+            .append_query_results([vec![cart_hold(&cart.cart_id, 2)]])
+            .append_exec_results([ok(), ok(), ok(), ok()])
+            .into_connection();
+        let state = AppState { db: db.into(), ..state };
+
+        let req = AddItemRequest {
+            sku: "WIDGET:C001".to_string(),
+            quantity: 2,
+        };
+        let err = add_item(State(state.clone()), TENANT, Path(cart.cart_id.clone()), Json(req))
+            .await
+            .err()
+            .expect("repricing failed");
+        assert_eq!(err.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let unchanged = state.carts.get(&cart.cart_id).await.unwrap().unwrap();
+        assert!(unchanged.get_item("WIDGET:C001").is_none());
+        let statements = statements(state);
+        let release = last(&statements, r#"DELETE FROM "inventory_detail""#);
+        assert!(release.contains(r#""id" IN (11)"#), "{}", release);
+        let counters = last(&statements, r#"UPDATE "sku_lookup""#);
+        assert!(counters.contains(r#""qty_reserved" = 0"#), "{}", counters);
+    }
+
+    #[tokio::test]
+    async fn test_bundle_holds_its_components() {
+        let state = state();
+        let cart = new_cart(&state).await;
+        let (kit, component) = kit();
+        // Expand KIT to find its components, quote it, then expand the cart
+        // and hold the components it needs
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![component.clone()], vec![]])
+            .append_query_results([vec![kit]])
+            .append_query_results([vec![component], vec![]])
+            .append_query_results([vec![widget()]])
+            .append_query_results([vec![total("SIMPLE", 5)]])
+            .append_query_results([Vec::<InventoryDetail>::new(), vec![cart_hold(&cart.cart_id, 2)]])
+            .append_exec_results([ok(), ok(), ok()])
+            .into_connection();
+        let state = AppState { db: db.into(), ..state };
+
+        let req = AddItemRequest {
+            sku: "KIT".to_string(),
+            quantity: 1,
+        };
+        let Json(added) = add_item(State(state.clone()), TENANT, Path(cart.cart_id.clone()), Json(req))
+            .await
+            .unwrap();
+        assert_eq!(added.items[0].sku, "KIT");
+        assert_eq!(added.items[0].quantity, 1);
+        assert_eq!(added.items[0].unit_price, Decimal::new(1799, 2));
+
+        let statements = statements(state);
+        let insert = last(&statements, r#"INSERT INTO "inventory_detail""#);
+        assert!(insert.contains("'WIDGET:C001'"), "{}", insert);
+        let counters = last(&statements, r#"UPDATE "sku_lookup""#);
+        assert!(counters.contains(r#""qty_reserved" = 2"#), "{}", counters);
+        assert!(!statements.iter().any(|s| s.contains("FOR UPDATE") && s.contains("'KIT'")), "{:#?}", statements);
+    }
+
+    /// Carts that can be read but never saved
+    struct UnsavableCarts(InMemoryCartRepository);
+
+    #[axum::async_trait]
+    impl CartRepository for UnsavableCarts {
+        async fn create(&self, mid: i32) -> commercerack_cart::Result<Cart> {
+            self.0.create(mid).await
+        }
+
+        async fn get(&self, cart_id: &str) -> commercerack_cart::Result<Option<Cart>> {
+            self.0.get(cart_id).await
+        }
+
+        async fn save(&self, _cart: Cart) -> commercerack_cart::Result<Cart> {
+            Err(CartError::Db(DbErr::Custom("cart store unavailable".to_string())))
+        }
+
+        async fn delete(&self, cart_id: &str) -> commercerack_cart::Result<bool> {
+            self.0.delete(cart_id).await
+        }
+
+        async fn purge_expired(&self) -> commercerack_cart::Result<u64> {
+            self.0.purge_expired().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_quantity_change_restores_the_hold() {
+        let carts = InMemoryCartRepository::default();
+        let cart = carts.create(1).await.unwrap();
+        let cart = update_cart(&carts, &cart.cart_id, |cart| {
+            cart.add_item("WIDGET:C001".to_string(), "Blue Widget".to_string(), 1, Decimal::new(999, 2));
+            Ok(())
+        })
+        .await
+        .unwrap();
+        // Quote and raise the hold to 3, fail to save the cart, then lower
+        // the hold back to the 1 the line still has
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![widget()], vec![widget()]])
+            .append_query_results([vec![total("SIMPLE", 5), total("HOLD", 1)]])
+            .append_query_results([vec![cart_hold(&cart.cart_id, 1)]])
+            .append_exec_results([ok(), ok(), ok(), ok()])
+            .append_query_results([vec![widget()]])
+            .append_query_results([vec![total("SIMPLE", 5), total("HOLD", 3)]])
+            .append_query_results([vec![cart_hold(&cart.cart_id, 3)]])
+            .append_exec_results([ok(), ok(), ok(), ok()])
+            .into_connection();
+        let state = AppState { carts: std::sync::Arc::new(UnsavableCarts(carts)), ..AppState::new(db) };
+
+        let path = Path((cart.cart_id.clone(), "WIDGET:C001".to_string()));
+        let err = update_quantity(State(state.clone()), TENANT, path, Json(UpdateQuantityRequest { quantity: 3 }))
+            .await
+            .err()
+            .expect("the cart could not be saved");
+        assert_eq!(err.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let unchanged = state.carts.get(&cart.cart_id).await.unwrap().unwrap();
+        assert_eq!(unchanged.get_item("WIDGET:C001").unwrap().quantity, 1);
+        let statements = statements(state);
+        let detail = last(&statements, r#"UPDATE "inventory_detail" SET "qty""#);
+        assert!(detail.contains(r#""qty" = 1"#), "{}", detail);
+        let counters = last(&statements, r#"UPDATE "sku_lookup""#);
+        assert!(counters.contains(r#""qty_reserved" = 1"#), "{}", counters);
+    }
+
+    #[tokio::test]
+    async fn test_missing_cart_and_item_are_404() {
+        let state = state();
+
+        let err = get_cart(State(state.clone()), TENANT, Path("nope".to_string()))
+            .await
+            .err()
+            .expect("cart should be missing");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let cart = new_cart(&state).await;
+        let err = remove_item(State(state), TENANT, Path((cart.cart_id, "SKU404".to_string())))
+            .await
+            .err()
+            .expect("item should be missing");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_other_merchants_cart_is_404() {
+        let state = state();
+        let other = TENANT.mid + 1;
+        let err = create_cart(State(state.clone()), TENANT, Json(CreateCartRequest { mid: Some(other) }))
+            .await
+            .err()
+            .expect("carts are created for the caller's merchant only");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let cart = state.carts.create(other).await.unwrap();
+        let id = || cart.cart_id.clone();
+        let item = || (cart.cart_id.clone(), "WIDGET:C001".to_string());
+        let statuses = [
+            get_cart(State(state.clone()), TENANT, Path(id())).await.err().map(|e| e.status()),
+            add_item(
+                State(state.clone()),
+                TENANT,
+                Path(id()),
+                Json(AddItemRequest { sku: "WIDGET:C001".to_string(), quantity: 1 }),
+            )
+            .await
+            .err()
+            .map(|e| e.status()),
+            update_quantity(State(state.clone()), TENANT, Path(item()), Json(UpdateQuantityRequest { quantity: 2 }))
+                .await
+                .err()
+                .map(|e| e.status()),
+            remove_item(State(state.clone()), TENANT, Path(item())).await.err().map(|e| e.status()),
+            clear_cart(State(state.clone()), TENANT, Path(id())).await.err().map(|e| e.status()),
+            also_need(State(state.clone()), TENANT, Path(id())).await.err().map(|e| e.status()),
+            delete_cart(State(state.clone()), TENANT, Path(id())).await.err().map(|e| e.status()),
+        ];
+        assert!(statuses.iter().all(|s| *s == Some(StatusCode::NOT_FOUND)), "{:?}", statuses);
+
+        // Nothing was changed or held, and the cart is still there
+        let kept = state.carts.get(&cart.cart_id).await.unwrap().expect("cart kept");
+        assert_eq!(kept.version, cart.version);
+        assert!(statements(state).is_empty());
     }
 }
@@ -0,0 +1,150 @@
+use axum::{extract::State, http::StatusCode};
+use commercerack_checkout::{CheckoutRequest, CheckoutService};
+use commercerack_merchant::ApiScope;
+use serde::Deserialize;
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path};
+use crate::routes::orders::OrderResponse;
+use crate::tenant::Tenant;
+use crate::AppState;
+
+/// Totals and prices come from the cart and catalog, never from the client
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CheckoutBody {
+    /// Merchant ID; defaults to the caller's
+    pub mid: Option<i32>,
+    /// Customer to check out as; defaults to the logged-in customer, whose
+    /// own ID is the only one accepted. API keys must give it.
+    pub customer: Option<i32>,
+    /// `customer_addrs.id` to bill; the customer's default address if absent
+    pub bill_address: Option<i32>,
+    /// `customer_addrs.id` to ship to; the customer's default address if absent
+    pub ship_address: Option<i32>,
+    pub sdomain: Option<String>,
+}
+
+/// Check out a cart, placing its order
+///
+/// Safe to retry: checking out the same cart again returns the order it
+/// already produced with `200 OK`.
+#[utoipa::path(
+    post,
+    path = "/api/carts/{cart_id}/checkout",
+    params(("cart_id" = String, Path, description = "Cart ID")),
+    request_body = CheckoutBody,
+    responses(
+        (status = 201, description = "Order placed", body = OrderResponse),
+        (status = 200, description = "Cart was already checked out", body = OrderResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 404, description = "Cart or customer not found, or another customer named", body = ProblemDetails),
+        (status = 409, description = "Prices changed or stock ran out", body = ProblemDetails),
+        (status = 422, description = "Empty cart, unknown address, or an API key gave no customer", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "checkout"
+)]
+pub async fn checkout(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(cart_id): Path<String>,
+    Json(body): Json<CheckoutBody>,
+) -> Result<(StatusCode, Json<OrderResponse>), ApiError> {
+    tenant.require_storefront(ApiScope::OrdersWrite)?;
+    let req = CheckoutRequest {
+        mid: tenant.scope(body.mid)?,
+        cart_id,
+        customer: tenant.customer(body.customer)?,
+        bill_address: body.bill_address,
+        ship_address: body.ship_address,
+        sdomain: body.sdomain,
+    };
+
+    let outcome = CheckoutService::checkout(&state.db, state.carts.as_ref(), &req).await?;
+    let status = if outcome.created { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, Json(outcome.order.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenant::Caller;
+    use ::entity::prelude::Order;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    const TENANT: Tenant = Tenant { mid: 1, caller: Caller::Customer(7) };
+
+    fn body() -> CheckoutBody {
+        CheckoutBody {
+            mid: None,
+            customer: None,
+            bill_address: None,
+            ship_address: None,
+            sdomain: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_cart_is_404() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Order>::new()])
+            .into_connection();
+        let state = AppState::new(db);
+
+        let err = checkout(State(state), TENANT, Path("NOPE".to_string()), Json(body()))
+            .await
+            .err()
+            .expect("missing cart should be rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_empty_cart_is_422() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Order>::new()])
+            .into_connection();
+        let state = AppState::new(db);
+        let cart = state.carts.create(1).await.unwrap();
+
+        let err = checkout(State(state), TENANT, Path(cart.cart_id), Json(body()))
+            .await
+            .err()
+            .expect("empty cart should be rejected");
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_other_merchants_cart_is_404() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Order>::new()])
+            .into_connection();
+        let state = AppState::new(db);
+        let cart = state.carts.create(2).await.unwrap();
+
+        let err = checkout(State(state.clone()), TENANT, Path(cart.cart_id.clone()), Json(body()))
+            .await
+            .err()
+            .expect("another merchant's cart is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let other = CheckoutBody { mid: Some(2), ..body() };
+        let err = checkout(State(state), TENANT, Path(cart.cart_id), Json(other))
+            .await
+            .err()
+            .expect("checking out as another merchant is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_checking_out_as_another_customer_is_404() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let cart = state.carts.create(1).await.unwrap();
+
+        let other = CheckoutBody { customer: Some(8), ..body() };
+        let err = checkout(State(state), TENANT, Path(cart.cart_id), Json(other))
+            .await
+            .err()
+            .expect("checking out as another customer is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+}
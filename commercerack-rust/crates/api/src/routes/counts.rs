@@ -0,0 +1,495 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use commercerack_inventory::{CountEntry, CountScope, CountService, CountSheet, CountStatus, Variance};
+use commercerack_merchant::ApiScope;
+use ::entity::prelude::{InventoryCount, InventoryCountLine};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::routes::warehouse::LocationRequest;
+use crate::tenant::Tenant;
+use crate::AppState;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct StartCountRequest {
+    /// Warehouse of the zone to count
+    pub geo: Option<String>,
+    /// Zone to count; every bin in it gets a line
+    pub zone: Option<String>,
+    /// SKUs to count instead of a zone; their bins and shelf get lines
+    #[serde(default)]
+    pub skus: Vec<String>,
+    #[serde(default)]
+    pub note: String,
+}
+
+impl StartCountRequest {
+    fn scope(self) -> Result<CountScope, ApiError> {
+        match (self.geo, self.zone, self.skus.is_empty()) {
+            (Some(geo), Some(zone), true) => Ok(CountScope::Zone { geo, zone }),
+            (None, None, false) => Ok(CountScope::Skus(self.skus)),
+            _ => Err(ApiError::invalid_field("skus", "give either geo and zone, or skus")),
+        }
+    }
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CountListQuery {
+    /// OPEN, APPROVED or CANCELLED
+    pub status: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CountEntryRequest {
+    pub sku: String,
+    /// Bin counted; the plain shelf when left out
+    pub location: Option<LocationRequest>,
+    pub qty: i32,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct RecordCountsRequest {
+    pub entries: Vec<CountEntryRequest>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ApproveCountRequest {
+    /// Lines to write back; every counted line when empty
+    #[serde(default)]
+    pub line_ids: Vec<i32>,
+}
+
+#[derive(Deserialize, Default, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VarianceQuery {
+    /// json (default) or csv
+    #[serde(default)]
+    #[param(inline)]
+    pub format: ExportFormat,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct CountSummaryResponse {
+    pub id: i32,
+    /// OPEN, APPROVED or CANCELLED
+    pub status: String,
+    pub geo: Option<String>,
+    pub zone: Option<String>,
+    pub skus: Vec<String>,
+    pub note: String,
+    pub created_by: String,
+    pub created_gmt: i32,
+    pub closed_by: String,
+    pub closed_gmt: i32,
+}
+
+impl From<InventoryCount> for CountSummaryResponse {
+    fn from(count: InventoryCount) -> Self {
+        let skus = count
+            .skus
+            .as_array()
+            .map(|skus| skus.iter().filter_map(|s| s.as_str().map(String::from)).collect())
+            .unwrap_or_default();
+        Self {
+            id: count.id,
+            status: count.status,
+            geo: count.wms_geo,
+            zone: count.wms_zone,
+            skus,
+            note: count.note,
+            created_by: count.created_by,
+            created_gmt: count.created_gmt,
+            closed_by: count.closed_by,
+            closed_gmt: count.closed_gmt,
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct CountLineResponse {
+    pub id: i32,
+    pub sku: String,
+    /// Bin as warehouse:zone:position; the plain shelf when null
+    pub location: Option<String>,
+    /// Ledger quantity when counted; hidden until then
+    pub expected: Option<i32>,
+    pub counted: Option<i32>,
+    pub counted_by: String,
+    /// Units written back on approval
+    pub adjusted: Option<i32>,
+}
+
+impl From<InventoryCountLine> for CountLineResponse {
+    fn from(line: InventoryCountLine) -> Self {
+        Self {
+            id: line.id,
+            sku: line.sku,
+            location: Some(line.location).filter(|l| !l.is_empty()),
+            expected: line.counted.map(|_| line.expected),
+            counted: line.counted,
+            counted_by: line.counted_by,
+            adjusted: line.adjusted,
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct CountResponse {
+    #[serde(flatten)]
+    pub count: CountSummaryResponse,
+    /// In location, then SKU order; shelf lines first
+    pub lines: Vec<CountLineResponse>,
+}
+
+impl From<CountSheet> for CountResponse {
+    fn from(sheet: CountSheet) -> Self {
+        Self {
+            count: sheet.count.into(),
+            lines: sheet.lines.into_iter().map(CountLineResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct VarianceResponse {
+    pub line_id: i32,
+    pub sku: String,
+    pub location: Option<String>,
+    pub expected: Option<i32>,
+    pub counted: Option<i32>,
+    /// Counted less expected
+    pub variance: Option<i32>,
+    #[schema(value_type = String, example = "2.50")]
+    pub unit_cost: Decimal,
+    /// Variance at unit cost
+    #[schema(value_type = Option<String>, example = "-5.00")]
+    pub value: Option<Decimal>,
+}
+
+impl From<Variance> for VarianceResponse {
+    fn from(v: Variance) -> Self {
+        let line = CountLineResponse::from(v.line);
+        Self {
+            line_id: line.id,
+            sku: line.sku,
+            location: line.location,
+            expected: line.expected,
+            counted: line.counted,
+            variance: v.variance,
+            unit_cost: v.unit_cost,
+            value: v.value,
+        }
+    }
+}
+
+const CSV_HEADER: &str = "line_id,sku,location,expected,counted,variance,unit_cost,value\r\n";
+
+/// RFC 4180 field, quoted only when it has to be
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn variances_csv(rows: &[VarianceResponse]) -> String {
+    let opt = |v: Option<i32>| v.map(|v| v.to_string()).unwrap_or_default();
+    let mut csv = String::from(CSV_HEADER);
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\r\n",
+            row.line_id,
+            csv_field(&row.sku),
+            csv_field(row.location.as_deref().unwrap_or("")),
+            opt(row.expected),
+            opt(row.counted),
+            opt(row.variance),
+            row.unit_cost,
+            row.value.map(|v| v.to_string()).unwrap_or_default(),
+        ));
+    }
+    csv
+}
+
+/// Start a cycle count of a zone or a set of SKUs
+#[utoipa::path(
+    post,
+    path = "/api/inventory-counts",
+    request_body = StartCountRequest,
+    responses(
+        (status = 201, description = "Count started", body = CountResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU not found", body = ProblemDetails),
+        (status = 422, description = "Neither a zone nor SKUs given, or too many SKUs", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "counts"
+)]
+pub async fn start(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Json(req): Json<StartCountRequest>,
+) -> Result<(StatusCode, Json<CountResponse>), ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let note = req.note.clone();
+    let sheet = CountService::start(&state.db, tenant.mid, &tenant.luser(), req.scope()?, &note).await?;
+    Ok((StatusCode::CREATED, Json(sheet.into())))
+}
+
+/// List cycle counts, newest first
+#[utoipa::path(
+    get,
+    path = "/api/inventory-counts",
+    params(CountListQuery),
+    responses(
+        (status = 200, description = "Counts", body = [CountSummaryResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 422, description = "Unknown status", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "counts"
+)]
+pub async fn list(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Query(query): Query<CountListQuery>,
+) -> Result<Json<Vec<CountSummaryResponse>>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let status = query
+        .status
+        .map(|s| CountStatus::parse(&s).ok_or_else(|| ApiError::invalid_field("status", "must be OPEN, APPROVED or CANCELLED")))
+        .transpose()?;
+    let counts = CountService::list(&state.db, tenant.mid, status).await?;
+    Ok(Json(counts.into_iter().map(CountSummaryResponse::from).collect()))
+}
+
+/// A cycle count and its lines
+#[utoipa::path(
+    get,
+    path = "/api/inventory-counts/{id}",
+    params(("id" = i32, Path, description = "Count ID")),
+    responses(
+        (status = 200, description = "The count", body = CountResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Count not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "counts"
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+) -> Result<Json<CountResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let sheet = CountService::get(&state.db, tenant.mid, id).await?;
+    Ok(Json(sheet.into()))
+}
+
+/// Enter counted quantities
+#[utoipa::path(
+    post,
+    path = "/api/inventory-counts/{id}/entries",
+    params(("id" = i32, Path, description = "Count ID")),
+    request_body = RecordCountsRequest,
+    responses(
+        (status = 200, description = "Lines entered", body = [CountLineResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Count or SKU not found", body = ProblemDetails),
+        (status = 409, description = "Count already closed", body = ProblemDetails),
+        (status = 422, description = "Negative quantity, or a place outside the count", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "counts"
+)]
+pub async fn record(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+    Json(req): Json<RecordCountsRequest>,
+) -> Result<Json<Vec<CountLineResponse>>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    if req.entries.is_empty() {
+        return Err(ApiError::invalid_field("entries", "must hold at least one count"));
+    }
+    let entries = req
+        .entries
+        .into_iter()
+        .map(|e| {
+            Ok(CountEntry {
+                location: e.location.as_ref().map(LocationRequest::location).transpose()?,
+                sku: e.sku,
+                qty: e.qty,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+    let lines = CountService::record(&state.db, tenant.mid, &tenant.luser(), id, &entries).await?;
+    Ok(Json(lines.into_iter().map(CountLineResponse::from).collect()))
+}
+
+/// Variance report of a count, as JSON or CSV
+#[utoipa::path(
+    get,
+    path = "/api/inventory-counts/{id}/variances",
+    params(("id" = i32, Path, description = "Count ID"), VarianceQuery),
+    responses(
+        (status = 200, description = "Each line's variance from the ledger", content(
+            ([VarianceResponse] = "application/json"),
+            (String = "text/csv")
+        )),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Count not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "counts"
+)]
+pub async fn variances(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+    Query(query): Query<VarianceQuery>,
+) -> Result<Response, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let rows: Vec<VarianceResponse> = CountService::variances(&state.db, tenant.mid, id)
+        .await?
+        .into_iter()
+        .map(VarianceResponse::from)
+        .collect();
+
+    if query.format == ExportFormat::Csv {
+        let disposition = format!("attachment; filename=\"count-{}-variances.csv\"", id);
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, disposition),
+            ],
+            variances_csv(&rows),
+        )
+            .into_response());
+    }
+    Ok(Json(rows).into_response())
+}
+
+/// Write a count's variances back to the ledger and close it
+#[utoipa::path(
+    post,
+    path = "/api/inventory-counts/{id}/approve",
+    params(("id" = i32, Path, description = "Count ID")),
+    request_body = ApproveCountRequest,
+    responses(
+        (status = 200, description = "The approved count", body = CountResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Count not found", body = ProblemDetails),
+        (status = 409, description = "Count already closed", body = ProblemDetails),
+        (status = 422, description = "A line isn't on the count or wasn't counted", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "counts"
+)]
+pub async fn approve(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+    Json(req): Json<ApproveCountRequest>,
+) -> Result<Json<CountResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let sheet = CountService::approve(&state.db, tenant.mid, &tenant.luser(), id, &req.line_ids).await?;
+    Ok(Json(sheet.into()))
+}
+
+/// Close a count without changing the ledger
+#[utoipa::path(
+    post,
+    path = "/api/inventory-counts/{id}/cancel",
+    params(("id" = i32, Path, description = "Count ID")),
+    responses(
+        (status = 200, description = "The cancelled count", body = CountSummaryResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Count not found", body = ProblemDetails),
+        (status = 409, description = "Count already closed", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "counts"
+)]
+pub async fn cancel(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+) -> Result<Json<CountSummaryResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let count = CountService::cancel(&state.db, tenant.mid, &tenant.luser(), id).await?;
+    Ok(Json(count.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variances_export_as_csv() {
+        let rows = vec![
+            VarianceResponse {
+                line_id: 3,
+                sku: "WIDGET:C001".to_string(),
+                location: None,
+                expected: Some(5),
+                counted: Some(3),
+                variance: Some(-2),
+                unit_cost: Decimal::new(250, 2),
+                value: Some(Decimal::new(-500, 2)),
+            },
+            VarianceResponse {
+                line_id: 4,
+                sku: "ODD,\"SKU\"".to_string(),
+                location: Some("MN:A:01".to_string()),
+                expected: None,
+                counted: None,
+                variance: None,
+                unit_cost: Decimal::ZERO,
+                value: None,
+            },
+        ];
+
+        let csv = variances_csv(&rows);
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], "line_id,sku,location,expected,counted,variance,unit_cost,value");
+        assert_eq!(lines[1], "3,WIDGET:C001,,5,3,-2,2.50,-5.00");
+        assert_eq!(lines[2], "4,\"ODD,\"\"SKU\"\"\",MN:A:01,,,,0,");
+    }
+
+    #[test]
+    fn test_count_needs_a_zone_or_skus() {
+        let req = |geo: Option<&str>, skus: &[&str]| StartCountRequest {
+            geo: geo.map(String::from),
+            zone: geo.map(|_| "A".to_string()),
+            skus: skus.iter().map(|s| s.to_string()).collect(),
+            note: String::new(),
+        };
+
+        assert!(matches!(req(Some("MN"), &[]).scope(), Ok(CountScope::Zone { .. })));
+        assert!(matches!(req(None, &["WIDGET"]).scope(), Ok(CountScope::Skus(_))));
+        assert_eq!(req(Some("MN"), &["WIDGET"]).scope().unwrap_err().status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(req(None, &[]).scope().unwrap_err().status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -1,11 +1,12 @@
-use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
-};
-use commercerack_customer::CustomerService;
+use axum::{extract::State, http::StatusCode};
+use commercerack_customer::{CustomerFilter, CustomerService, CustomerSort, SchemeCounts};
+use commercerack_db::pagination::{Page, PageRequest, SortDirection};
 use ::entity::prelude::Customer;
 use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::tenant::Tenant;
+use commercerack_merchant::ApiScope;
 use crate::AppState;
 
 #[derive(Deserialize, utoipa::ToSchema)]
@@ -42,17 +43,82 @@ impl From<Customer> for CustomerResponse {
     }
 }
 
+/// Query parameters for `GET /api/customers`
 #[derive(Deserialize, utoipa::IntoParams)]
-pub struct ListQuery {
-    pub mid: i32,
-    #[serde(default = "default_limit")]
-    pub limit: u64,
+#[into_params(parameter_in = Query)]
+pub struct ListCustomersQuery {
+    /// Merchant ID; defaults to the caller's
+    pub mid: Option<i32>,
+    /// Email starts with
+    pub email: Option<String>,
+    /// First or last name contains (case-insensitive)
+    pub name: Option<String>,
+    /// Created at or after (unix seconds)
+    pub created_from: Option<i32>,
+    /// Created at or before (unix seconds)
+    pub created_to: Option<i32>,
+    /// Modified at or after (unix seconds)
+    pub modified_from: Option<i32>,
+    /// Modified at or before (unix seconds)
+    pub modified_to: Option<i32>,
+    pub is_locked: Option<bool>,
+    pub newsletter: Option<bool>,
+    /// Sort column: `cid` (default), `created`, `modified`, `email` or `lastname`
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    pub sort: CustomerSort,
+    /// Sort direction: `asc` (default) or `desc`
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    pub direction: SortDirection,
+    /// Page size (default 20, max 200)
     #[serde(default)]
-    pub offset: u64,
+    pub limit: u64,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+impl ListCustomersQuery {
+    fn into_parts(self) -> (Option<i32>, CustomerFilter, PageRequest<CustomerSort>) {
+        let filter = CustomerFilter {
+            email_prefix: self.email,
+            name: self.name,
+            created_from: self.created_from,
+            created_to: self.created_to,
+            modified_from: self.modified_from,
+            modified_to: self.modified_to,
+            is_locked: self.is_locked,
+            newsletter: self.newsletter,
+        };
+        let page = PageRequest {
+            sort: self.sort,
+            direction: self.direction,
+            limit: self.limit,
+            cursor: self.cursor,
+        };
+        (self.mid, filter, page)
+    }
 }
 
-fn default_limit() -> u64 {
-    20
+/// One page of customers
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct CustomerPage {
+    pub items: Vec<CustomerResponse>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    pub next_cursor: Option<String>,
+    /// Customers matching the filters across all pages
+    pub total: u64,
+}
+
+impl From<Page<Customer>> for CustomerPage {
+    fn from(page: Page<Customer>) -> Self {
+        let page = page.map(CustomerResponse::from);
+        Self {
+            items: page.items,
+            next_cursor: page.next_cursor,
+            total: page.total,
+        }
+    }
 }
 
 /// Create a new customer
@@ -62,25 +128,27 @@ fn default_limit() -> u64 {
     request_body = CreateCustomerRequest,
     responses(
         (status = 201, description = "Customer created successfully", body = CustomerResponse),
-        (status = 500, description = "Internal server error")
+        (status = 409, description = "Email already registered", body = ProblemDetails),
+        (status = 422, description = "Invalid request body", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
     ),
     tag = "customers"
 )]
 pub async fn create(
     State(state): State<AppState>,
     Json(req): Json<CreateCustomerRequest>,
-) -> Result<(StatusCode, Json<CustomerResponse>), StatusCode> {
-    CustomerService::create(
-        &*state.db,
+) -> Result<(StatusCode, Json<CustomerResponse>), ApiError> {
+    let customer = CustomerService::create(
+        &state.db,
         req.mid,
         &req.email,
         &req.firstname,
         &req.lastname,
         req.password.as_deref(),
     )
-    .await
-    .map(|customer| (StatusCode::CREATED, Json(customer.into())))
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(customer.into())))
 }
 
 /// Get a customer by ID
@@ -93,39 +161,203 @@ pub async fn create(
     ),
     responses(
         (status = 200, description = "Customer found", body = CustomerResponse),
-        (status = 404, description = "Customer not found"),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the customers:admin scope", body = ProblemDetails),
+        (status = 404, body = ProblemDetails, description = "Customer not found, or not the logged-in customer"),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
     ),
     tag = "customers"
 )]
 pub async fn get(
     State(state): State<AppState>,
+    tenant: Tenant,
     Path((mid, id)): Path<(i32, i32)>,
-) -> Result<Json<CustomerResponse>, StatusCode> {
-    CustomerService::find_by_id(&*state.db, mid, id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+) -> Result<Json<CustomerResponse>, ApiError> {
+    tenant.require_self_or(id, ApiScope::CustomersAdmin)?;
+    let mid = tenant.scope(Some(mid))?;
+    CustomerService::find_by_id(&state.db, mid, id)
+        .await?
         .map(|customer| Json(customer.into()))
-        .ok_or(StatusCode::NOT_FOUND)
+        .ok_or_else(|| ApiError::not_found("Customer"))
 }
 
-/// List customers (placeholder - not implemented in CustomerService yet)
+/// List and search customers with cursor pagination
+#[utoipa::path(
+    get,
+    path = "/api/customers",
+    params(ListCustomersQuery),
+    responses(
+        (status = 200, description = "Page of matching customers", body = CustomerPage),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the customers:admin scope", body = ProblemDetails),
+        (status = 404, description = "Merchant is not the caller's", body = ProblemDetails),
+        (status = 422, description = "Invalid filter or cursor", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
 pub async fn list(
     State(state): State<AppState>,
-    Query(query): Query<ListQuery>,
-) -> Result<Json<Vec<CustomerResponse>>, StatusCode> {
-    // TODO: Implement list in CustomerService
-    Ok(Json(vec![]))
+    tenant: Tenant,
+    Query(query): Query<ListCustomersQuery>,
+) -> Result<Json<CustomerPage>, ApiError> {
+    let (mid, filter, page) = query.into_parts();
+    tenant.require(ApiScope::CustomersAdmin)?;
+    let mid = tenant.scope(mid)?;
+    let page = CustomerService::search(&state.db, mid, &filter, &page).await?;
+    Ok(Json(page.into()))
+}
+
+/// Customers by how their password is stored
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct PasswordSchemesResponse {
+    pub argon2: u64,
+    pub md5: u64,
+    pub sha1: u64,
+    pub plaintext: u64,
+    /// `passhash` in no recognised format; these customers cannot log in
+    pub unknown: u64,
+    /// No password set
+    pub none: u64,
+    /// Legacy hashes still to be rehashed at the customer's next login
+    pub legacy: u64,
+}
+
+impl From<SchemeCounts> for PasswordSchemesResponse {
+    fn from(counts: SchemeCounts) -> Self {
+        Self {
+            argon2: counts.argon2,
+            md5: counts.md5,
+            sha1: counts.sha1,
+            plaintext: counts.plaintext,
+            unknown: counts.unknown,
+            none: counts.none,
+            legacy: counts.legacy(),
+        }
+    }
+}
+
+/// Count the caller's customers by password hash scheme
+///
+/// Tracks the migration away from legacy hashes.
+#[utoipa::path(
+    get,
+    path = "/api/customers/password-schemes",
+    responses(
+        (status = 200, description = "Customers per scheme", body = PasswordSchemesResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the customers:admin scope", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn password_schemes(
+    State(state): State<AppState>,
+    tenant: Tenant,
+) -> Result<Json<PasswordSchemesResponse>, ApiError> {
+    tenant.require(ApiScope::CustomersAdmin)?;
+    let counts = SchemeCounts::load(&state.db, tenant.mid).await?;
+    Ok(Json(counts.into()))
+}
+
+/// Unlock a customer's login
+///
+/// Clears both an administrative lock and a lockout after failed logins.
+#[utoipa::path(
+    post,
+    path = "/api/customers/{id}/unlock",
+    params(("id" = i32, Path, description = "Customer ID")),
+    responses(
+        (status = 204, description = "Customer unlocked"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the customers:admin scope", body = ProblemDetails),
+        (status = 404, description = "Customer not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn unlock(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    tenant.require(ApiScope::CustomersAdmin)?;
+    CustomerService::unlock(&state.db, tenant.mid, id).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tenant::Caller;
     use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
-    use axum::{
-        body::Body,
-        http::{Request, StatusCode},
-    };
+    use std::collections::BTreeMap;
+
+    fn state_with(db: sea_orm::DatabaseConnection) -> AppState {
+        AppState::new(db)
+    }
+
+    fn customer(cid: i32) -> Customer {
+        Customer {
+            cid,
+            orgid: 0,
+            mid: 1,
+            username: String::new(),
+            prt: 0,
+            email: format!("c{}@example.com", cid),
+            password: String::new(),
+            passhash: String::new(),
+            passsalt: String::new(),
+            firstname: "Test".to_string(),
+            lastname: "User".to_string(),
+            phone: String::new(),
+            created_gmt: 1_700_000_000 + cid,
+            modified_gmt: 1_700_000_000 + cid,
+            lastlogin_gmt: 0,
+            lastorder_gmt: 0,
+            order_count: 0,
+            newsletter: Some(1),
+            optin_gmt: 0,
+            hint_num: 0,
+            hint_answer: String::new(),
+            hint_attempts: 0,
+            ip: 0,
+            origin: 0,
+            schedule: String::new(),
+            has_notes: 0,
+            reward_balance: None,
+            is_affiliate: 0,
+            is_locked: 0,
+            locked_until_gmt: 0,
+        }
+    }
+
+    const TENANT: Tenant = Tenant { mid: 1, caller: Caller::Customer(7) };
+
+    fn admin() -> Tenant {
+        Tenant {
+            mid: 1,
+            caller: Caller::ApiKey { id: 3, scopes: vec![ApiScope::CustomersAdmin] },
+        }
+    }
+
+    fn list_query(limit: u64, cursor: Option<String>) -> ListCustomersQuery {
+        ListCustomersQuery {
+            mid: None,
+            email: None,
+            name: None,
+            created_from: None,
+            created_to: None,
+            modified_from: None,
+            modified_to: None,
+            is_locked: None,
+            newsletter: None,
+            sort: CustomerSort::Cid,
+            direction: SortDirection::Asc,
+            limit,
+            cursor,
+        }
+    }
 
     #[tokio::test]
     async fn test_create_customer() {
@@ -138,12 +370,7 @@ mod tests {
             ])
             .into_connection();
 
-        let state = AppState {
-            db: std::sync::Arc::new(db),
-            cart_store: std::sync::Arc::new(std::sync::Mutex::new(
-                commercerack_cart::CartStore::new()
-            )),
-        };
+        let state = AppState::new(db);
 
         let req = CreateCustomerRequest {
             mid: 1,
@@ -159,4 +386,114 @@ mod tests {
         // We expect an error with mock database, but this validates the code compiles
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_list_returns_page_with_next_cursor() {
+        let count = BTreeMap::from([("num_items".to_string(), sea_orm::Value::BigInt(Some(3)))]);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![count]])
+            .append_query_results([vec![customer(1), customer(2), customer(3)]])
+            .into_connection();
+
+        let Json(page) = list(State(state_with(db)), admin(), Query(list_query(2, None)))
+            .await
+            .unwrap();
+
+        assert_eq!(page.total, 3);
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[1].cid, 2);
+        assert!(page.next_cursor.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_list_rejects_malformed_cursor() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+        let err = list(State(state_with(db)), admin(), Query(list_query(0, Some("not-a-cursor".to_string()))))
+            .await
+            .err()
+            .expect("malformed cursor is rejected");
+
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_cross_tenant_reads_are_404() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Customer>::new()])
+            .into_connection();
+        let state = state_with(db);
+
+        let err = get(State(state.clone()), admin(), Path((2, 7)))
+            .await
+            .err()
+            .expect("another merchant's path is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let query = ListCustomersQuery { mid: Some(2), ..list_query(20, None) };
+        let err = list(State(state.clone()), admin(), Query(query))
+            .await
+            .err()
+            .expect("another merchant's query is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        // A customer of another merchant is not found under the caller's own
+        let err = get(State(state.clone()), admin(), Path((1, 99)))
+            .await
+            .err()
+            .expect("customer 99 belongs to another merchant");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let db = std::sync::Arc::try_unwrap(state.db).ok().unwrap();
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 1, "mismatched merchants never reach the database");
+        assert!(log[0].statements()[0].to_string().contains(r#""customers"."mid" = 1"#));
+    }
+
+    #[tokio::test]
+    async fn test_unlock_needs_an_admin_key() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .into_connection();
+        let state = state_with(db);
+
+        let err = unlock(State(state.clone()), TENANT, Path(7))
+            .await
+            .expect_err("customers cannot unlock accounts");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+
+        let status = unlock(State(state.clone()), admin(), Path(7)).await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let db = std::sync::Arc::try_unwrap(state.db).ok().unwrap();
+        let update = db.into_transaction_log()[0].statements()[0].to_string();
+        assert!(update.contains(r#""is_locked" = 0"#), "{}", update);
+        assert!(update.contains(r#""locked_until_gmt" = 0"#));
+    }
+
+    #[tokio::test]
+    async fn test_customers_only_see_themselves() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer(7)]])
+            .into_connection();
+        let state = state_with(db);
+
+        let Json(own) = get(State(state.clone()), TENANT, Path((1, 7))).await.unwrap();
+        assert_eq!(own.cid, 7);
+
+        let err = get(State(state.clone()), TENANT, Path((1, 8)))
+            .await
+            .err()
+            .expect("customer 8 is not customer 7's to read");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let err = list(State(state.clone()), TENANT, Query(list_query(20, None)))
+            .await
+            .err()
+            .expect("customers can't list the merchant's customers");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+
+        let db = std::sync::Arc::try_unwrap(state.db).ok().unwrap();
+        assert_eq!(db.into_transaction_log().len(), 1, "only the customer's own record was read");
+    }
 }
@@ -0,0 +1,449 @@
+use axum::{extract::State, http::StatusCode};
+use commercerack_inventory::{InventoryBasetype, InventoryService, Location, NewDetail};
+use commercerack_merchant::ApiScope;
+use commercerack_product::SkuService;
+use ::entity::prelude::{InventoryDetail, InventoryLogEntry};
+use sea_orm::{ActiveEnum, Iterable};
+use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::tenant::Tenant;
+use crate::AppState;
+
+/// Most log entries returned at once
+const MAX_LOG: u64 = 500;
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct DetailResponse {
+    pub uuid: String,
+    /// e.g. SIMPLE, WMS, ONORDER, UNPAID, PICK, SHIPPED
+    pub basetype: Option<String>,
+    pub qty: i32,
+    /// Unit cost in integer cents
+    pub cost_i: i32,
+    pub note: String,
+    pub origin: String,
+    pub container: String,
+    pub our_orderid: String,
+    pub supplier_id: Option<String>,
+    pub supplier_sku: String,
+    /// Bin as warehouse:zone:position, for stock kept in bins
+    pub location: Option<String>,
+    pub pick_batchid: String,
+    /// WMS or SIMPLE, for rows on a pick batch
+    pub pick_route: Option<String>,
+    pub modified_by: String,
+    pub modified_inc: i64,
+}
+
+impl From<InventoryDetail> for DetailResponse {
+    fn from(detail: InventoryDetail) -> Self {
+        Self {
+            location: Location::of(&detail).map(|l| l.to_string()),
+            pick_batchid: detail.pick_batchid,
+            pick_route: detail.pick_route.map(|r| r.to_value()),
+            uuid: detail.uuid,
+            basetype: detail.basetype.map(|b| b.to_value()),
+            qty: detail.qty,
+            cost_i: detail.cost_i,
+            note: detail.note,
+            origin: detail.origin,
+            container: detail.container,
+            our_orderid: detail.our_orderid,
+            supplier_id: detail.supplier_id,
+            supplier_sku: detail.supplier_sku,
+            modified_by: detail.modified_by,
+            modified_inc: detail.modified_inc,
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct InventoryResponse {
+    pub sku: String,
+    /// Available to sell; negative when a backorderable SKU is oversold
+    pub available: i32,
+    pub on_shelf: i32,
+    pub on_order: i32,
+    pub reserved: i32,
+    pub need_ship: i32,
+    pub markets: i32,
+    pub details: Vec<DetailResponse>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct LogEntryResponse {
+    pub created_gmt: i32,
+    pub uuid: String,
+    pub action: String,
+    pub qty_before: i32,
+    pub qty: i32,
+    pub luser: String,
+    pub note: String,
+    pub orderid: String,
+}
+
+impl From<InventoryLogEntry> for LogEntryResponse {
+    fn from(entry: InventoryLogEntry) -> Self {
+        Self {
+            created_gmt: entry.created_gmt,
+            uuid: entry.uuid,
+            action: entry.action,
+            qty_before: entry.qty_before,
+            qty: entry.qty,
+            luser: entry.luser,
+            note: entry.note,
+            orderid: entry.orderid,
+        }
+    }
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LogQuery {
+    /// Defaults to 50, at most 500
+    pub limit: Option<u64>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateDetailRequest {
+    #[schema(example = "ONORDER")]
+    pub basetype: String,
+    pub qty: i32,
+    /// Generated when left out
+    #[serde(default)]
+    pub uuid: String,
+    #[serde(default)]
+    pub cost_i: i32,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub origin: String,
+    #[serde(default)]
+    pub container: String,
+    #[serde(default)]
+    pub our_orderid: String,
+    pub supplier_id: Option<String>,
+    #[serde(default)]
+    pub supplier_sku: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SetQuantityRequest {
+    pub qty: i32,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct AdjustRequest {
+    /// Added to the quantity; negative to take away
+    pub delta: i32,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct MoveRequest {
+    #[schema(example = "SIMPLE")]
+    pub basetype: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RemoveQuery {
+    /// Recorded in the inventory log
+    pub note: Option<String>,
+}
+
+fn basetype(value: &str) -> Result<InventoryBasetype, ApiError> {
+    let value = value.trim().to_uppercase();
+    InventoryBasetype::iter()
+        .find(|b| b.to_value() == value)
+        .ok_or_else(|| ApiError::invalid_field("basetype", format!("unknown basetype {}", value)))
+}
+
+/// Stock counters and ledger rows of a SKU
+#[utoipa::path(
+    get,
+    path = "/api/inventory/{sku}",
+    params(("sku" = String, Path, description = "SKU code")),
+    responses(
+        (status = 200, description = "Counters and detail rows", body = InventoryResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the catalog:read scope", body = ProblemDetails),
+        (status = 404, description = "SKU not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "inventory"
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(sku): Path<String>,
+) -> Result<Json<InventoryResponse>, ApiError> {
+    tenant.require(ApiScope::CatalogRead)?;
+    let sku = SkuService::find_by_sku(&state.db, tenant.mid, &sku)
+        .await?
+        .ok_or_else(|| ApiError::not_found("SKU"))?;
+    let details = InventoryService::details(&state.db, tenant.mid, &sku.sku).await?;
+
+    Ok(Json(InventoryResponse {
+        sku: sku.sku,
+        available: sku.inv_available,
+        on_shelf: sku.qty_onshelf,
+        on_order: sku.qty_onorder,
+        reserved: sku.qty_reserved,
+        need_ship: sku.qty_needship,
+        markets: sku.qty_markets,
+        details: details.into_iter().map(DetailResponse::from).collect(),
+    }))
+}
+
+/// Audit log of a SKU's stock changes, newest first
+#[utoipa::path(
+    get,
+    path = "/api/inventory/{sku}/log",
+    params(("sku" = String, Path, description = "SKU code"), LogQuery),
+    responses(
+        (status = 200, description = "Log entries", body = [LogEntryResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "inventory"
+)]
+pub async fn log(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(sku): Path<String>,
+    Query(query): Query<LogQuery>,
+) -> Result<Json<Vec<LogEntryResponse>>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let limit = query.limit.unwrap_or(50).min(MAX_LOG);
+    let entries = InventoryService::history(&state.db, tenant.mid, &sku, limit).await?;
+    Ok(Json(entries.into_iter().map(LogEntryResponse::from).collect()))
+}
+
+/// Add a ledger row to a SKU
+#[utoipa::path(
+    post,
+    path = "/api/inventory/{sku}/details",
+    params(("sku" = String, Path, description = "SKU code")),
+    request_body = CreateDetailRequest,
+    responses(
+        (status = 201, description = "Row added", body = DetailResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU not found", body = ProblemDetails),
+        (status = 409, description = "A row with this uuid exists", body = ProblemDetails),
+        (status = 422, description = "Invalid row", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "inventory"
+)]
+pub async fn create_detail(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(sku): Path<String>,
+    Json(req): Json<CreateDetailRequest>,
+) -> Result<(StatusCode, Json<DetailResponse>), ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let mut input = NewDetail::new(sku, basetype(&req.basetype)?, req.qty);
+    input.uuid = req.uuid;
+    input.cost_i = req.cost_i;
+    input.note = req.note;
+    input.origin = req.origin;
+    input.container = req.container;
+    input.our_orderid = req.our_orderid;
+    input.supplier_id = req.supplier_id;
+    input.supplier_sku = req.supplier_sku;
+
+    let detail = InventoryService::add(&state.db, tenant.mid, &tenant.luser(), input).await?;
+    Ok((StatusCode::CREATED, Json(detail.into())))
+}
+
+/// Set a ledger row's quantity
+#[utoipa::path(
+    put,
+    path = "/api/inventory/{sku}/details/{uuid}",
+    params(
+        ("sku" = String, Path, description = "SKU code"),
+        ("uuid" = String, Path, description = "Row uuid")
+    ),
+    request_body = SetQuantityRequest,
+    responses(
+        (status = 200, description = "Row updated", body = DetailResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU or row not found", body = ProblemDetails),
+        (status = 422, description = "Negative quantity", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "inventory"
+)]
+pub async fn set_quantity(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((sku, uuid)): Path<(String, String)>,
+    Json(req): Json<SetQuantityRequest>,
+) -> Result<Json<DetailResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let detail =
+        InventoryService::set_quantity(&state.db, tenant.mid, &tenant.luser(), &sku, &uuid, req.qty, &req.note)
+            .await?;
+    Ok(Json(detail.into()))
+}
+
+/// Add to or take from a ledger row's quantity
+#[utoipa::path(
+    post,
+    path = "/api/inventory/{sku}/details/{uuid}/adjust",
+    params(
+        ("sku" = String, Path, description = "SKU code"),
+        ("uuid" = String, Path, description = "Row uuid")
+    ),
+    request_body = AdjustRequest,
+    responses(
+        (status = 200, description = "Row updated", body = DetailResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU or row not found", body = ProblemDetails),
+        (status = 422, description = "Quantity would go below zero", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "inventory"
+)]
+pub async fn adjust(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((sku, uuid)): Path<(String, String)>,
+    Json(req): Json<AdjustRequest>,
+) -> Result<Json<DetailResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let detail = InventoryService::adjust(&state.db, tenant.mid, &tenant.luser(), &sku, &uuid, req.delta, &req.note)
+        .await?;
+    Ok(Json(detail.into()))
+}
+
+/// Move a ledger row to another basetype
+#[utoipa::path(
+    post,
+    path = "/api/inventory/{sku}/details/{uuid}/move",
+    params(
+        ("sku" = String, Path, description = "SKU code"),
+        ("uuid" = String, Path, description = "Row uuid")
+    ),
+    request_body = MoveRequest,
+    responses(
+        (status = 200, description = "Row moved", body = DetailResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU or row not found", body = ProblemDetails),
+        (status = 422, description = "Unknown basetype", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "inventory"
+)]
+pub async fn move_detail(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((sku, uuid)): Path<(String, String)>,
+    Json(req): Json<MoveRequest>,
+) -> Result<Json<DetailResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let to = basetype(&req.basetype)?;
+    let detail =
+        InventoryService::set_basetype(&state.db, tenant.mid, &tenant.luser(), &sku, &uuid, to, &req.note).await?;
+    Ok(Json(detail.into()))
+}
+
+/// Delete a ledger row
+#[utoipa::path(
+    delete,
+    path = "/api/inventory/{sku}/details/{uuid}",
+    params(
+        ("sku" = String, Path, description = "SKU code"),
+        ("uuid" = String, Path, description = "Row uuid"),
+        RemoveQuery
+    ),
+    responses(
+        (status = 204, description = "Row deleted"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU or row not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "inventory"
+)]
+pub async fn delete_detail(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((sku, uuid)): Path<(String, String)>,
+    Query(query): Query<RemoveQuery>,
+) -> Result<StatusCode, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let note = query.note.unwrap_or_default();
+    InventoryService::remove(&state.db, tenant.mid, &tenant.luser(), &sku, &uuid, &note).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Set the on-shelf quantity of a SKU kept without bin locations
+#[utoipa::path(
+    put,
+    path = "/api/inventory/{sku}/on-shelf",
+    params(("sku" = String, Path, description = "SKU code")),
+    request_body = SetQuantityRequest,
+    responses(
+        (status = 200, description = "The SKU's SIMPLE row", body = DetailResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU not found", body = ProblemDetails),
+        (status = 422, description = "Negative quantity", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "inventory"
+)]
+pub async fn set_on_shelf(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(sku): Path<String>,
+    Json(req): Json<SetQuantityRequest>,
+) -> Result<Json<DetailResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let detail = InventoryService::set_on_shelf(&state.db, tenant.mid, &tenant.luser(), &sku, req.qty, &req.note)
+        .await?;
+    Ok(Json(detail.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenant::Caller;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    #[test]
+    fn test_basetype_names_are_case_insensitive() {
+        assert_eq!(basetype("onorder").unwrap(), InventoryBasetype::Onorder);
+        assert_eq!(basetype(" WMS ").unwrap(), InventoryBasetype::Wms);
+        assert_eq!(basetype("SHELF").unwrap_err().status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_changes_need_inventory_scope() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let tenant = Tenant {
+            mid: 1,
+            caller: Caller::ApiKey { id: 3, scopes: vec![ApiScope::CatalogRead] },
+        };
+        let req = SetQuantityRequest { qty: 4, note: String::new() };
+
+        let err = set_on_shelf(State(state), tenant, Path("WIDGET".to_string()), Json(req))
+            .await
+            .err()
+            .expect("catalog:read can't change stock");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+    }
+}
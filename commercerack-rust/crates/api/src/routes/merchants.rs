@@ -0,0 +1,113 @@
+use axum::extract::State;
+use chrono::Utc;
+use commercerack_order::counter::{render_orderid, DEFAULT_FORMAT};
+use commercerack_merchant::ApiScope;
+use commercerack_order::OrderIdAllocator;
+use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path};
+use crate::tenant::Tenant;
+use crate::AppState;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct OrderIdFormatRequest {
+    /// Template such as `{YYYY}-{MM}-{N:5}`; empty restores the legacy format
+    #[schema(example = "WEB{YY}{MM}-{N:6}")]
+    pub format: String,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct OrderIdFormatResponse {
+    pub mid: i32,
+    pub format: String,
+    /// What an order ID allocated today with counter 1 looks like
+    pub example: String,
+}
+
+/// Set the template for a merchant's order IDs
+///
+/// Placeholders: `{YYYY}`, `{YY}`, `{MM}`, `{DD}`, `{MID}`, and the counter
+/// as `{N}` or zero-padded as `{N:5}`.
+#[utoipa::path(
+    put,
+    path = "/api/merchants/{mid}/orderid-format",
+    params(("mid" = i32, Path, description = "Merchant ID")),
+    request_body = OrderIdFormatRequest,
+    responses(
+        (status = 200, description = "Format saved", body = OrderIdFormatResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the merchant:settings scope", body = ProblemDetails),
+        (status = 404, description = "Merchant is not the caller's", body = ProblemDetails),
+        (status = 422, description = "Invalid template", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "merchants"
+)]
+pub async fn set_orderid_format(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(mid): Path<i32>,
+    Json(req): Json<OrderIdFormatRequest>,
+) -> Result<Json<OrderIdFormatResponse>, ApiError> {
+    tenant.require(ApiScope::MerchantSettings)?;
+    let mid = tenant.scope(Some(mid))?;
+    OrderIdAllocator::set_format(state.db.as_ref(), mid, &req.format).await?;
+
+    let format = if req.format.is_empty() { DEFAULT_FORMAT.to_string() } else { req.format };
+    let example = render_orderid(&format, Utc::now(), mid, 1)?;
+    Ok(Json(OrderIdFormatResponse { mid, format, example }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenant::Caller;
+    use axum::http::StatusCode;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn admin() -> Tenant {
+        Tenant {
+            mid: 7,
+            caller: Caller::ApiKey { id: 3, scopes: vec![ApiScope::MerchantSettings] },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_format_returns_example() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<::entity::prelude::Zuser>::new()])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .into_connection();
+        let req = OrderIdFormatRequest { format: "WEB-{MID}-{N:4}".to_string() };
+
+        let Json(saved) = set_orderid_format(State(AppState::new(db)), admin(), Path(7), Json(req))
+            .await
+            .unwrap();
+        assert_eq!(saved.example, "WEB-7-0001");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_format_is_422() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let req = OrderIdFormatRequest { format: "{YYYY}-{MM}".to_string() };
+
+        let err = set_orderid_format(State(AppState::new(db)), admin(), Path(7), Json(req))
+            .await
+            .err()
+            .expect("template without a counter should be rejected");
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_customers_cant_change_the_format() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let req = OrderIdFormatRequest { format: "WEB-{N:4}".to_string() };
+        let customer = Tenant { mid: 7, caller: Caller::Customer(1) };
+
+        let err = set_orderid_format(State(AppState::new(db)), customer, Path(7), Json(req))
+            .await
+            .err()
+            .expect("merchant settings take an API key");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+    }
+}
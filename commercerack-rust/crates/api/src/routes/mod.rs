@@ -1,4 +1,15 @@
+pub mod auth;
 pub mod customers;
+pub mod addresses;
+pub mod notes;
 pub mod products;
+pub mod skus;
+pub mod relations;
+pub mod inventory;
+pub mod warehouse;
+pub mod counts;
+pub mod purchasing;
 pub mod orders;
 pub mod cart;
+pub mod checkout;
+pub mod merchants;
@@ -0,0 +1,458 @@
+use axum::{extract::State, http::StatusCode};
+use commercerack_customer::{CustomerError, CustomerService, NewNote, NoteService, NoteType, SessionService};
+use commercerack_merchant::{ApiScope, MerchantService};
+use commercerack_order::OrderService;
+use ::entity::prelude::CustomerNote;
+use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::routes::orders::OrderResponse;
+use crate::tenant::Tenant;
+use crate::AppState;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateNoteRequest {
+    #[serde(rename = "type", default)]
+    #[schema(value_type = String, example = "support")]
+    pub note_type: NoteType,
+    pub note: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdateNoteRequest {
+    #[serde(rename = "type", default)]
+    #[schema(value_type = String, example = "billing")]
+    pub note_type: NoteType,
+    pub note: String,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct NoteResponse {
+    pub id: i32,
+    pub cid: i32,
+    /// Who wrote the note, e.g. `key3` for API key 3
+    pub author: String,
+    /// `general`, `support`, `billing`, `shipping` or `fraud`
+    #[serde(rename = "type")]
+    #[schema(value_type = String, example = "support")]
+    pub note_type: NoteType,
+    pub note: String,
+    pub created_gmt: i32,
+}
+
+impl From<CustomerNote> for NoteResponse {
+    fn from(note: CustomerNote) -> Self {
+        Self {
+            id: note.id,
+            cid: note.cid,
+            note_type: NoteType::of(&note),
+            author: note.luser,
+            note: note.note,
+            created_gmt: note.created_gmt,
+        }
+    }
+}
+
+/// Query parameters for the notes and timeline listings
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    /// Only entries before this time (unix seconds); pass the oldest `at` seen to page back
+    pub before: Option<i32>,
+    /// Page size (default 50, max 200)
+    #[serde(default)]
+    pub limit: u64,
+}
+
+impl HistoryQuery {
+    fn limit(&self) -> u64 {
+        match self.limit {
+            0 => 50,
+            n => n.min(200),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineKind {
+    Note,
+    Order,
+    Login,
+}
+
+/// One entry on a customer's timeline; `note` or `order` is set to match `kind`
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct TimelineEvent {
+    pub kind: TimelineKind,
+    /// When it happened (unix seconds)
+    pub at: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<NoteResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<OrderResponse>,
+}
+
+/// List notes on a customer, newest first
+#[utoipa::path(
+    get,
+    path = "/api/customers/{id}/notes",
+    params(("id" = i32, Path, description = "Customer ID"), HistoryQuery),
+    responses(
+        (status = 200, description = "Notes on the customer", body = [NoteResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the customers:notes scope", body = ProblemDetails),
+        (status = 404, description = "Customer not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn list(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(cid): Path<i32>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<NoteResponse>>, ApiError> {
+    tenant.require(ApiScope::CustomerNotes)?;
+    require_customer(&state, tenant.mid, cid).await?;
+    let notes = NoteService::list(&state.db, tenant.mid, cid, query.limit(), query.before).await?;
+    Ok(Json(notes.into_iter().map(NoteResponse::from).collect()))
+}
+
+/// Add a note to a customer
+#[utoipa::path(
+    post,
+    path = "/api/customers/{id}/notes",
+    params(("id" = i32, Path, description = "Customer ID")),
+    request_body = CreateNoteRequest,
+    responses(
+        (status = 201, description = "Note added", body = NoteResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the customers:notes scope", body = ProblemDetails),
+        (status = 404, description = "Customer not found", body = ProblemDetails),
+        (status = 422, description = "Invalid note", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn create(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(cid): Path<i32>,
+    Json(req): Json<CreateNoteRequest>,
+) -> Result<(StatusCode, Json<NoteResponse>), ApiError> {
+    tenant.require(ApiScope::CustomerNotes)?;
+    let username = MerchantService::find_by_id(&state.db, tenant.mid)
+        .await?
+        .map(|merchant| merchant.username)
+        .unwrap_or_default();
+    let new = NewNote {
+        username,
+        author: tenant.luser(),
+        note_type: req.note_type,
+        note: req.note,
+    };
+
+    let note = NoteService::create(&state.db, tenant.mid, cid, new).await?;
+    Ok((StatusCode::CREATED, Json(note.into())))
+}
+
+/// Change a note's text or type
+#[utoipa::path(
+    put,
+    path = "/api/customers/{id}/notes/{note_id}",
+    params(
+        ("id" = i32, Path, description = "Customer ID"),
+        ("note_id" = i32, Path, description = "Note ID")
+    ),
+    request_body = UpdateNoteRequest,
+    responses(
+        (status = 200, description = "Note updated", body = NoteResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the customers:notes scope", body = ProblemDetails),
+        (status = 404, description = "Note not found", body = ProblemDetails),
+        (status = 422, description = "Invalid note", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn update(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((cid, id)): Path<(i32, i32)>,
+    Json(req): Json<UpdateNoteRequest>,
+) -> Result<Json<NoteResponse>, ApiError> {
+    tenant.require(ApiScope::CustomerNotes)?;
+    let note = NoteService::update(&state.db, tenant.mid, cid, id, req.note_type, &req.note).await?;
+    Ok(Json(note.into()))
+}
+
+/// Delete a note
+#[utoipa::path(
+    delete,
+    path = "/api/customers/{id}/notes/{note_id}",
+    params(
+        ("id" = i32, Path, description = "Customer ID"),
+        ("note_id" = i32, Path, description = "Note ID")
+    ),
+    responses(
+        (status = 204, description = "Note deleted"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the customers:notes scope", body = ProblemDetails),
+        (status = 404, description = "Note not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn delete(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((cid, id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiError> {
+    tenant.require(ApiScope::CustomerNotes)?;
+    NoteService::delete(&state.db, tenant.mid, cid, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A customer's notes, orders and logins interleaved, newest first
+#[utoipa::path(
+    get,
+    path = "/api/customers/{id}/timeline",
+    params(("id" = i32, Path, description = "Customer ID"), HistoryQuery),
+    responses(
+        (status = 200, description = "Timeline entries", body = [TimelineEvent]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the customers:notes scope", body = ProblemDetails),
+        (status = 404, description = "Customer not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "customers"
+)]
+pub async fn timeline(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(cid): Path<i32>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<TimelineEvent>>, ApiError> {
+    tenant.require(ApiScope::CustomerNotes)?;
+    require_customer(&state, tenant.mid, cid).await?;
+
+    // 🤓 each source is fetched `limit` deep, which is enough to fill the
+    // merged page whichever source the newest entries come from
+    let (limit, before) = (query.limit(), query.before);
+    let notes = NoteService::list(&state.db, tenant.mid, cid, limit, before).await?;
+    let orders = OrderService::list_by_customer(&state.db, tenant.mid, cid, limit, before).await?;
+    let logins = SessionService::logins(&state.db, tenant.mid, cid, limit, before).await?;
+
+    let mut events: Vec<TimelineEvent> = notes
+        .into_iter()
+        .map(|note| TimelineEvent {
+            kind: TimelineKind::Note,
+            at: note.created_gmt,
+            note: Some(note.into()),
+            order: None,
+        })
+        .chain(orders.into_iter().map(|order| TimelineEvent {
+            kind: TimelineKind::Order,
+            at: order.created_gmt,
+            note: None,
+            order: Some(order.into()),
+        }))
+        .chain(logins.into_iter().map(|at| TimelineEvent {
+            kind: TimelineKind::Login,
+            at,
+            note: None,
+            order: None,
+        }))
+        .collect();
+    events.sort_by_key(|event| std::cmp::Reverse(event.at));
+    events.truncate(limit as usize);
+
+    Ok(Json(events))
+}
+
+async fn require_customer(state: &AppState, mid: i32, cid: i32) -> Result<(), ApiError> {
+    CustomerService::find_by_id(&state.db, mid, cid)
+        .await?
+        .ok_or(CustomerError::NotFound)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenant::Caller;
+    use ::entity::prelude::{Customer, Order, Zuser};
+    use commercerack_order::OrderPool;
+    use rust_decimal::Decimal;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use std::collections::BTreeMap;
+
+    fn support() -> Tenant {
+        Tenant {
+            mid: 1,
+            caller: Caller::ApiKey { id: 3, scopes: vec![ApiScope::CustomerNotes] },
+        }
+    }
+
+    fn customer() -> Customer {
+        Customer {
+            cid: 7,
+            orgid: 0,
+            mid: 1,
+            username: String::new(),
+            prt: 0,
+            email: "ada@example.com".to_string(),
+            password: String::new(),
+            passhash: String::new(),
+            passsalt: String::new(),
+            firstname: "Ada".to_string(),
+            lastname: "Lovelace".to_string(),
+            phone: String::new(),
+            created_gmt: 0,
+            modified_gmt: 0,
+            lastlogin_gmt: 0,
+            lastorder_gmt: 0,
+            order_count: 0,
+            newsletter: None,
+            optin_gmt: 0,
+            hint_num: 0,
+            hint_answer: String::new(),
+            hint_attempts: 0,
+            ip: 0,
+            origin: 0,
+            schedule: String::new(),
+            has_notes: 1,
+            reward_balance: None,
+            is_affiliate: 0,
+            is_locked: 0,
+            locked_until_gmt: 0,
+        }
+    }
+
+    fn note(id: i32, created_gmt: i32) -> CustomerNote {
+        CustomerNote {
+            id,
+            mid: 1,
+            username: "demo".to_string(),
+            cid: 7,
+            created_gmt,
+            luser: "jane".to_string(),
+            note: "Asked for a callback".to_string(),
+            r#type: "SUP".to_string(),
+        }
+    }
+
+    fn order(created_gmt: i32) -> Order {
+        Order {
+            id: 1,
+            merchant: "demo".to_string(),
+            mid: 1,
+            prt: 0,
+            orderid: "2025-11-00001".to_string(),
+            bs_settlement: 0,
+            v: Some(0),
+            created_gmt,
+            modified_gmt: created_gmt,
+            paid_gmt: 0,
+            paid_txn: String::new(),
+            inv_gmt: 0,
+            shipped_gmt: 0,
+            synced_gmt: 0,
+            customer: 7,
+            pool: OrderPool::Recent,
+            order_bill_name: String::new(),
+            order_bill_email: "ada@example.com".to_string(),
+            order_bill_zone: String::new(),
+            order_bill_phone: String::new(),
+            order_ship_name: String::new(),
+            order_ship_zone: String::new(),
+            review_status: String::new(),
+            order_payment_status: "000".to_string(),
+            order_payment_method: String::new(),
+            order_payment_lookup: String::new(),
+            order_erefid: None,
+            order_total: Decimal::new(1999, 2),
+            order_special: String::new(),
+            ship_method: String::new(),
+            mkt: Some(0),
+            mkt_bitstr: String::new(),
+            flags: 0,
+            items: 1,
+            yaml: String::new(),
+            cartid: None,
+            sdomain: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeline_interleaves_newest_first() {
+        let login = |at: i32| BTreeMap::from([("started_gmt".to_string(), sea_orm::Value::Int(Some(at)))]);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer()]])
+            .append_query_results([vec![note(2, 300), note(1, 100)]])
+            .append_query_results([vec![order(250)]])
+            .append_query_results([vec![login(400), login(50)]])
+            .into_connection();
+        let query = HistoryQuery { before: None, limit: 4 };
+
+        let Json(events) = timeline(State(AppState::new(db)), support(), Path(7), Query(query))
+            .await
+            .unwrap();
+
+        let kinds: Vec<_> = events.iter().map(|e| (e.kind, e.at)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (TimelineKind::Login, 400),
+                (TimelineKind::Note, 300),
+                (TimelineKind::Order, 250),
+                (TimelineKind::Note, 100),
+            ]
+        );
+        assert_eq!(events[1].note.as_ref().unwrap().note_type, NoteType::Support);
+    }
+
+    #[tokio::test]
+    async fn test_customers_cannot_read_notes() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let tenant = Tenant { mid: 1, caller: Caller::Customer(7) };
+        let query = HistoryQuery { before: None, limit: 0 };
+
+        let err = list(State(AppState::new(db)), tenant, Path(7), Query(query))
+            .await
+            .err()
+            .expect("notes are for support staff");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_note_author_is_the_caller() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Zuser>::new()])
+            .append_query_results([vec![customer()]])
+            .append_query_results([vec![CustomerNote { luser: "key3".to_string(), ..note(3, 500) }]])
+            .into_connection();
+        let state = AppState::new(db);
+        let req = CreateNoteRequest {
+            note_type: NoteType::Support,
+            note: "Asked for a callback".to_string(),
+        };
+
+        let (status, Json(created)) = create(State(state.clone()), support(), Path(7), Json(req))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created.author, "key3");
+
+        let db = std::sync::Arc::try_unwrap(state.db).ok().unwrap();
+        let insert = db
+            .into_transaction_log()
+            .iter()
+            .flat_map(|t| t.statements().to_vec())
+            .map(|s| s.to_string())
+            .find(|s| s.starts_with(r#"INSERT INTO "customer_notes""#))
+            .expect("note inserted");
+        assert!(insert.contains("'key3'"), "{}", insert);
+    }
+}
@@ -1,21 +1,27 @@
-use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
-};
-use commercerack_order::OrderService;
+use axum::{extract::State, http::StatusCode};
+use commercerack_customer::CustomerService;
+use commercerack_db::pagination::{Page, PageRequest, SortDirection};
+use commercerack_merchant::ApiScope;
+use commercerack_order::{OrderFilter, OrderPool, OrderService, OrderSort};
 use ::entity::prelude::Order as OrderModel;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::tenant::Tenant;
 use crate::AppState;
 
+/// The order ID is allocated by the server from the merchant's counter
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct CreateOrderRequest {
-    pub mid: i32,
-    pub orderid: String,
+    /// Merchant ID; defaults to the caller's
+    pub mid: Option<i32>,
     pub cartid: String,
+    /// Customer ID, or 0 for a guest order
     pub customer: i32,
-    pub pool: String,
+    /// Initial pool, e.g. `RECENT`
+    #[schema(value_type = String, example = "RECENT")]
+    pub pool: OrderPool,
     pub total: String,
 }
 
@@ -26,11 +32,14 @@ pub struct OrderResponse {
     pub orderid: String,
     pub cartid: String,
     pub customer: i32,
-    pub pool: String,
+    #[schema(value_type = String, example = "RECENT")]
+    pub pool: OrderPool,
     pub total: String,
     pub created_gmt: i32,
     pub paid_gmt: Option<i32>,
     pub shipped_gmt: Option<i32>,
+    pub order_bill_email: String,
+    pub order_payment_status: String,
 }
 
 impl From<OrderModel> for OrderResponse {
@@ -39,28 +48,108 @@ impl From<OrderModel> for OrderResponse {
             id: order.id,
             mid: order.mid,
             orderid: order.orderid,
-            cartid: order.cartid,
+            cartid: order.cartid.unwrap_or_default(),
             customer: order.customer,
             pool: order.pool,
-            total: order.total.to_string(),
+            total: order.order_total.to_string(),
             created_gmt: order.created_gmt,
-            paid_gmt: order.paid_gmt,
-            shipped_gmt: order.shipped_gmt,
+            // 🤓 the legacy schema stores 0 for "not yet"
+            paid_gmt: (order.paid_gmt > 0).then_some(order.paid_gmt),
+            shipped_gmt: (order.shipped_gmt > 0).then_some(order.shipped_gmt),
+            order_bill_email: order.order_bill_email,
+            order_payment_status: order.order_payment_status,
         }
     }
 }
 
+/// Query parameters for `GET /api/orders`
 #[derive(Deserialize, utoipa::IntoParams)]
-pub struct ListQuery {
-    pub mid: i32,
-    #[serde(default = "default_limit")]
-    pub limit: u64,
+#[into_params(parameter_in = Query)]
+pub struct ListOrdersQuery {
+    /// Merchant ID; defaults to the caller's
+    pub mid: Option<i32>,
+    /// Order pool, e.g. `RECENT`
+    #[param(value_type = Option<String>)]
+    pub pool: Option<OrderPool>,
+    /// Customer ID
+    pub customer: Option<i32>,
+    /// Created at or after (unix seconds)
+    pub created_from: Option<i32>,
+    /// Created at or before (unix seconds)
+    pub created_to: Option<i32>,
+    /// Paid at or after (unix seconds)
+    pub paid_from: Option<i32>,
+    /// Paid at or before (unix seconds)
+    pub paid_to: Option<i32>,
+    /// Shipped at or after (unix seconds)
+    pub shipped_from: Option<i32>,
+    /// Shipped at or before (unix seconds)
+    pub shipped_to: Option<i32>,
+    /// Payment status code or prefix, e.g. `0` for every paid status
+    pub payment_status: Option<String>,
+    /// Exact billing email
+    pub bill_email: Option<String>,
+    /// Marketplace bitmask; matches orders on any of the given marketplaces
+    pub marketplace: Option<i32>,
+    /// Sort column: `id` (default), `created` or `orderid`
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    pub sort: OrderSort,
+    /// Sort direction: `asc` (default) or `desc`
     #[serde(default)]
-    pub offset: u64,
+    #[param(value_type = Option<String>)]
+    pub direction: SortDirection,
+    /// Page size (default 20, max 200)
+    #[serde(default)]
+    pub limit: u64,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
 }
 
-fn default_limit() -> u64 {
-    20
+impl ListOrdersQuery {
+    fn into_parts(self) -> (Option<i32>, OrderFilter, PageRequest<OrderSort>) {
+        let filter = OrderFilter {
+            pool: self.pool,
+            customer: self.customer,
+            created_from: self.created_from,
+            created_to: self.created_to,
+            paid_from: self.paid_from,
+            paid_to: self.paid_to,
+            shipped_from: self.shipped_from,
+            shipped_to: self.shipped_to,
+            payment_status: self.payment_status,
+            bill_email: self.bill_email,
+            marketplace: self.marketplace,
+        };
+        let page = PageRequest {
+            sort: self.sort,
+            direction: self.direction,
+            limit: self.limit,
+            cursor: self.cursor,
+        };
+        (self.mid, filter, page)
+    }
+}
+
+/// One page of orders
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct OrderPage {
+    pub items: Vec<OrderResponse>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    pub next_cursor: Option<String>,
+    /// Orders matching the filters across all pages
+    pub total: u64,
+}
+
+impl From<Page<OrderModel>> for OrderPage {
+    fn from(page: Page<OrderModel>) -> Self {
+        let page = page.map(OrderResponse::from);
+        Self {
+            items: page.items,
+            next_cursor: page.next_cursor,
+            total: page.total,
+        }
+    }
 }
 
 /// Create a new order
@@ -70,29 +159,42 @@ fn default_limit() -> u64 {
     request_body = CreateOrderRequest,
     responses(
         (status = 201, description = "Order created successfully", body = OrderResponse),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the orders:write scope", body = ProblemDetails),
+        (status = 404, description = "Merchant or customer is not the caller's", body = ProblemDetails),
+        (status = 409, description = "An order for this cart already exists", body = ProblemDetails),
+        (status = 422, description = "Invalid request body", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
     ),
     tag = "orders"
 )]
 pub async fn create(
     State(state): State<AppState>,
+    tenant: Tenant,
     Json(req): Json<CreateOrderRequest>,
-) -> Result<(StatusCode, Json<OrderResponse>), StatusCode> {
+) -> Result<(StatusCode, Json<OrderResponse>), ApiError> {
+    tenant.require(ApiScope::OrdersWrite)?;
+    let mid = tenant.scope(req.mid)?;
     let total = req.total.parse::<Decimal>()
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+        .map_err(|_| ApiError::invalid_field("total", "must be a decimal number"))?;
+
+    if req.customer != 0 {
+        CustomerService::find_by_id(&state.db, mid, req.customer)
+            .await?
+            .ok_or_else(|| ApiError::not_found("Customer"))?;
+    }
 
-    OrderService::create(
-        &*state.db,
-        req.mid,
-        &req.orderid,
+    let order = OrderService::create(
+        &state.db,
+        mid,
         &req.cartid,
         req.customer,
-        &req.pool,
+        req.pool,
         total,
     )
-    .await
-    .map(|order| (StatusCode::CREATED, Json(order.into())))
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(order.into())))
 }
 
 /// Get an order by ID
@@ -105,65 +207,259 @@ pub async fn create(
     ),
     responses(
         (status = 200, description = "Order found", body = OrderResponse),
-        (status = 404, description = "Order not found"),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the orders:read or orders:write scope", body = ProblemDetails),
+        (status = 404, body = ProblemDetails, description = "Order not found"),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
     ),
     tag = "orders"
 )]
 pub async fn get(
     State(state): State<AppState>,
+    tenant: Tenant,
     Path((mid, id)): Path<(i32, i32)>,
-) -> Result<Json<OrderResponse>, StatusCode> {
-    OrderService::find_by_id(&*state.db, mid, id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+) -> Result<Json<OrderResponse>, ApiError> {
+    tenant.require_any(&[ApiScope::OrdersRead, ApiScope::OrdersWrite])?;
+    let mid = tenant.scope(Some(mid))?;
+    OrderService::find_by_id(&state.db, mid, id)
+        .await?
         .map(|order| Json(order.into()))
-        .ok_or(StatusCode::NOT_FOUND)
+        .ok_or_else(|| ApiError::not_found("Order"))
 }
 
-/// List orders (placeholder - needs implementation in OrderService)
+/// List orders by pool, customer, dates, payment status and marketplace
+#[utoipa::path(
+    get,
+    path = "/api/orders",
+    params(ListOrdersQuery),
+    responses(
+        (status = 200, description = "Page of matching orders", body = OrderPage),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the orders:read or orders:write scope", body = ProblemDetails),
+        (status = 404, description = "Merchant is not the caller's", body = ProblemDetails),
+        (status = 422, description = "Invalid filter or cursor", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "orders"
+)]
 pub async fn list(
     State(state): State<AppState>,
-    Query(query): Query<ListQuery>,
-) -> Result<Json<Vec<OrderResponse>>, StatusCode> {
-    // TODO: Implement general list in OrderService
-    Ok(Json(vec![]))
+    tenant: Tenant,
+    Query(query): Query<ListOrdersQuery>,
+) -> Result<Json<OrderPage>, ApiError> {
+    let (mid, filter, page) = query.into_parts();
+    tenant.require_any(&[ApiScope::OrdersRead, ApiScope::OrdersWrite])?;
+    let mid = tenant.scope(mid)?;
+    let page = OrderService::search(&state.db, mid, &filter, &page).await?;
+    Ok(Json(page.into()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use crate::tenant::Caller;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    fn tenant() -> Tenant {
+        Tenant {
+            mid: 1,
+            caller: Caller::ApiKey { id: 3, scopes: vec![ApiScope::OrdersWrite] },
+        }
+    }
+
+    fn order(id: i32) -> OrderModel {
+        OrderModel {
+            id,
+            merchant: "demo".to_string(),
+            mid: 1,
+            prt: 0,
+            orderid: format!("2025-01-{:05}", id),
+            bs_settlement: 0,
+            v: Some(0),
+            created_gmt: 1_700_000_000 + id,
+            modified_gmt: 1_700_000_000 + id,
+            paid_gmt: 0,
+            paid_txn: String::new(),
+            inv_gmt: 0,
+            shipped_gmt: 0,
+            synced_gmt: 0,
+            customer: 7,
+            pool: OrderPool::Recent,
+            order_bill_name: String::new(),
+            order_bill_email: "buyer@example.com".to_string(),
+            order_bill_zone: String::new(),
+            order_bill_phone: String::new(),
+            order_ship_name: String::new(),
+            order_ship_zone: String::new(),
+            review_status: String::new(),
+            order_payment_status: "000".to_string(),
+            order_payment_method: String::new(),
+            order_payment_lookup: String::new(),
+            order_erefid: None,
+            order_total: Decimal::new(1999, 2),
+            order_special: String::new(),
+            ship_method: String::new(),
+            mkt: Some(0),
+            mkt_bitstr: String::new(),
+            flags: 0,
+            items: 1,
+            yaml: String::new(),
+            cartid: Some(format!("CART{}", id)),
+            sdomain: None,
+        }
+    }
 
     #[tokio::test]
-    async fn test_create_order() {
+    async fn test_create_order_allocates_orderid() {
+        let counter = ::entity::prelude::OrderCounter {
+            mid: 1,
+            merchant: "demo".to_string(),
+            counter: Some(1),
+            last_pid: Some(0),
+            last_server: String::new(),
+            orderid_format: String::new(),
+        };
         let db = MockDatabase::new(DatabaseBackend::Postgres)
-            .append_exec_results([
-                MockExecResult {
-                    last_insert_id: 1,
-                    rows_affected: 1,
-                },
-            ])
+            .append_query_results([vec![counter]])
+            .append_query_results([vec![order(1)]])
             .into_connection();
 
-        let state = AppState {
-            db: std::sync::Arc::new(db),
-            cart_store: std::sync::Arc::new(std::sync::Mutex::new(
-                commercerack_cart::CartStore::new()
-            )),
-        };
+        let state = AppState::new(db);
 
         let req = CreateOrderRequest {
-            mid: 1,
-            orderid: "ORD001".to_string(),
+            mid: None,
             cartid: "CART001".to_string(),
-            customer: 1,
-            pool: "RECENT".to_string(),
+            customer: 0,
+            pool: OrderPool::Recent,
             total: "199.99".to_string(),
         };
 
-        // This will fail in mock but validates the structure
-        let result = create(State(state), Json(req)).await;
-        assert!(result.is_err());
+        let (status, Json(order)) = create(State(state.clone()), tenant(), Json(req)).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(order.orderid, "2025-01-00001");
+
+        // Counter bump and insert commit together
+        let db = Arc::try_unwrap(state.db).ok().unwrap();
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 1);
+        let statements = log[0].statements();
+        assert!(statements[1].sql.starts_with(r#"UPDATE "order_counters""#));
+        assert!(statements[2].sql.starts_with(r#"INSERT INTO "orders""#));
+    }
+
+    #[tokio::test]
+    async fn test_list_last_page_has_no_cursor() {
+        let count = BTreeMap::from([("num_items".to_string(), sea_orm::Value::BigInt(Some(2)))]);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![count]])
+            .append_query_results([vec![order(1), order(2)]])
+            .into_connection();
+
+        let state = AppState::new(db);
+        let query = ListOrdersQuery {
+            mid: None,
+            pool: Some(OrderPool::Recent),
+            customer: Some(7),
+            created_from: None,
+            created_to: None,
+            paid_from: None,
+            paid_to: None,
+            shipped_from: None,
+            shipped_to: None,
+            payment_status: None,
+            bill_email: None,
+            marketplace: None,
+            sort: OrderSort::Created,
+            direction: SortDirection::Desc,
+            limit: 20,
+            cursor: None,
+        };
+
+        let Json(page) = list(State(state), tenant(), Query(query)).await.unwrap();
+
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items.len(), 2);
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cross_tenant_reads_and_writes_are_404() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<::entity::prelude::Customer>::new()])
+            .into_connection();
+        let state = AppState::new(db);
+
+        let err = get(State(state.clone()), tenant(), Path((2, 1)))
+            .await
+            .err()
+            .expect("another merchant's order is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let req = |mid, customer| CreateOrderRequest {
+            mid,
+            cartid: "CART002".to_string(),
+            customer,
+            pool: OrderPool::Recent,
+            total: "5.00".to_string(),
+        };
+        let err = create(State(state.clone()), tenant(), Json(req(Some(2), 0)))
+            .await
+            .err()
+            .expect("writing into another merchant is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let err = create(State(state.clone()), tenant(), Json(req(None, 99)))
+            .await
+            .err()
+            .expect("another merchant's customer is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        // Only the customer lookup, scoped to the caller, reached the database
+        let db = Arc::try_unwrap(state.db).ok().unwrap();
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 1);
+        assert!(log[0].statements()[0].to_string().contains(r#""customers"."mid" = 1"#));
+    }
+
+    #[tokio::test]
+    async fn test_customers_cant_read_merchant_orders() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let customer = Tenant { mid: 1, caller: Caller::Customer(7) };
+
+        let err = get(State(state), customer, Path((1, 1)))
+            .await
+            .err()
+            .expect("a shopper's token isn't an admin credential");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_keys_can_look_orders_up() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![order(1)]])
+            .into_connection();
+        let state = AppState::new(db);
+        let reader = || Tenant {
+            mid: 1,
+            caller: Caller::ApiKey { id: 4, scopes: vec![ApiScope::OrdersRead] },
+        };
+
+        let Json(found) = get(State(state.clone()), reader(), Path((1, 1))).await.unwrap();
+        assert_eq!(found.id, 1);
+
+        let req = CreateOrderRequest {
+            mid: None,
+            cartid: "CART003".to_string(),
+            customer: 7,
+            pool: OrderPool::Recent,
+            total: "5.00".to_string(),
+        };
+        let err = create(State(state), reader(), Json(req))
+            .await
+            .err()
+            .expect("orders:read does not place orders");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
     }
 }
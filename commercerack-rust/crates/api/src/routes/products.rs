@@ -1,17 +1,18 @@
-use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::State, http::StatusCode};
 use commercerack_product::ProductService;
+use commercerack_merchant::ApiScope;
 use ::entity::prelude::Product;
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::tenant::Tenant;
 use crate::AppState;
 
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct CreateProductRequest {
-    pub mid: i32,
+    /// Merchant ID; defaults to the caller's
+    pub mid: Option<i32>,
     pub merchant: String,
     pub product_id: String,
     pub product_name: String,
@@ -29,8 +30,8 @@ pub struct ProductResponse {
     pub ts: i32,
     pub product_name: String,
     pub category: String,
-    pub base_price: String,
-    pub base_cost: String,
+    pub base_price: Option<String>,
+    pub base_cost: Option<String>,
     pub supplier: String,
     pub supplier_id: String,
     pub upc: String,
@@ -48,20 +49,21 @@ impl From<Product> for ProductResponse {
             ts: product.ts,
             product_name: product.product_name,
             category: product.category,
-            base_price: product.base_price.to_string(),
-            base_cost: product.base_cost.to_string(),
-            supplier: product.supplier,
-            supplier_id: product.supplier_id,
+            base_price: product.base_price.map(|p| p.to_string()),
+            base_cost: product.base_cost.map(|c| c.to_string()),
+            supplier: product.supplier.unwrap_or_default(),
+            supplier_id: product.supplier_id.unwrap_or_default(),
             upc: product.upc,
             created_gmt: product.created_gmt,
-            lastsold_gmt: product.lastsold_gmt,
+            lastsold_gmt: (product.lastsold_gmt > 0).then_some(product.lastsold_gmt),
         }
     }
 }
 
 #[derive(Deserialize, utoipa::IntoParams)]
 pub struct ListQuery {
-    pub mid: i32,
+    /// Merchant ID; defaults to the caller's
+    pub mid: Option<i32>,
     #[serde(default = "default_limit")]
     pub limit: u64,
     #[serde(default)]
@@ -79,22 +81,30 @@ fn default_limit() -> u64 {
     request_body = CreateProductRequest,
     responses(
         (status = 201, description = "Product created successfully", body = ProductResponse),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the catalog:write scope", body = ProblemDetails),
+        (status = 404, description = "Merchant is not the caller's", body = ProblemDetails),
+        (status = 409, description = "Product ID already exists", body = ProblemDetails),
+        (status = 422, description = "Invalid request body", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
     ),
     tag = "products"
 )]
 pub async fn create(
     State(state): State<AppState>,
+    tenant: Tenant,
     Json(req): Json<CreateProductRequest>,
-) -> Result<(StatusCode, Json<ProductResponse>), StatusCode> {
+) -> Result<(StatusCode, Json<ProductResponse>), ApiError> {
+    tenant.require(ApiScope::CatalogWrite)?;
+    let mid = tenant.scope(req.mid)?;
     let base_price = req.base_price.parse::<Decimal>()
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+        .map_err(|_| ApiError::invalid_field("base_price", "must be a decimal number"))?;
     let base_cost = req.base_cost.parse::<Decimal>()
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+        .map_err(|_| ApiError::invalid_field("base_cost", "must be a decimal number"))?;
 
-    ProductService::create(
-        &*state.db,
-        req.mid,
+    let product = ProductService::create(
+        &state.db,
+        mid,
         &req.merchant,
         &req.product_id,
         &req.product_name,
@@ -102,9 +112,9 @@ pub async fn create(
         base_price,
         base_cost,
     )
-    .await
-    .map(|product| (StatusCode::CREATED, Json(product.into())))
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(product.into())))
 }
 
 /// Get a product by ID
@@ -117,38 +127,51 @@ pub async fn create(
     ),
     responses(
         (status = 200, description = "Product found", body = ProductResponse),
-        (status = 404, description = "Product not found"),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 404, body = ProblemDetails, description = "Product not found"),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
     ),
     tag = "products"
 )]
 pub async fn get(
     State(state): State<AppState>,
+    tenant: Tenant,
     Path((mid, id)): Path<(i32, i32)>,
-) -> Result<Json<ProductResponse>, StatusCode> {
-    ProductService::find_by_id(&*state.db, mid, id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+) -> Result<Json<ProductResponse>, ApiError> {
+    tenant.require_storefront(ApiScope::CatalogRead)?;
+    let mid = tenant.scope(Some(mid))?;
+    ProductService::find_by_id(&state.db, mid, id)
+        .await?
         .map(|product| Json(product.into()))
-        .ok_or(StatusCode::NOT_FOUND)
+        .ok_or_else(|| ApiError::not_found("Product"))
 }
 
 /// List products
 pub async fn list(
     State(state): State<AppState>,
+    tenant: Tenant,
     Query(query): Query<ListQuery>,
-) -> Result<Json<Vec<ProductResponse>>, StatusCode> {
-    ProductService::list(&*state.db, query.mid, query.limit, query.offset)
-        .await
-        .map(|products| Json(products.into_iter().map(|p| p.into()).collect()))
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+) -> Result<Json<Vec<ProductResponse>>, ApiError> {
+    tenant.require_storefront(ApiScope::CatalogRead)?;
+    let mid = tenant.scope(query.mid)?;
+    let products = ProductService::list(&state.db, mid, query.limit, query.offset).await?;
+    Ok(Json(products.into_iter().map(|p| p.into()).collect()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tenant::Caller;
     use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
 
+    fn catalog_writer() -> Tenant {
+        Tenant {
+            mid: 1,
+            caller: Caller::ApiKey { id: 3, scopes: vec![ApiScope::CatalogWrite] },
+        }
+    }
+
     #[tokio::test]
     async fn test_create_product() {
         let db = MockDatabase::new(DatabaseBackend::Postgres)
@@ -160,15 +183,10 @@ mod tests {
             ])
             .into_connection();
 
-        let state = AppState {
-            db: std::sync::Arc::new(db),
-            cart_store: std::sync::Arc::new(std::sync::Mutex::new(
-                commercerack_cart::CartStore::new()
-            )),
-        };
+        let state = AppState::new(db);
 
         let req = CreateProductRequest {
-            mid: 1,
+            mid: None,
             merchant: "testmerchant".to_string(),
             product_id: "PROD001".to_string(),
             product_name: "Test Product".to_string(),
@@ -178,7 +196,61 @@ mod tests {
         };
 
         // This will fail in mock but validates the structure
-        let result = create(State(state), Json(req)).await;
+        let result = create(State(state), catalog_writer(), Json(req)).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_customers_cant_create_products() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let req = CreateProductRequest {
+            mid: None,
+            merchant: "testmerchant".to_string(),
+            product_id: "PROD001".to_string(),
+            product_name: "Test Product".to_string(),
+            category: String::new(),
+            base_price: "9.99".to_string(),
+            base_cost: "4.99".to_string(),
+        };
+
+        let err = create(State(state), Tenant { mid: 1, caller: Caller::Customer(7) }, Json(req))
+            .await
+            .err()
+            .expect("catalog writes take an API key");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_cross_tenant_reads_and_writes_are_404() {
+        let tenant = Tenant { mid: 1, caller: Caller::Customer(7) };
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+
+        let err = get(State(state.clone()), tenant.clone(), Path((2, 1)))
+            .await
+            .err()
+            .expect("another merchant's product is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let query = ListQuery { mid: Some(2), limit: 20, offset: 0 };
+        let err = list(State(state.clone()), tenant.clone(), Query(query))
+            .await
+            .err()
+            .expect("another merchant's catalog is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+
+        let req = CreateProductRequest {
+            mid: Some(2),
+            merchant: "other".to_string(),
+            product_id: "PROD002".to_string(),
+            product_name: "Other Product".to_string(),
+            category: String::new(),
+            base_price: "1.00".to_string(),
+            base_cost: "0.50".to_string(),
+        };
+        let err = create(State(state), catalog_writer(), Json(req))
+            .await
+            .err()
+            .expect("writing into another merchant is rejected");
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
 }
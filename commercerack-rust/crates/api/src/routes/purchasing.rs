@@ -0,0 +1,577 @@
+use axum::{extract::State, http::StatusCode};
+use commercerack_inventory::{
+    InventoryVendorStatus, OrderLine, PurchaseOrderService, PurchaseOrderSheet, Receipt, SupplierInput, SupplierService,
+};
+use commercerack_merchant::ApiScope;
+use ::entity::prelude::{PurchaseOrder, PurchaseOrderLine, Supplier};
+use sea_orm::{ActiveEnum, Iterable};
+use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::routes::warehouse::LocationRequest;
+use crate::tenant::Tenant;
+use crate::AppState;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SupplierRequest {
+    pub name: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub phone: String,
+    #[serde(default)]
+    pub note: String,
+    /// Days from ordering to delivery
+    #[serde(default)]
+    pub lead_days: i32,
+}
+
+impl From<SupplierRequest> for SupplierInput {
+    fn from(req: SupplierRequest) -> Self {
+        Self {
+            name: req.name,
+            email: req.email,
+            phone: req.phone,
+            note: req.note,
+            lead_days: req.lead_days,
+        }
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateSupplierRequest {
+    /// Up to 6 characters, stored upper-case
+    #[schema(example = "ACME")]
+    pub code: String,
+    #[serde(flatten)]
+    pub supplier: SupplierRequest,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct SupplierResponse {
+    pub code: String,
+    pub name: String,
+    pub email: String,
+    pub phone: String,
+    pub note: String,
+    pub lead_days: i32,
+    pub created_gmt: i32,
+    pub modified_gmt: i32,
+}
+
+impl From<Supplier> for SupplierResponse {
+    fn from(supplier: Supplier) -> Self {
+        Self {
+            code: supplier.code,
+            name: supplier.name,
+            email: supplier.email,
+            phone: supplier.phone,
+            note: supplier.note,
+            lead_days: supplier.lead_days,
+            created_gmt: supplier.created_gmt,
+            modified_gmt: supplier.modified_gmt,
+        }
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct OrderLineRequest {
+    pub sku: String,
+    pub qty: i32,
+    /// Unit cost in integer cents
+    #[serde(default)]
+    pub cost_i: i32,
+    /// Supplier's part number; the SKU's supplier id when left out
+    pub vendor_sku: Option<String>,
+}
+
+impl From<OrderLineRequest> for OrderLine {
+    fn from(req: OrderLineRequest) -> Self {
+        Self {
+            sku: req.sku,
+            qty: req.qty,
+            cost_i: req.cost_i,
+            vendor_sku: req.vendor_sku,
+        }
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreatePurchaseOrderRequest {
+    /// Supplier code
+    pub supplier: String,
+    #[serde(default)]
+    pub note: String,
+    pub lines: Vec<OrderLineRequest>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SetLinesRequest {
+    pub lines: Vec<OrderLineRequest>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ConfirmOrderRequest {
+    /// Supplier's order number, up to 30 characters
+    #[serde(default)]
+    pub reference: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ReceiptRequest {
+    pub sku: String,
+    pub qty: i32,
+    /// Bin to put the units in; the plain shelf when left out
+    pub location: Option<LocationRequest>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ReceiveRequest {
+    pub receipts: Vec<ReceiptRequest>,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PurchaseOrderListQuery {
+    /// NEW, ONORDER, CONFIRMED, RECEIVED or CANCELLED
+    pub status: Option<String>,
+    /// Supplier code
+    pub supplier: Option<String>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct PurchaseOrderSummaryResponse {
+    pub id: i32,
+    /// Supplier code
+    pub supplier: String,
+    /// NEW, ONORDER, CONFIRMED, RECEIVED or CANCELLED
+    pub status: String,
+    pub reference: String,
+    pub note: String,
+    pub created_by: String,
+    pub created_gmt: i32,
+    pub ordered_gmt: i32,
+    pub confirmed_gmt: i32,
+    pub closed_gmt: i32,
+}
+
+impl PurchaseOrderSummaryResponse {
+    fn new(order: PurchaseOrder, supplier: &Supplier) -> Self {
+        Self {
+            id: order.id,
+            supplier: supplier.code.clone(),
+            status: order.status.to_value(),
+            reference: order.reference,
+            note: order.note,
+            created_by: order.created_by,
+            created_gmt: order.created_gmt,
+            ordered_gmt: order.ordered_gmt,
+            confirmed_gmt: order.confirmed_gmt,
+            closed_gmt: order.closed_gmt,
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct PurchaseOrderLineResponse {
+    pub sku: String,
+    pub vendor_sku: String,
+    pub qty: i32,
+    pub qty_received: i32,
+    /// Unit cost in integer cents
+    pub cost_i: i32,
+    /// Inventory record holding the units on order, once submitted
+    pub uuid: Option<String>,
+}
+
+impl From<PurchaseOrderLine> for PurchaseOrderLineResponse {
+    fn from(line: PurchaseOrderLine) -> Self {
+        Self {
+            sku: line.sku,
+            vendor_sku: line.vendor_sku,
+            qty: line.qty,
+            qty_received: line.qty_received,
+            cost_i: line.cost_i,
+            uuid: Some(line.uuid).filter(|u| !u.is_empty()),
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct PurchaseOrderResponse {
+    #[serde(flatten)]
+    pub order: PurchaseOrderSummaryResponse,
+    /// In SKU order
+    pub lines: Vec<PurchaseOrderLineResponse>,
+}
+
+impl From<PurchaseOrderSheet> for PurchaseOrderResponse {
+    fn from(sheet: PurchaseOrderSheet) -> Self {
+        Self {
+            order: PurchaseOrderSummaryResponse::new(sheet.order, &sheet.supplier),
+            lines: sheet.lines.into_iter().map(PurchaseOrderLineResponse::from).collect(),
+        }
+    }
+}
+
+fn vendor_status(value: &str) -> Result<InventoryVendorStatus, ApiError> {
+    let value = value.trim().to_uppercase();
+    InventoryVendorStatus::iter()
+        .find(|s| s.to_value() == value)
+        .ok_or_else(|| ApiError::invalid_field("status", format!("unknown status {}", value)))
+}
+
+fn order_lines(lines: Vec<OrderLineRequest>) -> Vec<OrderLine> {
+    lines.into_iter().map(OrderLine::from).collect()
+}
+
+/// List suppliers
+#[utoipa::path(
+    get,
+    path = "/api/suppliers",
+    responses(
+        (status = 200, description = "Suppliers in code order", body = [SupplierResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn list_suppliers(
+    State(state): State<AppState>,
+    tenant: Tenant,
+) -> Result<Json<Vec<SupplierResponse>>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let suppliers = SupplierService::list(&state.db, tenant.mid).await?;
+    Ok(Json(suppliers.into_iter().map(SupplierResponse::from).collect()))
+}
+
+/// Add a supplier
+#[utoipa::path(
+    post,
+    path = "/api/suppliers",
+    request_body = CreateSupplierRequest,
+    responses(
+        (status = 201, description = "Supplier added", body = SupplierResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 409, description = "Supplier code already taken", body = ProblemDetails),
+        (status = 422, description = "Invalid code or details", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn create_supplier(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Json(req): Json<CreateSupplierRequest>,
+) -> Result<(StatusCode, Json<SupplierResponse>), ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let supplier = SupplierService::create(&state.db, tenant.mid, &req.code, req.supplier.into()).await?;
+    Ok((StatusCode::CREATED, Json(supplier.into())))
+}
+
+/// A supplier
+#[utoipa::path(
+    get,
+    path = "/api/suppliers/{code}",
+    params(("code" = String, Path, description = "Supplier code")),
+    responses(
+        (status = 200, description = "The supplier", body = SupplierResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Supplier not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn get_supplier(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(code): Path<String>,
+) -> Result<Json<SupplierResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let supplier = SupplierService::get(&state.db, tenant.mid, &code).await?;
+    Ok(Json(supplier.into()))
+}
+
+/// Replace a supplier's details
+#[utoipa::path(
+    put,
+    path = "/api/suppliers/{code}",
+    params(("code" = String, Path, description = "Supplier code")),
+    request_body = SupplierRequest,
+    responses(
+        (status = 200, description = "Supplier updated", body = SupplierResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Supplier not found", body = ProblemDetails),
+        (status = 422, description = "Invalid details", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn update_supplier(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(code): Path<String>,
+    Json(req): Json<SupplierRequest>,
+) -> Result<Json<SupplierResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let supplier = SupplierService::update(&state.db, tenant.mid, &code, req.into()).await?;
+    Ok(Json(supplier.into()))
+}
+
+/// Delete a supplier without purchase orders
+#[utoipa::path(
+    delete,
+    path = "/api/suppliers/{code}",
+    params(("code" = String, Path, description = "Supplier code")),
+    responses(
+        (status = 204, description = "Supplier deleted"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Supplier not found", body = ProblemDetails),
+        (status = 409, description = "Supplier has purchase orders", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn delete_supplier(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(code): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    SupplierService::delete(&state.db, tenant.mid, &code).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Draft a purchase order
+#[utoipa::path(
+    post,
+    path = "/api/purchase-orders",
+    request_body = CreatePurchaseOrderRequest,
+    responses(
+        (status = 201, description = "NEW purchase order", body = PurchaseOrderResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Supplier or SKU not found", body = ProblemDetails),
+        (status = 422, description = "No lines, a repeated SKU or a bad quantity", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn create_order(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Json(req): Json<CreatePurchaseOrderRequest>,
+) -> Result<(StatusCode, Json<PurchaseOrderResponse>), ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let lines = order_lines(req.lines);
+    let sheet =
+        PurchaseOrderService::create(&state.db, tenant.mid, &tenant.luser(), &req.supplier, &req.note, &lines).await?;
+    Ok((StatusCode::CREATED, Json(sheet.into())))
+}
+
+/// List purchase orders, newest first
+#[utoipa::path(
+    get,
+    path = "/api/purchase-orders",
+    params(PurchaseOrderListQuery),
+    responses(
+        (status = 200, description = "Purchase orders", body = [PurchaseOrderSummaryResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 422, description = "Unknown status", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn list_orders(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Query(query): Query<PurchaseOrderListQuery>,
+) -> Result<Json<Vec<PurchaseOrderSummaryResponse>>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let status = query.status.as_deref().map(vendor_status).transpose()?;
+    let orders = PurchaseOrderService::list(&state.db, tenant.mid, status, query.supplier.as_deref()).await?;
+    Ok(Json(
+        orders
+            .into_iter()
+            .map(|(order, supplier)| PurchaseOrderSummaryResponse::new(order, &supplier))
+            .collect(),
+    ))
+}
+
+/// A purchase order and its lines
+#[utoipa::path(
+    get,
+    path = "/api/purchase-orders/{id}",
+    params(("id" = i32, Path, description = "Purchase order ID")),
+    responses(
+        (status = 200, description = "The purchase order", body = PurchaseOrderResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Purchase order not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn get_order(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+) -> Result<Json<PurchaseOrderResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let sheet = PurchaseOrderService::get(&state.db, tenant.mid, id).await?;
+    Ok(Json(sheet.into()))
+}
+
+/// Replace the lines of a NEW purchase order
+#[utoipa::path(
+    put,
+    path = "/api/purchase-orders/{id}/lines",
+    params(("id" = i32, Path, description = "Purchase order ID")),
+    request_body = SetLinesRequest,
+    responses(
+        (status = 200, description = "The purchase order", body = PurchaseOrderResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Purchase order or SKU not found", body = ProblemDetails),
+        (status = 409, description = "Purchase order already submitted", body = ProblemDetails),
+        (status = 422, description = "No lines, a repeated SKU or a bad quantity", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn set_lines(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+    Json(req): Json<SetLinesRequest>,
+) -> Result<Json<PurchaseOrderResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let sheet = PurchaseOrderService::set_lines(&state.db, tenant.mid, id, &order_lines(req.lines)).await?;
+    Ok(Json(sheet.into()))
+}
+
+/// Send a NEW purchase order, putting its lines on order
+#[utoipa::path(
+    post,
+    path = "/api/purchase-orders/{id}/submit",
+    params(("id" = i32, Path, description = "Purchase order ID")),
+    responses(
+        (status = 200, description = "ONORDER purchase order", body = PurchaseOrderResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Purchase order not found", body = ProblemDetails),
+        (status = 409, description = "Purchase order already submitted", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn submit(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+) -> Result<Json<PurchaseOrderResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let sheet = PurchaseOrderService::submit(&state.db, tenant.mid, &tenant.luser(), id).await?;
+    Ok(Json(sheet.into()))
+}
+
+/// Record the supplier's confirmation of an order
+#[utoipa::path(
+    post,
+    path = "/api/purchase-orders/{id}/confirm",
+    params(("id" = i32, Path, description = "Purchase order ID")),
+    request_body = ConfirmOrderRequest,
+    responses(
+        (status = 200, description = "CONFIRMED purchase order", body = PurchaseOrderResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Purchase order not found", body = ProblemDetails),
+        (status = 409, description = "Purchase order isn't ONORDER", body = ProblemDetails),
+        (status = 422, description = "Reference too long", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn confirm(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+    Json(req): Json<ConfirmOrderRequest>,
+) -> Result<Json<PurchaseOrderResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let sheet = PurchaseOrderService::confirm(&state.db, tenant.mid, id, &req.reference).await?;
+    Ok(Json(sheet.into()))
+}
+
+/// Take delivery of units against a purchase order
+#[utoipa::path(
+    post,
+    path = "/api/purchase-orders/{id}/receive",
+    params(("id" = i32, Path, description = "Purchase order ID")),
+    request_body = ReceiveRequest,
+    responses(
+        (status = 200, description = "The purchase order; RECEIVED once every line is in", body = PurchaseOrderResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Purchase order not found", body = ProblemDetails),
+        (status = 409, description = "Purchase order isn't on order", body = ProblemDetails),
+        (status = 422, description = "SKU not on the order, or more than outstanding", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn receive(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+    Json(req): Json<ReceiveRequest>,
+) -> Result<Json<PurchaseOrderResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    if req.receipts.is_empty() {
+        return Err(ApiError::invalid_field("receipts", "must hold at least one receipt"));
+    }
+    let receipts = req
+        .receipts
+        .into_iter()
+        .map(|r| {
+            Ok(Receipt {
+                location: r.location.as_ref().map(LocationRequest::location).transpose()?,
+                sku: r.sku,
+                qty: r.qty,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+    let sheet = PurchaseOrderService::receive(&state.db, tenant.mid, &tenant.luser(), id, &receipts).await?;
+    Ok(Json(sheet.into()))
+}
+
+/// Cancel a purchase order and whatever is still outstanding on it
+#[utoipa::path(
+    post,
+    path = "/api/purchase-orders/{id}/cancel",
+    params(("id" = i32, Path, description = "Purchase order ID")),
+    responses(
+        (status = 200, description = "CANCELLED purchase order", body = PurchaseOrderResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Purchase order not found", body = ProblemDetails),
+        (status = 409, description = "Purchase order already closed", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "purchasing"
+)]
+pub async fn cancel(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(id): Path<i32>,
+) -> Result<Json<PurchaseOrderResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let sheet = PurchaseOrderService::cancel(&state.db, tenant.mid, &tenant.luser(), id).await?;
+    Ok(Json(sheet.into()))
+}
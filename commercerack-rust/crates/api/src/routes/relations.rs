@@ -0,0 +1,265 @@
+use axum::{extract::State, http::StatusCode};
+use commercerack_merchant::ApiScope;
+use commercerack_product::{ProductRelationService, RelatedProduct, RelationKind, SkuQty};
+use ::entity::prelude::ProductRelation;
+use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::tenant::Tenant;
+use crate::AppState;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct RelatedProductRequest {
+    pub child_pid: String,
+    /// Units of the child per unit of the parent
+    #[serde(default = "one")]
+    pub qty: i16,
+    #[serde(default = "active")]
+    pub is_active: bool,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SetRelationsRequest {
+    /// The whole list, in display order
+    pub children: Vec<RelatedProductRequest>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct RelationResponse {
+    pub pid: String,
+    pub child_pid: String,
+    /// accessory, related, bundle or assembly
+    pub kind: String,
+    pub qty: i16,
+    pub is_active: bool,
+    pub list_pos: i16,
+    pub created_gmt: i32,
+}
+
+impl From<ProductRelation> for RelationResponse {
+    fn from(relation: ProductRelation) -> Self {
+        Self {
+            kind: RelationKind::of(&relation).map_or(relation.relation.to_lowercase(), |k| k.to_string()),
+            pid: relation.pid,
+            child_pid: relation.child_pid,
+            qty: relation.qty,
+            is_active: relation.is_active != 0,
+            list_pos: relation.list_pos,
+            created_gmt: relation.created_gmt,
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ComponentResponse {
+    pub sku: String,
+    pub qty: i32,
+}
+
+impl From<SkuQty> for ComponentResponse {
+    fn from(line: SkuQty) -> Self {
+        Self { sku: line.sku, qty: line.qty }
+    }
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ComponentsQuery {
+    /// Units of the product; defaults to 1
+    pub qty: Option<i32>,
+}
+
+fn one() -> i16 {
+    1
+}
+
+fn active() -> bool {
+    true
+}
+
+/// List a product's relations of one kind
+#[utoipa::path(
+    get,
+    path = "/api/products/{pid}/relations/{kind}",
+    params(
+        ("pid" = String, Path, description = "Merchant product ID"),
+        ("kind" = String, Path, description = "accessory, related, bundle or assembly")
+    ),
+    responses(
+        (status = 200, description = "The list in display order", body = [RelationResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 422, description = "Unknown relation kind", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn list(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((pid, kind)): Path<(String, String)>,
+) -> Result<Json<Vec<RelationResponse>>, ApiError> {
+    tenant.require_storefront(ApiScope::CatalogRead)?;
+    let kind: RelationKind = kind.parse()?;
+    let relations = ProductRelationService::list(&state.db, tenant.mid, &pid, kind).await?;
+    Ok(Json(relations.into_iter().map(RelationResponse::from).collect()))
+}
+
+/// Replace a product's relations of one kind
+///
+/// Bundle and assembly components must be products without options and
+/// must not contain the product itself.
+#[utoipa::path(
+    put,
+    path = "/api/products/{pid}/relations/{kind}",
+    params(
+        ("pid" = String, Path, description = "Merchant product ID"),
+        ("kind" = String, Path, description = "accessory, related, bundle or assembly")
+    ),
+    request_body = SetRelationsRequest,
+    responses(
+        (status = 200, description = "The new list", body = [RelationResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the catalog:write scope", body = ProblemDetails),
+        (status = 404, description = "Product not found", body = ProblemDetails),
+        (status = 409, description = "A component contains the product", body = ProblemDetails),
+        (status = 422, description = "Invalid list", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn set(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((pid, kind)): Path<(String, String)>,
+    Json(req): Json<SetRelationsRequest>,
+) -> Result<Json<Vec<RelationResponse>>, ApiError> {
+    tenant.require(ApiScope::CatalogWrite)?;
+    let kind: RelationKind = kind.parse()?;
+    let children: Vec<RelatedProduct> = req
+        .children
+        .into_iter()
+        .map(|c| RelatedProduct {
+            child_pid: c.child_pid.trim().to_string(),
+            qty: c.qty,
+            is_active: c.is_active,
+        })
+        .collect();
+    let relations = ProductRelationService::set(&state.db, tenant.mid, &pid, kind, &children).await?;
+    Ok(Json(relations.into_iter().map(RelationResponse::from).collect()))
+}
+
+/// Take a product off a relation list
+#[utoipa::path(
+    delete,
+    path = "/api/products/{pid}/relations/{kind}/{child_pid}",
+    params(
+        ("pid" = String, Path, description = "Merchant product ID"),
+        ("kind" = String, Path, description = "accessory, related, bundle or assembly"),
+        ("child_pid" = String, Path, description = "Related product ID")
+    ),
+    responses(
+        (status = 204, description = "Relation removed"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the catalog:write scope", body = ProblemDetails),
+        (status = 404, description = "Relation not found", body = ProblemDetails),
+        (status = 422, description = "Unknown relation kind", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn delete(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((pid, kind, child_pid)): Path<(String, String, String)>,
+) -> Result<StatusCode, ApiError> {
+    tenant.require(ApiScope::CatalogWrite)?;
+    let kind: RelationKind = kind.parse()?;
+    ProductRelationService::remove(&state.db, tenant.mid, &pid, kind, &child_pid).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// SKUs consumed by selling a product
+///
+/// Bundles and assemblies are expanded recursively with quantities
+/// multiplied; any other product is its own single component.
+#[utoipa::path(
+    get,
+    path = "/api/products/{pid}/components",
+    params(("pid" = String, Path, description = "Merchant product ID"), ComponentsQuery),
+    responses(
+        (status = 200, description = "Component SKUs and quantities", body = [ComponentResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 409, description = "Components are nested too deeply", body = ProblemDetails),
+        (status = 422, description = "Invalid quantity", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn components(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(pid): Path<String>,
+    Query(query): Query<ComponentsQuery>,
+) -> Result<Json<Vec<ComponentResponse>>, ApiError> {
+    tenant.require_storefront(ApiScope::CatalogRead)?;
+    let qty = query.qty.unwrap_or(1);
+    if qty <= 0 {
+        return Err(ApiError::invalid_field("qty", "must be greater than zero"));
+    }
+    let lines = ProductRelationService::expand(&state.db, tenant.mid, &[SkuQty { sku: pid, qty }]).await?;
+    Ok(Json(lines.into_iter().map(ComponentResponse::from).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenant::Caller;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    #[tokio::test]
+    async fn test_unknown_kind_is_rejected() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let tenant = Tenant { mid: 1, caller: Caller::Customer(7) };
+
+        let err = list(State(state), tenant, Path(("KIT".to_string(), "kit".to_string())))
+            .await
+            .err()
+            .expect("kit is not a relation kind");
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_changes_need_catalog_write() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let customer = || Tenant { mid: 1, caller: Caller::Customer(7) };
+
+        let req = SetRelationsRequest {
+            children: vec![RelatedProductRequest {
+                child_pid: "CASE".to_string(),
+                qty: 1,
+                is_active: true,
+            }],
+        };
+        let err = set(
+            State(state.clone()),
+            customer(),
+            Path(("PHONE".to_string(), "accessory".to_string())),
+            Json(req),
+        )
+        .await
+        .err()
+        .expect("customers cannot rewrite relations");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+
+        let err = delete(
+            State(state),
+            customer(),
+            Path(("PHONE".to_string(), "accessory".to_string(), "CASE".to_string())),
+        )
+        .await
+        .expect_err("customers cannot remove relations");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,449 @@
+use axum::{extract::State, http::StatusCode};
+use commercerack_merchant::ApiScope;
+use commercerack_product::sku::sellable_quantity;
+use commercerack_product::{OptionGroup, OptionValue, SkuInput, SkuService};
+use ::entity::prelude::Sku;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::tenant::Tenant;
+use crate::AppState;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SkuRequest {
+    #[serde(default)]
+    pub title: String,
+    pub price: String,
+    #[serde(default = "zero")]
+    pub cost: String,
+    /// 12-digit UPC or 13-digit EAN
+    #[serde(default)]
+    pub upc: String,
+    /// Manufacturer part number
+    #[serde(default)]
+    pub mfgid: String,
+    #[serde(default)]
+    pub supplierid: String,
+    #[serde(default)]
+    pub amz_asin: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateSkuRequest {
+    /// Option blocks appended to the product ID to form the SKU code
+    #[serde(default)]
+    #[schema(example = ":C001:S00L")]
+    pub invopts: String,
+    #[serde(flatten)]
+    pub sku: SkuRequest,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct OptionValueRequest {
+    /// Two letters or digits, unique within the group
+    #[schema(example = "01")]
+    pub id: String,
+    pub name: String,
+    /// Added to the product's base price
+    #[serde(default = "zero")]
+    pub price_delta: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct OptionGroupRequest {
+    /// Two letters or digits, unique within the product
+    #[schema(example = "C0")]
+    pub id: String,
+    pub name: String,
+    pub values: Vec<OptionValueRequest>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct GenerateVariantsRequest {
+    /// At most three groups
+    pub groups: Vec<OptionGroupRequest>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct SkuResponse {
+    pub id: i64,
+    pub pid: String,
+    pub sku: String,
+    pub invopts: String,
+    pub title: String,
+    pub price: String,
+    pub cost: String,
+    pub upc: String,
+    pub mfgid: String,
+    pub supplierid: String,
+    pub amz_asin: String,
+    pub inv_available: i32,
+    /// Units that can still be sold; absent when backorders are allowed
+    pub sellable: Option<i32>,
+}
+
+impl From<Sku> for SkuResponse {
+    fn from(sku: Sku) -> Self {
+        Self {
+            sellable: sellable_quantity(&sku),
+            id: sku.id,
+            pid: sku.pid,
+            sku: sku.sku,
+            invopts: sku.invopts,
+            title: sku.title,
+            price: sku.price.to_string(),
+            cost: sku.cost.to_string(),
+            upc: sku.upc,
+            mfgid: sku.mfgid,
+            supplierid: sku.supplierid,
+            amz_asin: sku.amz_asin,
+            inv_available: sku.inv_available,
+        }
+    }
+}
+
+/// Query parameters for `GET /api/skus`; exactly one is required
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LookupQuery {
+    pub upc: Option<String>,
+    pub asin: Option<String>,
+    /// Manufacturer part number
+    pub mfgid: Option<String>,
+}
+
+fn zero() -> String {
+    "0".to_string()
+}
+
+fn decimal(field: &'static str, value: &str) -> Result<Decimal, ApiError> {
+    value
+        .trim()
+        .parse::<Decimal>()
+        .map_err(|_| ApiError::invalid_field(field, "must be a decimal number"))
+}
+
+impl SkuRequest {
+    fn into_input(self) -> Result<SkuInput, ApiError> {
+        Ok(SkuInput {
+            price: decimal("price", &self.price)?,
+            cost: decimal("cost", &self.cost)?,
+            title: self.title,
+            upc: self.upc,
+            mfgid: self.mfgid,
+            supplierid: self.supplierid,
+            amz_asin: self.amz_asin,
+        })
+    }
+}
+
+impl GenerateVariantsRequest {
+    fn into_groups(self) -> Result<Vec<OptionGroup>, ApiError> {
+        self.groups
+            .into_iter()
+            .map(|group| {
+                let values = group
+                    .values
+                    .into_iter()
+                    .map(|value| {
+                        Ok(OptionValue {
+                            price_delta: decimal("price_delta", &value.price_delta)?,
+                            id: value.id,
+                            name: value.name,
+                        })
+                    })
+                    .collect::<Result<_, ApiError>>()?;
+                Ok(OptionGroup { id: group.id, name: group.name, values })
+            })
+            .collect()
+    }
+}
+
+/// List a product's SKUs
+#[utoipa::path(
+    get,
+    path = "/api/products/{pid}/skus",
+    params(("pid" = String, Path, description = "Merchant product ID")),
+    responses(
+        (status = 200, description = "The product's SKUs in code order", body = [SkuResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn list(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(pid): Path<String>,
+) -> Result<Json<Vec<SkuResponse>>, ApiError> {
+    tenant.require_storefront(ApiScope::CatalogRead)?;
+    let skus = SkuService::list_by_product(&state.db, tenant.mid, &pid).await?;
+    Ok(Json(skus.into_iter().map(SkuResponse::from).collect()))
+}
+
+/// Add a SKU to a product
+#[utoipa::path(
+    post,
+    path = "/api/products/{pid}/skus",
+    params(("pid" = String, Path, description = "Merchant product ID")),
+    request_body = CreateSkuRequest,
+    responses(
+        (status = 201, description = "SKU created", body = SkuResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the catalog:write scope", body = ProblemDetails),
+        (status = 404, description = "Product not found", body = ProblemDetails),
+        (status = 409, description = "SKU already exists", body = ProblemDetails),
+        (status = 422, description = "Invalid SKU", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn create(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(pid): Path<String>,
+    Json(req): Json<CreateSkuRequest>,
+) -> Result<(StatusCode, Json<SkuResponse>), ApiError> {
+    tenant.require(ApiScope::CatalogWrite)?;
+    let input = req.sku.into_input()?;
+    let sku = SkuService::create(&state.db, tenant.mid, &pid, &req.invopts, input).await?;
+    Ok((StatusCode::CREATED, Json(sku.into())))
+}
+
+/// Get a SKU of a product
+#[utoipa::path(
+    get,
+    path = "/api/products/{pid}/skus/{sku}",
+    params(
+        ("pid" = String, Path, description = "Merchant product ID"),
+        ("sku" = String, Path, description = "SKU code")
+    ),
+    responses(
+        (status = 200, description = "SKU found", body = SkuResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 404, description = "SKU not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((pid, sku)): Path<(String, String)>,
+) -> Result<Json<SkuResponse>, ApiError> {
+    tenant.require_storefront(ApiScope::CatalogRead)?;
+    SkuService::find(&state.db, tenant.mid, &pid, &sku)
+        .await?
+        .map(|sku| Json(sku.into()))
+        .ok_or_else(|| ApiError::not_found("SKU"))
+}
+
+/// Replace a SKU's details
+///
+/// Stock counters are not changed here.
+#[utoipa::path(
+    put,
+    path = "/api/products/{pid}/skus/{sku}",
+    params(
+        ("pid" = String, Path, description = "Merchant product ID"),
+        ("sku" = String, Path, description = "SKU code")
+    ),
+    request_body = SkuRequest,
+    responses(
+        (status = 200, description = "SKU updated", body = SkuResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the catalog:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU not found", body = ProblemDetails),
+        (status = 422, description = "Invalid SKU", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn update(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((pid, sku)): Path<(String, String)>,
+    Json(req): Json<SkuRequest>,
+) -> Result<Json<SkuResponse>, ApiError> {
+    tenant.require(ApiScope::CatalogWrite)?;
+    let input = req.into_input()?;
+    let sku = SkuService::update(&state.db, tenant.mid, &pid, &sku, input).await?;
+    Ok(Json(sku.into()))
+}
+
+/// Delete a SKU
+#[utoipa::path(
+    delete,
+    path = "/api/products/{pid}/skus/{sku}",
+    params(
+        ("pid" = String, Path, description = "Merchant product ID"),
+        ("sku" = String, Path, description = "SKU code")
+    ),
+    responses(
+        (status = 204, description = "SKU deleted"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the catalog:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn delete(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path((pid, sku)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    tenant.require(ApiScope::CatalogWrite)?;
+    SkuService::delete(&state.db, tenant.mid, &pid, &sku).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Create a SKU for every combination of option values
+///
+/// Existing variants are kept, so adding a value and posting again only
+/// creates the new combinations.
+#[utoipa::path(
+    post,
+    path = "/api/products/{pid}/skus/variants",
+    params(("pid" = String, Path, description = "Merchant product ID")),
+    request_body = GenerateVariantsRequest,
+    responses(
+        (status = 200, description = "All of the product's SKUs", body = [SkuResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the catalog:write scope", body = ProblemDetails),
+        (status = 404, description = "Product not found", body = ProblemDetails),
+        (status = 422, description = "Invalid option groups, or more than 1000 combinations", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn generate_variants(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(pid): Path<String>,
+    Json(req): Json<GenerateVariantsRequest>,
+) -> Result<Json<Vec<SkuResponse>>, ApiError> {
+    tenant.require(ApiScope::CatalogWrite)?;
+    let groups = req.into_groups()?;
+    let skus = SkuService::generate_variants(&state.db, tenant.mid, &pid, &groups).await?;
+    Ok(Json(skus.into_iter().map(SkuResponse::from).collect()))
+}
+
+/// Find SKUs by barcode, ASIN or manufacturer part number
+#[utoipa::path(
+    get,
+    path = "/api/skus",
+    params(LookupQuery),
+    responses(
+        (status = 200, description = "Matching SKUs", body = [SkuResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "API key lacks the required scope", body = ProblemDetails),
+        (status = 422, description = "Not exactly one of upc, asin and mfgid", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "products"
+)]
+pub async fn lookup(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Query(query): Query<LookupQuery>,
+) -> Result<Json<Vec<SkuResponse>>, ApiError> {
+    tenant.require_storefront(ApiScope::CatalogRead)?;
+    let skus = match (query.upc, query.asin, query.mfgid) {
+        (Some(upc), None, None) => SkuService::find_by_upc(&state.db, tenant.mid, &upc).await?,
+        (None, Some(asin), None) => SkuService::find_by_asin(&state.db, tenant.mid, &asin).await?,
+        (None, None, Some(mfgid)) => SkuService::find_by_mfgid(&state.db, tenant.mid, &mfgid).await?,
+        _ => return Err(ApiError::invalid_field("upc", "give exactly one of upc, asin and mfgid")),
+    };
+    Ok(Json(skus.into_iter().map(SkuResponse::from).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenant::Caller;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    const KEY: Tenant = Tenant {
+        mid: 1,
+        caller: Caller::ApiKey { id: 3, scopes: Vec::new() },
+    };
+
+    fn catalog_writer() -> Tenant {
+        Tenant {
+            mid: 1,
+            caller: Caller::ApiKey { id: 3, scopes: vec![ApiScope::CatalogWrite] },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_needs_exactly_one_key() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let tenant = Tenant { mid: 1, caller: Caller::Customer(7) };
+        let query = LookupQuery {
+            upc: Some("012345678905".to_string()),
+            asin: Some("B000000000".to_string()),
+            mfgid: None,
+        };
+
+        let err = lookup(State(state), tenant, Query(query))
+            .await
+            .err()
+            .expect("two keys are ambiguous");
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_changes_need_catalog_write() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let req = || GenerateVariantsRequest { groups: Vec::new() };
+
+        let err = generate_variants(State(state.clone()), KEY, Path("TSHIRT".to_string()), Json(req()))
+            .await
+            .err()
+            .expect("the key lacks catalog:write");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+
+        let customer = Tenant { mid: 1, caller: Caller::Customer(7) };
+        let err = generate_variants(State(state.clone()), customer.clone(), Path("TSHIRT".to_string()), Json(req()))
+            .await
+            .err()
+            .expect("shoppers can't change the catalog");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+
+        let err = delete(State(state.clone()), customer, Path(("TSHIRT".to_string(), "TSHIRT".to_string())))
+            .await
+            .expect_err("shoppers can't delete SKUs");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+
+        let err = list(State(state), KEY, Path("TSHIRT".to_string()))
+            .await
+            .err()
+            .expect("reading needs catalog:read");
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_price_is_422() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let tenant = catalog_writer();
+        let req = SkuRequest {
+            title: String::new(),
+            price: "cheap".to_string(),
+            cost: zero(),
+            upc: String::new(),
+            mfgid: String::new(),
+            supplierid: String::new(),
+            amz_asin: String::new(),
+        };
+
+        let err = update(State(state), tenant, Path(("TSHIRT".to_string(), "TSHIRT".to_string())), Json(req))
+            .await
+            .err()
+            .expect("price must be a decimal");
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -0,0 +1,361 @@
+use axum::{extract::State, http::StatusCode};
+use commercerack_inventory::{Location, WmsService};
+use commercerack_merchant::ApiScope;
+use ::entity::prelude::InventoryDetail;
+use sea_orm::ActiveEnum;
+use serde::{Deserialize, Serialize};
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{Json, Path, Query};
+use crate::tenant::Tenant;
+use crate::AppState;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct LocationRequest {
+    /// Warehouse, up to 3 characters
+    #[schema(example = "MN")]
+    pub geo: String,
+    /// Zone in the warehouse, up to 3 characters
+    #[schema(example = "A")]
+    pub zone: String,
+    /// Position in the zone, up to 12 characters
+    #[schema(example = "04-2")]
+    pub pos: String,
+}
+
+impl LocationRequest {
+    pub(crate) fn location(&self) -> Result<Location, ApiError> {
+        Ok(Location::new(&self.geo, &self.zone, &self.pos)?)
+    }
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BinsQuery {
+    /// Only bins in this warehouse
+    pub geo: Option<String>,
+    /// Only bins in this zone
+    pub zone: Option<String>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct BinResponse {
+    /// warehouse:zone:position
+    pub location: String,
+    pub sku: String,
+    pub qty: i32,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct PutAwayRequest {
+    pub sku: String,
+    pub location: LocationRequest,
+    pub qty: i32,
+    /// SIMPLE or ONORDER row the units come off; new stock when left out
+    pub from_uuid: Option<String>,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct RelocateRequest {
+    pub sku: String,
+    pub from: LocationRequest,
+    pub to: LocationRequest,
+    pub qty: i32,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreatePickBatchRequest {
+    /// Paid orders to pick
+    pub orderids: Vec<String>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ConfirmPicksRequest {
+    /// Rows picked; every row still to pick when empty
+    #[serde(default)]
+    pub uuids: Vec<String>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ShipmentRequest {
+    pub orderid: String,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct PickLineResponse {
+    pub uuid: String,
+    pub sku: String,
+    pub orderid: String,
+    pub qty: i32,
+    /// PICK, PICKED or SHIPPED
+    pub basetype: Option<String>,
+    /// WMS (from a bin) or SIMPLE (from the shelf)
+    pub pick_route: Option<String>,
+    /// Bin to pick from, as warehouse:zone:position
+    pub location: Option<String>,
+    pub pick_batchid: String,
+}
+
+impl From<InventoryDetail> for PickLineResponse {
+    fn from(detail: InventoryDetail) -> Self {
+        Self {
+            location: Location::of(&detail).map(|l| l.to_string()),
+            uuid: detail.uuid,
+            sku: detail.sku,
+            orderid: detail.our_orderid,
+            qty: detail.qty,
+            basetype: detail.basetype.map(|b| b.to_value()),
+            pick_route: detail.pick_route.map(|r| r.to_value()),
+            pick_batchid: detail.pick_batchid,
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct PickBatchResponse {
+    pub batchid: String,
+    /// In walking order: warehouse, zone, position, then SKU
+    pub lines: Vec<PickLineResponse>,
+}
+
+fn bin(detail: InventoryDetail) -> BinResponse {
+    BinResponse {
+        location: Location::of(&detail).map(|l| l.to_string()).unwrap_or_default(),
+        sku: detail.sku,
+        qty: detail.qty,
+    }
+}
+
+/// Bins holding stock, in warehouse, zone and position order
+#[utoipa::path(
+    get,
+    path = "/api/warehouse/bins",
+    params(BinsQuery),
+    responses(
+        (status = 200, description = "Bin contents", body = [BinResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the catalog:read scope", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "warehouse"
+)]
+pub async fn bins(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Query(query): Query<BinsQuery>,
+) -> Result<Json<Vec<BinResponse>>, ApiError> {
+    tenant.require(ApiScope::CatalogRead)?;
+    let bins = WmsService::bins(&state.db, tenant.mid, query.geo.as_deref(), query.zone.as_deref()).await?;
+    Ok(Json(bins.into_iter().map(bin).collect()))
+}
+
+/// Shelve stock into a bin
+#[utoipa::path(
+    post,
+    path = "/api/warehouse/put-away",
+    request_body = PutAwayRequest,
+    responses(
+        (status = 200, description = "The bin's contents", body = BinResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU or source row not found", body = ProblemDetails),
+        (status = 409, description = "Source row has fewer units", body = ProblemDetails),
+        (status = 422, description = "Invalid location, quantity or source row", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "warehouse"
+)]
+pub async fn put_away(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Json(req): Json<PutAwayRequest>,
+) -> Result<Json<BinResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let to = req.location.location()?;
+    let detail = WmsService::put_away(
+        &state.db,
+        tenant.mid,
+        &tenant.luser(),
+        &req.sku,
+        &to,
+        req.qty,
+        req.from_uuid.as_deref(),
+        &req.note,
+    )
+    .await?;
+    Ok(Json(bin(detail)))
+}
+
+/// Move stock from one bin to another
+#[utoipa::path(
+    post,
+    path = "/api/warehouse/relocate",
+    request_body = RelocateRequest,
+    responses(
+        (status = 200, description = "The destination bin's contents", body = BinResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "SKU or source bin not found", body = ProblemDetails),
+        (status = 409, description = "Not enough free in the source bin", body = ProblemDetails),
+        (status = 422, description = "Invalid location or quantity", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "warehouse"
+)]
+pub async fn relocate(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Json(req): Json<RelocateRequest>,
+) -> Result<Json<BinResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let from = req.from.location()?;
+    let to = req.to.location()?;
+    let detail =
+        WmsService::relocate(&state.db, tenant.mid, &tenant.luser(), &req.sku, &from, &to, req.qty, &req.note)
+            .await?;
+    Ok(Json(bin(detail)))
+}
+
+/// Start a pick batch for paid orders
+#[utoipa::path(
+    post,
+    path = "/api/warehouse/pick-batches",
+    request_body = CreatePickBatchRequest,
+    responses(
+        (status = 201, description = "The batch's pick list", body = PickBatchResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 409, description = "An order isn't paid, or there's nothing to pick or not enough stock", body = ProblemDetails),
+        (status = 422, description = "No orders given", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "warehouse"
+)]
+pub async fn create_pick_batch(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Json(req): Json<CreatePickBatchRequest>,
+) -> Result<(StatusCode, Json<PickBatchResponse>), ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let batch = WmsService::create_batch(&state.db, tenant.mid, &tenant.luser(), &req.orderids).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(PickBatchResponse {
+            batchid: batch.batchid,
+            lines: batch.lines.into_iter().map(PickLineResponse::from).collect(),
+        }),
+    ))
+}
+
+/// Pick list of a batch
+#[utoipa::path(
+    get,
+    path = "/api/warehouse/pick-batches/{batchid}",
+    params(("batchid" = String, Path, description = "Pick batch ID")),
+    responses(
+        (status = 200, description = "The batch's pick list", body = PickBatchResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Pick batch not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "warehouse"
+)]
+pub async fn pick_batch(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(batchid): Path<String>,
+) -> Result<Json<PickBatchResponse>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let lines = WmsService::pick_list(state.db.as_ref(), tenant.mid, &batchid).await?;
+    Ok(Json(PickBatchResponse {
+        batchid,
+        lines: lines.into_iter().map(PickLineResponse::from).collect(),
+    }))
+}
+
+/// Confirm rows of a batch as picked
+#[utoipa::path(
+    post,
+    path = "/api/warehouse/pick-batches/{batchid}/confirm",
+    params(("batchid" = String, Path, description = "Pick batch ID")),
+    request_body = ConfirmPicksRequest,
+    responses(
+        (status = 200, description = "Rows now PICKED", body = [PickLineResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 404, description = "Pick batch or row not found", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "warehouse"
+)]
+pub async fn confirm_picks(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Path(batchid): Path<String>,
+    Json(req): Json<ConfirmPicksRequest>,
+) -> Result<Json<Vec<PickLineResponse>>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let picked = WmsService::confirm_picks(&state.db, tenant.mid, &tenant.luser(), &batchid, &req.uuids).await?;
+    Ok(Json(picked.into_iter().map(PickLineResponse::from).collect()))
+}
+
+/// Ship an order's picked rows
+#[utoipa::path(
+    post,
+    path = "/api/warehouse/shipments",
+    request_body = ShipmentRequest,
+    responses(
+        (status = 200, description = "Rows now SHIPPED", body = [PickLineResponse]),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails),
+        (status = 403, description = "Caller is not an API key with the inventory:write scope", body = ProblemDetails),
+        (status = 409, description = "Nothing picked, or the bin holds fewer units than picked", body = ProblemDetails),
+        (status = 500, description = "Internal server error", body = ProblemDetails)
+    ),
+    tag = "warehouse"
+)]
+pub async fn ship(
+    State(state): State<AppState>,
+    tenant: Tenant,
+    Json(req): Json<ShipmentRequest>,
+) -> Result<Json<Vec<PickLineResponse>>, ApiError> {
+    tenant.require(ApiScope::InventoryWrite)?;
+    let shipped = WmsService::ship(&state.db, tenant.mid, &tenant.luser(), &req.orderid).await?;
+    Ok(Json(shipped.into_iter().map(PickLineResponse::from).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenant::Caller;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    #[tokio::test]
+    async fn test_bad_location_is_rejected() {
+        let state = AppState::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let tenant = Tenant {
+            mid: 1,
+            caller: Caller::ApiKey { id: 3, scopes: vec![ApiScope::InventoryWrite] },
+        };
+        let req = PutAwayRequest {
+            sku: "WIDGET".to_string(),
+            location: LocationRequest {
+                geo: "MAIN".to_string(),
+                zone: "A".to_string(),
+                pos: "1".to_string(),
+            },
+            qty: 4,
+            from_uuid: None,
+            note: String::new(),
+        };
+
+        let err = put_away(State(state), tenant, Json(req))
+            .await
+            .err()
+            .expect("warehouse codes are at most 3 characters");
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
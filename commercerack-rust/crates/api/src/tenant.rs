@@ -0,0 +1,263 @@
+//! Merchant tenancy
+//!
+//! The merchant a request acts for comes from its credentials, never from
+//! the path, query or body: a customer's bearer token or a merchant API key.
+//! Routes that still name a `mid` must name the caller's own; any other
+//! merchant is reported as not found, so callers can't probe which merchants
+//! or records exist.
+//!
+//! Customers reach the storefront routes and their own records; everything
+//! that administers the merchant takes an API key with the matching scope.
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts, http::StatusCode};
+use commercerack_merchant::ApiScope;
+
+use crate::auth::{ApiKey, Claims, API_KEY_HEADER};
+use crate::error::ApiError;
+use crate::AppState;
+
+/// Who is calling on the tenant's behalf
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caller {
+    /// A logged-in customer (`Claims.sub`)
+    Customer(i32),
+    /// A server-to-server integration
+    ApiKey { id: i32, scopes: Vec<ApiScope> },
+}
+
+/// The merchant the authenticated caller belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant {
+    pub mid: i32,
+    pub caller: Caller,
+}
+
+impl Tenant {
+    /// The merchant to act on, given the `mid` a request names (if any)
+    pub fn scope(&self, requested: Option<i32>) -> Result<i32, ApiError> {
+        match requested {
+            Some(mid) if mid != self.mid => Err(ApiError::not_found("Merchant")),
+            _ => Ok(self.mid),
+        }
+    }
+
+    /// The customer to act for, given the one a request names (if any):
+    /// customers act as themselves, API keys must name one
+    pub fn customer(&self, requested: Option<i32>) -> Result<i32, ApiError> {
+        match (&self.caller, requested) {
+            (Caller::Customer(own), None) => Ok(*own),
+            (Caller::Customer(own), Some(cid)) if cid != *own => Err(ApiError::not_found("Customer")),
+            (_, Some(cid)) => Ok(cid),
+            (Caller::ApiKey { .. }, None) => Err(ApiError::invalid_field("customer", "is required for API keys")),
+        }
+    }
+
+    /// Allow only API keys granted `scope`: merchant administration, which
+    /// customers never reach
+    pub fn require(&self, scope: ApiScope) -> Result<(), ApiError> {
+        self.require_any(&[scope])
+    }
+
+    /// Allow only API keys granted at least one of `allowed`, such as a
+    /// read scope or the write scope that implies it
+    pub fn require_any(&self, allowed: &[ApiScope]) -> Result<(), ApiError> {
+        match &self.caller {
+            Caller::ApiKey { scopes, .. } if allowed.iter().any(|s| scopes.contains(s)) => Ok(()),
+            Caller::ApiKey { .. } => {
+                let names: Vec<&str> = allowed.iter().map(ApiScope::as_str).collect();
+                Err(insufficient_scope(format!("API key lacks the {} scope", names.join(" or "))))
+            }
+            Caller::Customer(_) => Err(insufficient_scope(
+                "This endpoint is only available to API keys".to_string(),
+            )),
+        }
+    }
+
+    /// Allow customers, and API keys granted `scope`, on the storefront
+    /// routes shoppers call themselves
+    pub fn require_storefront(&self, scope: ApiScope) -> Result<(), ApiError> {
+        match self.caller {
+            Caller::Customer(_) => Ok(()),
+            Caller::ApiKey { .. } => self.require(scope),
+        }
+    }
+
+    /// Reject API keys on routes no scope covers
+    pub fn require_session(&self) -> Result<(), ApiError> {
+        match self.caller {
+            Caller::ApiKey { .. } => Err(insufficient_scope(
+                "This endpoint is not available to API keys".to_string(),
+            )),
+            Caller::Customer(_) => Ok(()),
+        }
+    }
+
+    /// Who to record in audit columns such as `inventory_log.luser`
+    pub fn luser(&self) -> String {
+        match &self.caller {
+            Caller::Customer(cid) => format!("c{}", cid),
+            Caller::ApiKey { id, .. } => format!("key{}", id),
+        }
+    }
+
+    /// Allow customers on their own record, and API keys granted `scope` on
+    /// any of the merchant's
+    pub fn require_self_or(&self, cid: i32, scope: ApiScope) -> Result<(), ApiError> {
+        match self.caller {
+            Caller::Customer(_) => self.require_self(cid),
+            Caller::ApiKey { .. } => self.require(scope),
+        }
+    }
+
+    /// Allow customers to act only on their own record
+    pub fn require_self(&self, cid: i32) -> Result<(), ApiError> {
+        self.require_session()?;
+        match self.caller {
+            Caller::Customer(own) if own != cid => Err(ApiError::not_found("Customer")),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn insufficient_scope(message: String) -> ApiError {
+    ApiError::Status {
+        status: StatusCode::FORBIDDEN,
+        code: "insufficient_scope",
+        message,
+    }
+}
+
+impl TryFrom<&Claims> for Tenant {
+    type Error = ApiError;
+
+    fn try_from(claims: &Claims) -> Result<Self, Self::Error> {
+        let cid = claims
+            .customer_id()
+            .ok_or_else(|| ApiError::Unauthorized("Invalid token subject".to_string()))?;
+
+        Ok(Self {
+            mid: claims.mid,
+            caller: Caller::Customer(cid),
+        })
+    }
+}
+
+impl From<&ApiKey> for Tenant {
+    fn from(key: &ApiKey) -> Self {
+        Self {
+            mid: key.0.mid,
+            caller: Caller::ApiKey {
+                id: key.0.id,
+                scopes: key.scopes(),
+            },
+        }
+    }
+}
+
+/// Resolves the tenant once per request; the auth layer and the handler
+/// share the result through the request extensions
+#[async_trait]
+impl FromRequestParts<AppState> for Tenant {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(tenant) = parts.extensions.get::<Tenant>() {
+            return Ok(tenant.clone());
+        }
+
+        let tenant = if parts.headers.contains_key(API_KEY_HEADER) {
+            Tenant::from(&ApiKey::from_request_parts(parts, state).await?)
+        } else {
+            Tenant::try_from(&Claims::from_request_parts(parts, state).await?)?
+        };
+
+        parts.extensions.insert(tenant.clone());
+        Ok(tenant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(scopes: Vec<ApiScope>) -> Tenant {
+        Tenant {
+            mid: 1,
+            caller: Caller::ApiKey { id: 3, scopes },
+        }
+    }
+
+    #[test]
+    fn test_scope_rejects_other_merchants() {
+        let tenant = Tenant { mid: 1, caller: Caller::Customer(7) };
+
+        assert_eq!(tenant.scope(None).unwrap(), 1);
+        assert_eq!(tenant.scope(Some(1)).unwrap(), 1);
+        assert_eq!(tenant.scope(Some(2)).unwrap_err().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_customers_act_as_themselves() {
+        let customer = Tenant { mid: 1, caller: Caller::Customer(7) };
+
+        assert_eq!(customer.customer(None).unwrap(), 7);
+        assert_eq!(customer.customer(Some(7)).unwrap(), 7);
+        assert_eq!(customer.customer(Some(8)).unwrap_err().status(), StatusCode::NOT_FOUND);
+        assert_eq!(key(Vec::new()).customer(Some(8)).unwrap(), 8);
+        assert_eq!(key(Vec::new()).customer(None).unwrap_err().status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn test_api_keys_need_matching_scope() {
+        let tenant = key(vec![ApiScope::CatalogRead]);
+
+        assert!(tenant.require(ApiScope::CatalogRead).is_ok());
+        assert_eq!(
+            tenant.require(ApiScope::OrdersWrite).unwrap_err().status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(tenant.require_session().unwrap_err().status(), StatusCode::FORBIDDEN);
+
+        assert!(tenant.require_storefront(ApiScope::CatalogRead).is_ok());
+        assert_eq!(
+            tenant.require_storefront(ApiScope::OrdersWrite).unwrap_err().status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(tenant.require_self(7).unwrap_err().status(), StatusCode::FORBIDDEN);
+
+        assert!(tenant.require_any(&[ApiScope::OrdersRead, ApiScope::CatalogRead]).is_ok());
+        assert_eq!(
+            tenant.require_any(&[ApiScope::OrdersRead, ApiScope::OrdersWrite]).unwrap_err().status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[test]
+    fn test_customers_are_kept_off_admin_routes() {
+        let customer = Tenant { mid: 1, caller: Caller::Customer(7) };
+
+        assert_eq!(customer.require(ApiScope::InventoryWrite).unwrap_err().status(), StatusCode::FORBIDDEN);
+        assert_eq!(customer.require(ApiScope::OrdersWrite).unwrap_err().status(), StatusCode::FORBIDDEN);
+        assert_eq!(customer.require(ApiScope::CatalogRead).unwrap_err().status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            customer.require_any(&[ApiScope::OrdersRead, ApiScope::OrdersWrite]).unwrap_err().status(),
+            StatusCode::FORBIDDEN
+        );
+
+        assert!(customer.require_storefront(ApiScope::CatalogRead).is_ok());
+        assert!(customer.require_session().is_ok());
+        assert!(customer.require_self(7).is_ok());
+        assert_eq!(customer.require_self(8).unwrap_err().status(), StatusCode::NOT_FOUND);
+        assert!(customer.require_self_or(7, ApiScope::CustomersAdmin).is_ok());
+        assert_eq!(
+            customer.require_self_or(8, ApiScope::CustomersAdmin).unwrap_err().status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_luser_names_the_caller() {
+        assert_eq!(Tenant { mid: 1, caller: Caller::Customer(7) }.luser(), "c7");
+        assert_eq!(key(Vec::new()).luser(), "key3");
+    }
+}
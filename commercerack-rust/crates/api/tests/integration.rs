@@ -50,3 +50,29 @@ async fn test_rapidoc_available() {
     let response = app.oneshot(request).await.unwrap();
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn test_invalid_body_returns_problem_details() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+    let app = app(db);
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/customers")
+        .header("content-type", "application/json")
+        .header("x-request-id", "test-req-1")
+        .body(Body::from(r#"{"mid": 1}"#))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.headers()["content-type"], "application/problem+json");
+    assert_eq!(response.headers()["x-request-id"], "test-req-1");
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(problem["code"], "validation_failed");
+    assert_eq!(problem["request_id"], "test-req-1");
+}
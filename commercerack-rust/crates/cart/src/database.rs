@@ -0,0 +1,179 @@
+//! Cart storage in the `carts` table via SeaORM
+
+use ::entity::carts::{self, Column};
+use async_trait::async_trait;
+use sea_orm::sea_query::Expr;
+use sea_orm::{entity::*, query::*, DatabaseConnection, Set};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::{CartError, Result};
+use crate::repository::{expires_gmt, now_gmt, CartRepository};
+use crate::Cart;
+
+pub struct SeaOrmCartRepository {
+    db: Arc<DatabaseConnection>,
+    ttl: Duration,
+}
+
+impl SeaOrmCartRepository {
+    pub fn new(db: Arc<DatabaseConnection>, ttl: Duration) -> Self {
+        Self { db, ttl }
+    }
+}
+
+fn from_row(row: carts::Model) -> Result<Cart> {
+    Ok(Cart {
+        cart_id: row.cartid,
+        mid: row.mid,
+        items: serde_json::from_value(row.items)?,
+        version: row.version,
+        created_gmt: row.created_gmt,
+        modified_gmt: row.modified_gmt,
+    })
+}
+
+#[async_trait]
+impl CartRepository for SeaOrmCartRepository {
+    async fn create(&self, mid: i32) -> Result<Cart> {
+        let cart = Cart::new(mid);
+
+        carts::ActiveModel {
+            cartid: Set(cart.cart_id.clone()),
+            mid: Set(cart.mid),
+            items: Set(serde_json::to_value(&cart.items)?),
+            version: Set(cart.version),
+            created_gmt: Set(cart.created_gmt),
+            modified_gmt: Set(cart.modified_gmt),
+            expires_gmt: Set(expires_gmt(cart.modified_gmt, self.ttl)),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        Ok(cart)
+    }
+
+    async fn get(&self, cart_id: &str) -> Result<Option<Cart>> {
+        carts::Entity::find_by_id(cart_id)
+            .filter(Column::ExpiresGmt.gt(now_gmt()))
+            .one(self.db.as_ref())
+            .await?
+            .map(from_row)
+            .transpose()
+    }
+
+    async fn save(&self, mut cart: Cart) -> Result<Cart> {
+        let now = now_gmt();
+
+        // 🤓 the version check and the write are one statement, so two
+        // replicas saving the same copy can't both win
+        let result = carts::Entity::update_many()
+            .col_expr(Column::Items, Expr::value(serde_json::to_value(&cart.items)?))
+            .col_expr(Column::Version, Expr::value(cart.version + 1))
+            .col_expr(Column::ModifiedGmt, Expr::value(now))
+            .col_expr(Column::ExpiresGmt, Expr::value(expires_gmt(now, self.ttl)))
+            .filter(Column::Cartid.eq(cart.cart_id.as_str()))
+            .filter(Column::Version.eq(cart.version))
+            .filter(Column::ExpiresGmt.gt(now))
+            .exec(self.db.as_ref())
+            .await?;
+
+        if result.rows_affected == 0 {
+            return match self.get(&cart.cart_id).await? {
+                Some(_) => Err(CartError::VersionConflict {
+                    cart_id: cart.cart_id,
+                    expected: cart.version,
+                }),
+                None => Err(CartError::NotFound),
+            };
+        }
+
+        cart.version += 1;
+        cart.modified_gmt = now;
+        Ok(cart)
+    }
+
+    async fn delete(&self, cart_id: &str) -> Result<bool> {
+        let result = carts::Entity::delete_by_id(cart_id).exec(self.db.as_ref()).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn purge_expired(&self) -> Result<u64> {
+        let result = carts::Entity::delete_many()
+            .filter(Column::ExpiresGmt.lte(now_gmt()))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::DEFAULT_TTL;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn row(version: i64) -> carts::Model {
+        carts::Model {
+            cartid: "c1".to_string(),
+            mid: 1,
+            items: serde_json::json!([]),
+            version,
+            created_gmt: 1_700_000_000,
+            modified_gmt: 1_700_000_000,
+            expires_gmt: i32::MAX,
+        }
+    }
+
+    fn cart(version: i64) -> Cart {
+        from_row(row(version)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_save_bumps_version() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let repo = SeaOrmCartRepository::new(Arc::new(db), DEFAULT_TTL);
+
+        let saved = repo.save(cart(3)).await.unwrap();
+        assert_eq!(saved.version, 4);
+
+        let db = Arc::into_inner(repo.db).unwrap();
+        let log = db.into_transaction_log();
+        let sql = log[0].statements()[0].sql.clone();
+        assert!(sql.contains(r#""version" = $"#), "{sql}");
+    }
+
+    #[tokio::test]
+    async fn test_stale_save_is_a_conflict() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            }])
+            .append_query_results([vec![row(4)]])
+            .into_connection();
+        let repo = SeaOrmCartRepository::new(Arc::new(db), DEFAULT_TTL);
+
+        let err = repo.save(cart(3)).await.unwrap_err();
+        assert!(matches!(err, CartError::VersionConflict { expected: 3, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_save_of_expired_cart_is_not_found() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            }])
+            .append_query_results([Vec::<carts::Model>::new()])
+            .into_connection();
+        let repo = SeaOrmCartRepository::new(Arc::new(db), DEFAULT_TTL);
+
+        assert!(matches!(repo.save(cart(3)).await, Err(CartError::NotFound)));
+    }
+}
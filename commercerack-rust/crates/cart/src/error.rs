@@ -0,0 +1,37 @@
+//! Cart service error types
+
+use commercerack_product::ProductError;
+use sea_orm::DbErr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CartError {
+    #[error("Cart not found")]
+    NotFound,
+
+    #[error("Item {0} is not in the cart")]
+    ItemNotFound(String),
+
+    #[error("Unknown SKU {0}")]
+    UnknownSku(String),
+
+    #[error("Only {available} of {sku} in stock")]
+    OutOfStock { sku: String, available: i32 },
+
+    #[error("Cart {cart_id} was changed by another request (expected version {expected})")]
+    VersionConflict { cart_id: String, expected: i64 },
+
+    #[error(transparent)]
+    Catalog(#[from] ProductError),
+
+    #[error(transparent)]
+    Serialization(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Db(#[from] DbErr),
+
+    #[error(transparent)]
+    Redis(#[from] redis::RedisError),
+}
+
+pub type Result<T> = std::result::Result<T, CartError>;
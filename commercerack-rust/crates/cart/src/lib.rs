@@ -1,8 +1,24 @@
+//! Shopping carts and their storage backends
+
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+pub mod database;
+pub mod error;
+pub mod memory;
+pub mod pricing;
+pub mod redis_store;
+pub mod repository;
+
+pub use database::SeaOrmCartRepository;
+pub use error::{CartError, Result};
+pub use memory::InMemoryCartRepository;
+pub use pricing::CartPricing;
+pub use redis_store::RedisCartRepository;
+pub use repository::{update_cart, CartRepository, DEFAULT_TTL};
+
 /// Represents a single item in the shopping cart
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CartItem {
@@ -10,6 +26,9 @@ pub struct CartItem {
     pub product_name: String,
     pub quantity: i32,
     pub unit_price: Decimal,
+    /// Price the line was added at, kept while the catalog price differs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_unit_price: Option<Decimal>,
 }
 
 impl CartItem {
@@ -19,35 +38,66 @@ impl CartItem {
             product_name,
             quantity,
             unit_price,
+            previous_unit_price: None,
         }
     }
 
     pub fn subtotal(&self) -> Decimal {
         self.unit_price * Decimal::from(self.quantity)
     }
+
+    /// Whether the catalog price changed since the line was added
+    pub fn price_changed(&self) -> bool {
+        self.previous_unit_price.is_some()
+    }
+
+    /// Move the line to `unit_price`, remembering the price it was added at.
+    /// Returns false if the price was already current.
+    pub fn reprice(&mut self, unit_price: Decimal) -> bool {
+        if self.unit_price == unit_price {
+            return false;
+        }
+
+        let original = self.previous_unit_price.unwrap_or(self.unit_price);
+        self.previous_unit_price = (original != unit_price).then_some(original);
+        self.unit_price = unit_price;
+        true
+    }
 }
 
-/// Shopping cart with in-memory storage
+/// Shopping cart; persisted through a [`CartRepository`]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Cart {
     pub cart_id: String,
+    /// Merchant whose catalog prices this cart
+    #[serde(default)]
+    pub mid: i32,
     pub items: Vec<CartItem>,
+    /// Version this copy was loaded at; saving a stale copy is rejected
+    #[serde(default)]
+    pub version: i64,
+    #[serde(default)]
+    pub created_gmt: i32,
+    #[serde(default)]
+    pub modified_gmt: i32,
 }
 
 impl Cart {
     /// Create a new empty cart with a unique ID
-    pub fn new() -> Self {
-        Self {
-            cart_id: Uuid::new_v4().to_string(),
-            items: Vec::new(),
-        }
+    pub fn new(mid: i32) -> Self {
+        Self::with_id(mid, Uuid::new_v4().to_string())
     }
 
     /// Create a cart with a specific ID (for restoration)
-    pub fn with_id(cart_id: String) -> Self {
+    pub fn with_id(mid: i32, cart_id: String) -> Self {
+        let now = repository::now_gmt();
         Self {
             cart_id,
+            mid,
             items: Vec::new(),
+            version: 0,
+            created_gmt: now,
+            modified_gmt: now,
         }
     }
 
@@ -60,6 +110,18 @@ impl Cart {
         }
     }
 
+    /// Apply current catalog prices by SKU; lines missing from `prices` keep
+    /// theirs. Returns true if any line changed.
+    pub fn apply_prices(&mut self, prices: &HashMap<String, Decimal>) -> bool {
+        let mut changed = false;
+        for item in &mut self.items {
+            if let Some(price) = prices.get(&item.sku) {
+                changed |= item.reprice(*price);
+            }
+        }
+        changed
+    }
+
     /// Remove an item completely from the cart
     pub fn remove_item(&mut self, sku: &str) -> bool {
         if let Some(pos) = self.items.iter().position(|item| item.sku == sku) {
@@ -110,62 +172,13 @@ impl Cart {
     }
 }
 
-impl Default for Cart {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-/// Cart store for managing multiple carts (in-memory storage)
-/// In production, this would be backed by Redis or a database
-pub struct CartStore {
-    carts: HashMap<String, Cart>,
-}
-
-impl CartStore {
-    pub fn new() -> Self {
-        Self {
-            carts: HashMap::new(),
-        }
-    }
-
-    pub fn create_cart(&mut self) -> String {
-        let cart = Cart::new();
-        let cart_id = cart.cart_id.clone();
-        self.carts.insert(cart_id.clone(), cart);
-        cart_id
-    }
-
-    pub fn get_cart(&self, cart_id: &str) -> Option<&Cart> {
-        self.carts.get(cart_id)
-    }
-
-    pub fn get_cart_mut(&mut self, cart_id: &str) -> Option<&mut Cart> {
-        self.carts.get_mut(cart_id)
-    }
-
-    pub fn save_cart(&mut self, cart: Cart) {
-        self.carts.insert(cart.cart_id.clone(), cart);
-    }
-
-    pub fn delete_cart(&mut self, cart_id: &str) -> bool {
-        self.carts.remove(cart_id).is_some()
-    }
-}
-
-impl Default for CartStore {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_cart_operations() {
-        let mut cart = Cart::new();
+        let mut cart = Cart::new(1);
         assert!(cart.is_empty());
         assert_eq!(cart.item_count(), 0);
 
@@ -205,7 +218,7 @@ mod tests {
 
     #[test]
     fn test_cart_update_and_remove() {
-        let mut cart = Cart::new();
+        let mut cart = Cart::new(1);
 
         cart.add_item("SKU001".to_string(), "Widget".to_string(), 5, Decimal::new(1000, 2));
         cart.add_item("SKU002".to_string(), "Gadget".to_string(), 3, Decimal::new(2000, 2));
@@ -226,25 +239,25 @@ mod tests {
     }
 
     #[test]
-    fn test_cart_store() {
-        let mut store = CartStore::new();
-
-        // Create cart
-        let cart_id = store.create_cart();
-        assert!(store.get_cart(&cart_id).is_some());
-
-        // Add items to cart
-        if let Some(cart) = store.get_cart_mut(&cart_id) {
-            cart.add_item("SKU001".to_string(), "Widget".to_string(), 2, Decimal::new(1999, 2));
-        }
-
-        // Verify cart has item
-        let cart = store.get_cart(&cart_id).unwrap();
-        assert_eq!(cart.items.len(), 1);
-        assert_eq!(cart.item_count(), 2);
-
-        // Delete cart
-        assert!(store.delete_cart(&cart_id));
-        assert!(store.get_cart(&cart_id).is_none());
+    fn test_apply_prices_flags_changed_lines() {
+        let mut cart = Cart::new(1);
+        cart.add_item("SKU001".to_string(), "Widget".to_string(), 1, Decimal::new(1000, 2));
+        cart.add_item("SKU002".to_string(), "Gadget".to_string(), 1, Decimal::new(2000, 2));
+
+        let mut prices = HashMap::new();
+        prices.insert("SKU001".to_string(), Decimal::new(1200, 2));
+        prices.insert("SKU002".to_string(), Decimal::new(2000, 2));
+        assert!(cart.apply_prices(&prices));
+
+        let widget = cart.get_item("SKU001").unwrap();
+        assert_eq!(widget.unit_price, Decimal::new(1200, 2));
+        assert_eq!(widget.previous_unit_price, Some(Decimal::new(1000, 2)));
+        assert!(!cart.get_item("SKU002").unwrap().price_changed());
+        assert!(!cart.apply_prices(&prices));
+
+        // Back to the price it was added at: no longer flagged
+        prices.insert("SKU001".to_string(), Decimal::new(1000, 2));
+        assert!(cart.apply_prices(&prices));
+        assert!(!cart.get_item("SKU001").unwrap().price_changed());
     }
 }
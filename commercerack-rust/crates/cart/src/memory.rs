@@ -0,0 +1,149 @@
+//! In-process cart storage for development and tests
+//!
+//! Carts live in this process only: they are lost on restart and not shared
+//! between replicas.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use crate::error::{CartError, Result};
+use crate::repository::{expires_gmt, now_gmt, CartRepository, DEFAULT_TTL};
+use crate::Cart;
+
+pub struct InMemoryCartRepository {
+    carts: RwLock<HashMap<String, Cart>>,
+    ttl: Duration,
+}
+
+impl InMemoryCartRepository {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            carts: RwLock::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    fn is_live(&self, cart: &Cart, now: i32) -> bool {
+        expires_gmt(cart.modified_gmt, self.ttl) > now
+    }
+}
+
+impl Default for InMemoryCartRepository {
+    fn default() -> Self {
+        Self::new(DEFAULT_TTL)
+    }
+}
+
+#[async_trait]
+impl CartRepository for InMemoryCartRepository {
+    async fn create(&self, mid: i32) -> Result<Cart> {
+        let cart = Cart::new(mid);
+        self.carts
+            .write()
+            .await
+            .insert(cart.cart_id.clone(), cart.clone());
+        Ok(cart)
+    }
+
+    async fn get(&self, cart_id: &str) -> Result<Option<Cart>> {
+        let now = now_gmt();
+        let carts = self.carts.read().await;
+        Ok(carts.get(cart_id).filter(|c| self.is_live(c, now)).cloned())
+    }
+
+    async fn save(&self, mut cart: Cart) -> Result<Cart> {
+        let now = now_gmt();
+        let mut carts = self.carts.write().await;
+
+        let stored = carts
+            .get(&cart.cart_id)
+            .filter(|c| self.is_live(c, now))
+            .ok_or(CartError::NotFound)?;
+        if stored.version != cart.version {
+            return Err(CartError::VersionConflict {
+                cart_id: cart.cart_id,
+                expected: cart.version,
+            });
+        }
+
+        cart.version += 1;
+        cart.modified_gmt = now;
+        carts.insert(cart.cart_id.clone(), cart.clone());
+        Ok(cart)
+    }
+
+    async fn delete(&self, cart_id: &str) -> Result<bool> {
+        Ok(self.carts.write().await.remove(cart_id).is_some())
+    }
+
+    async fn purge_expired(&self) -> Result<u64> {
+        let now = now_gmt();
+        let mut carts = self.carts.write().await;
+        let before = carts.len();
+        carts.retain(|_, c| self.is_live(c, now));
+        Ok((before - carts.len()) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::update_cart;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_create_get_delete() {
+        let repo = InMemoryCartRepository::default();
+
+        let cart = repo.create(1).await.unwrap();
+        assert_eq!(cart.version, 0);
+        assert!(repo.get(&cart.cart_id).await.unwrap().is_some());
+
+        assert!(repo.delete(&cart.cart_id).await.unwrap());
+        assert!(repo.get(&cart.cart_id).await.unwrap().is_none());
+        assert!(!repo.delete(&cart.cart_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_stale_save_is_rejected() {
+        let repo = InMemoryCartRepository::default();
+        let cart = repo.create(1).await.unwrap();
+
+        let mut first = cart.clone();
+        first.add_item("SKU001".to_string(), "Widget".to_string(), 1, Decimal::ONE);
+        let saved = repo.save(first).await.unwrap();
+        assert_eq!(saved.version, 1);
+
+        let err = repo.save(cart).await.unwrap_err();
+        assert!(matches!(err, CartError::VersionConflict { expected: 0, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_update_cart_applies_change() {
+        let repo = InMemoryCartRepository::default();
+        let cart = repo.create(1).await.unwrap();
+
+        let updated = update_cart(&repo, &cart.cart_id, |c| {
+            c.add_item("SKU001".to_string(), "Widget".to_string(), 2, Decimal::ONE);
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(updated.version, 1);
+        assert_eq!(repo.get(&cart.cart_id).await.unwrap().unwrap().item_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_carts_expire() {
+        let repo = InMemoryCartRepository::new(Duration::ZERO);
+        let cart = repo.create(1).await.unwrap();
+        repo.create(1).await.unwrap();
+
+        assert!(repo.get(&cart.cart_id).await.unwrap().is_none());
+        assert!(matches!(repo.save(cart).await, Err(CartError::NotFound)));
+        assert_eq!(repo.purge_expired().await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,197 @@
+//! Cart line pricing from the SKU catalog
+//!
+//! Clients only say which SKU and how many; name and unit price always come
+//! from `sku_lookup`.
+
+use ::entity::prelude::Sku;
+use commercerack_product::sku::sellable_quantity;
+use commercerack_product::{ProductService, SkuService};
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::collections::HashMap;
+
+use crate::error::{CartError, Result};
+use crate::{Cart, CartItem};
+
+/// Catalog pricing for cart lines
+pub struct CartPricing;
+
+impl CartPricing {
+    /// Catalog-priced line for `quantity` units of `sku`
+    ///
+    /// `quantity` is the total the cart would hold, so stock is checked
+    /// against everything already in the cart too. `held` of those units are
+    /// already reserved by the cart's stock hold and need not be available.
+    pub async fn quote(
+        db: &DatabaseConnection,
+        mid: i32,
+        sku: &str,
+        quantity: i32,
+        held: i32,
+    ) -> Result<CartItem> {
+        let entry = find(db, mid, sku).await?;
+        check_stock(&entry, quantity, held)?;
+        line(db, mid, entry, quantity).await
+    }
+
+    /// Catalog-priced line for `quantity` units of a bundle or assembly
+    ///
+    /// The kit's own SKU carries no stock; holding its components is the
+    /// stock check.
+    pub async fn quote_kit(
+        db: &DatabaseConnection,
+        mid: i32,
+        sku: &str,
+        quantity: i32,
+    ) -> Result<CartItem> {
+        let entry = find(db, mid, sku).await?;
+        line(db, mid, entry, quantity).await
+    }
+
+    /// Current catalog price of every SKU in `cart` that is still listed
+    pub async fn current_prices(
+        db: &DatabaseConnection,
+        cart: &Cart,
+    ) -> Result<HashMap<String, Decimal>> {
+        let skus: Vec<&str> = cart.items.iter().map(|i| i.sku.as_str()).collect();
+        let entries = SkuService::find_many(db, cart.mid, &skus).await?;
+
+        Ok(entries.into_iter().map(|e| (e.sku, e.price)).collect())
+    }
+}
+
+async fn find(db: &DatabaseConnection, mid: i32, sku: &str) -> Result<Sku> {
+    SkuService::find_by_sku(db, mid, sku)
+        .await?
+        .ok_or_else(|| CartError::UnknownSku(sku.to_string()))
+}
+
+async fn line(db: &DatabaseConnection, mid: i32, entry: Sku, quantity: i32) -> Result<CartItem> {
+    let product_name = if entry.title.is_empty() {
+        ProductService::find_by_product_id(db, mid, &entry.pid)
+            .await?
+            .map(|p| p.product_name)
+            .unwrap_or_default()
+    } else {
+        entry.title
+    };
+
+    Ok(CartItem::new(entry.sku, product_name, quantity, entry.price))
+}
+
+fn check_stock(entry: &Sku, quantity: i32, held: i32) -> Result<()> {
+    match sellable_quantity(entry).map(|available| available + held) {
+        Some(available) if quantity > available => Err(CartError::OutOfStock {
+            sku: entry.sku.clone(),
+            available: available.max(0),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    fn sku(code: &str, price: Decimal, available: i32) -> Sku {
+        Sku {
+            id: 1,
+            mid: 1,
+            pid: "WIDGET".to_string(),
+            invopts: String::new(),
+            grp_parent: String::new(),
+            sku: code.to_string(),
+            title: "Blue Widget".to_string(),
+            cost: Decimal::ZERO,
+            price,
+            upc: String::new(),
+            mfgid: String::new(),
+            supplierid: String::new(),
+            prodasm: None,
+            assembly: None,
+            inv_available: available,
+            qty_onshelf: available,
+            qty_onorder: 0,
+            qty_needship: 0,
+            qty_markets: 0,
+            qty_legacy: 0,
+            qty_reserved: 0,
+            amz_asin: String::new(),
+            amz_feeds_done: 0,
+            amz_feeds_todo: 0,
+            amz_feeds_sent: 0,
+            amz_feeds_wait: 0,
+            amz_feeds_warn: 0,
+            amz_feeds_error: 0,
+            amz_productdb_gmt: 0,
+            amz_error: String::new(),
+            inv_on_shelf: available,
+            inv_on_order: 0,
+            inv_is_bo: 0,
+            inv_reorder: 0,
+            inv_is_rsvp: 0,
+            dss_agent: String::new(),
+            dss_run: None,
+            dss_mood: None,
+            dss_config: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quote_uses_catalog_price() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku("WIDGET:BLU", Decimal::new(1999, 2), 5)]])
+            .into_connection();
+
+        let line = CartPricing::quote(&db, 1, "WIDGET:BLU", 2, 0).await.unwrap();
+        assert_eq!(line.unit_price, Decimal::new(1999, 2));
+        assert_eq!(line.product_name, "Blue Widget");
+        assert_eq!(line.quantity, 2);
+    }
+
+    #[tokio::test]
+    async fn test_quote_rejects_unknown_sku() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Sku>::new()])
+            .into_connection();
+
+        let err = CartPricing::quote(&db, 1, "NOPE", 1, 0).await.unwrap_err();
+        assert!(matches!(err, CartError::UnknownSku(s) if s == "NOPE"));
+    }
+
+    #[tokio::test]
+    async fn test_quote_rejects_more_than_in_stock() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku("WIDGET:BLU", Decimal::ONE, 3)]])
+            .append_query_results([vec![sku("WIDGET:BLU", Decimal::ONE, 3)]])
+            .into_connection();
+
+        let err = CartPricing::quote(&db, 1, "WIDGET:BLU", 4, 0).await.unwrap_err();
+        assert!(matches!(err, CartError::OutOfStock { available: 3, .. }));
+
+        // Units the cart already holds don't have to be available again
+        assert!(CartPricing::quote(&db, 1, "WIDGET:BLU", 4, 1).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_backorderable_sku_ignores_stock() {
+        let mut entry = sku("WIDGET:BLU", Decimal::ONE, 0);
+        entry.inv_is_bo = 1;
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![entry]])
+            .into_connection();
+
+        assert!(CartPricing::quote(&db, 1, "WIDGET:BLU", 10, 0).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_kit_quote_skips_its_own_stock() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku("KIT", Decimal::TEN, 0)]])
+            .into_connection();
+
+        let item = CartPricing::quote_kit(&db, 1, "KIT", 2).await.unwrap();
+        assert_eq!((item.quantity, item.unit_price), (2, Decimal::TEN));
+    }
+}
@@ -0,0 +1,149 @@
+//! Cart storage in Redis
+//!
+//! Each cart is one JSON string under `cart:{id}` with a Redis TTL, so
+//! abandoned carts expire on their own and [`CartRepository::purge_expired`]
+//! has nothing to do.
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Script};
+use std::time::Duration;
+
+use crate::error::{CartError, Result};
+use crate::repository::{now_gmt, CartRepository};
+use crate::Cart;
+
+const KEY_PREFIX: &str = "cart:";
+
+/// Compare-and-set on the stored version: 1 = saved, 0 = missing, -1 = stale
+const SAVE_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if not current then return 0 end
+if cjson.decode(current)['version'] ~= tonumber(ARGV[1]) then return -1 end
+redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[3])
+return 1
+"#;
+
+pub struct RedisCartRepository {
+    conn: ConnectionManager,
+    ttl: Duration,
+    save_script: Script,
+}
+
+impl RedisCartRepository {
+    pub fn new(conn: ConnectionManager, ttl: Duration) -> Self {
+        Self {
+            conn,
+            ttl,
+            save_script: Script::new(SAVE_SCRIPT),
+        }
+    }
+
+    /// Connect to the Redis server at `url`, e.g. `redis://127.0.0.1/`
+    pub async fn connect(url: &str, ttl: Duration) -> Result<Self> {
+        let client = redis::Client::open(url)?;
+        let conn = ConnectionManager::new(client).await?;
+        Ok(Self::new(conn, ttl))
+    }
+
+    fn key(cart_id: &str) -> String {
+        format!("{}{}", KEY_PREFIX, cart_id)
+    }
+
+    /// Redis rejects `EX 0`, so round the TTL up to a second
+    fn ttl_secs(&self) -> u64 {
+        self.ttl.as_secs().max(1)
+    }
+}
+
+#[async_trait]
+impl CartRepository for RedisCartRepository {
+    async fn create(&self, mid: i32) -> Result<Cart> {
+        let cart = Cart::new(mid);
+        let mut conn = self.conn.clone();
+
+        redis::cmd("SET")
+            .arg(Self::key(&cart.cart_id))
+            .arg(serde_json::to_string(&cart)?)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.ttl_secs())
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        Ok(cart)
+    }
+
+    async fn get(&self, cart_id: &str) -> Result<Option<Cart>> {
+        let mut conn = self.conn.clone();
+        let raw: Option<String> = conn.get(Self::key(cart_id)).await?;
+        Ok(raw.map(|json| serde_json::from_str(&json)).transpose()?)
+    }
+
+    async fn save(&self, cart: Cart) -> Result<Cart> {
+        let mut conn = self.conn.clone();
+
+        let mut next = cart.clone();
+        next.version += 1;
+        next.modified_gmt = now_gmt();
+
+        let outcome: i64 = self
+            .save_script
+            .key(Self::key(&cart.cart_id))
+            .arg(cart.version)
+            .arg(serde_json::to_string(&next)?)
+            .arg(self.ttl_secs())
+            .invoke_async(&mut conn)
+            .await?;
+
+        match outcome {
+            1 => Ok(next),
+            0 => Err(CartError::NotFound),
+            _ => Err(CartError::VersionConflict {
+                cart_id: cart.cart_id,
+                expected: cart.version,
+            }),
+        }
+    }
+
+    async fn delete(&self, cart_id: &str) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        let removed: i64 = conn.del(Self::key(cart_id)).await?;
+        Ok(removed > 0)
+    }
+
+    async fn purge_expired(&self) -> Result<u64> {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_versioned_save_round_trip() {
+        let Ok(url) = std::env::var("TEST_REDIS_URL") else {
+            eprintln!("Skipping test: TEST_REDIS_URL not set");
+            return;
+        };
+        let repo = RedisCartRepository::connect(&url, Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let cart = repo.create(1).await.unwrap();
+        let mut changed = cart.clone();
+        changed.add_item("SKU001".to_string(), "Widget".to_string(), 2, Decimal::ONE);
+
+        let saved = repo.save(changed).await.unwrap();
+        assert_eq!(saved.version, 1);
+        assert_eq!(repo.get(&cart.cart_id).await.unwrap().unwrap().item_count(), 2);
+
+        let err = repo.save(cart.clone()).await.unwrap_err();
+        assert!(matches!(err, CartError::VersionConflict { expected: 0, .. }));
+
+        assert!(repo.delete(&cart.cart_id).await.unwrap());
+        assert!(matches!(repo.save(saved).await, Err(CartError::NotFound)));
+    }
+}
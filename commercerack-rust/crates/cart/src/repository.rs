@@ -0,0 +1,70 @@
+//! Storage-agnostic cart persistence
+//!
+//! Every backend keeps carts for a sliding TTL: each save pushes the expiry
+//! out again, and a cart nobody touched for that long is abandoned and reads
+//! back as missing. Saves are optimistic: a [`Cart`] carries the `version` it
+//! was loaded at and [`CartRepository::save`] refuses a stale copy.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use std::time::Duration;
+
+use crate::error::{CartError, Result};
+use crate::Cart;
+
+/// Default abandonment window: one week without changes
+pub const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How often [`update_cart`] retries after losing a race with another writer
+const MAX_SAVE_ATTEMPTS: usize = 3;
+
+#[async_trait]
+pub trait CartRepository: Send + Sync {
+    /// Create and persist a new empty cart for merchant `mid`
+    async fn create(&self, mid: i32) -> Result<Cart>;
+
+    /// Load a cart; abandoned carts read back as `None`
+    async fn get(&self, cart_id: &str) -> Result<Option<Cart>>;
+
+    /// Persist `cart` if nobody saved it since it was loaded
+    ///
+    /// Returns the stored cart with its version bumped and expiry extended,
+    /// [`CartError::VersionConflict`] for a stale copy, or
+    /// [`CartError::NotFound`] if the cart is gone.
+    async fn save(&self, cart: Cart) -> Result<Cart>;
+
+    /// Delete a cart; `false` if it did not exist
+    async fn delete(&self, cart_id: &str) -> Result<bool>;
+
+    /// Remove abandoned carts, returning how many were removed
+    async fn purge_expired(&self) -> Result<u64>;
+}
+
+/// Load a cart, apply `change` and save it, retrying on version conflicts
+///
+/// `change` may run more than once, each time against the latest copy.
+pub async fn update_cart<F>(repo: &dyn CartRepository, cart_id: &str, mut change: F) -> Result<Cart>
+where
+    F: FnMut(&mut Cart) -> Result<()> + Send,
+{
+    let mut attempt = 1;
+    loop {
+        let mut cart = repo.get(cart_id).await?.ok_or(CartError::NotFound)?;
+        change(&mut cart)?;
+
+        match repo.save(cart).await {
+            Err(CartError::VersionConflict { .. }) if attempt < MAX_SAVE_ATTEMPTS => attempt += 1,
+            result => return result,
+        }
+    }
+}
+
+pub(crate) fn now_gmt() -> i32 {
+    Utc::now().timestamp() as i32
+}
+
+/// Expiry for a cart saved at `now`
+pub(crate) fn expires_gmt(now: i32, ttl: Duration) -> i32 {
+    let ttl = i32::try_from(ttl.as_secs()).unwrap_or(i32::MAX);
+    now.saturating_add(ttl)
+}
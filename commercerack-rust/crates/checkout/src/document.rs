@@ -0,0 +1,111 @@
+//! Order document stored in `orders.yaml`
+//!
+//! The legacy `orders` row only indexes a few summary columns; lines,
+//! addresses and totals live in the YAML document, as they always have.
+
+use ::entity::prelude::{Customer, CustomerAddr};
+use commercerack_cart::{Cart, CartItem};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderAddress {
+    pub firstname: String,
+    pub lastname: String,
+    pub company: String,
+    pub address1: String,
+    pub address2: String,
+    pub city: String,
+    pub state: String,
+    pub zip: String,
+    pub country: String,
+    pub phone: String,
+}
+
+impl OrderAddress {
+    pub fn full_name(&self) -> String {
+        format!("{} {}", self.firstname, self.lastname).trim().to_string()
+    }
+}
+
+impl From<&CustomerAddr> for OrderAddress {
+    fn from(addr: &CustomerAddr) -> Self {
+        Self {
+            firstname: addr.firstname.clone(),
+            lastname: addr.lastname.clone(),
+            company: addr.company.clone(),
+            address1: addr.address1.clone(),
+            address2: addr.address2.clone(),
+            city: addr.city.clone(),
+            state: addr.state.clone(),
+            zip: addr.zip.clone(),
+            country: addr.country.clone(),
+            phone: addr.phone.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderLine {
+    pub sku: String,
+    pub name: String,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub extended: Decimal,
+}
+
+impl From<&CartItem> for OrderLine {
+    fn from(item: &CartItem) -> Self {
+        Self {
+            sku: item.sku.clone(),
+            name: item.product_name.clone(),
+            quantity: item.quantity,
+            unit_price: item.unit_price,
+            extended: item.subtotal(),
+        }
+    }
+}
+
+/// Order totals; no shipping or tax is charged yet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderTotals {
+    pub item_count: i32,
+    pub subtotal: Decimal,
+    pub total: Decimal,
+}
+
+impl OrderTotals {
+    pub fn of(cart: &Cart) -> Self {
+        let subtotal = cart.subtotal();
+        Self {
+            item_count: cart.item_count(),
+            subtotal,
+            total: subtotal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderDocument {
+    pub cartid: String,
+    pub customer: i32,
+    pub email: String,
+    pub bill: OrderAddress,
+    pub ship: OrderAddress,
+    pub lines: Vec<OrderLine>,
+    pub totals: OrderTotals,
+}
+
+impl OrderDocument {
+    pub fn new(cart: &Cart, customer: &Customer, bill: &CustomerAddr, ship: &CustomerAddr) -> Self {
+        Self {
+            cartid: cart.cart_id.clone(),
+            customer: customer.cid,
+            email: customer.email.clone(),
+            bill: bill.into(),
+            ship: ship.into(),
+            lines: cart.items.iter().map(Into::into).collect(),
+            totals: OrderTotals::of(cart),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Checkout error types
+
+use commercerack_cart::CartError;
+use commercerack_customer::CustomerError;
+use commercerack_inventory::InventoryError;
+use commercerack_order::OrderError;
+use sea_orm::DbErr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CheckoutError {
+    #[error("Cart is empty")]
+    EmptyCart,
+
+    #[error("Prices changed for {}; review the cart and check out again", .0.join(", "))]
+    PricesChanged(Vec<String>),
+
+    #[error("Address {0} not found")]
+    AddressNotFound(i32),
+
+    #[error("Customer has no address on file")]
+    MissingAddress,
+
+    #[error("Not enough {0} in stock")]
+    OutOfStock(String),
+
+    #[error(transparent)]
+    Cart(#[from] CartError),
+
+    #[error(transparent)]
+    Customer(#[from] CustomerError),
+
+    #[error(transparent)]
+    Order(#[from] OrderError),
+
+    #[error(transparent)]
+    Inventory(#[from] InventoryError),
+
+    #[error(transparent)]
+    Document(#[from] serde_yaml::Error),
+
+    #[error(transparent)]
+    Db(#[from] DbErr),
+}
+
+pub type Result<T> = std::result::Result<T, CheckoutError>;
@@ -0,0 +1,709 @@
+//! Checkout: turning a cart into an order
+//!
+//! A checkout re-prices the cart against the catalog, attaches the customer
+//! and their addresses, then allocates an order ID, turns the cart's stock
+//! holds into the order's reservation and inserts the order in one transaction.
+//! Bundles and assemblies reserve the components they are made of, never
+//! their own SKU. Checkouts are keyed by cart:
+//! checking out the same cart again returns the order it already produced,
+//! backed by the unique `idx_orders_mid_cartid` index.
+
+use ::entity::checkouts;
+use ::entity::prelude::{Checkout, Checkouts, CustomerAddr, CustomerAddrs, Order};
+use ::entity::sea_orm_active_enums::CheckoutAssist;
+use chrono::Utc;
+use commercerack_cart::{update_cart, Cart, CartError, CartPricing, CartRepository};
+use commercerack_customer::{CustomerError, CustomerService};
+use commercerack_inventory::{InventoryError, ReservationService, StockLine};
+use commercerack_order::{NewOrder, OrderError, OrderIdAllocator, OrderPool, OrderService};
+use commercerack_product::{ProductRelationService, SkuQty};
+use sea_orm::*;
+
+pub mod document;
+pub mod error;
+
+pub use document::{OrderAddress, OrderDocument, OrderLine, OrderTotals};
+pub use error::{CheckoutError, Result};
+
+/// `checkouts.checkout_stage` while the cart is still being checked out
+pub const STAGE_OPEN: &str = "OPEN";
+/// `checkouts.checkout_stage` once the order is placed
+pub const STAGE_PLACED: &str = "PLACED";
+
+/// `luser` recorded on the inventory rows a checkout reserves
+const LUSER: &str = "checkout";
+
+#[derive(Debug, Clone)]
+pub struct CheckoutRequest {
+    pub mid: i32,
+    pub cart_id: String,
+    pub customer: i32,
+    /// `customer_addrs.id` to bill; defaults to the customer's default address
+    pub bill_address: Option<i32>,
+    /// `customer_addrs.id` to ship to; defaults to the customer's default address
+    pub ship_address: Option<i32>,
+    pub sdomain: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckoutOutcome {
+    pub order: Order,
+    /// False when the cart had already been checked out
+    pub created: bool,
+}
+
+/// Checkout service
+pub struct CheckoutService;
+
+impl CheckoutService {
+    /// Place the order for a cart, or return the one it already produced
+    ///
+    /// The cart is deleted once the order is committed. If any catalog price
+    /// moved, the cart is re-priced and [`CheckoutError::PricesChanged`]
+    /// is returned so the customer can review it first.
+    pub async fn checkout(
+        db: &DatabaseConnection,
+        carts: &dyn CartRepository,
+        req: &CheckoutRequest,
+    ) -> Result<CheckoutOutcome> {
+        if let Some(order) = OrderService::find_by_cartid(db, req.mid, &req.cart_id).await? {
+            return Self::replay(carts, req, order).await;
+        }
+
+        let cart = carts
+            .get(&req.cart_id)
+            .await?
+            .filter(|cart| cart.mid == req.mid)
+            .ok_or(CartError::NotFound)?;
+        Self::validate(db, carts, &cart).await?;
+
+        let customer = CustomerService::find_by_id(db, req.mid, req.customer)
+            .await?
+            .ok_or(CustomerError::NotFound)?;
+        let addresses = CustomerAddrs::find()
+            .filter(::entity::customer_addrs::Column::Mid.eq(req.mid))
+            .filter(::entity::customer_addrs::Column::Cid.eq(req.customer))
+            .order_by_asc(::entity::customer_addrs::Column::Id)
+            .all(db)
+            .await?;
+        let bill = pick_address(&addresses, req.bill_address)?;
+        let ship = pick_address(&addresses, req.ship_address)?;
+        let document = OrderDocument::new(&cart, &customer, bill, ship);
+
+        let checkout = Self::open(db, req).await?;
+
+        let stock = stock_lines(db, req.mid, &cart).await?;
+
+        let txn = db.begin().await?;
+        let orderid = OrderIdAllocator::next(&txn, req.mid).await?;
+        reserve_stock(&txn, req.mid, &cart, &orderid, &stock).await?;
+        let new_order = NewOrder {
+            mid: req.mid,
+            orderid,
+            cartid: cart.cart_id.clone(),
+            customer: customer.cid,
+            pool: OrderPool::Recent,
+            total: document.totals.total,
+            items: i16::try_from(document.totals.item_count).unwrap_or(i16::MAX),
+            bill_name: document.bill.full_name(),
+            bill_email: customer.email.clone(),
+            bill_phone: document.bill.phone.clone(),
+            bill_zone: document.bill.zip.clone(),
+            ship_name: document.ship.full_name(),
+            ship_zone: document.ship.zip.clone(),
+            sdomain: req.sdomain.clone(),
+            yaml: serde_yaml::to_string(&document)?,
+        };
+
+        let order = match OrderService::insert(&txn, new_order).await {
+            Ok(order) => order,
+            Err(err @ OrderError::DuplicateOrder(_)) => {
+                // 🤓 a concurrent checkout of this cart won the unique index
+                txn.rollback().await?;
+                return match OrderService::find_by_cartid(db, req.mid, &req.cart_id).await? {
+                    Some(order) => Self::replay(carts, req, order).await,
+                    None => Err(err.into()),
+                };
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut checkout: checkouts::ActiveModel = checkout.into();
+        checkout.cid = Set(customer.cid);
+        checkout.closed_gmt = Set(now_gmt());
+        checkout.checkout_stage = Set(STAGE_PLACED.to_string());
+        checkout.update(&txn).await?;
+
+        txn.commit().await?;
+
+        if let Err(err) = carts.delete(&cart.cart_id).await {
+            tracing::warn!(cart_id = %cart.cart_id, error = %err, "checked-out cart not deleted");
+        }
+
+        Ok(CheckoutOutcome {
+            order,
+            created: true,
+        })
+    }
+
+    /// The order a cart already produced, if it belongs to this customer
+    async fn replay(
+        carts: &dyn CartRepository,
+        req: &CheckoutRequest,
+        order: Order,
+    ) -> Result<CheckoutOutcome> {
+        if order.customer != req.customer {
+            return Err(CartError::NotFound.into());
+        }
+
+        // A crash between commit and delete can leave the cart behind
+        carts.delete(&req.cart_id).await?;
+        Ok(CheckoutOutcome {
+            order,
+            created: false,
+        })
+    }
+
+    /// Refuse empty carts and carts whose prices no longer match the catalog
+    async fn validate(
+        db: &DatabaseConnection,
+        carts: &dyn CartRepository,
+        cart: &Cart,
+    ) -> Result<()> {
+        if cart.is_empty() {
+            return Err(CheckoutError::EmptyCart);
+        }
+
+        let prices = CartPricing::current_prices(db, cart).await?;
+        if let Some(item) = cart.items.iter().find(|i| !prices.contains_key(&i.sku)) {
+            return Err(CartError::UnknownSku(item.sku.clone()).into());
+        }
+
+        let changed: Vec<String> = cart
+            .items
+            .iter()
+            .filter(|i| prices[&i.sku] != i.unit_price)
+            .map(|i| i.sku.clone())
+            .collect();
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        update_cart(carts, &cart.cart_id, |cart| {
+            cart.apply_prices(&prices);
+            Ok(())
+        })
+        .await?;
+        Err(CheckoutError::PricesChanged(changed))
+    }
+
+    /// The cart's open `checkouts` row, created on the first attempt
+    async fn open(db: &DatabaseConnection, req: &CheckoutRequest) -> Result<Checkout> {
+        let open = Checkouts::find()
+            .filter(checkouts::Column::Mid.eq(req.mid))
+            .filter(checkouts::Column::Cartid.eq(&req.cart_id))
+            .filter(checkouts::Column::ClosedGmt.eq(0))
+            .one(db)
+            .await?;
+        if let Some(checkout) = open {
+            return Ok(checkout);
+        }
+
+        let checkout = checkouts::ActiveModel {
+            mid: Set(req.mid),
+            sdomain: Set(req.sdomain.clone().unwrap_or_default()),
+            assist: Set(CheckoutAssist::None),
+            cartid: Set(req.cart_id.clone()),
+            cid: Set(req.customer),
+            created_gmt: Set(now_gmt()),
+            checkout_stage: Set(STAGE_OPEN.to_string()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(checkout)
+    }
+}
+
+/// The requested address, or the customer's default (else oldest) one
+fn pick_address(addresses: &[CustomerAddr], id: Option<i32>) -> Result<&CustomerAddr> {
+    match id {
+        Some(id) => addresses
+            .iter()
+            .find(|a| a.id == id)
+            .ok_or(CheckoutError::AddressNotFound(id)),
+        None => addresses
+            .iter()
+            .find(|a| a.is_default != 0)
+            .or_else(|| addresses.first())
+            .ok_or(CheckoutError::MissingAddress),
+    }
+}
+
+/// What the cart's lines take from stock, bundles and assemblies replaced by
+/// their components
+async fn stock_lines(db: &DatabaseConnection, mid: i32, cart: &Cart) -> Result<Vec<StockLine>> {
+    let lines: Vec<SkuQty> = cart
+        .items
+        .iter()
+        .map(|item| SkuQty {
+            sku: item.sku.clone(),
+            qty: item.quantity,
+        })
+        .collect();
+
+    let expanded = ProductRelationService::expand(db, mid, &lines)
+        .await
+        .map_err(CartError::from)?;
+    Ok(expanded
+        .into_iter()
+        .map(|line| StockLine {
+            sku: line.sku,
+            qty: line.qty,
+        })
+        .collect())
+}
+
+/// Reserve each of `lines` for the order in place of the cart's holds
+///
+/// Fails while a line wants more than the cart holds plus what is
+/// available, unless its SKU is backorderable.
+async fn reserve_stock<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    cart: &Cart,
+    orderid: &str,
+    lines: &[StockLine],
+) -> Result<()> {
+    ReservationService::allocate(db, mid, LUSER, &cart.cart_id, orderid, lines)
+        .await
+        .map_err(|err| match err {
+            InventoryError::OutOfStock(sku) => CheckoutError::OutOfStock(sku),
+            InventoryError::UnknownSku(sku) => CartError::UnknownSku(sku).into(),
+            err => err.into(),
+        })
+}
+
+fn now_gmt() -> i32 {
+    Utc::now().timestamp() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::entity::prelude::{Customer, InventoryDetail, OrderCounter, ProductRelation, Sku};
+    use commercerack_cart::InMemoryCartRepository;
+    use rust_decimal::Decimal;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    const PRICE: Decimal = Decimal::from_parts(1999, 0, 0, false, 2);
+
+    fn request(cart_id: &str) -> CheckoutRequest {
+        CheckoutRequest {
+            mid: 1,
+            cart_id: cart_id.to_string(),
+            customer: 7,
+            bill_address: None,
+            ship_address: None,
+            sdomain: None,
+        }
+    }
+
+    async fn cart_with(repo: &InMemoryCartRepository, quantity: i32) -> Cart {
+        let mut cart = repo.create(1).await.unwrap();
+        cart.add_item("WIDGET:BLU".to_string(), "Blue Widget".to_string(), quantity, PRICE);
+        repo.save(cart).await.unwrap()
+    }
+
+    fn sku(price: Decimal) -> Sku {
+        Sku {
+            id: 1,
+            mid: 1,
+            pid: "WIDGET".to_string(),
+            invopts: String::new(),
+            grp_parent: String::new(),
+            sku: "WIDGET:BLU".to_string(),
+            title: "Blue Widget".to_string(),
+            cost: Decimal::ZERO,
+            price,
+            upc: String::new(),
+            mfgid: String::new(),
+            supplierid: String::new(),
+            prodasm: None,
+            assembly: None,
+            inv_available: 5,
+            qty_onshelf: 5,
+            qty_onorder: 0,
+            qty_needship: 0,
+            qty_markets: 0,
+            qty_legacy: 0,
+            qty_reserved: 0,
+            amz_asin: String::new(),
+            amz_feeds_done: 0,
+            amz_feeds_todo: 0,
+            amz_feeds_sent: 0,
+            amz_feeds_wait: 0,
+            amz_feeds_warn: 0,
+            amz_feeds_error: 0,
+            amz_productdb_gmt: 0,
+            amz_error: String::new(),
+            inv_on_shelf: 5,
+            inv_on_order: 0,
+            inv_is_bo: 0,
+            inv_reorder: 0,
+            inv_is_rsvp: 0,
+            dss_agent: String::new(),
+            dss_run: None,
+            dss_mood: None,
+            dss_config: None,
+        }
+    }
+
+    fn customer() -> Customer {
+        Customer {
+            cid: 7,
+            orgid: 0,
+            mid: 1,
+            username: String::new(),
+            prt: 0,
+            email: "c7@example.com".to_string(),
+            password: String::new(),
+            passhash: String::new(),
+            passsalt: String::new(),
+            firstname: "Test".to_string(),
+            lastname: "User".to_string(),
+            phone: String::new(),
+            created_gmt: 1_700_000_000,
+            modified_gmt: 1_700_000_000,
+            lastlogin_gmt: 0,
+            lastorder_gmt: 0,
+            order_count: 0,
+            newsletter: Some(1),
+            optin_gmt: 0,
+            hint_num: 0,
+            hint_answer: String::new(),
+            hint_attempts: 0,
+            ip: 0,
+            origin: 0,
+            schedule: String::new(),
+            has_notes: 0,
+            reward_balance: None,
+            is_affiliate: 0,
+            is_locked: 0,
+            locked_until_gmt: 0,
+        }
+    }
+
+    fn address(id: i32, is_default: i16) -> CustomerAddr {
+        CustomerAddr {
+            id,
+            mid: 1,
+            cid: 7,
+            created_gmt: 1_700_000_000,
+            guid: format!("addr-{}", id),
+            is_default,
+            label: String::new(),
+            firstname: "Test".to_string(),
+            lastname: "User".to_string(),
+            address1: "1 Main St".to_string(),
+            address2: String::new(),
+            city: "Springfield".to_string(),
+            state: "OR".to_string(),
+            zip: format!("9747{}", id),
+            country: "US".to_string(),
+            phone: String::new(),
+            company: String::new(),
+        }
+    }
+
+    fn checkout_row(stage: &str) -> Checkout {
+        Checkout {
+            id: 3,
+            mid: 1,
+            username: String::new(),
+            sdomain: String::new(),
+            assist: CheckoutAssist::None,
+            cartid: "CART".to_string(),
+            cid: 7,
+            created_gmt: 1_700_000_000,
+            handled_gmt: 0,
+            closed_gmt: 0,
+            assistid: String::new(),
+            checkout_stage: stage.to_string(),
+        }
+    }
+
+    fn order(cartid: &str, customer: i32) -> Order {
+        Order {
+            id: 9,
+            merchant: String::new(),
+            mid: 1,
+            prt: 0,
+            orderid: "2025-01-00042".to_string(),
+            bs_settlement: 0,
+            v: Some(0),
+            created_gmt: 1_700_000_000,
+            modified_gmt: 1_700_000_000,
+            paid_gmt: 0,
+            paid_txn: String::new(),
+            inv_gmt: 0,
+            shipped_gmt: 0,
+            synced_gmt: 0,
+            customer,
+            pool: OrderPool::Recent,
+            order_bill_name: "Test User".to_string(),
+            order_bill_email: "c7@example.com".to_string(),
+            order_bill_zone: String::new(),
+            order_bill_phone: String::new(),
+            order_ship_name: "Test User".to_string(),
+            order_ship_zone: String::new(),
+            review_status: String::new(),
+            order_payment_status: String::new(),
+            order_payment_method: String::new(),
+            order_payment_lookup: String::new(),
+            order_erefid: None,
+            order_total: PRICE * Decimal::TWO,
+            order_special: String::new(),
+            ship_method: String::new(),
+            mkt: Some(0),
+            mkt_bitstr: String::new(),
+            flags: 0,
+            items: 2,
+            yaml: String::new(),
+            cartid: Some(cartid.to_string()),
+            sdomain: None,
+        }
+    }
+
+    fn counter() -> OrderCounter {
+        OrderCounter {
+            mid: 1,
+            merchant: "demo".to_string(),
+            counter: Some(42),
+            last_pid: Some(0),
+            last_server: String::new(),
+            orderid_format: String::new(),
+        }
+    }
+
+    /// Ledger totals of a SKU with `qty` on the shelf
+    fn on_shelf(qty: i64) -> std::collections::BTreeMap<String, sea_orm::Value> {
+        std::collections::BTreeMap::from([
+            ("basetype".to_string(), sea_orm::Value::from("SIMPLE")),
+            ("qty".to_string(), sea_orm::Value::from(qty)),
+        ])
+    }
+
+    /// Everything up to the transaction: no order yet, catalog, customer, addresses
+    fn prepared(price: Decimal) -> MockDatabase {
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Order>::new()])
+            .append_query_results([vec![sku(price)]])
+            .append_query_results([vec![customer()]])
+            .append_query_results([vec![address(1, 0), address(2, 1)]])
+            .append_query_results([Vec::<Checkout>::new()])
+            .append_query_results([vec![checkout_row(STAGE_OPEN)]])
+    }
+
+    #[tokio::test]
+    async fn test_checkout_places_order_and_deletes_cart() {
+        let repo = InMemoryCartRepository::default();
+        let cart = cart_with(&repo, 2).await;
+        let db = prepared(PRICE)
+            .append_query_results([vec![counter()]])
+            .append_query_results([Vec::<InventoryDetail>::new()])
+            .append_query_results([vec![sku(PRICE)]])
+            .append_query_results([vec![on_shelf(5)]])
+            .append_query_results([Vec::<InventoryDetail>::new()])
+            .append_exec_results([
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+            ])
+            .append_query_results([vec![order(&cart.cart_id, 7)]])
+            .append_query_results([vec![checkout_row(STAGE_PLACED)]])
+            .into_connection();
+
+        let outcome = CheckoutService::checkout(&db, &repo, &request(&cart.cart_id))
+            .await
+            .unwrap();
+        assert!(outcome.created);
+        assert_eq!(outcome.order.orderid, "2025-01-00042");
+        assert!(repo.get(&cart.cart_id).await.unwrap().is_none());
+
+        let log = db.into_transaction_log();
+        let statements = log.last().unwrap().statements();
+        let txn: Vec<String> = statements.iter().map(|s| s.sql.clone()).collect();
+        assert!(txn[1].starts_with(r#"UPDATE "order_counters""#), "{txn:?}");
+        assert!(txn[2].starts_with(r#"SELECT "inventory_detail""#), "{txn:?}");
+        assert!(txn[3].ends_with("FOR UPDATE"), "{txn:?}");
+        assert!(txn[6].starts_with(r#"INSERT INTO "inventory_detail""#), "{txn:?}");
+        assert!(txn[7].starts_with(r#"INSERT INTO "inventory_log""#), "{txn:?}");
+        assert!(txn[8].starts_with(r#"UPDATE "sku_lookup""#), "{txn:?}");
+        assert!(txn[9].starts_with(r#"INSERT INTO "orders""#), "{txn:?}");
+        assert!(txn[10].starts_with(r#"UPDATE "checkouts""#), "{txn:?}");
+
+        // The reservation is an UNPAID row for the new order
+        let reserved = statements[6].to_string();
+        assert!(reserved.contains("'UNPAID'") && reserved.contains("-00042'"), "{reserved}");
+        assert!(statements[8].to_string().contains(r#""inv_available" = 3"#), "{}", statements[8]);
+
+        // Addresses default to the customer's default one; totals come from the cart
+        let insert = statements[9].to_string();
+        assert!(insert.contains("'97472'"), "{insert}");
+        assert!(insert.contains("39.98"), "{insert}");
+    }
+
+    #[tokio::test]
+    async fn test_bundle_reserves_its_components() {
+        // KIT is two WIDGET:BLUs and has no stock of its own
+        let repo = InMemoryCartRepository::default();
+        let mut cart = repo.create(1).await.unwrap();
+        cart.add_item("KIT".to_string(), "Widget Pair".to_string(), 2, PRICE);
+        let cart = repo.save(cart).await.unwrap();
+        let kit = Sku {
+            pid: "KIT".to_string(),
+            sku: "KIT".to_string(),
+            inv_available: 0,
+            ..sku(PRICE)
+        };
+        let component = ProductRelation {
+            mid: 1,
+            pid: "KIT".to_string(),
+            child_pid: "WIDGET:BLU".to_string(),
+            relation: "BUNDLE".to_string(),
+            qty: 2,
+            is_active: 1,
+            list_pos: 0,
+            created_gmt: 0,
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Order>::new()])
+            .append_query_results([vec![kit]])
+            .append_query_results([vec![customer()]])
+            .append_query_results([vec![address(1, 0), address(2, 1)]])
+            .append_query_results([Vec::<Checkout>::new()])
+            .append_query_results([vec![checkout_row(STAGE_OPEN)]])
+            .append_query_results([vec![component], vec![]])
+            .append_query_results([vec![counter()]])
+            .append_query_results([Vec::<InventoryDetail>::new()])
+            .append_query_results([vec![sku(PRICE)]])
+            .append_query_results([vec![on_shelf(5)]])
+            .append_query_results([Vec::<InventoryDetail>::new()])
+            .append_exec_results([
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+            ])
+            .append_query_results([vec![order(&cart.cart_id, 7)]])
+            .append_query_results([vec![checkout_row(STAGE_PLACED)]])
+            .into_connection();
+
+        CheckoutService::checkout(&db, &repo, &request(&cart.cart_id))
+            .await
+            .unwrap();
+
+        let log = db.into_transaction_log();
+        let txn: Vec<String> = log.last().unwrap().statements().iter().map(|s| s.to_string()).collect();
+        let find = |prefix: &str| {
+            txn.iter()
+                .find(|s| s.starts_with(prefix))
+                .unwrap_or_else(|| panic!("no {prefix} in {txn:#?}"))
+        };
+        let locked = txn.iter().find(|s| s.ends_with("FOR UPDATE")).expect("SKU locked");
+        assert!(locked.contains("'WIDGET:BLU'") && !locked.contains("'KIT'"), "{locked}");
+        let reserved = find(r#"INSERT INTO "inventory_detail""#);
+        assert!(reserved.contains("'WIDGET:BLU'"), "{reserved}");
+        let counters = find(r#"UPDATE "sku_lookup""#);
+        assert!(counters.contains(r#""inv_available" = 1"#), "{counters}");
+    }
+
+    #[tokio::test]
+    async fn test_checkout_again_returns_existing_order() {
+        let repo = InMemoryCartRepository::default();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![order("CART", 7)]])
+            .into_connection();
+
+        let outcome = CheckoutService::checkout(&db, &repo, &request("CART"))
+            .await
+            .unwrap();
+        assert!(!outcome.created);
+        assert_eq!(outcome.order.id, 9);
+    }
+
+    #[tokio::test]
+    async fn test_other_customers_order_is_not_returned() {
+        let repo = InMemoryCartRepository::default();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![order("CART", 8)]])
+            .into_connection();
+
+        let err = CheckoutService::checkout(&db, &repo, &request("CART"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CheckoutError::Cart(CartError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_empty_cart_is_rejected() {
+        let repo = InMemoryCartRepository::default();
+        let cart = repo.create(1).await.unwrap();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Order>::new()])
+            .into_connection();
+
+        let err = CheckoutService::checkout(&db, &repo, &request(&cart.cart_id))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CheckoutError::EmptyCart));
+    }
+
+    #[tokio::test]
+    async fn test_price_change_reprices_cart_and_stops() {
+        let repo = InMemoryCartRepository::default();
+        let cart = cart_with(&repo, 2).await;
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Order>::new()])
+            .append_query_results([vec![sku(Decimal::new(2499, 2))]])
+            .into_connection();
+
+        let err = CheckoutService::checkout(&db, &repo, &request(&cart.cart_id))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CheckoutError::PricesChanged(ref skus) if skus == &["WIDGET:BLU"]));
+
+        let line = repo.get(&cart.cart_id).await.unwrap().unwrap().items[0].clone();
+        assert_eq!(line.unit_price, Decimal::new(2499, 2));
+        assert!(line.price_changed());
+    }
+
+    #[tokio::test]
+    async fn test_out_of_stock_keeps_cart() {
+        let repo = InMemoryCartRepository::default();
+        let cart = cart_with(&repo, 2).await;
+        let db = prepared(PRICE)
+            .append_query_results([vec![counter()]])
+            .append_query_results([Vec::<InventoryDetail>::new()])
+            .append_query_results([vec![sku(PRICE)]])
+            .append_query_results([vec![on_shelf(1)]])
+            .append_query_results([Vec::<InventoryDetail>::new()])
+            .into_connection();
+
+        let err = CheckoutService::checkout(&db, &repo, &request(&cart.cart_id))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CheckoutError::OutOfStock(ref sku) if sku == "WIDGET:BLU"));
+        assert!(repo.get(&cart.cart_id).await.unwrap().is_some());
+    }
+
+    #[test]
+    fn test_pick_address() {
+        let addresses = [address(1, 0), address(2, 1)];
+        assert_eq!(pick_address(&addresses, None).unwrap().id, 2);
+        assert_eq!(pick_address(&addresses, Some(1)).unwrap().id, 1);
+        assert!(matches!(pick_address(&addresses, Some(5)), Err(CheckoutError::AddressNotFound(5))));
+        assert!(matches!(pick_address(&[], None), Err(CheckoutError::MissingAddress)));
+    }
+}
@@ -1,33 +1,447 @@
-//! Customer address management (placeholder - to be implemented with SeaORM)
+//! Customer address book
 //!
-//! TODO: Implement CustomerAddress entity and service layer
-//! For now, this is a stub to allow compilation
+//! A customer has at most one default address. Every change that sets the
+//! flag runs in a transaction that locks the customer row first, so two
+//! concurrent requests can't both leave a default behind; a partial unique
+//! index on `(mid, cid)` backs this up.
 
+use ::entity::customer_addrs::{self, Column};
+use ::entity::prelude::{CustomerAddr, CustomerAddrs};
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CustomerAddress {
-    pub id: i32,
-    pub cid: i32,
-    pub mid: i32,
+use crate::error::{CustomerError, Result};
+use crate::lock_customer;
+
+/// Fields a caller supplies when creating or replacing an address
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AddressInput {
+    #[serde(default)]
     pub label: String,
     pub firstname: String,
     pub lastname: String,
+    #[serde(default)]
     pub company: String,
     pub address1: String,
+    #[serde(default)]
     pub address2: String,
     pub city: String,
+    #[serde(default)]
     pub state: String,
+    #[serde(default)]
     pub zip: String,
+    /// ISO 3166-1 alpha-2
     pub country: String,
+    #[serde(default)]
     pub phone: String,
+    /// Make this the customer's default address
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+impl AddressInput {
+    /// Trim and normalise every field, checking lengths, the country code and
+    /// the postal code format of that country
+    pub fn validate(mut self) -> Result<Self> {
+        for (field, value, max) in [
+            ("label", &mut self.label, 15),
+            ("firstname", &mut self.firstname, 30),
+            ("lastname", &mut self.lastname, 30),
+            ("company", &mut self.company, 30),
+            ("address1", &mut self.address1, 50),
+            ("address2", &mut self.address2, 30),
+            ("city", &mut self.city, 30),
+            ("state", &mut self.state, 20),
+            ("zip", &mut self.zip, 10),
+            ("phone", &mut self.phone, 12),
+        ] {
+            *value = value.trim().to_string();
+            if value.chars().count() > max {
+                return Err(invalid(field, format!("must be at most {} characters", max)));
+            }
+        }
+        for (field, value) in [("address1", &self.address1), ("city", &self.city)] {
+            if value.is_empty() {
+                return Err(invalid(field, "is required"));
+            }
+        }
+
+        self.country = self.country.trim().to_ascii_uppercase();
+        if self.country.len() != 2 || !ISO_COUNTRIES.split(' ').any(|c| c == self.country) {
+            return Err(invalid("country", "must be an ISO 3166-1 alpha-2 code"));
+        }
+
+        self.zip = self.zip.to_ascii_uppercase();
+        match postal_formats(&self.country) {
+            // 🤓 storefront forms often insist on a postal code anyway
+            Some([]) => self.zip.clear(),
+            Some(formats) if !formats.iter().any(|f| matches_format(f, &self.zip)) => {
+                return Err(invalid(
+                    "zip",
+                    format!("is not a valid {} postal code (expected {})", self.country, formats.join(" or ")),
+                ));
+            }
+            _ => {}
+        }
+
+        Ok(self)
+    }
+}
+
+/// Customer address service
+pub struct AddressService;
+
+impl AddressService {
+    /// A customer's addresses, default first
+    pub async fn list(db: &DatabaseConnection, mid: i32, cid: i32) -> Result<Vec<CustomerAddr>> {
+        let addresses = CustomerAddrs::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Cid.eq(cid))
+            .order_by_desc(Column::IsDefault)
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(addresses)
+    }
+
+    /// Find an address by its `guid`
+    pub async fn find_by_guid(db: &DatabaseConnection, mid: i32, guid: &str) -> Result<Option<CustomerAddr>> {
+        let address = CustomerAddrs::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Guid.eq(guid))
+            .one(db)
+            .await?;
+
+        Ok(address)
+    }
+
+    /// Add an address; a customer's first address becomes the default
+    pub async fn create(db: &DatabaseConnection, mid: i32, cid: i32, input: AddressInput) -> Result<CustomerAddr> {
+        let input = input.validate()?;
+
+        let txn = db.begin().await?;
+        lock_customer(&txn, mid, cid).await?;
+        let existing = CustomerAddrs::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Cid.eq(cid))
+            .count(&txn)
+            .await?;
+        let is_default = input.is_default || existing == 0;
+        if is_default {
+            clear_default(&txn, mid, cid).await?;
+        }
+
+        let mut address = customer_addrs::ActiveModel {
+            mid: Set(mid),
+            cid: Set(cid),
+            guid: Set(Uuid::new_v4().to_string()),
+            created_gmt: Set(Utc::now().timestamp() as i32),
+            ..Default::default()
+        };
+        apply(&mut address, input, is_default);
+        let address = address.insert(&txn).await?;
+
+        txn.commit().await?;
+        Ok(address)
+    }
+
+    /// Replace an address; clearing `is_default` leaves the customer without one
+    pub async fn update(
+        db: &DatabaseConnection,
+        mid: i32,
+        cid: i32,
+        guid: &str,
+        input: AddressInput,
+    ) -> Result<CustomerAddr> {
+        let input = input.validate()?;
+
+        let txn = db.begin().await?;
+        lock_customer(&txn, mid, cid).await?;
+        let current = CustomerAddrs::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Cid.eq(cid))
+            .filter(Column::Guid.eq(guid))
+            .one(&txn)
+            .await?
+            .ok_or(CustomerError::AddressNotFound)?;
+        let is_default = input.is_default;
+        if is_default && current.is_default == 0 {
+            clear_default(&txn, mid, cid).await?;
+        }
+
+        let mut address: customer_addrs::ActiveModel = current.into();
+        apply(&mut address, input, is_default);
+        let address = address.update(&txn).await?;
+
+        txn.commit().await?;
+        Ok(address)
+    }
+
+    /// Delete an address; checkout falls back to the oldest one if it was the default
+    pub async fn delete(db: &DatabaseConnection, mid: i32, cid: i32, guid: &str) -> Result<()> {
+        let result = CustomerAddrs::delete_many()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Cid.eq(cid))
+            .filter(Column::Guid.eq(guid))
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(CustomerError::AddressNotFound);
+        }
+        Ok(())
+    }
+}
+
+async fn clear_default<C: ConnectionTrait>(db: &C, mid: i32, cid: i32) -> Result<()> {
+    CustomerAddrs::update_many()
+        .col_expr(Column::IsDefault, Expr::value(0i16))
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Cid.eq(cid))
+        .filter(Column::IsDefault.ne(0))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+fn apply(address: &mut customer_addrs::ActiveModel, input: AddressInput, is_default: bool) {
+    address.is_default = Set(i16::from(is_default));
+    address.label = Set(input.label);
+    address.firstname = Set(input.firstname);
+    address.lastname = Set(input.lastname);
+    address.company = Set(input.company);
+    address.address1 = Set(input.address1);
+    address.address2 = Set(input.address2);
+    address.city = Set(input.city);
+    address.state = Set(input.state);
+    address.zip = Set(input.zip);
+    address.country = Set(input.country);
+    address.phone = Set(input.phone);
 }
 
-// TODO: Implement AddressService with SeaORM
-// pub struct AddressService;
-//
-// impl AddressService {
-//     pub async fn create(db: &DatabaseConnection, addr: CustomerAddress) -> Result<CustomerAddress> { ... }
-//     pub async fn get_by_customer(db: &DatabaseConnection, mid: i32, cid: i32) -> Result<Vec<CustomerAddress>> { ... }
-//     pub async fn delete(db: &DatabaseConnection, mid: i32, id: i32) -> Result<()> { ... }
-// }
+fn invalid(field: &'static str, message: impl Into<String>) -> CustomerError {
+    CustomerError::InvalidAddress {
+        field,
+        message: message.into(),
+    }
+}
+
+/// ISO 3166-1 alpha-2 codes
+const ISO_COUNTRIES: &str = "AD AE AF AG AI AL AM AO AQ AR AS AT AU AW AX AZ BA BB BD BE BF BG BH BI \
+BJ BL BM BN BO BQ BR BS BT BV BW BY BZ CA CC CD CF CG CH CI CK CL CM CN CO CR CU CV CW CX CY CZ DE DJ \
+DK DM DO DZ EC EE EG EH ER ES ET FI FJ FK FM FO FR GA GB GD GE GF GG GH GI GL GM GN GP GQ GR GS GT GU \
+GW GY HK HM HN HR HT HU ID IE IL IM IN IO IQ IR IS IT JE JM JO JP KE KG KH KI KM KN KP KR KW KY KZ LA \
+LB LC LI LK LR LS LT LU LV LY MA MC MD ME MF MG MH MK ML MM MN MO MP MQ MR MS MT MU MV MW MX MY MZ NA \
+NC NE NF NG NI NL NO NP NR NU NZ OM PA PE PF PG PH PK PL PM PN PR PS PT PW PY QA RE RO RS RU RW SA SB \
+SC SD SE SG SH SI SJ SK SL SM SN SO SR SS ST SV SX SY SZ TC TD TF TG TH TJ TK TL TM TN TO TR TT TV TW \
+TZ UA UG UM US UY UZ VA VC VE VG VI VN VU WF WS YE YT ZA ZM ZW";
+
+/// Postal code formats by country: `9` is a digit, `A` a letter, `X` either,
+/// anything else itself. An empty list means the country has no postal codes;
+/// `None` means any code is accepted.
+fn postal_formats(country: &str) -> Option<&'static [&'static str]> {
+    let formats: &[&str] = match country {
+        "US" => &["99999", "99999-9999"],
+        "CA" => &["A9A 9A9", "A9A9A9"],
+        "GB" | "GG" | "JE" | "IM" => &["A9 9AA", "A99 9AA", "A9A 9AA", "AA9 9AA", "AA99 9AA", "AA9A 9AA"],
+        "IE" => &["A9X XXXX", "A9XXXXX"],
+        "NL" => &["9999 AA", "9999AA"],
+        "SE" | "CZ" | "SK" | "GR" => &["999 99", "99999"],
+        "PL" => &["99-999"],
+        "PT" => &["9999-999"],
+        "JP" => &["999-9999", "9999999"],
+        "BR" => &["99999-999", "99999999"],
+        "IL" => &["9999999"],
+        "DE" | "FR" | "IT" | "ES" | "FI" | "MX" | "MY" | "TR" | "KR" | "EE" | "HR" | "LT" => &["99999"],
+        "AT" | "AU" | "BE" | "BG" | "CH" | "CY" | "DK" | "HU" | "LU" | "NO" | "NZ" | "PH" | "SI" | "ZA" => {
+            &["9999"]
+        }
+        "CN" | "IN" | "KZ" | "RO" | "RU" | "SG" => &["999999"],
+        "AE" | "AG" | "AO" | "AW" | "BF" | "BI" | "BJ" | "BO" | "BS" | "BW" | "BZ" | "CD" | "CF" | "CG"
+        | "CI" | "CK" | "CM" | "DJ" | "DM" | "ER" | "FJ" | "GA" | "GD" | "GH" | "GM" | "GQ" | "GY" | "HK"
+        | "KI" | "KM" | "KN" | "KP" | "LC" | "ML" | "MO" | "MR" | "MW" | "NR" | "NU" | "QA" | "RW" | "SB"
+        | "SC" | "SL" | "SR" | "SS" | "ST" | "SY" | "TD" | "TG" | "TK" | "TL" | "TO" | "TV" | "UG" | "VU"
+        | "YE" | "ZW" => &[],
+        _ => return None,
+    };
+    Some(formats)
+}
+
+fn matches_format(format: &str, zip: &str) -> bool {
+    format.len() == zip.len()
+        && format.chars().zip(zip.chars()).all(|(f, c)| match f {
+            '9' => c.is_ascii_digit(),
+            'A' => c.is_ascii_uppercase(),
+            'X' => c.is_ascii_digit() || c.is_ascii_uppercase(),
+            literal => c == literal,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::entity::prelude::Customer;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use std::collections::BTreeMap;
+
+    fn input(country: &str, zip: &str) -> AddressInput {
+        AddressInput {
+            firstname: "Ada".to_string(),
+            lastname: "Lovelace".to_string(),
+            address1: "12 St James's Square".to_string(),
+            city: "London".to_string(),
+            zip: zip.to_string(),
+            country: country.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn address(id: i32, is_default: i16) -> CustomerAddr {
+        CustomerAddr {
+            id,
+            mid: 1,
+            cid: 7,
+            created_gmt: 0,
+            guid: format!("guid-{}", id),
+            is_default,
+            label: String::new(),
+            firstname: "Ada".to_string(),
+            lastname: "Lovelace".to_string(),
+            address1: "1 Main St".to_string(),
+            address2: String::new(),
+            city: "Springfield".to_string(),
+            state: String::new(),
+            zip: "12345".to_string(),
+            country: "US".to_string(),
+            phone: String::new(),
+            company: String::new(),
+        }
+    }
+
+    fn customer() -> Customer {
+        Customer {
+            cid: 7,
+            orgid: 0,
+            mid: 1,
+            username: String::new(),
+            prt: 0,
+            email: "ada@example.com".to_string(),
+            password: String::new(),
+            passhash: String::new(),
+            passsalt: String::new(),
+            firstname: "Ada".to_string(),
+            lastname: "Lovelace".to_string(),
+            phone: String::new(),
+            created_gmt: 0,
+            modified_gmt: 0,
+            lastlogin_gmt: 0,
+            lastorder_gmt: 0,
+            order_count: 0,
+            newsletter: None,
+            optin_gmt: 0,
+            hint_num: 0,
+            hint_answer: String::new(),
+            hint_attempts: 0,
+            ip: 0,
+            origin: 0,
+            schedule: String::new(),
+            has_notes: 0,
+            reward_balance: None,
+            is_affiliate: 0,
+            is_locked: 0,
+            locked_until_gmt: 0,
+        }
+    }
+
+    fn count(n: i64) -> BTreeMap<String, Value> {
+        BTreeMap::from([("num_items".to_string(), Value::BigInt(Some(n)))])
+    }
+
+    fn field_of(err: CustomerError) -> &'static str {
+        match err {
+            CustomerError::InvalidAddress { field, .. } => field,
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_postal_codes_are_checked_per_country() {
+        assert!(input("US", "12345-6789").validate().is_ok());
+        assert_eq!(field_of(input("US", "1234").validate().unwrap_err()), "zip");
+        assert!(input("GB", "SW1A 1AA").validate().is_ok());
+        assert!(input("NL", "1012 ab").validate().is_ok());
+        assert_eq!(field_of(input("DE", "1012 AB").validate().unwrap_err()), "zip");
+
+        let canadian = input("ca", " k1a 0b1 ").validate().unwrap();
+        assert_eq!((canadian.country.as_str(), canadian.zip.as_str()), ("CA", "K1A 0B1"));
+
+        // No postal codes in Hong Kong; anything unlisted is accepted as is
+        assert_eq!(input("HK", "00000").validate().unwrap().zip, "");
+        assert!(input("IS", "101").validate().is_ok());
+    }
+
+    #[test]
+    fn test_rejects_unknown_countries_and_oversized_fields() {
+        assert_eq!(field_of(input("UK", "SW1A 1AA").validate().unwrap_err()), "country");
+        assert_eq!(field_of(input("USA", "12345").validate().unwrap_err()), "country");
+
+        let long = AddressInput {
+            phone: "+1 555 555 0100".to_string(),
+            ..input("US", "12345")
+        };
+        assert_eq!(field_of(long.validate().unwrap_err()), "phone");
+
+        let blank = AddressInput {
+            city: "  ".to_string(),
+            ..input("US", "12345")
+        };
+        assert_eq!(field_of(blank.validate().unwrap_err()), "city");
+    }
+
+    #[tokio::test]
+    async fn test_first_address_becomes_default() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer()]])
+            .append_query_results([vec![count(0)]])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 0 }])
+            .append_query_results([vec![address(1, 1)]])
+            .into_connection();
+
+        let created = AddressService::create(&db, 1, 7, input("US", "12345")).await.unwrap();
+        assert_eq!(created.is_default, 1);
+
+        let log = db.into_transaction_log();
+        let txn = log[0].statements();
+        assert!(txn[1].sql.ends_with("FOR UPDATE"), "{}", txn[1].sql);
+        assert!(txn[3].sql.starts_with(r#"UPDATE "customer_addrs" SET "is_default""#));
+        assert!(txn[4].to_string().contains("'12345'"));
+    }
+
+    #[tokio::test]
+    async fn test_update_of_another_customers_address_is_not_found() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer()]])
+            .append_query_results([Vec::<CustomerAddr>::new()])
+            .into_connection();
+
+        let err = AddressService::update(&db, 1, 7, "guid-9", input("US", "12345"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CustomerError::AddressNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_create_for_missing_customer_is_not_found() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Customer>::new()])
+            .into_connection();
+
+        let err = AddressService::create(&db, 1, 99, input("US", "12345")).await.unwrap_err();
+        assert!(matches!(err, CustomerError::NotFound));
+    }
+}
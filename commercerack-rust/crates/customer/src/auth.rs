@@ -1,45 +1,304 @@
-//! Authentication helpers
+//! Customer sessions backed by rotating refresh tokens
+//!
+//! A login starts a token family. Each refresh exchanges the presented token
+//! for a new one in the same family; presenting a token that was already
+//! exchanged means it leaked, so the whole family is revoked. Only a SHA-256
+//! of each token is stored.
 
-use serde::{Deserialize, Serialize};
+use ::entity::customer_sessions::{self, Column};
+use ::entity::prelude::{CustomerSession, CustomerSessions};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Session {
-    pub cid: i64,
-    pub mid: i32,
-    pub created_at: i64,
-    pub expires_at: i64,
+use crate::error::{CustomerError, Result};
+
+/// A refresh token and the session row it belongs to
+#[derive(Debug, Clone)]
+pub struct IssuedToken {
+    /// Plaintext token; returned to the client once and never stored
+    pub token: String,
+    pub session: CustomerSession,
 }
 
-impl Session {
-    pub fn new(cid: i64, mid: i32, duration_secs: i64) -> Self {
-        let now = chrono::Utc::now().timestamp();
-        Self {
-            cid,
-            mid,
-            created_at: now,
-            expires_at: now + duration_secs,
+/// Refresh token service
+pub struct SessionService;
+
+impl SessionService {
+    /// Start a new session for a customer who just logged in
+    pub async fn start(
+        db: &DatabaseConnection,
+        mid: i32,
+        cid: i32,
+        ttl: Duration,
+    ) -> Result<IssuedToken> {
+        let family = Uuid::new_v4().to_string();
+        Self::issue(db, mid, cid, family, ttl).await
+    }
+
+    /// Exchange a refresh token for a new one in the same session
+    pub async fn refresh(db: &DatabaseConnection, token: &str, ttl: Duration) -> Result<IssuedToken> {
+        let session = Self::find_active(db, token).await?;
+
+        let txn = db.begin().await?;
+        // 🤓 only one of two concurrent refreshes with the same token wins
+        let rotated = CustomerSessions::update_many()
+            .col_expr(Column::RotatedGmt, Expr::value(now_gmt()))
+            .filter(Column::Id.eq(session.id))
+            .filter(Column::RotatedGmt.eq(0))
+            .filter(Column::RevokedGmt.eq(0))
+            .exec(&txn)
+            .await?;
+        if rotated.rows_affected == 0 {
+            txn.rollback().await?;
+            Self::revoke_family(db, &session.family).await?;
+            return Err(CustomerError::InvalidRefreshToken);
         }
+
+        let issued = Self::issue(&txn, session.mid, session.cid, session.family, ttl).await?;
+        txn.commit().await?;
+        Ok(issued)
     }
 
-    pub fn is_valid(&self) -> bool {
-        chrono::Utc::now().timestamp() < self.expires_at
+    /// End the session a refresh token belongs to; `false` if the token was unknown
+    pub async fn end(db: &DatabaseConnection, token: &str) -> Result<bool> {
+        let session = CustomerSessions::find()
+            .filter(Column::TokenHash.eq(hash_token(token)))
+            .one(db)
+            .await?;
+
+        match session {
+            Some(session) => {
+                Self::revoke_family(db, &session.family).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// End every session of a customer, e.g. after a password change
+    pub async fn end_all(db: &DatabaseConnection, mid: i32, cid: i32) -> Result<u64> {
+        revoke_all(db, mid, cid).await
+    }
+
+    /// When each of a customer's sessions started, i.e. their logins, newest
+    /// first, optionally only those before `before`
+    pub async fn logins(
+        db: &DatabaseConnection,
+        mid: i32,
+        cid: i32,
+        limit: u64,
+        before: Option<i32>,
+    ) -> Result<Vec<i32>> {
+        let mut query = CustomerSessions::find()
+            .select_only()
+            .column_as(Column::CreatedGmt.min(), "started_gmt")
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Cid.eq(cid))
+            .group_by(Column::Family);
+        if let Some(before) = before {
+            query = query.having(Expr::expr(Column::CreatedGmt.min()).lt(before));
+        }
+
+        let logins = query
+            .order_by_desc(Column::CreatedGmt.min())
+            .limit(limit)
+            .into_tuple::<i32>()
+            .all(db)
+            .await?;
+
+        Ok(logins)
+    }
+
+    /// The session for `token` if it is still usable, revoking its family
+    /// when an already rotated token is replayed
+    async fn find_active(db: &DatabaseConnection, token: &str) -> Result<CustomerSession> {
+        let session = CustomerSessions::find()
+            .filter(Column::TokenHash.eq(hash_token(token)))
+            .one(db)
+            .await?
+            .ok_or(CustomerError::InvalidRefreshToken)?;
+
+        if session.revoked_gmt != 0 || session.expires_gmt <= now_gmt() {
+            return Err(CustomerError::InvalidRefreshToken);
+        }
+        if session.rotated_gmt != 0 {
+            tracing::warn!(mid = session.mid, cid = session.cid, "refresh token reused; revoking session");
+            Self::revoke_family(db, &session.family).await?;
+            return Err(CustomerError::InvalidRefreshToken);
+        }
+
+        Ok(session)
+    }
+
+    async fn issue<C: ConnectionTrait>(
+        db: &C,
+        mid: i32,
+        cid: i32,
+        family: String,
+        ttl: Duration,
+    ) -> Result<IssuedToken> {
+        let token = generate_token();
+        let now = now_gmt();
+
+        let session = customer_sessions::ActiveModel {
+            mid: Set(mid),
+            cid: Set(cid),
+            family: Set(family),
+            token_hash: Set(hash_token(&token)),
+            created_gmt: Set(now),
+            expires_gmt: Set(now.saturating_add(i32::try_from(ttl.as_secs()).unwrap_or(i32::MAX))),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(IssuedToken { token, session })
+    }
+
+    async fn revoke_family(db: &DatabaseConnection, family: &str) -> Result<()> {
+        CustomerSessions::update_many()
+            .col_expr(Column::RevokedGmt, Expr::value(now_gmt()))
+            .filter(Column::Family.eq(family))
+            .filter(Column::RevokedGmt.eq(0))
+            .exec(db)
+            .await?;
+
+        Ok(())
     }
 }
 
+/// Revoke every live session of a customer; usable inside a transaction
+pub(crate) async fn revoke_all<C: ConnectionTrait>(db: &C, mid: i32, cid: i32) -> Result<u64> {
+    let result = CustomerSessions::update_many()
+        .col_expr(Column::RevokedGmt, Expr::value(now_gmt()))
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Cid.eq(cid))
+        .filter(Column::RevokedGmt.eq(0))
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected)
+}
+
+/// 256 random bits, URL-safe
+pub(crate) fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+pub(crate) fn now_gmt() -> i32 {
+    Utc::now().timestamp() as i32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use std::collections::BTreeMap;
 
-    #[test]
-    fn test_session_creation() {
-        let session = Session::new(1, 1, 3600);
-        assert!(session.is_valid());
-        assert_eq!(session.cid, 1);
+    fn session(token: &str, rotated_gmt: i32) -> CustomerSession {
+        CustomerSession {
+            id: 1,
+            mid: 1,
+            cid: 7,
+            family: "family-1".to_string(),
+            token_hash: hash_token(token),
+            created_gmt: now_gmt() - 60,
+            expires_gmt: now_gmt() + 3600,
+            rotated_gmt,
+            revoked_gmt: 0,
+        }
+    }
+
+    fn exec(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
     }
 
     #[test]
-    fn test_session_expiry() {
-        let session = Session::new(1, 1, -1);
-        assert!(!session.is_valid());
+    fn test_tokens_are_random_and_hashed() {
+        let (a, b) = (generate_token(), generate_token());
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 43);
+        assert_eq!(hash_token(&a).len(), 64);
+        assert_ne!(hash_token(&a), a);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_within_family() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![session("old", 0)]])
+            .append_exec_results([exec(1)])
+            .append_query_results([vec![CustomerSession { id: 2, ..session("new", 0) }]])
+            .into_connection();
+
+        let issued = SessionService::refresh(&db, "old", Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(issued.session.family, "family-1");
+        assert_ne!(issued.token, "old");
+
+        let log = db.into_transaction_log();
+        let txn = log[1].statements();
+        assert!(txn[1].sql.starts_with(r#"UPDATE "customer_sessions""#));
+        assert!(txn[2].sql.starts_with(r#"INSERT INTO "customer_sessions""#));
+    }
+
+    #[tokio::test]
+    async fn test_reused_token_revokes_family() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![session("old", now_gmt() - 10)]])
+            .append_exec_results([exec(2)])
+            .into_connection();
+
+        let err = SessionService::refresh(&db, "old", Duration::from_secs(3600))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CustomerError::InvalidRefreshToken));
+
+        let log = db.into_transaction_log();
+        let revoke = &log[1].statements()[0];
+        assert!(revoke.sql.starts_with(r#"UPDATE "customer_sessions" SET "revoked_gmt""#));
+        assert!(revoke.to_string().contains("'family-1'"), "{}", revoke);
+    }
+
+    #[tokio::test]
+    async fn test_logins_are_session_starts() {
+        let row = |started: i32| BTreeMap::from([("started_gmt".to_string(), Value::Int(Some(started)))]);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![row(200), row(100)]])
+            .into_connection();
+
+        let logins = SessionService::logins(&db, 1, 7, 10, Some(300)).await.unwrap();
+        assert_eq!(logins, [200, 100]);
+
+        let sql = db.into_transaction_log()[0].statements()[0].to_string();
+        assert!(sql.contains(r#"HAVING MIN("customer_sessions"."created_gmt") < 300"#), "{}", sql);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_token_is_rejected() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<CustomerSession>::new()])
+            .into_connection();
+
+        let err = SessionService::refresh(&db, "nope", Duration::from_secs(3600))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CustomerError::InvalidRefreshToken));
     }
 }
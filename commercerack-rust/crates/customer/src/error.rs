@@ -0,0 +1,70 @@
+//! Customer service error types
+
+use commercerack_db::pagination::CursorError;
+use sea_orm::{DbErr, SqlErr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CustomerError {
+    #[error("Customer not found")]
+    NotFound,
+
+    #[error("A customer with email {0} already exists")]
+    DuplicateEmail(String),
+
+    #[error("Invalid email or password")]
+    InvalidCredentials,
+
+    #[error("Account is locked")]
+    AccountLocked,
+
+    #[error("Account is locked after too many failed logins")]
+    TemporarilyLocked { until: i32 },
+
+    #[error("Password must be at least {0} characters")]
+    WeakPassword(usize),
+
+    #[error("Password reset token is invalid, expired or already used")]
+    InvalidResetToken,
+
+    #[error("Refresh token is invalid, expired or revoked")]
+    InvalidRefreshToken,
+
+    #[error("Address not found")]
+    AddressNotFound,
+
+    #[error("Invalid {field}: {message}")]
+    InvalidAddress { field: &'static str, message: String },
+
+    #[error("Note not found")]
+    NoteNotFound,
+
+    #[error("Invalid {field}: {message}")]
+    InvalidNote { field: &'static str, message: String },
+
+    #[error("Notification failed: {0}")]
+    Notify(String),
+
+    #[error("Password hashing failed: {0}")]
+    PasswordHash(String),
+
+    #[error(transparent)]
+    InvalidCursor(#[from] CursorError),
+
+    #[error(transparent)]
+    Db(#[from] DbErr),
+}
+
+impl CustomerError {
+    /// Map a unique violation on insert/update to [`CustomerError::DuplicateEmail`]
+    /// and an update that matched no row to [`CustomerError::NotFound`]
+    pub(crate) fn from_write(err: DbErr, email: &str) -> Self {
+        match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => Self::DuplicateEmail(email.to_string()),
+            _ if matches!(err, DbErr::RecordNotUpdated) => Self::NotFound,
+            _ => Self::Db(err),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, CustomerError>;
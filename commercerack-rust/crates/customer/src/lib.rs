@@ -1,14 +1,29 @@
 //! Customer management module using SeaORM
 
-use anyhow::Result;
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
-use argon2::password_hash::{SaltString, rand_core::OsRng};
 use chrono::Utc;
+use std::time::Duration;
 use sea_orm::*;
+use sea_orm::sea_query::Expr;
 use ::entity::prelude::*;
+use commercerack_db::pagination::{Cursor, Page, PageRequest, SortDirection};
 
 pub mod auth;
 pub mod address;
+pub mod error;
+pub mod note;
+pub mod notify;
+pub mod password;
+pub mod query;
+pub mod reset;
+
+pub use address::{AddressInput, AddressService};
+pub use auth::{IssuedToken, SessionService};
+pub use error::{CustomerError, Result};
+pub use note::{NewNote, NoteService, NoteType};
+pub use password::{PasswordScheme, SchemeCounts};
+pub use notify::{FileNotifier, LogNotifier, Notifier, PasswordResetMessage};
+pub use query::{CustomerFilter, CustomerSort};
+pub use reset::PasswordResetService;
 
 /// Customer service for managing customer operations
 pub struct CustomerService;
@@ -24,15 +39,9 @@ impl CustomerService {
         password: Option<&str>,
     ) -> Result<Customer> {
         let now = Utc::now().timestamp() as i32;
-        let (passhash, passsalt) = if let Some(pwd) = password {
-            let salt = SaltString::generate(&mut OsRng);
-            let argon2 = Argon2::default();
-            let hash = argon2.hash_password(pwd.as_bytes(), &salt)
-                .map_err(|e| anyhow::anyhow!("Password hashing failed: {:?}", e))?
-                .to_string();
-            (hash, salt.to_string())
-        } else {
-            (String::new(), String::new())
+        let passhash = match password {
+            Some(pwd) => password::hash(pwd)?,
+            None => String::new(),
         };
 
         let customer = ::entity::customers::ActiveModel {
@@ -43,12 +52,15 @@ impl CustomerService {
             created_gmt: Set(now),
             modified_gmt: Set(now),
             passhash: Set(passhash),
-            passsalt: Set(passsalt),
+            newsletter: Set(Some(1)),
+            is_locked: Set(0),
             ..Default::default()
         };
 
-        let result = customer.insert(db).await?;
-        Ok(result)
+        customer
+            .insert(db)
+            .await
+            .map_err(|e| CustomerError::from_write(e, email))
     }
 
     /// Find customer by ID
@@ -81,16 +93,81 @@ impl CustomerService {
         Ok(customer)
     }
 
-    /// Update customer
+    /// List customers for a merchant, one keyset page at a time
+    pub async fn list(
+        db: &DatabaseConnection,
+        mid: i32,
+        page: &PageRequest<CustomerSort>,
+    ) -> Result<Page<Customer>> {
+        Self::search(db, mid, &CustomerFilter::default(), page).await
+    }
+
+    /// Search customers matching `filter`, one keyset page at a time
+    ///
+    /// Rows are ordered by the requested sort column with `cid` as the
+    /// tiebreaker, so paging stays stable while customers are added.
+    pub async fn search(
+        db: &DatabaseConnection,
+        mid: i32,
+        filter: &CustomerFilter,
+        page: &PageRequest<CustomerSort>,
+    ) -> Result<Page<Customer>> {
+        use ::entity::customers::Column;
+
+        let sort = page.sort;
+        let after = page
+            .cursor
+            .as_deref()
+            .map(|token| Cursor::decode(token, sort.as_str()))
+            .transpose()?;
+
+        let condition = filter.condition(mid);
+        let total = Customers::find()
+            .filter(condition.clone())
+            .count(db)
+            .await?;
+        let page_size = page.page_size();
+
+        let select = Customers::find().filter(condition);
+        let rows = match sort.column() {
+            None => {
+                let mut cursor = select.cursor_by(Column::Cid);
+                if let Some(after) = &after {
+                    cursor.after(after.id as i32);
+                }
+                apply_direction(&mut cursor, page.direction);
+                cursor.first(page_size + 1).all(db).await?
+            }
+            Some(column) => {
+                let mut cursor = select.cursor_by((column, Column::Cid));
+                if let Some(after) = &after {
+                    cursor.after((sort.key_value(after)?, after.id as i32));
+                }
+                apply_direction(&mut cursor, page.direction);
+                cursor.first(page_size + 1).all(db).await?
+            }
+        };
+
+        Ok(Page::from_rows(rows, page_size, total, |c| {
+            Cursor::new(sort.as_str(), sort.key_of(c), c.cid as i64)
+        }))
+    }
+
+    /// Update a customer of merchant `mid`
     pub async fn update(
         db: &DatabaseConnection,
+        mid: i32,
         customer: Customer,
     ) -> Result<Customer> {
+        let email = customer.email.clone();
         let mut active: ::entity::customers::ActiveModel = customer.into();
         active.modified_gmt = Set(Utc::now().timestamp() as i32);
 
-        let result = active.update(db).await?;
-        Ok(result)
+        Customers::update(active)
+            .filter(::entity::customers::Column::Mid.eq(mid))
+            .exec(db)
+            .await
+            .map_err(|e| CustomerError::from_write(e, &email))
     }
 
     /// Delete customer
@@ -108,45 +185,416 @@ impl CustomerService {
         Ok(())
     }
 
-    /// Verify customer password
+    /// Verify customer password; locked accounts never verify
     pub async fn verify_password(
         customer: &Customer,
         password: &str,
     ) -> Result<bool> {
-        if customer.passhash.is_empty() {
+        if customer.is_locked != 0 {
             return Ok(false);
         }
+        password::verify(customer, password)
+    }
+
+    /// Check a login and record it in `lastlogin_gmt`
+    ///
+    /// Unknown emails and wrong passwords are indistinguishable to the caller.
+    /// A password still stored in a legacy scheme is rehashed with Argon2id.
+    /// Each wrong password counts towards `policy`; reaching the limit locks
+    /// the account until `locked_until_gmt` and starts the count over.
+    pub async fn authenticate(
+        db: &DatabaseConnection,
+        mid: i32,
+        email: &str,
+        password: &str,
+        policy: &LoginPolicy,
+    ) -> Result<Customer> {
+        let mut customer = Self::find_by_email(db, mid, email)
+            .await?
+            .ok_or(CustomerError::InvalidCredentials)?;
+
+        if customer.is_locked != 0 {
+            return Err(CustomerError::AccountLocked);
+        }
+        let now = Utc::now().timestamp() as i32;
+        if customer.locked_until_gmt > now {
+            return Err(CustomerError::TemporarilyLocked { until: customer.locked_until_gmt });
+        }
+
+        if !Self::verify_password(&customer, password).await? {
+            record_failed_login(db, &customer, policy, now).await?;
+            return Err(CustomerError::InvalidCredentials);
+        }
+
+        let mut login = Customers::update_many()
+            .col_expr(::entity::customers::Column::LastloginGmt, Expr::value(now))
+            .col_expr(::entity::customers::Column::HintAttempts, Expr::value(0i16))
+            .col_expr(::entity::customers::Column::LockedUntilGmt, Expr::value(0));
+        let scheme = PasswordScheme::of(&customer);
+        if scheme.is_legacy() {
+            let passhash = password::hash(password)?;
+            login = login
+                .col_expr(::entity::customers::Column::Passhash, Expr::value(passhash.clone()))
+                .col_expr(::entity::customers::Column::Passsalt, Expr::value(""))
+                .col_expr(::entity::customers::Column::Password, Expr::value(""));
+            tracing::info!(mid, cid = customer.cid, ?scheme, "rehashed legacy customer password");
+            customer.passhash = passhash;
+            customer.passsalt.clear();
+            customer.password.clear();
+        }
+        login
+            .filter(::entity::customers::Column::Mid.eq(mid))
+            .filter(::entity::customers::Column::Cid.eq(customer.cid))
+            .exec(db)
+            .await?;
+
+        customer.lastlogin_gmt = now;
+        customer.hint_attempts = 0;
+        customer.locked_until_gmt = 0;
+        Ok(customer)
+    }
 
-        let parsed_hash = PasswordHash::new(&customer.passhash)
-            .map_err(|e| anyhow::anyhow!("Invalid password hash: {:?}", e))?;
+    /// Clear an account lock, both the permanent `is_locked` flag and a
+    /// lockout after failed logins
+    pub async fn unlock(db: &DatabaseConnection, mid: i32, cid: i32) -> Result<()> {
+        let result = Customers::update_many()
+            .col_expr(::entity::customers::Column::IsLocked, Expr::value(0i16))
+            .col_expr(::entity::customers::Column::HintAttempts, Expr::value(0i16))
+            .col_expr(::entity::customers::Column::LockedUntilGmt, Expr::value(0))
+            .filter(::entity::customers::Column::Mid.eq(mid))
+            .filter(::entity::customers::Column::Cid.eq(cid))
+            .exec(db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(CustomerError::NotFound);
+        }
 
-        let argon2 = Argon2::default();
-        Ok(argon2.verify_password(password.as_bytes(), &parsed_hash).is_ok())
+        Ok(())
     }
 
-    /// Set customer password
+    /// Set customer password and end all of their sessions
+    ///
+    /// A new password also clears any lockout after failed logins.
     pub async fn set_password(
         db: &DatabaseConnection,
-        mut customer: Customer,
+        customer: Customer,
         password: &str,
     ) -> Result<Customer> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let hash = argon2.hash_password(password.as_bytes(), &salt)
-            .map_err(|e| anyhow::anyhow!("Password hashing failed: {:?}", e))?
-            .to_string();
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(CustomerError::WeakPassword(MIN_PASSWORD_LEN));
+        }
 
-        customer.passhash = hash;
-        customer.passsalt = salt.to_string();
+        let hash = password::hash(password)?;
 
-        Self::update(db, customer).await
+        let txn = db.begin().await?;
+        let customer = store_password(&txn, customer, hash).await?;
+        txn.commit().await?;
+        Ok(customer)
     }
 }
 
+/// Shortest password [`CustomerService::set_password`] accepts
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// How many wrong passwords lock an account, and for how long
+#[derive(Debug, Clone, Copy)]
+pub struct LoginPolicy {
+    pub max_failed_attempts: i16,
+    pub lockout: Duration,
+}
+
+impl Default for LoginPolicy {
+    fn default() -> Self {
+        Self {
+            max_failed_attempts: 5,
+            lockout: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// Count a wrong password in `hint_attempts`, locking the account once the
+/// count reaches the limit
+///
+/// Done in one UPDATE so concurrent attempts cannot lose counts.
+async fn record_failed_login(
+    db: &DatabaseConnection,
+    customer: &Customer,
+    policy: &LoginPolicy,
+    now: i32,
+) -> Result<()> {
+    let max = policy.max_failed_attempts.max(1);
+    let until = now.saturating_add(policy.lockout.as_secs().min(i32::MAX as u64) as i32);
+    Customers::update_many()
+        .col_expr(
+            ::entity::customers::Column::HintAttempts,
+            Expr::cust_with_values(
+                r#"CASE WHEN "hint_attempts" + 1 >= $1 THEN 0 ELSE "hint_attempts" + 1 END"#,
+                [max],
+            ),
+        )
+        .col_expr(
+            ::entity::customers::Column::LockedUntilGmt,
+            Expr::cust_with_values(
+                r#"CASE WHEN "hint_attempts" + 1 >= $1 THEN $2 ELSE "locked_until_gmt" END"#,
+                [Value::from(max), Value::from(until)],
+            ),
+        )
+        .filter(::entity::customers::Column::Mid.eq(customer.mid))
+        .filter(::entity::customers::Column::Cid.eq(customer.cid))
+        .exec(db)
+        .await?;
+
+    if customer.hint_attempts + 1 >= max {
+        tracing::warn!(mid = customer.mid, cid = customer.cid, until, "customer locked out after failed logins");
+    }
+    Ok(())
+}
+
+/// Write an already hashed password and end the customer's sessions
+///
+/// The caller owns the transaction so the change can commit together with
+/// whatever authorised it.
+pub(crate) async fn store_password<C: ConnectionTrait>(
+    db: &C,
+    mut customer: Customer,
+    hash: String,
+) -> Result<Customer> {
+    let (mid, cid) = (customer.mid, customer.cid);
+    let now = Utc::now().timestamp() as i32;
+    let updated = Customers::update_many()
+        .col_expr(::entity::customers::Column::Passhash, Expr::value(hash.clone()))
+        .col_expr(::entity::customers::Column::Passsalt, Expr::value(""))
+        .col_expr(::entity::customers::Column::Password, Expr::value(""))
+        .col_expr(::entity::customers::Column::HintAttempts, Expr::value(0i16))
+        .col_expr(::entity::customers::Column::LockedUntilGmt, Expr::value(0))
+        .col_expr(::entity::customers::Column::ModifiedGmt, Expr::value(now))
+        .filter(::entity::customers::Column::Mid.eq(mid))
+        .filter(::entity::customers::Column::Cid.eq(cid))
+        .exec(db)
+        .await?;
+    if updated.rows_affected == 0 {
+        return Err(CustomerError::NotFound);
+    }
+    auth::revoke_all(db, mid, cid).await?;
+
+    customer.passhash = hash;
+    customer.passsalt.clear();
+    customer.password.clear();
+    customer.hint_attempts = 0;
+    customer.locked_until_gmt = 0;
+    customer.modified_gmt = now;
+    Ok(customer)
+}
+
+/// Lock a customer's row so changes to their addresses or notes serialise;
+/// the customer must exist
+pub(crate) async fn lock_customer<C: ConnectionTrait>(db: &C, mid: i32, cid: i32) -> Result<Customer> {
+    Customers::find()
+        .filter(::entity::customers::Column::Mid.eq(mid))
+        .filter(::entity::customers::Column::Cid.eq(cid))
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or(CustomerError::NotFound)
+}
+
+fn apply_direction<S: SelectorTrait>(cursor: &mut sea_orm::Cursor<S>, direction: SortDirection) {
+    match direction {
+        SortDirection::Asc => cursor.asc(),
+        SortDirection::Desc => cursor.desc(),
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn customer(password: &str, is_locked: i16) -> Customer {
+        let passhash = password::hash(password).unwrap();
+
+        Customer {
+            cid: 7,
+            orgid: 0,
+            mid: 1,
+            username: String::new(),
+            prt: 0,
+            email: "c7@example.com".to_string(),
+            password: String::new(),
+            passhash,
+            passsalt: String::new(),
+            firstname: "Test".to_string(),
+            lastname: "User".to_string(),
+            phone: String::new(),
+            created_gmt: 1_700_000_000,
+            modified_gmt: 1_700_000_000,
+            lastlogin_gmt: 0,
+            lastorder_gmt: 0,
+            order_count: 0,
+            newsletter: Some(1),
+            optin_gmt: 0,
+            hint_num: 0,
+            hint_answer: String::new(),
+            hint_attempts: 0,
+            ip: 0,
+            origin: 0,
+            schedule: String::new(),
+            has_notes: 0,
+            reward_balance: None,
+            is_affiliate: 0,
+            is_locked,
+            locked_until_gmt: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_records_login() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer("hunter22", 0)]])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .into_connection();
+
+        let customer = CustomerService::authenticate(&db, 1, "c7@example.com", "hunter22", &LoginPolicy::default())
+            .await
+            .unwrap();
+        assert!(customer.lastlogin_gmt > 0);
+
+        let log = db.into_transaction_log();
+        assert!(log[1].statements()[0].sql.contains(r#""lastlogin_gmt""#));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_and_unknown_email_look_alike() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer("hunter22", 0)]])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .append_query_results([Vec::<Customer>::new()])
+            .into_connection();
+
+        let policy = LoginPolicy::default();
+        let wrong = CustomerService::authenticate(&db, 1, "c7@example.com", "nope", &policy).await;
+        let unknown = CustomerService::authenticate(&db, 1, "x@example.com", "hunter22", &policy).await;
+        assert!(matches!(wrong, Err(CustomerError::InvalidCredentials)));
+        assert!(matches!(unknown, Err(CustomerError::InvalidCredentials)));
+    }
 
-    // Tests will be added when we have a test database setup
-    // For now, compilation success validates the API design
+    #[tokio::test]
+    async fn test_locked_account_cannot_log_in() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer("hunter22", 1)]])
+            .into_connection();
+
+        let err = CustomerService::authenticate(&db, 1, "c7@example.com", "hunter22", &LoginPolicy::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CustomerError::AccountLocked));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_password_is_rehashed_on_login() {
+        let legacy = Customer {
+            password: "hunter22".to_string(),
+            passhash: String::new(),
+            ..customer("unused", 0)
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![legacy]])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .into_connection();
+
+        let customer = CustomerService::authenticate(&db, 1, "c7@example.com", "hunter22", &LoginPolicy::default())
+            .await
+            .unwrap();
+        assert_eq!(PasswordScheme::of(&customer), PasswordScheme::Argon2);
+        assert!(customer.password.is_empty());
+
+        let log = db.into_transaction_log();
+        let update = log[1].statements()[0].to_string();
+        assert!(update.contains(r#""passhash" = '$argon2id$"#), "{}", update);
+        assert!(update.contains(r#""password" = ''"#));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_counts_towards_lockout() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer("hunter22", 0)]])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .into_connection();
+
+        let policy = LoginPolicy { max_failed_attempts: 3, lockout: Duration::from_secs(600) };
+        let err = CustomerService::authenticate(&db, 1, "c7@example.com", "nope", &policy)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CustomerError::InvalidCredentials));
+
+        let log = db.into_transaction_log();
+        let update = log[1].statements()[0].to_string();
+        assert!(
+            update.contains(r#""hint_attempts" = CASE WHEN "hint_attempts" + 1 >= 3 THEN 0"#),
+            "{}",
+            update
+        );
+        assert!(update.contains(r#""locked_until_gmt" = CASE WHEN "hint_attempts" + 1 >= 3 THEN "#));
+    }
+
+    #[tokio::test]
+    async fn test_temporarily_locked_account_is_refused_before_checking_password() {
+        let until = Utc::now().timestamp() as i32 + 600;
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![Customer { locked_until_gmt: until, ..customer("hunter22", 0) }]])
+            .into_connection();
+
+        let err = CustomerService::authenticate(&db, 1, "c7@example.com", "hunter22", &LoginPolicy::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CustomerError::TemporarilyLocked { until: u } if u == until));
+        assert_eq!(db.into_transaction_log().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_locked_account_does_not_verify() {
+        assert!(CustomerService::verify_password(&customer("hunter22", 0), "hunter22").await.unwrap());
+        assert!(!CustomerService::verify_password(&customer("hunter22", 1), "hunter22").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_set_password_ends_sessions() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+                MockExecResult { last_insert_id: 0, rows_affected: 2 },
+            ])
+            .into_connection();
+
+        let updated = CustomerService::set_password(&db, customer("hunter22", 0), "correct horse")
+            .await
+            .unwrap();
+        assert_eq!(updated.cid, 7);
+
+        let log = db.into_transaction_log();
+        let txn = log[0].statements();
+        assert!(txn[1].sql.starts_with(r#"UPDATE "customers" SET "passhash""#));
+        assert!(txn[2].sql.starts_with(r#"UPDATE "customer_sessions" SET "revoked_gmt""#));
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let err = CustomerService::set_password(&db, customer("hunter22", 0), "short")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CustomerError::WeakPassword(8)));
+    }
+
+    #[tokio::test]
+    async fn test_update_is_scoped_to_merchant() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Customer>::new()])
+            .into_connection();
+
+        let err = CustomerService::update(&db, 2, customer("hunter22", 0))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CustomerError::NotFound));
+
+        let log = db.into_transaction_log();
+        assert!(log[0].statements()[0].to_string().contains(r#""customers"."mid" = 2"#));
+    }
 }
@@ -0,0 +1,378 @@
+//! Support notes on customers
+//!
+//! `customers.has_notes` flags customers with at least one note so listings
+//! can show it without a join. It is updated in the same transaction as the
+//! notes, under the customer's row lock.
+
+use ::entity::customer_notes::{self, Column};
+use ::entity::prelude::{CustomerNote, CustomerNotes, Customers};
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{CustomerError, Result};
+use crate::lock_customer;
+
+/// What a note is about; stored as a three-letter code in `type`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteType {
+    #[default]
+    General,
+    Support,
+    Billing,
+    Shipping,
+    Fraud,
+}
+
+impl NoteType {
+    pub const ALL: [NoteType; 5] = [
+        Self::General,
+        Self::Support,
+        Self::Billing,
+        Self::Shipping,
+        Self::Fraud,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::General => "GEN",
+            Self::Support => "SUP",
+            Self::Billing => "BIL",
+            Self::Shipping => "SHP",
+            Self::Fraud => "FRD",
+        }
+    }
+
+    /// Type of a stored note; legacy rows without a known code are general
+    pub fn of(note: &CustomerNote) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.code() == note.r#type)
+            .unwrap_or_default()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::General => "general",
+            Self::Support => "support",
+            Self::Billing => "billing",
+            Self::Shipping => "shipping",
+            Self::Fraud => "fraud",
+        }
+    }
+}
+
+impl fmt::Display for NoteType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for NoteType {
+    type Err = CustomerError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| CustomerError::InvalidNote {
+                field: "type",
+                message: format!("unknown note type {}", s),
+            })
+    }
+}
+
+/// A note to add
+#[derive(Debug, Clone)]
+pub struct NewNote {
+    /// Merchant username, kept in the legacy `username` column
+    pub username: String,
+    /// Who wrote the note (`luser`), e.g. `key3` for API key 3
+    pub author: String,
+    pub note_type: NoteType,
+    pub note: String,
+}
+
+/// Customer note service
+pub struct NoteService;
+
+impl NoteService {
+    /// Notes on a customer, newest first, optionally only those created before `before`
+    pub async fn list(
+        db: &DatabaseConnection,
+        mid: i32,
+        cid: i32,
+        limit: u64,
+        before: Option<i32>,
+    ) -> Result<Vec<CustomerNote>> {
+        let mut query = CustomerNotes::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Cid.eq(cid));
+        if let Some(before) = before {
+            query = query.filter(Column::CreatedGmt.lt(before));
+        }
+
+        let notes = query
+            .order_by_desc(Column::CreatedGmt)
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(notes)
+    }
+
+    /// Find a note on a customer
+    pub async fn find(db: &DatabaseConnection, mid: i32, cid: i32, id: i32) -> Result<Option<CustomerNote>> {
+        let note = CustomerNotes::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Cid.eq(cid))
+            .filter(Column::Id.eq(id))
+            .one(db)
+            .await?;
+
+        Ok(note)
+    }
+
+    /// Add a note and flag the customer as having notes
+    pub async fn create(db: &DatabaseConnection, mid: i32, cid: i32, new: NewNote) -> Result<CustomerNote> {
+        let note = validate_note(&new.note)?;
+        let author = validate_author(&new.author)?;
+
+        let txn = db.begin().await?;
+        let customer = lock_customer(&txn, mid, cid).await?;
+        let created = customer_notes::ActiveModel {
+            mid: Set(mid),
+            cid: Set(cid),
+            // 🤓 legacy column is varchar(20)
+            username: Set(new.username.chars().take(20).collect()),
+            luser: Set(author),
+            r#type: Set(new.note_type.code().to_string()),
+            note: Set(note),
+            created_gmt: Set(Utc::now().timestamp() as i32),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        if customer.has_notes == 0 {
+            set_has_notes(&txn, mid, cid, true).await?;
+        }
+        txn.commit().await?;
+        Ok(created)
+    }
+
+    /// Change a note's text and type; the author stays as written
+    pub async fn update(
+        db: &DatabaseConnection,
+        mid: i32,
+        cid: i32,
+        id: i32,
+        note_type: NoteType,
+        note: &str,
+    ) -> Result<CustomerNote> {
+        let note = validate_note(note)?;
+        let current = Self::find(db, mid, cid, id)
+            .await?
+            .ok_or(CustomerError::NoteNotFound)?;
+
+        let mut active: customer_notes::ActiveModel = current.into();
+        active.r#type = Set(note_type.code().to_string());
+        active.note = Set(note);
+        active.update(db).await.map_err(|e| match e {
+            DbErr::RecordNotUpdated => CustomerError::NoteNotFound,
+            other => other.into(),
+        })
+    }
+
+    /// Delete a note, clearing `has_notes` with the last one
+    pub async fn delete(db: &DatabaseConnection, mid: i32, cid: i32, id: i32) -> Result<()> {
+        let txn = db.begin().await?;
+        lock_customer(&txn, mid, cid).await?;
+        let deleted = CustomerNotes::delete_many()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Cid.eq(cid))
+            .filter(Column::Id.eq(id))
+            .exec(&txn)
+            .await?;
+        if deleted.rows_affected == 0 {
+            return Err(CustomerError::NoteNotFound);
+        }
+
+        let remaining = CustomerNotes::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Cid.eq(cid))
+            .count(&txn)
+            .await?;
+        if remaining == 0 {
+            set_has_notes(&txn, mid, cid, false).await?;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+}
+
+async fn set_has_notes<C: ConnectionTrait>(db: &C, mid: i32, cid: i32, has_notes: bool) -> Result<()> {
+    Customers::update_many()
+        .col_expr(::entity::customers::Column::HasNotes, Expr::value(i16::from(has_notes)))
+        .filter(::entity::customers::Column::Mid.eq(mid))
+        .filter(::entity::customers::Column::Cid.eq(cid))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+fn validate_note(note: &str) -> Result<String> {
+    let note = note.trim();
+    if note.is_empty() {
+        return Err(CustomerError::InvalidNote {
+            field: "note",
+            message: "is required".to_string(),
+        });
+    }
+    Ok(note.to_string())
+}
+
+/// Authors are legacy login names, at most 10 characters
+fn validate_author(author: &str) -> Result<String> {
+    let author = author.trim();
+    if author.is_empty() || author.chars().count() > 10 {
+        return Err(CustomerError::InvalidNote {
+            field: "author",
+            message: "must be 1 to 10 characters".to_string(),
+        });
+    }
+    Ok(author.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::entity::prelude::Customer;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use std::collections::BTreeMap;
+
+    fn customer(has_notes: i16) -> Customer {
+        Customer {
+            cid: 7,
+            orgid: 0,
+            mid: 1,
+            username: String::new(),
+            prt: 0,
+            email: "ada@example.com".to_string(),
+            password: String::new(),
+            passhash: String::new(),
+            passsalt: String::new(),
+            firstname: "Ada".to_string(),
+            lastname: "Lovelace".to_string(),
+            phone: String::new(),
+            created_gmt: 0,
+            modified_gmt: 0,
+            lastlogin_gmt: 0,
+            lastorder_gmt: 0,
+            order_count: 0,
+            newsletter: None,
+            optin_gmt: 0,
+            hint_num: 0,
+            hint_answer: String::new(),
+            hint_attempts: 0,
+            ip: 0,
+            origin: 0,
+            schedule: String::new(),
+            has_notes,
+            reward_balance: None,
+            is_affiliate: 0,
+            is_locked: 0,
+            locked_until_gmt: 0,
+        }
+    }
+
+    fn note(id: i32) -> CustomerNote {
+        CustomerNote {
+            id,
+            mid: 1,
+            username: "demo".to_string(),
+            cid: 7,
+            created_gmt: 1_700_000_000,
+            luser: "jane".to_string(),
+            note: "Called about a late parcel".to_string(),
+            r#type: "SHP".to_string(),
+        }
+    }
+
+    fn exec(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
+
+    fn new_note(author: &str) -> NewNote {
+        NewNote {
+            username: "demo".to_string(),
+            author: author.to_string(),
+            note_type: NoteType::Shipping,
+            note: " Called about a late parcel ".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_note_types_round_trip_through_codes() {
+        assert_eq!(NoteType::of(&note(1)), NoteType::Shipping);
+        assert_eq!(NoteType::of(&CustomerNote { r#type: String::new(), ..note(1) }), NoteType::General);
+        assert_eq!("fraud".parse::<NoteType>().unwrap(), NoteType::Fraud);
+        assert!("SHP".parse::<NoteType>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_first_note_sets_has_notes() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer(0)]])
+            .append_query_results([vec![note(1)]])
+            .append_exec_results([exec(1)])
+            .into_connection();
+
+        let created = NoteService::create(&db, 1, 7, new_note("jane")).await.unwrap();
+        assert_eq!(created.luser, "jane");
+
+        let log = db.into_transaction_log();
+        let txn = log[0].statements();
+        assert!(txn[2].to_string().contains("'Called about a late parcel'"), "{}", txn[2]);
+        assert!(txn[2].to_string().contains("'SHP'"));
+        assert!(txn[3].sql.starts_with(r#"UPDATE "customers" SET "has_notes""#));
+    }
+
+    #[tokio::test]
+    async fn test_deleting_last_note_clears_has_notes() {
+        let count = BTreeMap::from([("num_items".to_string(), Value::BigInt(Some(0)))]);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer(1)]])
+            .append_exec_results([exec(1)])
+            .append_query_results([vec![count]])
+            .append_exec_results([exec(1)])
+            .into_connection();
+
+        NoteService::delete(&db, 1, 7, 1).await.unwrap();
+
+        let log = db.into_transaction_log();
+        let update = &log[0].statements()[4];
+        assert!(update.sql.starts_with(r#"UPDATE "customers" SET "has_notes""#));
+        assert!(update.to_string().contains("\"has_notes\" = 0"), "{}", update);
+    }
+
+    #[tokio::test]
+    async fn test_author_is_required() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+        let err = NoteService::create(&db, 1, 7, new_note("")).await.unwrap_err();
+        assert!(matches!(err, CustomerError::InvalidNote { field: "author", .. }));
+        let err = NoteService::create(&db, 1, 7, new_note("a.very.long.name")).await.unwrap_err();
+        assert!(matches!(err, CustomerError::InvalidNote { field: "author", .. }));
+    }
+}
@@ -0,0 +1,126 @@
+//! Customer notifications
+//!
+//! Messages such as password reset links leave the system through a
+//! [`Notifier`]. The log and file notifiers are for development and for
+//! handing messages to an external mailer; neither sends email itself.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::error::{CustomerError, Result};
+
+/// A password reset link to deliver to a customer
+#[derive(Debug, Clone, Serialize)]
+pub struct PasswordResetMessage {
+    pub mid: i32,
+    pub cid: i32,
+    pub email: String,
+    pub firstname: String,
+    /// The raw token; only its hash is stored
+    pub token: String,
+    pub expires_gmt: i32,
+}
+
+/// Delivers customer notifications
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn password_reset(&self, message: &PasswordResetMessage) -> Result<()>;
+}
+
+/// Writes notifications to the tracing log, token included
+#[derive(Debug, Default)]
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn password_reset(&self, message: &PasswordResetMessage) -> Result<()> {
+        tracing::info!(
+            mid = message.mid,
+            cid = message.cid,
+            email = %message.email,
+            token = %message.token,
+            expires_gmt = message.expires_gmt,
+            "password reset requested"
+        );
+        Ok(())
+    }
+}
+
+/// Appends notifications to a file, one JSON object per line
+#[derive(Debug)]
+pub struct FileNotifier {
+    path: PathBuf,
+    write: Mutex<()>,
+}
+
+impl FileNotifier {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            write: Mutex::new(()),
+        }
+    }
+
+    async fn append(&self, line: serde_json::Value) -> Result<()> {
+        let mut line = line.to_string();
+        line.push('\n');
+
+        let _guard = self.write.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| CustomerError::Notify(format!("{}: {}", self.path.display(), e)))?;
+        // tokio only finishes the write on flush, not on drop
+        async {
+            file.write_all(line.as_bytes()).await?;
+            file.flush().await
+        }
+        .await
+        .map_err(|e| CustomerError::Notify(format!("{}: {}", self.path.display(), e)))
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn password_reset(&self, message: &PasswordResetMessage) -> Result<()> {
+        self.append(serde_json::json!({ "kind": "password_reset", "message": message }))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_notifier_appends_json_lines() {
+        let path = std::env::temp_dir().join(format!("notify-{}.jsonl", uuid::Uuid::new_v4()));
+        let notifier = FileNotifier::new(&path);
+        let message = PasswordResetMessage {
+            mid: 1,
+            cid: 7,
+            email: "ada@example.com".to_string(),
+            firstname: "Ada".to_string(),
+            token: "abc".to_string(),
+            expires_gmt: 1_700_003_600,
+        };
+
+        notifier.password_reset(&message).await.unwrap();
+        notifier.password_reset(&message).await.unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<serde_json::Value> = written
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["kind"], "password_reset");
+        assert_eq!(lines[0]["message"]["token"], "abc");
+    }
+}
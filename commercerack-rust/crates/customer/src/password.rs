@@ -0,0 +1,257 @@
+//! Customer password hashes
+//!
+//! New passwords are stored as Argon2id PHC strings in `passhash`, which
+//! carry their own salt. Accounts imported from the Perl system may still
+//! hold a plaintext `password`, or an unpadded base64 MD5 or SHA-1 digest
+//! of the password and `passsalt`. Those verify here and are rehashed on
+//! the next successful login.
+
+use ::entity::customers::Column;
+use ::entity::prelude::{Customer, Customers};
+use argon2::password_hash::{rand_core::OsRng, SaltString};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use md5::Md5;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use subtle::ConstantTimeEq;
+
+use crate::error::{CustomerError, Result};
+
+/// How a customer's password is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordScheme {
+    /// Argon2 PHC string in `passhash`
+    Argon2,
+    /// Legacy base64 MD5 digest (22 characters) in `passhash`
+    Md5,
+    /// Legacy base64 SHA-1 digest (27 characters) in `passhash`
+    Sha1,
+    /// Legacy plaintext in `password`
+    Plaintext,
+    /// A `passhash` in no known format; never verifies
+    Unknown,
+    /// No password set
+    None,
+}
+
+impl PasswordScheme {
+    pub fn of(customer: &Customer) -> Self {
+        let hash = customer.passhash.trim_end_matches('=');
+        if customer.passhash.starts_with("$argon2") {
+            Self::Argon2
+        } else if !hash.is_empty() {
+            match hash.len() {
+                22 => Self::Md5,
+                27 => Self::Sha1,
+                _ => Self::Unknown,
+            }
+        } else if !customer.password.is_empty() {
+            Self::Plaintext
+        } else {
+            Self::None
+        }
+    }
+
+    /// Verifies, but should be replaced by an Argon2 hash
+    pub fn is_legacy(&self) -> bool {
+        matches!(self, Self::Md5 | Self::Sha1 | Self::Plaintext)
+    }
+}
+
+/// Customers of a merchant by password scheme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SchemeCounts {
+    pub argon2: u64,
+    pub md5: u64,
+    pub sha1: u64,
+    pub plaintext: u64,
+    pub unknown: u64,
+    pub none: u64,
+}
+
+impl SchemeCounts {
+    /// Accounts that will be rehashed on their next login
+    pub fn legacy(&self) -> u64 {
+        self.md5 + self.sha1 + self.plaintext
+    }
+
+    /// Count a merchant's customers by scheme, classified in SQL the same
+    /// way as [`PasswordScheme::of`]
+    pub async fn load(db: &DatabaseConnection, mid: i32) -> Result<Self> {
+        let scheme = Expr::cust(
+            r#"CASE WHEN "passhash" LIKE '$argon2%' THEN 'argon2'
+            WHEN LENGTH(RTRIM("passhash", '=')) = 22 THEN 'md5'
+            WHEN LENGTH(RTRIM("passhash", '=')) = 27 THEN 'sha1'
+            WHEN RTRIM("passhash", '=') <> '' THEN 'unknown'
+            WHEN "password" <> '' THEN 'plaintext'
+            ELSE 'none' END"#,
+        );
+        let rows = Customers::find()
+            .select_only()
+            .column_as(scheme.clone(), "scheme")
+            .column_as(Column::Cid.count(), "customers")
+            .filter(Column::Mid.eq(mid))
+            .group_by(scheme)
+            .into_model::<SchemeRow>()
+            .all(db)
+            .await?;
+
+        let mut counts = Self::default();
+        for row in rows {
+            let n = row.customers as u64;
+            match row.scheme.as_str() {
+                "argon2" => counts.argon2 = n,
+                "md5" => counts.md5 = n,
+                "sha1" => counts.sha1 = n,
+                "plaintext" => counts.plaintext = n,
+                "unknown" => counts.unknown = n,
+                _ => counts.none = n,
+            }
+        }
+        Ok(counts)
+    }
+}
+
+#[derive(FromQueryResult)]
+struct SchemeRow {
+    scheme: String,
+    customers: i64,
+}
+
+/// Argon2id PHC string for `password`
+pub fn hash(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| CustomerError::PasswordHash(e.to_string()))
+}
+
+/// Check `password` against whichever scheme the customer's hash uses
+pub fn verify(customer: &Customer, password: &str) -> Result<bool> {
+    let stored = customer.passhash.trim_end_matches('=');
+    let salt = customer.passsalt.as_str();
+    Ok(match PasswordScheme::of(customer) {
+        PasswordScheme::Argon2 => {
+            let parsed = PasswordHash::new(&customer.passhash)
+                .map_err(|e| CustomerError::PasswordHash(e.to_string()))?;
+            Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok()
+        }
+        // 🤓 the salt is tried on both sides; exports from the Perl system disagree
+        PasswordScheme::Md5 => {
+            salted::<Md5>(stored, password, salt, true) | salted::<Md5>(stored, password, salt, false)
+        }
+        PasswordScheme::Sha1 => {
+            salted::<Sha1>(stored, password, salt, true) | salted::<Sha1>(stored, password, salt, false)
+        }
+        PasswordScheme::Plaintext => bool::from(customer.password.as_bytes().ct_eq(password.as_bytes())),
+        PasswordScheme::Unknown | PasswordScheme::None => false,
+    })
+}
+
+fn salted<D: Digest>(stored: &str, password: &str, salt: &str, salt_first: bool) -> bool {
+    let mut digest = D::new();
+    if salt_first {
+        digest.update(salt.as_bytes());
+        digest.update(password.as_bytes());
+    } else {
+        digest.update(password.as_bytes());
+        digest.update(salt.as_bytes());
+    }
+    let computed = STANDARD_NO_PAD.encode(digest.finalize());
+    bool::from(computed.as_bytes().ct_eq(stored.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    fn customer(password: &str, passhash: &str, passsalt: &str) -> Customer {
+        Customer {
+            cid: 7,
+            orgid: 0,
+            mid: 1,
+            username: String::new(),
+            prt: 0,
+            email: "ada@example.com".to_string(),
+            password: password.to_string(),
+            passhash: passhash.to_string(),
+            passsalt: passsalt.to_string(),
+            firstname: String::new(),
+            lastname: String::new(),
+            phone: String::new(),
+            created_gmt: 0,
+            modified_gmt: 0,
+            lastlogin_gmt: 0,
+            lastorder_gmt: 0,
+            order_count: 0,
+            newsletter: None,
+            optin_gmt: 0,
+            hint_num: 0,
+            hint_answer: String::new(),
+            hint_attempts: 0,
+            ip: 0,
+            origin: 0,
+            schedule: String::new(),
+            has_notes: 0,
+            reward_balance: None,
+            is_affiliate: 0,
+            is_locked: 0,
+            locked_until_gmt: 0,
+        }
+    }
+
+    #[test]
+    fn test_schemes_are_recognised() {
+        assert_eq!(PasswordScheme::of(&customer("", &hash("hunter22").unwrap(), "")), PasswordScheme::Argon2);
+        assert_eq!(PasswordScheme::of(&customer("", "X03MO1qnZdYdgyfeuILPmQ", "s")), PasswordScheme::Md5);
+        assert_eq!(PasswordScheme::of(&customer("", "X03MO1qnZdYdgyfeuILPmQ==", "s")), PasswordScheme::Md5);
+        assert_eq!(PasswordScheme::of(&customer("", "2jmj7l5rSw0yVb/vlWAYkK/YBwk", "")), PasswordScheme::Sha1);
+        assert_eq!(PasswordScheme::of(&customer("hunter22", "", "")), PasswordScheme::Plaintext);
+        assert_eq!(PasswordScheme::of(&customer("", "abc", "")), PasswordScheme::Unknown);
+        assert_eq!(PasswordScheme::of(&customer("", "", "")), PasswordScheme::None);
+    }
+
+    #[tokio::test]
+    async fn test_scheme_counts_are_grouped_in_sql() {
+        let row = |scheme: &str, n: i64| {
+            std::collections::BTreeMap::from([
+                ("scheme".to_string(), Value::from(scheme)),
+                ("customers".to_string(), Value::BigInt(Some(n))),
+            ])
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![row("argon2", 5), row("md5", 3), row("plaintext", 2)]])
+            .into_connection();
+
+        let counts = SchemeCounts::load(&db, 1).await.unwrap();
+        assert_eq!(counts.argon2, 5);
+        assert_eq!(counts.legacy(), 5);
+
+        let log = db.into_transaction_log();
+        let sql = log[0].statements()[0].to_string();
+        assert!(sql.contains("GROUP BY CASE WHEN"), "{}", sql);
+        assert!(sql.contains(r#""customers"."mid" = 1"#));
+    }
+
+    #[test]
+    fn test_legacy_digests_verify_with_salt_either_side() {
+        // md5_base64("pepperhunter22") and sha1_base64("hunter22pepper")
+        let md5 = STANDARD_NO_PAD.encode(Md5::digest(b"pepperhunter22"));
+        let sha1 = STANDARD_NO_PAD.encode(Sha1::digest(b"hunter22pepper"));
+
+        assert!(verify(&customer("", &md5, "pepper"), "hunter22").unwrap());
+        assert!(!verify(&customer("", &md5, "pepper"), "hunter23").unwrap());
+        assert!(!verify(&customer("", &md5, "salt"), "hunter22").unwrap());
+        assert!(verify(&customer("", &format!("{}=", sha1), "pepper"), "hunter22").unwrap());
+        assert!(verify(&customer("hunter22", "", ""), "hunter22").unwrap());
+        assert!(!verify(&customer("hunter22", "", ""), "hunter2").unwrap());
+        assert!(!verify(&customer("", "", ""), "").unwrap());
+    }
+}
@@ -0,0 +1,184 @@
+//! Customer list filters and sort options
+
+use ::entity::customers::{Column, Model as Customer};
+use commercerack_db::pagination::{Cursor, CursorError};
+use sea_orm::sea_query::{Expr, Func, LikeExpr};
+use sea_orm::{ColumnTrait, Condition, Value};
+use serde::{Deserialize, Serialize};
+
+/// Filters for [`crate::CustomerService::search`]; unset fields match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomerFilter {
+    /// Email starts with this value (case-sensitive, as stored)
+    pub email_prefix: Option<String>,
+    /// First or last name contains this value (case-insensitive)
+    pub name: Option<String>,
+    pub created_from: Option<i32>,
+    pub created_to: Option<i32>,
+    pub modified_from: Option<i32>,
+    pub modified_to: Option<i32>,
+    pub is_locked: Option<bool>,
+    /// Subscribed to the newsletter
+    pub newsletter: Option<bool>,
+}
+
+impl CustomerFilter {
+    /// SQL condition for this filter within merchant `mid`
+    pub fn condition(&self, mid: i32) -> Condition {
+        let mut cond = Condition::all().add(Column::Mid.eq(mid));
+
+        if let Some(prefix) = self.email_prefix.as_deref().filter(|p| !p.is_empty()) {
+            let pattern = format!("{}%", escape_like(prefix));
+            cond = cond.add(Expr::col(Column::Email).like(LikeExpr::new(pattern).escape('\\')));
+        }
+
+        if let Some(name) = self.name.as_deref().filter(|n| !n.is_empty()) {
+            let pattern = format!("%{}%", escape_like(&name.to_lowercase()));
+            let contains = |col: Column| {
+                Expr::expr(Func::lower(Expr::col(col)))
+                    .like(LikeExpr::new(pattern.clone()).escape('\\'))
+            };
+            cond = cond.add(
+                Condition::any()
+                    .add(contains(Column::Firstname))
+                    .add(contains(Column::Lastname)),
+            );
+        }
+
+        if let Some(from) = self.created_from {
+            cond = cond.add(Column::CreatedGmt.gte(from));
+        }
+        if let Some(to) = self.created_to {
+            cond = cond.add(Column::CreatedGmt.lte(to));
+        }
+        if let Some(from) = self.modified_from {
+            cond = cond.add(Column::ModifiedGmt.gte(from));
+        }
+        if let Some(to) = self.modified_to {
+            cond = cond.add(Column::ModifiedGmt.lte(to));
+        }
+
+        if let Some(locked) = self.is_locked {
+            cond = cond.add(if locked {
+                Column::IsLocked.gt(0)
+            } else {
+                Column::IsLocked.eq(0)
+            });
+        }
+
+        if let Some(subscribed) = self.newsletter {
+            cond = cond.add(if subscribed {
+                Condition::all().add(Column::Newsletter.gt(0))
+            } else {
+                Condition::any()
+                    .add(Column::Newsletter.eq(0))
+                    .add(Column::Newsletter.is_null())
+            });
+        }
+
+        cond
+    }
+}
+
+/// Sort order for customer listings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomerSort {
+    /// Customer ID (creation order)
+    #[default]
+    Cid,
+    Created,
+    Modified,
+    Email,
+    Lastname,
+}
+
+impl CustomerSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cid => "cid",
+            Self::Created => "created",
+            Self::Modified => "modified",
+            Self::Email => "email",
+            Self::Lastname => "lastname",
+        }
+    }
+
+    /// Sort column ahead of the `cid` tiebreaker, `None` when sorting by `cid`
+    pub(crate) fn column(&self) -> Option<Column> {
+        match self {
+            Self::Cid => None,
+            Self::Created => Some(Column::CreatedGmt),
+            Self::Modified => Some(Column::ModifiedGmt),
+            Self::Email => Some(Column::Email),
+            Self::Lastname => Some(Column::Lastname),
+        }
+    }
+
+    /// Sort key of `customer` as stored in a cursor
+    pub(crate) fn key_of(&self, customer: &Customer) -> serde_json::Value {
+        match self {
+            Self::Cid => customer.cid.into(),
+            Self::Created => customer.created_gmt.into(),
+            Self::Modified => customer.modified_gmt.into(),
+            Self::Email => customer.email.clone().into(),
+            Self::Lastname => customer.lastname.clone().into(),
+        }
+    }
+
+    /// Sort key of `cursor` as a column value
+    pub(crate) fn key_value(&self, cursor: &Cursor) -> Result<Value, CursorError> {
+        Ok(match self {
+            Self::Cid | Self::Created | Self::Modified => {
+                let key = i32::try_from(cursor.key_i64()?).map_err(|_| CursorError::Malformed)?;
+                key.into()
+            }
+            Self::Email | Self::Lastname => cursor.key_str()?.to_string().into(),
+        })
+    }
+}
+
+/// Escape LIKE wildcards so user input matches literally
+fn escape_like(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
+
+    fn sql(filter: &CustomerFilter) -> String {
+        ::entity::customers::Entity::find()
+            .filter(filter.condition(7))
+            .build(DbBackend::Postgres)
+            .to_string()
+    }
+
+    #[test]
+    fn test_empty_filter_scopes_by_merchant_only() {
+        let sql = sql(&CustomerFilter::default());
+        assert!(sql.ends_with(r#"WHERE "customers"."mid" = 7"#));
+    }
+
+    #[test]
+    fn test_filters_build_expected_predicates() {
+        let sql = sql(&CustomerFilter {
+            email_prefix: Some("100%_".to_string()),
+            name: Some("Ann".to_string()),
+            created_from: Some(10),
+            is_locked: Some(true),
+            newsletter: Some(false),
+            ..Default::default()
+        });
+
+        assert!(sql.contains(r#""email" LIKE E'100\\%\\_%' ESCAPE E'\\'"#), "{sql}");
+        assert!(sql.contains(r#"LOWER("firstname") LIKE '%ann%'"#), "{sql}");
+        assert!(sql.contains(r#""created_gmt" >= 10"#));
+        assert!(sql.contains(r#""is_locked" > 0"#));
+        assert!(sql.contains(r#""newsletter" IS NULL"#));
+    }
+}
@@ -0,0 +1,296 @@
+//! Password reset tokens
+//!
+//! A reset request issues a random token that is delivered to the customer
+//! through a [`Notifier`]; only its SHA-256 is stored. Tokens expire, are
+//! single use, and a new request supersedes any earlier unused token.
+
+use ::entity::customer_password_resets::{self, Column};
+use ::entity::prelude::{Customer, CustomerPasswordResets, Customers};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use std::time::Duration;
+
+use crate::auth::{generate_token, hash_token, now_gmt};
+use crate::error::{CustomerError, Result};
+use crate::notify::{Notifier, PasswordResetMessage};
+use crate::{password, store_password, CustomerService, MIN_PASSWORD_LEN};
+
+/// Password reset service
+pub struct PasswordResetService;
+
+impl PasswordResetService {
+    /// Issue a reset token for `email` and hand it to `notifier`
+    ///
+    /// Unknown emails and locked accounts are silently ignored so callers
+    /// cannot probe which addresses have accounts.
+    pub async fn request(
+        db: &DatabaseConnection,
+        notifier: &dyn Notifier,
+        mid: i32,
+        email: &str,
+        ttl: Duration,
+    ) -> Result<()> {
+        let Some(customer) = CustomerService::find_by_email(db, mid, email).await? else {
+            tracing::debug!(mid, "password reset requested for unknown email");
+            return Ok(());
+        };
+        if customer.is_locked != 0 {
+            tracing::info!(mid, cid = customer.cid, "password reset requested for locked account");
+            return Ok(());
+        }
+
+        let token = generate_token();
+        let now = now_gmt();
+        let expires_gmt = now.saturating_add(ttl.as_secs().min(i32::MAX as u64) as i32);
+
+        let txn = db.begin().await?;
+        supersede(&txn, mid, customer.cid, now).await?;
+        customer_password_resets::ActiveModel {
+            mid: Set(mid),
+            cid: Set(customer.cid),
+            token_hash: Set(hash_token(&token)),
+            created_gmt: Set(now),
+            expires_gmt: Set(expires_gmt),
+            used_gmt: Set(0),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+
+        notifier
+            .password_reset(&PasswordResetMessage {
+                mid,
+                cid: customer.cid,
+                email: customer.email,
+                firstname: customer.firstname,
+                token,
+                expires_gmt,
+            })
+            .await
+    }
+
+    /// Redeem a reset token, setting a new password and ending the
+    /// customer's sessions
+    ///
+    /// A password that is too short is rejected before the token is spent.
+    pub async fn reset(db: &DatabaseConnection, token: &str, password: &str) -> Result<Customer> {
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(CustomerError::WeakPassword(MIN_PASSWORD_LEN));
+        }
+
+        let now = now_gmt();
+        let reset = CustomerPasswordResets::find()
+            .filter(Column::TokenHash.eq(hash_token(token)))
+            .one(db)
+            .await?
+            .filter(|r| r.used_gmt == 0 && r.expires_gmt > now)
+            .ok_or(CustomerError::InvalidResetToken)?;
+
+        let hash = password::hash(password)?;
+
+        // Spending the token and storing the password commit together, so a
+        // failed update leaves the token usable for another attempt
+        let txn = db.begin().await?;
+        // Guarded on used_gmt so two concurrent redemptions cannot both win
+        let spent = CustomerPasswordResets::update_many()
+            .col_expr(Column::UsedGmt, Expr::value(now))
+            .filter(Column::Id.eq(reset.id))
+            .filter(Column::UsedGmt.eq(0))
+            .exec(&txn)
+            .await?;
+        if spent.rows_affected == 0 {
+            return Err(CustomerError::InvalidResetToken);
+        }
+
+        let customer = Customers::find()
+            .filter(::entity::customers::Column::Mid.eq(reset.mid))
+            .filter(::entity::customers::Column::Cid.eq(reset.cid))
+            .one(&txn)
+            .await?
+            .ok_or(CustomerError::InvalidResetToken)?;
+        if customer.is_locked != 0 {
+            return Err(CustomerError::AccountLocked);
+        }
+
+        let customer = store_password(&txn, customer, hash).await?;
+        txn.commit().await?;
+        Ok(customer)
+    }
+}
+
+/// Mark a customer's outstanding tokens as used
+async fn supersede<C: ConnectionTrait>(db: &C, mid: i32, cid: i32, now: i32) -> Result<()> {
+    CustomerPasswordResets::update_many()
+        .col_expr(Column::UsedGmt, Expr::value(now))
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Cid.eq(cid))
+        .filter(Column::UsedGmt.eq(0))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::entity::prelude::CustomerPasswordReset;
+    use async_trait::async_trait;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<PasswordResetMessage>>);
+
+    #[async_trait]
+    impl Notifier for Outbox {
+        async fn password_reset(&self, message: &PasswordResetMessage) -> Result<()> {
+            self.0.lock().unwrap().push(message.clone());
+            Ok(())
+        }
+    }
+
+    fn customer() -> Customer {
+        Customer {
+            cid: 7,
+            orgid: 0,
+            mid: 1,
+            username: String::new(),
+            prt: 0,
+            email: "ada@example.com".to_string(),
+            password: String::new(),
+            passhash: String::new(),
+            passsalt: String::new(),
+            firstname: "Ada".to_string(),
+            lastname: "Lovelace".to_string(),
+            phone: String::new(),
+            created_gmt: 0,
+            modified_gmt: 0,
+            lastlogin_gmt: 0,
+            lastorder_gmt: 0,
+            order_count: 0,
+            newsletter: None,
+            optin_gmt: 0,
+            hint_num: 0,
+            hint_answer: String::new(),
+            hint_attempts: 4,
+            ip: 0,
+            origin: 0,
+            schedule: String::new(),
+            has_notes: 0,
+            reward_balance: None,
+            is_affiliate: 0,
+            is_locked: 0,
+            locked_until_gmt: 0,
+        }
+    }
+
+    fn reset(token: &str, expires_gmt: i32, used_gmt: i32) -> CustomerPasswordReset {
+        CustomerPasswordReset {
+            id: 3,
+            mid: 1,
+            cid: 7,
+            token_hash: hash_token(token),
+            created_gmt: 0,
+            expires_gmt,
+            used_gmt,
+        }
+    }
+
+    fn exec(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_stores_hash_and_notifies_token() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![customer()]])
+            .append_exec_results([exec(1)])
+            .append_query_results([vec![reset("x", i32::MAX, 0)]])
+            .into_connection();
+        let outbox = Outbox::default();
+
+        PasswordResetService::request(&db, &outbox, 1, "ada@example.com", Duration::from_secs(3600))
+            .await
+            .unwrap();
+
+        let sent = outbox.0.lock().unwrap().pop().unwrap();
+        assert_eq!(sent.email, "ada@example.com");
+        let log = db.into_transaction_log();
+        let txn = log[1].statements();
+        assert!(txn[1].sql.starts_with(r#"UPDATE "customer_password_resets" SET "used_gmt""#));
+        let insert = txn[2].to_string();
+        assert!(insert.contains(&hash_token(&sent.token)), "{}", insert);
+        assert!(!insert.contains(&sent.token));
+    }
+
+    #[tokio::test]
+    async fn test_request_for_unknown_email_is_silent() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<Customer>::new()])
+            .into_connection();
+        let outbox = Outbox::default();
+
+        PasswordResetService::request(&db, &outbox, 1, "nobody@example.com", Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert!(outbox.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reset_spends_token_and_ends_sessions() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![reset("tok", i32::MAX, 0)]])
+            .append_exec_results([exec(1)])
+            .append_query_results([vec![customer()]])
+            .append_exec_results([exec(1), exec(1)])
+            .into_connection();
+
+        PasswordResetService::reset(&db, "tok", "correct horse").await.unwrap();
+
+        let log = db.into_transaction_log();
+        let txn = log[1].statements();
+        let spend = txn[1].to_string();
+        assert!(spend.contains(r#""used_gmt" = 0"#), "{}", spend);
+        assert!(txn[3].to_string().contains(r#""hint_attempts" = 0"#), "{}", txn[3]);
+        assert!(txn[4].sql.starts_with(r#"UPDATE "customer_sessions""#));
+        assert_eq!(txn.last().unwrap().sql, "COMMIT");
+    }
+
+    #[tokio::test]
+    async fn test_failed_password_update_leaves_token_unspent() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![reset("tok", i32::MAX, 0)]])
+            .append_exec_results([exec(1)])
+            .append_query_results([vec![customer()]])
+            .append_exec_errors([DbErr::Custom("connection reset".to_string())])
+            .into_connection();
+
+        let err = PasswordResetService::reset(&db, "tok", "correct horse").await.unwrap_err();
+        assert!(matches!(err, CustomerError::Db(_)), "{:?}", err);
+
+        let log = db.into_transaction_log();
+        let txn = log[1].statements();
+        assert!(txn[1].sql.starts_with(r#"UPDATE "customer_password_resets" SET "used_gmt""#));
+        assert_eq!(txn.last().unwrap().sql, "ROLLBACK");
+    }
+
+    #[tokio::test]
+    async fn test_used_expired_and_raced_tokens_are_rejected() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![reset("tok", i32::MAX, 100)]])
+            .append_query_results([vec![reset("tok", 100, 0)]])
+            .append_query_results([vec![reset("tok", i32::MAX, 0)]])
+            .append_exec_results([exec(0)])
+            .into_connection();
+
+        for _ in 0..3 {
+            let err = PasswordResetService::reset(&db, "tok", "correct horse").await.unwrap_err();
+            assert!(matches!(err, CustomerError::InvalidResetToken));
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! 📄 Keyset (cursor) pagination primitives shared by the service crates
+//!
+//! Pages are addressed by an opaque cursor holding the sort key and row id of
+//! the last row returned, so results stay stable while rows are inserted.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Default page size when the caller does not ask for one
+pub const DEFAULT_PAGE_SIZE: u64 = 20;
+
+/// Upper bound on page size to protect the database
+pub const MAX_PAGE_SIZE: u64 = 200;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CursorError {
+    #[error("Invalid pagination cursor")]
+    Malformed,
+
+    #[error("Cursor was issued for sort '{0}'")]
+    SortMismatch(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Position after the last row of a page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// Sort the cursor was issued for; a cursor is only valid for that sort
+    #[serde(rename = "s")]
+    pub sort: String,
+    /// Value of the sort column on the last row
+    #[serde(rename = "k")]
+    pub key: serde_json::Value,
+    /// Primary key of the last row, the tiebreaker for equal sort keys
+    #[serde(rename = "i")]
+    pub id: i64,
+}
+
+impl Cursor {
+    pub fn new(sort: &str, key: impl Into<serde_json::Value>, id: i64) -> Self {
+        Self {
+            sort: sort.to_string(),
+            key: key.into(),
+            id,
+        }
+    }
+
+    /// Encode as an opaque URL-safe token
+    pub fn encode(&self) -> String {
+        // 🤓 Serializing a struct of plain values cannot fail
+        let json = serde_json::to_vec(self).expect("cursor serializes");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decode a token produced by [`Cursor::encode`], checking it belongs to `sort`
+    pub fn decode(token: &str, sort: &str) -> Result<Self, CursorError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| CursorError::Malformed)?;
+        let cursor: Cursor = serde_json::from_slice(&bytes).map_err(|_| CursorError::Malformed)?;
+
+        if cursor.sort != sort {
+            return Err(CursorError::SortMismatch(cursor.sort));
+        }
+        Ok(cursor)
+    }
+
+    /// Sort key as an integer column value
+    pub fn key_i64(&self) -> Result<i64, CursorError> {
+        self.key.as_i64().ok_or(CursorError::Malformed)
+    }
+
+    /// Sort key as a string column value
+    pub fn key_str(&self) -> Result<&str, CursorError> {
+        self.key.as_str().ok_or(CursorError::Malformed)
+    }
+}
+
+/// Which page to fetch and in what order
+#[derive(Debug, Clone, Default)]
+pub struct PageRequest<S> {
+    pub sort: S,
+    pub direction: SortDirection,
+    pub limit: u64,
+    pub cursor: Option<String>,
+}
+
+impl<S> PageRequest<S> {
+    /// Requested page size clamped to `1..=MAX_PAGE_SIZE`
+    pub fn page_size(&self) -> u64 {
+        match self.limit {
+            0 => DEFAULT_PAGE_SIZE,
+            n => n.min(MAX_PAGE_SIZE),
+        }
+    }
+}
+
+/// One page of results
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the following page, `None` on the last page
+    pub next_cursor: Option<String>,
+    /// Total rows matching the filters, across all pages
+    pub total: u64,
+}
+
+impl<T> Page<T> {
+    /// Build a page from up to `page_size + 1` fetched rows; the extra row
+    /// only signals that another page exists and is dropped
+    pub fn from_rows(
+        mut rows: Vec<T>,
+        page_size: u64,
+        total: u64,
+        cursor_of: impl Fn(&T) -> Cursor,
+    ) -> Self {
+        let has_more = rows.len() as u64 > page_size;
+        rows.truncate(page_size as usize);
+        let next_cursor = if has_more {
+            rows.last().map(|row| cursor_of(row).encode())
+        } else {
+            None
+        };
+
+        Self {
+            items: rows,
+            next_cursor,
+            total,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total: self.total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor::new("email", "a@example.com", 42);
+        let token = cursor.encode();
+
+        assert!(!token.contains('='));
+        assert_eq!(Cursor::decode(&token, "email").unwrap(), cursor);
+    }
+
+    #[test]
+    fn test_cursor_rejects_other_sort_and_garbage() {
+        let token = Cursor::new("created", 1_700_000_000, 7).encode();
+
+        assert_eq!(
+            Cursor::decode(&token, "email"),
+            Err(CursorError::SortMismatch("created".to_string()))
+        );
+        assert_eq!(Cursor::decode("not-a-cursor", "email"), Err(CursorError::Malformed));
+    }
+
+    #[test]
+    fn test_page_from_rows() {
+        let page = Page::from_rows(vec![1, 2, 3], 2, 10, |n| Cursor::new("id", *n, *n as i64));
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.total, 10);
+
+        let next = Cursor::decode(page.next_cursor.as_deref().unwrap(), "id").unwrap();
+        assert_eq!(next.id, 2);
+
+        let last = Page::from_rows(vec![3], 2, 10, |n| Cursor::new("id", *n, *n as i64));
+        assert!(last.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_page_size_is_clamped() {
+        let mut req = PageRequest::<()>::default();
+        assert_eq!(req.page_size(), DEFAULT_PAGE_SIZE);
+        req.limit = 10_000;
+        assert_eq!(req.page_size(), MAX_PAGE_SIZE);
+    }
+}
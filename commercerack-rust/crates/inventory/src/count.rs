@@ -0,0 +1,666 @@
+//! Cycle counts
+//!
+//! A count covers the bins of one warehouse zone, or the bins and shelf of
+//! a set of SKUs, with a line per SKU and place (an empty location for the
+//! plain shelf). [`CountService::record`] enters counted quantities and
+//! notes what the ledger expected at that moment; units picked but not yet
+//! shipped have left the shelf, so they aren't expected. Lines start out
+//! blind so counters aren't led by the ledger.
+//!
+//! [`CountService::approve`] writes the variances back: an ADJUST for each
+//! row it changes, a VERIFY for rows found as recorded, stamping their
+//! `verify_ts` and `verify_inc`. Only the variance is applied, so stock
+//! that moved between counting and approval is left alone.
+
+use ::entity::inventory_detail::Column;
+use ::entity::prelude::{
+    InventoryCount, InventoryCountLine, InventoryCountLines, InventoryCounts, InventoryDetail,
+    InventoryDetails, Sku, SkuLookup,
+};
+use ::entity::sea_orm_active_enums::InventoryPickRoute;
+use ::entity::{inventory_count_lines, inventory_counts, sku_lookup};
+use chrono::Utc;
+use sea_orm::prelude::Decimal;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use std::collections::HashMap;
+
+use crate::error::{InventoryError, Result};
+use crate::rollup::Rollup;
+use crate::wms::{add_to_bin, location_part, Location};
+use crate::{add_to_shelf, clip, lock_sku, update_detail, write_log, Action, InventoryBasetype};
+
+/// Most SKUs one count can cover
+pub const MAX_COUNT_SKUS: usize = 500;
+
+/// Where a count is in its life; stored in `inventory_counts.status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountStatus {
+    Open,
+    /// Variances written back to the ledger
+    Approved,
+    Cancelled,
+}
+
+impl CountStatus {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Open => "OPEN",
+            Self::Approved => "APPROVED",
+            Self::Cancelled => "CANCELLED",
+        }
+    }
+
+    pub fn parse(code: &str) -> Option<Self> {
+        match code.trim().to_uppercase().as_str() {
+            "OPEN" => Some(Self::Open),
+            "APPROVED" => Some(Self::Approved),
+            "CANCELLED" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// What a count covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CountScope {
+    /// Every bin in a warehouse zone
+    Zone { geo: String, zone: String },
+    /// Every bin and the shelf of these SKUs
+    Skus(Vec<String>),
+}
+
+/// A counted quantity of a SKU in a bin, or on the shelf without one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountEntry {
+    pub sku: String,
+    pub location: Option<Location>,
+    pub qty: i32,
+}
+
+/// A count and its lines in location, then SKU order
+#[derive(Debug, Clone)]
+pub struct CountSheet {
+    pub count: InventoryCount,
+    pub lines: Vec<InventoryCountLine>,
+}
+
+/// How far a line's count is from the ledger
+#[derive(Debug, Clone)]
+pub struct Variance {
+    pub line: InventoryCountLine,
+    /// Counted less expected; `None` until counted
+    pub variance: Option<i32>,
+    pub unit_cost: Decimal,
+    /// Variance at unit cost
+    pub value: Option<Decimal>,
+}
+
+/// Cycle count service
+pub struct CountService;
+
+impl CountService {
+    /// Open a count with a line for every place in `scope` holding stock
+    pub async fn start(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        scope: CountScope,
+        note: &str,
+    ) -> Result<CountSheet> {
+        if note.chars().count() > 255 {
+            return Err(InventoryError::InvalidDetail {
+                field: "note",
+                message: "must be at most 255 characters".to_string(),
+            });
+        }
+
+        let (geo, zone, skus, places) = match scope {
+            CountScope::Zone { geo, zone } => {
+                let geo = location_part("wms_geo", &geo, 3)?;
+                let zone = location_part("wms_zone", &zone, 3)?;
+                let bins = InventoryDetails::find()
+                    .filter(Column::Mid.eq(mid))
+                    .filter(Column::Basetype.eq(InventoryBasetype::Wms))
+                    .filter(Column::WmsGeo.eq(&geo))
+                    .filter(Column::WmsZone.eq(&zone))
+                    .all(db)
+                    .await?;
+                let places = bins.iter().filter_map(|b| Some((b.sku.clone(), Location::of(b)?.to_string())));
+                (Some(geo), Some(zone), Vec::new(), places.collect::<Vec<_>>())
+            }
+            CountScope::Skus(skus) => {
+                let skus = known_skus(db, mid, skus).await?;
+                let bins = InventoryDetails::find()
+                    .filter(Column::Mid.eq(mid))
+                    .filter(Column::Basetype.eq(InventoryBasetype::Wms))
+                    .filter(Column::Sku.is_in(skus.iter().cloned()))
+                    .all(db)
+                    .await?;
+                let shelves = skus.iter().map(|sku| (sku.clone(), String::new()));
+                let places = bins.iter().filter_map(|b| Some((b.sku.clone(), Location::of(b)?.to_string())));
+                let places = shelves.chain(places).collect::<Vec<_>>();
+                (None, None, skus, places)
+            }
+        };
+        let mut places = places;
+        places.sort_unstable();
+        places.dedup();
+
+        let txn = db.begin().await?;
+        let count = inventory_counts::ActiveModel {
+            mid: Set(mid),
+            status: Set(CountStatus::Open.code().to_string()),
+            wms_geo: Set(geo),
+            wms_zone: Set(zone),
+            skus: Set(JsonValue::from(skus)),
+            note: Set(note.to_string()),
+            created_by: Set(clip(luser, 10)),
+            created_gmt: Set(Utc::now().timestamp() as i32),
+            closed_by: Set(String::new()),
+            closed_gmt: Set(0),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        if !places.is_empty() {
+            let lines = places.into_iter().map(|(sku, location)| inventory_count_lines::ActiveModel {
+                count_id: Set(count.id),
+                sku: Set(sku),
+                location: Set(location),
+                expected: Set(0),
+                counted: Set(None),
+                counted_by: Set(String::new()),
+                counted_gmt: Set(0),
+                adjusted: Set(None),
+                ..Default::default()
+            });
+            InventoryCountLines::insert_many(lines).exec_without_returning(&txn).await?;
+        }
+        let lines = lines_of(&txn, count.id).await?;
+        txn.commit().await?;
+
+        Ok(CountSheet { count, lines })
+    }
+
+    /// A merchant's counts, newest first
+    pub async fn list(db: &DatabaseConnection, mid: i32, status: Option<CountStatus>) -> Result<Vec<InventoryCount>> {
+        let mut query = InventoryCounts::find().filter(inventory_counts::Column::Mid.eq(mid));
+        if let Some(status) = status {
+            query = query.filter(inventory_counts::Column::Status.eq(status.code()));
+        }
+        Ok(query.order_by_desc(inventory_counts::Column::Id).all(db).await?)
+    }
+
+    /// A count and its lines
+    pub async fn get(db: &DatabaseConnection, mid: i32, id: i32) -> Result<CountSheet> {
+        let count = InventoryCounts::find_by_id(id)
+            .filter(inventory_counts::Column::Mid.eq(mid))
+            .one(db)
+            .await?
+            .ok_or(InventoryError::CountNotFound)?;
+        let lines = lines_of(db, id).await?;
+        Ok(CountSheet { count, lines })
+    }
+
+    /// Enter counted quantities, replacing earlier ones for the same place
+    ///
+    /// A place without a line (stock found where the ledger has none) gets
+    /// one, as long as it is within the count's zone or SKUs. Returns the
+    /// lines entered.
+    pub async fn record(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        id: i32,
+        entries: &[CountEntry],
+    ) -> Result<Vec<InventoryCountLine>> {
+        let txn = db.begin().await?;
+        let count = open_count(&txn, mid, id).await?;
+        let now = Utc::now().timestamp() as i32;
+
+        let mut recorded = Vec::with_capacity(entries.len());
+        for entry in entries {
+            if entry.qty < 0 {
+                return Err(InventoryError::NegativeQuantity(entry.sku.clone()));
+            }
+            let location = entry.location.as_ref().map(|l| l.to_string()).unwrap_or_default();
+            let line = InventoryCountLines::find()
+                .filter(inventory_count_lines::Column::CountId.eq(id))
+                .filter(inventory_count_lines::Column::Sku.eq(&entry.sku))
+                .filter(inventory_count_lines::Column::Location.eq(&location))
+                .one(&txn)
+                .await?;
+            if line.is_none() {
+                in_scope(&txn, &count, entry).await?;
+            }
+
+            let expected = on_hand(&txn, mid, &entry.sku, entry.location.as_ref()).await?;
+            let mut active = match line {
+                Some(line) => line.into(),
+                None => inventory_count_lines::ActiveModel {
+                    count_id: Set(id),
+                    sku: Set(entry.sku.clone()),
+                    location: Set(location),
+                    adjusted: Set(None),
+                    ..Default::default()
+                },
+            };
+            active.expected = Set(expected);
+            active.counted = Set(Some(entry.qty));
+            active.counted_by = Set(clip(luser, 10));
+            active.counted_gmt = Set(now);
+            recorded.push(active.save(&txn).await?.try_into_model()?);
+        }
+        txn.commit().await?;
+
+        Ok(recorded)
+    }
+
+    /// Each line's variance from the ledger, valued at the SKU's cost
+    pub async fn variances(db: &DatabaseConnection, mid: i32, id: i32) -> Result<Vec<Variance>> {
+        let sheet = Self::get(db, mid, id).await?;
+        let mut skus: Vec<&str> = sheet.lines.iter().map(|l| l.sku.as_str()).collect();
+        skus.sort_unstable();
+        skus.dedup();
+        let costs: HashMap<String, Decimal> = SkuLookup::find()
+            .filter(sku_lookup::Column::Mid.eq(mid))
+            .filter(sku_lookup::Column::Sku.is_in(skus))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|s| (s.sku, s.cost))
+            .collect();
+
+        Ok(sheet
+            .lines
+            .into_iter()
+            .map(|line| {
+                let unit_cost = costs.get(&line.sku).copied().unwrap_or_default();
+                let variance = line.counted.map(|counted| counted - line.expected);
+                Variance {
+                    variance,
+                    unit_cost,
+                    value: variance.map(|v| unit_cost * Decimal::from(v)),
+                    line,
+                }
+            })
+            .collect())
+    }
+
+    /// Write counted lines back to the ledger and close the count
+    ///
+    /// Applies the lines in `line_ids`, or every counted line when it is
+    /// empty; the rest are dropped with the count.
+    pub async fn approve(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        id: i32,
+        line_ids: &[i32],
+    ) -> Result<CountSheet> {
+        let txn = db.begin().await?;
+        let count = open_count(&txn, mid, id).await?;
+        let lines = lines_of(&txn, id).await?;
+        for line_id in line_ids {
+            match lines.iter().find(|l| l.id == *line_id) {
+                Some(line) if line.counted.is_none() => {
+                    return Err(InventoryError::InvalidDetail {
+                        field: "line_ids",
+                        message: format!("line {} hasn't been counted", line_id),
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    return Err(InventoryError::InvalidDetail {
+                        field: "line_ids",
+                        message: format!("line {} isn't on count {}", line_id, id),
+                    });
+                }
+            }
+        }
+
+        let mut approved: Vec<&InventoryCountLine> = lines
+            .iter()
+            .filter(|l| l.counted.is_some())
+            .filter(|l| line_ids.is_empty() || line_ids.contains(&l.id))
+            .collect();
+        approved.sort_by(|a, b| (&a.sku, &a.location).cmp(&(&b.sku, &b.location)));
+
+        let note = format!("count {}", id);
+        for group in approved.chunk_by(|a, b| a.sku == b.sku) {
+            let sku = lock_sku(&txn, mid, &group[0].sku).await?;
+            let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+            for line in group {
+                let applied = write_back(&txn, mid, luser, &sku, line, &note, &mut rollup).await?;
+                InventoryCountLines::update_many()
+                    .col_expr(inventory_count_lines::Column::Adjusted, Expr::value(applied))
+                    .filter(inventory_count_lines::Column::Id.eq(line.id))
+                    .exec(&txn)
+                    .await?;
+            }
+            rollup.store(&txn, sku.id).await?;
+        }
+
+        let count = close(&txn, luser, count, CountStatus::Approved).await?;
+        let lines = lines_of(&txn, id).await?;
+        txn.commit().await?;
+
+        Ok(CountSheet { count, lines })
+    }
+
+    /// Close a count without touching the ledger
+    pub async fn cancel(db: &DatabaseConnection, mid: i32, luser: &str, id: i32) -> Result<InventoryCount> {
+        let txn = db.begin().await?;
+        let count = open_count(&txn, mid, id).await?;
+        let count = close(&txn, luser, count, CountStatus::Cancelled).await?;
+        txn.commit().await?;
+        Ok(count)
+    }
+}
+
+/// Trimmed, de-duplicated SKUs, all of which must exist
+async fn known_skus<C: ConnectionTrait>(db: &C, mid: i32, skus: Vec<String>) -> Result<Vec<String>> {
+    let mut skus: Vec<String> = skus.into_iter().map(|s| s.trim().to_string()).collect();
+    skus.sort_unstable();
+    skus.dedup();
+    if skus.is_empty() || skus.len() > MAX_COUNT_SKUS {
+        return Err(InventoryError::InvalidDetail {
+            field: "skus",
+            message: format!("must name 1 to {} SKUs", MAX_COUNT_SKUS),
+        });
+    }
+
+    let known: Vec<String> = SkuLookup::find()
+        .select_only()
+        .column(sku_lookup::Column::Sku)
+        .filter(sku_lookup::Column::Mid.eq(mid))
+        .filter(sku_lookup::Column::Sku.is_in(skus.iter().cloned()))
+        .into_tuple()
+        .all(db)
+        .await?;
+    match skus.iter().find(|s| !known.contains(s)) {
+        Some(unknown) => Err(InventoryError::UnknownSku(unknown.clone())),
+        None => Ok(skus),
+    }
+}
+
+async fn lines_of<C: ConnectionTrait>(db: &C, id: i32) -> Result<Vec<InventoryCountLine>> {
+    Ok(InventoryCountLines::find()
+        .filter(inventory_count_lines::Column::CountId.eq(id))
+        .order_by_asc(inventory_count_lines::Column::Location)
+        .order_by_asc(inventory_count_lines::Column::Sku)
+        .all(db)
+        .await?)
+}
+
+/// Lock an open count against concurrent entries and approval
+async fn open_count<C: ConnectionTrait>(db: &C, mid: i32, id: i32) -> Result<InventoryCount> {
+    let count = InventoryCounts::find_by_id(id)
+        .filter(inventory_counts::Column::Mid.eq(mid))
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or(InventoryError::CountNotFound)?;
+    if count.status != CountStatus::Open.code() {
+        return Err(InventoryError::CountClosed(id));
+    }
+    Ok(count)
+}
+
+async fn close<C: ConnectionTrait>(
+    db: &C,
+    luser: &str,
+    count: InventoryCount,
+    status: CountStatus,
+) -> Result<InventoryCount> {
+    let mut active: inventory_counts::ActiveModel = count.into();
+    active.status = Set(status.code().to_string());
+    active.closed_by = Set(clip(luser, 10));
+    active.closed_gmt = Set(Utc::now().timestamp() as i32);
+    Ok(active.update(db).await?)
+}
+
+/// Whether a place without a line may still be counted
+async fn in_scope<C: ConnectionTrait>(db: &C, count: &InventoryCount, entry: &CountEntry) -> Result<()> {
+    let covered = match (&count.wms_geo, &count.wms_zone, &entry.location) {
+        (Some(geo), Some(zone), Some(at)) => *geo == at.geo && *zone == at.zone,
+        (Some(_), Some(_), None) => false,
+        _ => count.skus.as_array().is_some_and(|skus| skus.iter().any(|s| s.as_str() == Some(entry.sku.as_str()))),
+    };
+    if !covered {
+        let place = entry.location.as_ref().map(|l| l.to_string()).unwrap_or_else(|| "the shelf".to_string());
+        return Err(InventoryError::InvalidDetail {
+            field: "location",
+            message: format!("{} on {} isn't part of count {}", entry.sku, place, count.id),
+        });
+    }
+
+    let exists = SkuLookup::find()
+        .filter(sku_lookup::Column::Mid.eq(count.mid))
+        .filter(sku_lookup::Column::Sku.eq(&entry.sku))
+        .count(db)
+        .await?;
+    if exists == 0 {
+        return Err(InventoryError::UnknownSku(entry.sku.clone()));
+    }
+    Ok(())
+}
+
+/// Units the ledger says are physically in a bin, or on the shelf
+async fn on_hand<C: ConnectionTrait>(db: &C, mid: i32, sku: &str, location: Option<&Location>) -> Result<i32> {
+    let stock = InventoryDetails::find()
+        .select_only()
+        .column_as(Expr::col(Column::Qty).sum(), "qty")
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Sku.eq(sku));
+    let stock = match location {
+        Some(at) => stock
+            .filter(Column::Basetype.eq(InventoryBasetype::Wms))
+            .filter(Column::Uuid.eq(at.uuid())),
+        None => stock.filter(Column::Basetype.eq(InventoryBasetype::Simple)),
+    };
+
+    let picked = InventoryDetails::find()
+        .select_only()
+        .column_as(Expr::col(Column::Qty).sum(), "qty")
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Sku.eq(sku))
+        .filter(Column::Basetype.eq(InventoryBasetype::Picked));
+    let picked = match location {
+        Some(at) => picked
+            .filter(Column::PickRoute.eq(InventoryPickRoute::Wms))
+            .filter(Column::WmsGeo.eq(&at.geo))
+            .filter(Column::WmsZone.eq(&at.zone))
+            .filter(Column::WmsPos.eq(&at.pos)),
+        None => picked.filter(Column::PickRoute.eq(InventoryPickRoute::Simple)),
+    };
+
+    let stock: Option<Option<i64>> = stock.into_tuple().one(db).await?;
+    let picked: Option<Option<i64>> = picked.into_tuple().one(db).await?;
+    let on_hand = stock.flatten().unwrap_or(0) - picked.flatten().unwrap_or(0);
+    Ok(i32::try_from(on_hand).unwrap_or(i32::MAX))
+}
+
+/// Apply a line's variance to its bin or shelf rows; returns the units
+/// actually added (negative when taken away)
+async fn write_back<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    luser: &str,
+    sku: &Sku,
+    line: &InventoryCountLine,
+    note: &str,
+    rollup: &mut Rollup,
+) -> Result<i32> {
+    let variance = line.counted.unwrap_or(line.expected) - line.expected;
+    let location = match line.location.as_str() {
+        "" => None,
+        at => Some(at.parse::<Location>()?),
+    };
+    let rows = match &location {
+        Some(at) => {
+            InventoryDetails::find()
+                .filter(Column::Mid.eq(mid))
+                .filter(Column::Sku.eq(&sku.sku))
+                .filter(Column::Uuid.eq(at.uuid()))
+                .all(db)
+                .await?
+        }
+        None => {
+            InventoryDetails::find()
+                .filter(Column::Mid.eq(mid))
+                .filter(Column::Sku.eq(&sku.sku))
+                .filter(Column::Basetype.eq(InventoryBasetype::Simple))
+                .order_by_asc(Column::Id)
+                .all(db)
+                .await?
+        }
+    };
+
+    let mut touched: Vec<i64> = rows.iter().map(|r| r.id).collect();
+    let mut applied = 0;
+    if variance > 0 {
+        match (rows.first(), &location) {
+            (Some(row), _) => {
+                adjust(db, mid, luser, row, row.qty + variance, note).await?;
+            }
+            (None, Some(at)) => {
+                let bin = add_to_bin(db, mid, luser, sku, at, variance, Action::Adjust, note).await?;
+                touched.push(bin.id);
+            }
+            (None, None) => {
+                let shelf = add_to_shelf(db, mid, luser, sku, variance, Action::Adjust, note).await?;
+                touched.push(shelf.id);
+            }
+        }
+        applied = variance;
+    } else if variance < 0 {
+        let mut left = -variance;
+        for row in rows.iter().filter(|r| r.qty > 0) {
+            if left == 0 {
+                break;
+            }
+            let n = row.qty.min(left);
+            left -= n;
+            adjust(db, mid, luser, row, row.qty - n, note).await?;
+            applied -= n;
+        }
+    } else {
+        for row in &rows {
+            write_log(db, mid, luser, row, row.qty, Action::Verify, note).await?;
+        }
+    }
+    let basetype = match location {
+        Some(_) => InventoryBasetype::Wms,
+        None => InventoryBasetype::Simple,
+    };
+    rollup.apply(Some(&basetype), applied);
+
+    if !touched.is_empty() {
+        InventoryDetails::update_many()
+            .col_expr(Column::VerifyTs, Expr::value(Utc::now().naive_utc()))
+            .col_expr(Column::VerifyInc, Expr::col(Column::VerifyInc).add(1))
+            .filter(Column::Id.is_in(touched))
+            .exec(db)
+            .await?;
+    }
+    Ok(applied)
+}
+
+async fn adjust<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    luser: &str,
+    row: &InventoryDetail,
+    qty: i32,
+    note: &str,
+) -> Result<()> {
+    let updated = update_detail(db, luser, row, qty, row.basetype).await?;
+    write_log(db, mid, luser, &updated, row.qty, Action::Adjust, note).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{detail, ok, sku, total};
+
+    fn zone_count() -> InventoryCount {
+        InventoryCount {
+            id: 12,
+            mid: 1,
+            status: "OPEN".to_string(),
+            wms_geo: Some("MN".to_string()),
+            wms_zone: Some("A".to_string()),
+            skus: JsonValue::Array(Vec::new()),
+            note: String::new(),
+            created_by: "tester".to_string(),
+            created_gmt: 0,
+            closed_by: String::new(),
+            closed_gmt: 0,
+        }
+    }
+
+    fn line(location: &str, expected: i32, counted: Option<i32>) -> InventoryCountLine {
+        InventoryCountLine {
+            id: 5,
+            count_id: 12,
+            sku: "WIDGET:C001".to_string(),
+            location: location.to_string(),
+            expected,
+            counted,
+            counted_by: "tester".to_string(),
+            counted_gmt: 0,
+            adjusted: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entries_stay_within_the_zone() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![zone_count()]])
+            .append_query_results([Vec::<InventoryCountLine>::new()])
+            .into_connection();
+        let entry = CountEntry {
+            sku: "WIDGET:C001".to_string(),
+            location: Some(Location::new("MN", "B", "1").unwrap()),
+            qty: 3,
+        };
+
+        let err = CountService::record(&db, 1, "tester", 12, &[entry]).await.unwrap_err();
+        assert!(matches!(err, InventoryError::InvalidDetail { field: "location", .. }), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn test_approve_writes_back_the_variance() {
+        let at = Location::new("MN", "A", "1").unwrap();
+        let mut bin = detail(&at.uuid(), InventoryBasetype::Wms, 7);
+        bin.wms_geo = Some(at.geo.clone());
+        bin.wms_zone = Some(at.zone.clone());
+        bin.wms_pos = Some(at.pos.clone());
+        let mut closed = zone_count();
+        closed.status = "APPROVED".to_string();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![zone_count()]])
+            .append_query_results([vec![line("MN:A:1", 5, Some(3))]])
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("WMS", 7)]])
+            .append_query_results([vec![bin]])
+            .append_query_results([vec![closed]])
+            .append_query_results([vec![line("MN:A:1", 5, Some(3))]])
+            .append_exec_results([ok(), ok(), ok(), ok(), ok()])
+            .into_connection();
+
+        let sheet = CountService::approve(&db, 1, "tester", 12, &[]).await.unwrap();
+        assert_eq!(sheet.count.status, "APPROVED");
+
+        let log = db.into_transaction_log();
+        let txn: Vec<String> = log[0].statements().iter().map(|s| s.to_string()).collect();
+        // Ledger moved from 5 to 7 since counting; only the -2 variance applies
+        assert!(txn[6].contains(r#""qty" = 5"#), "{txn:?}");
+        assert!(txn[7].contains("5, 7, 'ADJUST'"), "{txn:?}");
+        assert!(txn[8].contains(r#""verify_inc" = "verify_inc" + 1"#), "{txn:?}");
+        assert!(txn[9].contains(r#""adjusted" = -2"#), "{txn:?}");
+        assert!(txn[10].contains(r#""qty_onshelf" = 5"#), "{txn:?}");
+    }
+}
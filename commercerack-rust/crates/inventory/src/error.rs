@@ -0,0 +1,76 @@
+//! Inventory service error types
+
+use sea_orm::{DbErr, SqlErr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum InventoryError {
+    #[error("Unknown SKU {0}")]
+    UnknownSku(String),
+
+    #[error("Inventory record not found")]
+    DetailNotFound,
+
+    #[error("Inventory record {0} already exists")]
+    DuplicateDetail(String),
+
+    #[error("Quantity of {0} can't go below zero")]
+    NegativeQuantity(String),
+
+    #[error("Not enough {0} in stock")]
+    OutOfStock(String),
+
+    #[error("Only {free} of {sku} free at {location}")]
+    ShelfShort { sku: String, location: String, free: i32 },
+
+    #[error("Order {0} isn't paid or has already shipped")]
+    OrderNotReady(String),
+
+    #[error("Nothing left to pick on these orders")]
+    NothingToPick,
+
+    #[error("Nothing picked on order {0}")]
+    NothingToShip(String),
+
+    #[error("Pick batch not found")]
+    PickBatchNotFound,
+
+    #[error("Inventory count not found")]
+    CountNotFound,
+
+    #[error("Inventory count {0} is already closed")]
+    CountClosed(i32),
+
+    #[error("Supplier not found")]
+    SupplierNotFound,
+
+    #[error("Supplier {0} already exists")]
+    DuplicateSupplier(String),
+
+    #[error("Supplier {0} has purchase orders")]
+    SupplierInUse(String),
+
+    #[error("Purchase order not found")]
+    PurchaseOrderNotFound,
+
+    #[error("Purchase order {id} is {status}")]
+    PurchaseOrderStatus { id: i32, status: String },
+
+    #[error("Invalid {field}: {message}")]
+    InvalidDetail { field: &'static str, message: String },
+
+    #[error(transparent)]
+    Db(#[from] DbErr),
+}
+
+impl InventoryError {
+    /// Map a unique violation on `(mid, sku, uuid)` to [`InventoryError::DuplicateDetail`]
+    pub(crate) fn from_write(err: DbErr, uuid: &str) -> Self {
+        match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => Self::DuplicateDetail(uuid.to_string()),
+            _ => Self::Db(err),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, InventoryError>;
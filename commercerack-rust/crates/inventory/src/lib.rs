@@ -1 +1,757 @@
-fn main() {}
+//! Inventory ledger (`inventory_detail`, `inventory_log`)
+//!
+//! Each detail row is a quantity of one SKU in one state, its `basetype`:
+//! on the shelf, on order from a supplier, reserved for an order, shipped,
+//! and so on. Every change is written to `inventory_log` with the quantity
+//! before and after, the action and the user making it, and the SKU's stock
+//! counters on `sku_lookup` are recomputed in the same transaction (see
+//! [`rollup`]). Nothing else writes those counters.
+//!
+//! Changes lock the SKU's `sku_lookup` row first, so changes to one SKU are
+//! serialised and its counters always match its detail rows.
+
+use ::entity::inventory_detail::{self, Column};
+use ::entity::prelude::{InventoryDetail, InventoryDetails, InventoryLog, InventoryLogEntry, Sku, SkuLookup};
+use ::entity::{inventory_log, sku_lookup};
+use chrono::Utc;
+use sea_orm::sea_query::{Alias, Expr, SimpleExpr};
+use sea_orm::*;
+use uuid::Uuid;
+
+pub mod count;
+pub mod error;
+pub mod purchasing;
+pub mod reservation;
+pub mod rollup;
+pub mod wms;
+
+pub use ::entity::sea_orm_active_enums::{InventoryBasetype, InventoryVendorStatus};
+pub use count::{CountEntry, CountScope, CountService, CountSheet, CountStatus, Variance};
+pub use error::{InventoryError, Result};
+pub use purchasing::{
+    OrderLine, PurchaseOrderService, PurchaseOrderSheet, Receipt, SupplierInput, SupplierService,
+};
+pub use reservation::{ReservationService, DEFAULT_HOLD_TTL};
+pub use rollup::Rollup;
+pub use wms::{Location, WmsService};
+
+/// `uuid` of the on-shelf row kept by [`InventoryService::set_on_shelf`]
+pub const SIMPLE_UUID: &str = "SIMPLE";
+
+/// What was done to a detail row; stored in `inventory_log.action`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Add,
+    Adjust,
+    Set,
+    /// Changed basetype, e.g. ONORDER to SIMPLE
+    Move,
+    Remove,
+    /// Allocated to an order
+    Reserve,
+    /// Held for a cart
+    Hold,
+    /// Cart hold given back
+    Release,
+    /// Shelved into a bin
+    Putaway,
+    /// Moved from one bin to another
+    Relocate,
+    /// Put on a pick batch
+    Pick,
+    /// Confirmed picked
+    Picked,
+    /// Left the warehouse
+    Ship,
+    /// Counted and found as recorded
+    Verify,
+    /// Delivered against a purchase order
+    Receive,
+}
+
+impl Action {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Add => "ADD",
+            Self::Adjust => "ADJUST",
+            Self::Set => "SET",
+            Self::Move => "MOVE",
+            Self::Remove => "REMOVE",
+            Self::Reserve => "RESERVE",
+            Self::Hold => "HOLD",
+            Self::Release => "RELEASE",
+            Self::Putaway => "PUTAWAY",
+            Self::Relocate => "RELOCATE",
+            Self::Pick => "PICK",
+            Self::Picked => "PICKED",
+            Self::Ship => "SHIP",
+            Self::Verify => "VERIFY",
+            Self::Receive => "RECEIVE",
+        }
+    }
+}
+
+/// A detail row to add
+#[derive(Debug, Clone)]
+pub struct NewDetail {
+    pub sku: String,
+    pub basetype: InventoryBasetype,
+    pub qty: i32,
+    /// Generated when empty
+    pub uuid: String,
+    /// Unit cost in integer cents
+    pub cost_i: i32,
+    pub note: String,
+    pub origin: String,
+    pub container: String,
+    pub our_orderid: String,
+    pub supplier_id: Option<String>,
+    pub supplier_sku: String,
+}
+
+impl NewDetail {
+    pub fn new(sku: impl Into<String>, basetype: InventoryBasetype, qty: i32) -> Self {
+        Self {
+            sku: sku.into(),
+            basetype,
+            qty,
+            uuid: String::new(),
+            cost_i: 0,
+            note: String::new(),
+            origin: String::new(),
+            container: String::new(),
+            our_orderid: String::new(),
+            supplier_id: None,
+            supplier_sku: String::new(),
+        }
+    }
+}
+
+/// A quantity of one SKU on an order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StockLine {
+    pub sku: String,
+    pub qty: i32,
+}
+
+/// Inventory service
+pub struct InventoryService;
+
+impl InventoryService {
+    /// A SKU's detail rows, oldest first
+    pub async fn details(db: &DatabaseConnection, mid: i32, sku: &str) -> Result<Vec<InventoryDetail>> {
+        let details = InventoryDetails::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Sku.eq(sku))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(details)
+    }
+
+    /// A SKU's audit log, newest first
+    pub async fn history(
+        db: &DatabaseConnection,
+        mid: i32,
+        sku: &str,
+        limit: u64,
+    ) -> Result<Vec<InventoryLogEntry>> {
+        use inventory_log::Column;
+
+        let entries = InventoryLog::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Sku.eq(sku))
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(entries)
+    }
+
+    /// Add a detail row
+    pub async fn add(db: &DatabaseConnection, mid: i32, luser: &str, input: NewDetail) -> Result<InventoryDetail> {
+        let input = input.validated()?;
+
+        let txn = db.begin().await?;
+        let sku = lock_sku(&txn, mid, &input.sku).await?;
+        let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+
+        let uuid = input.uuid.clone();
+        let detail = input
+            .into_active_model(mid, &sku, luser)
+            .insert(&txn)
+            .await
+            .map_err(|e| InventoryError::from_write(e, &uuid))?;
+        write_log(&txn, mid, luser, &detail, 0, Action::Add, &detail.note).await?;
+        rollup.apply(detail.basetype.as_ref(), detail.qty);
+        rollup.store(&txn, sku.id).await?;
+        txn.commit().await?;
+
+        Ok(detail)
+    }
+
+    /// Change a row's quantity by `delta`
+    pub async fn adjust(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        sku: &str,
+        uuid: &str,
+        delta: i32,
+        note: &str,
+    ) -> Result<InventoryDetail> {
+        modify(db, mid, luser, sku, uuid, Action::Adjust, note, |d| {
+            Ok((d.qty.saturating_add(delta), d.basetype))
+        })
+        .await
+    }
+
+    /// Set a row's quantity, e.g. after counting it
+    pub async fn set_quantity(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        sku: &str,
+        uuid: &str,
+        qty: i32,
+        note: &str,
+    ) -> Result<InventoryDetail> {
+        modify(db, mid, luser, sku, uuid, Action::Set, note, |d| Ok((qty, d.basetype))).await
+    }
+
+    /// Move a row to another basetype, keeping its quantity
+    pub async fn set_basetype(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        sku: &str,
+        uuid: &str,
+        basetype: InventoryBasetype,
+        note: &str,
+    ) -> Result<InventoryDetail> {
+        modify(db, mid, luser, sku, uuid, Action::Move, note, |d| Ok((d.qty, Some(basetype)))).await
+    }
+
+    /// Delete a row
+    pub async fn remove(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        sku: &str,
+        uuid: &str,
+        note: &str,
+    ) -> Result<()> {
+        let txn = db.begin().await?;
+        let sku = lock_sku(&txn, mid, sku).await?;
+        let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+        let detail = find_detail(&txn, mid, &sku.sku, uuid).await?;
+
+        InventoryDetails::delete_many()
+            .filter(Column::Id.eq(detail.id))
+            .exec(&txn)
+            .await?;
+        let removed = InventoryDetail { qty: 0, ..detail.clone() };
+        write_log(&txn, mid, luser, &removed, detail.qty, Action::Remove, note).await?;
+        rollup.apply(detail.basetype.as_ref(), -detail.qty);
+        rollup.store(&txn, sku.id).await?;
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Set the on-shelf quantity of a SKU kept without bin locations
+    ///
+    /// Uses the SKU's [`SIMPLE_UUID`] row, adding it the first time.
+    pub async fn set_on_shelf(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        sku: &str,
+        qty: i32,
+        note: &str,
+    ) -> Result<InventoryDetail> {
+        let exists = InventoryDetails::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Sku.eq(sku))
+            .filter(Column::Uuid.eq(SIMPLE_UUID))
+            .one(db)
+            .await?
+            .is_some();
+        if exists {
+            return Self::set_quantity(db, mid, luser, sku, SIMPLE_UUID, qty, note).await;
+        }
+
+        let mut input = NewDetail::new(sku, InventoryBasetype::Simple, qty);
+        input.uuid = SIMPLE_UUID.to_string();
+        input.note = note.to_string();
+        Self::add(db, mid, luser, input).await
+    }
+
+    /// Reserve stock for an order's lines as UNPAID rows
+    ///
+    /// Runs in the caller's transaction. Each line needs that much available
+    /// unless its SKU is backorderable. SKUs are locked in code order so
+    /// concurrent orders can't deadlock on each other.
+    pub async fn allocate<C: ConnectionTrait>(
+        db: &C,
+        mid: i32,
+        luser: &str,
+        orderid: &str,
+        lines: &[StockLine],
+    ) -> Result<()> {
+        let mut lines: Vec<&StockLine> = lines.iter().collect();
+        lines.sort_by(|a, b| a.sku.cmp(&b.sku));
+
+        for line in lines {
+            let sku = lock_sku(db, mid, &line.sku).await?;
+            let mut rollup = Rollup::load(db, mid, &sku.sku).await?;
+            reserve_line(db, mid, luser, orderid, &sku, &mut rollup, line.qty).await?;
+            rollup.store(db, sku.id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Recompute a SKU's counters from its detail rows
+    pub async fn recompute(db: &DatabaseConnection, mid: i32, sku: &str) -> Result<Rollup> {
+        let txn = db.begin().await?;
+        let sku = lock_sku(&txn, mid, sku).await?;
+        let rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+        rollup.store(&txn, sku.id).await?;
+        txn.commit().await?;
+
+        Ok(rollup)
+    }
+}
+
+/// Apply `change` (new quantity and basetype) to one detail row
+#[allow(clippy::too_many_arguments)]
+async fn modify(
+    db: &DatabaseConnection,
+    mid: i32,
+    luser: &str,
+    sku: &str,
+    uuid: &str,
+    action: Action,
+    note: &str,
+    change: impl FnOnce(&InventoryDetail) -> Result<(i32, Option<InventoryBasetype>)>,
+) -> Result<InventoryDetail> {
+    let txn = db.begin().await?;
+    let sku = lock_sku(&txn, mid, sku).await?;
+    let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+    let detail = find_detail(&txn, mid, &sku.sku, uuid).await?;
+
+    let (qty, basetype) = change(&detail)?;
+    if qty < 0 {
+        return Err(InventoryError::NegativeQuantity(sku.sku));
+    }
+    let updated = update_detail(&txn, luser, &detail, qty, basetype).await?;
+    let note = match action {
+        Action::Move => moved_note(detail.basetype.as_ref(), basetype.as_ref(), note),
+        _ => note.to_string(),
+    };
+    write_log(&txn, mid, luser, &updated, detail.qty, action, &note).await?;
+    rollup.apply(detail.basetype.as_ref(), -detail.qty);
+    rollup.apply(updated.basetype.as_ref(), updated.qty);
+    rollup.store(&txn, sku.id).await?;
+    txn.commit().await?;
+
+    Ok(updated)
+}
+
+/// Add an UNPAID row of `qty` units for an order; `sku` must be locked
+pub(crate) async fn reserve_line<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    luser: &str,
+    orderid: &str,
+    sku: &Sku,
+    rollup: &mut Rollup,
+    qty: i32,
+) -> Result<()> {
+    if sku.inv_is_bo == 0 && rollup.available() < qty {
+        return Err(InventoryError::OutOfStock(sku.sku.clone()));
+    }
+
+    let mut input = NewDetail::new(&sku.sku, InventoryBasetype::Unpaid, qty);
+    input.our_orderid = orderid.to_string();
+    let input = input.validated()?;
+    let active = input.into_active_model(mid, sku, luser);
+    let mut logged = active.clone();
+    logged.id = Set(0);
+    let detail = logged.try_into_model()?;
+    InventoryDetails::insert(active).exec_without_returning(db).await?;
+    write_log(db, mid, luser, &detail, 0, Action::Reserve, "").await?;
+    rollup.apply(detail.basetype.as_ref(), detail.qty);
+    Ok(())
+}
+
+/// Lock a SKU's `sku_lookup` row for the rest of the transaction
+pub(crate) async fn lock_sku<C: ConnectionTrait>(db: &C, mid: i32, sku: &str) -> Result<Sku> {
+    SkuLookup::find()
+        .filter(sku_lookup::Column::Mid.eq(mid))
+        .filter(sku_lookup::Column::Sku.eq(sku))
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| InventoryError::UnknownSku(sku.to_string()))
+}
+
+pub(crate) async fn find_detail<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    sku: &str,
+    uuid: &str,
+) -> Result<InventoryDetail> {
+    InventoryDetails::find()
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Sku.eq(sku))
+        .filter(Column::Uuid.eq(uuid))
+        .one(db)
+        .await?
+        .ok_or(InventoryError::DetailNotFound)
+}
+
+/// Write a row's new quantity and basetype, bumping its modification stamp
+pub(crate) async fn update_detail<C: ConnectionTrait>(
+    db: &C,
+    luser: &str,
+    detail: &InventoryDetail,
+    qty: i32,
+    basetype: Option<InventoryBasetype>,
+) -> Result<InventoryDetail> {
+    let now = Utc::now().naive_utc();
+    let luser = clip(luser, 10);
+
+    InventoryDetails::update_many()
+        .col_expr(Column::Qty, Expr::value(qty))
+        .col_expr(Column::Basetype, basetype_expr(basetype.as_ref()))
+        .col_expr(Column::ModifiedTs, Expr::value(now))
+        .col_expr(Column::ModifiedBy, Expr::value(luser.clone()))
+        .col_expr(Column::ModifiedInc, Expr::col(Column::ModifiedInc).add(1))
+        .col_expr(Column::ModifiedQtyWas, Expr::value(detail.qty))
+        .filter(Column::Id.eq(detail.id))
+        .exec(db)
+        .await?;
+
+    Ok(InventoryDetail {
+        qty,
+        basetype,
+        modified_ts: Some(now),
+        modified_by: luser,
+        modified_inc: detail.modified_inc + 1,
+        modified_qty_was: detail.qty,
+        ..detail.clone()
+    })
+}
+
+/// Record a change to `detail`, which holds the quantity after it
+pub(crate) async fn write_log<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    luser: &str,
+    detail: &InventoryDetail,
+    qty_before: i32,
+    action: Action,
+    note: &str,
+) -> Result<()> {
+    let entry = inventory_log::ActiveModel {
+        mid: Set(mid),
+        pid: Set(detail.pid.clone()),
+        sku: Set(detail.sku.clone()),
+        created_gmt: Set(Utc::now().timestamp() as i32),
+        qty: Set(detail.qty),
+        qty_before: Set(qty_before),
+        action: Set(action.code().to_string()),
+        luser: Set(clip(luser, 10)),
+        note: Set(note.to_string()),
+        orderid: Set(detail.our_orderid.clone()),
+        uuid: Set(detail.uuid.clone()),
+        ..Default::default()
+    };
+    InventoryLog::insert(entry).exec_without_returning(db).await?;
+    Ok(())
+}
+
+/// 🤓 Postgres won't assign text to an enum column without a cast
+pub(crate) fn basetype_expr(basetype: Option<&InventoryBasetype>) -> SimpleExpr {
+    Expr::val(basetype.map(|b| b.to_value())).as_enum(Alias::new("inventory_basetype_enum"))
+}
+
+pub(crate) fn vendor_status_expr(status: InventoryVendorStatus) -> SimpleExpr {
+    Expr::val(status.to_value()).as_enum(Alias::new("inventory_vendor_status_enum"))
+}
+
+/// Add `qty` units to a SKU's plain shelf: its SIMPLE row, or the first
+/// other SIMPLE row, creating the SIMPLE row when there is none
+pub(crate) async fn add_to_shelf<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    luser: &str,
+    sku: &Sku,
+    qty: i32,
+    action: Action,
+    note: &str,
+) -> Result<InventoryDetail> {
+    let shelf = InventoryDetails::find()
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Sku.eq(&sku.sku))
+        .filter(Column::Basetype.eq(InventoryBasetype::Simple))
+        .order_by_desc(Expr::col(Column::Uuid).eq(SIMPLE_UUID))
+        .order_by_asc(Column::Id)
+        .one(db)
+        .await?;
+
+    match shelf {
+        Some(row) => {
+            let updated = update_detail(db, luser, &row, row.qty + qty, row.basetype).await?;
+            write_log(db, mid, luser, &updated, row.qty, action, note).await?;
+            Ok(updated)
+        }
+        None => {
+            let mut input = NewDetail::new(&sku.sku, InventoryBasetype::Simple, qty);
+            input.uuid = SIMPLE_UUID.to_string();
+            input.note = clip(note, 25);
+            let row = input.validated()?.into_active_model(mid, sku, luser).insert(db).await?;
+            write_log(db, mid, luser, &row, 0, action, note).await?;
+            Ok(row)
+        }
+    }
+}
+
+fn moved_note(from: Option<&InventoryBasetype>, to: Option<&InventoryBasetype>, note: &str) -> String {
+    let name = |b: Option<&InventoryBasetype>| b.map_or_else(String::new, |b| b.to_value());
+    let moved = format!("{} to {}", name(from), name(to));
+    if note.is_empty() {
+        moved
+    } else {
+        format!("{}: {}", moved, note)
+    }
+}
+
+/// 🤓 legacy user columns are varchar(10)
+fn clip(value: &str, max: usize) -> String {
+    value.chars().take(max).collect()
+}
+
+impl NewDetail {
+    fn validated(mut self) -> Result<Self> {
+        if self.uuid.is_empty() {
+            self.uuid = Uuid::new_v4().to_string();
+        }
+        if self.qty < 0 {
+            return Err(InventoryError::NegativeQuantity(self.sku));
+        }
+        max_len("uuid", &self.uuid, 36)?;
+        max_len("note", &self.note, 25)?;
+        max_len("origin", &self.origin, 16)?;
+        max_len("container", &self.container, 8)?;
+        max_len("our_orderid", &self.our_orderid, 30)?;
+        max_len("supplier_id", self.supplier_id.as_deref().unwrap_or_default(), 10)?;
+        max_len("supplier_sku", &self.supplier_sku, 25)?;
+        Ok(self)
+    }
+
+    fn into_active_model(self, mid: i32, sku: &Sku, luser: &str) -> inventory_detail::ActiveModel {
+        let now = Utc::now().naive_utc();
+        inventory_detail::ActiveModel {
+            uuid: Set(self.uuid),
+            mid: Set(mid),
+            pid: Set(clip(&sku.pid, 20)),
+            sku: Set(sku.sku.clone()),
+            wms_geo: Set(None),
+            wms_zone: Set(None),
+            wms_pos: Set(None),
+            qty: Set(self.qty),
+            cost_i: Set(self.cost_i),
+            note: Set(self.note),
+            container: Set(self.container),
+            origin: Set(self.origin),
+            basetype: Set(Some(self.basetype)),
+            supplier_id: Set(self.supplier_id),
+            supplier_sku: Set(self.supplier_sku),
+            market_dst: Set(None),
+            market_refid: Set(String::new()),
+            market_ends_ts: Set(None),
+            market_sold_qty: Set(0),
+            market_sale_ts: Set(None),
+            preference: Set(0),
+            created_ts: Set(Some(now)),
+            modified_ts: Set(Some(now)),
+            modified_by: Set(clip(luser, 10)),
+            modified_inc: Set(0),
+            modified_qty_was: Set(0),
+            verify_ts: Set(None),
+            verify_inc: Set(0),
+            our_orderid: Set(self.our_orderid),
+            pick_batchid: Set(String::new()),
+            pick_route: Set(None),
+            pick_done_ts: Set(None),
+            grpasm_ref: Set(None),
+            description: Set(String::new()),
+            vendor_status: Set(None),
+            vendor: Set(String::new()),
+            vendor_order_dbid: Set(0),
+            vendor_sku: Set(String::new()),
+            hold_expires_ts: Set(None),
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) fn max_len(field: &'static str, value: &str, max: usize) -> Result<()> {
+    if value.chars().count() > max {
+        return Err(InventoryError::InvalidDetail {
+            field,
+            message: format!("must be at most {} characters", max),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    pub(crate) fn sku() -> Sku {
+        Sku {
+            id: 4,
+            mid: 1,
+            pid: "WIDGET".to_string(),
+            invopts: ":C001".to_string(),
+            grp_parent: String::new(),
+            sku: "WIDGET:C001".to_string(),
+            title: String::new(),
+            cost: Default::default(),
+            price: Default::default(),
+            upc: String::new(),
+            mfgid: String::new(),
+            supplierid: String::new(),
+            prodasm: None,
+            assembly: None,
+            inv_available: 0,
+            qty_onshelf: 0,
+            qty_onorder: 0,
+            qty_needship: 0,
+            qty_markets: 0,
+            qty_legacy: 0,
+            qty_reserved: 0,
+            amz_asin: String::new(),
+            amz_feeds_done: 0,
+            amz_feeds_todo: 0,
+            amz_feeds_sent: 0,
+            amz_feeds_wait: 0,
+            amz_feeds_warn: 0,
+            amz_feeds_error: 0,
+            amz_productdb_gmt: 0,
+            amz_error: String::new(),
+            inv_on_shelf: 0,
+            inv_on_order: 0,
+            inv_is_bo: 0,
+            inv_reorder: 0,
+            inv_is_rsvp: 0,
+            dss_agent: String::new(),
+            dss_run: None,
+            dss_mood: None,
+            dss_config: None,
+        }
+    }
+
+    pub(crate) fn detail(uuid: &str, basetype: InventoryBasetype, qty: i32) -> InventoryDetail {
+        let mut input = NewDetail::new("WIDGET:C001", basetype, qty);
+        input.uuid = uuid.to_string();
+        let mut active = input.into_active_model(1, &sku(), "tester");
+        active.id = Set(11);
+        active.try_into_model().unwrap()
+    }
+
+    pub(crate) fn total(basetype: &str, qty: i64) -> BTreeMap<String, Value> {
+        BTreeMap::from([
+            ("basetype".to_string(), Value::from(basetype)),
+            ("qty".to_string(), Value::from(qty)),
+        ])
+    }
+
+    pub(crate) fn ok() -> MockExecResult {
+        MockExecResult { last_insert_id: 0, rows_affected: 1 }
+    }
+
+    #[tokio::test]
+    async fn test_adjust_logs_and_rolls_up() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("SIMPLE", 10), total("UNPAID", 3)]])
+            .append_query_results([vec![detail(SIMPLE_UUID, InventoryBasetype::Simple, 10)]])
+            .append_exec_results([ok(), ok(), ok()])
+            .into_connection();
+
+        let updated = InventoryService::adjust(&db, 1, "a-very-long-user", "WIDGET:C001", SIMPLE_UUID, -4, "damaged")
+            .await
+            .unwrap();
+        assert_eq!(updated.qty, 6);
+        assert_eq!(updated.modified_qty_was, 10);
+
+        let log = db.into_transaction_log();
+        let txn = log[0].statements();
+        assert!(txn[1].sql.ends_with("FOR UPDATE"), "{}", txn[1].sql);
+        let entry = txn[5].to_string();
+        assert!(entry.starts_with(r#"INSERT INTO "inventory_log""#), "{}", entry);
+        assert!(entry.contains("6, 10, 'ADJUST', 'a-very-lon', 'damaged'"), "{}", entry);
+        let counters = txn[6].to_string();
+        assert!(counters.contains(r#""inv_available" = 3"#), "{}", counters);
+        assert!(counters.contains(r#""qty_onshelf" = 6"#), "{}", counters);
+        assert!(counters.contains(r#""qty_reserved" = 3"#), "{}", counters);
+    }
+
+    #[tokio::test]
+    async fn test_quantity_cannot_go_negative() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("SIMPLE", 2)]])
+            .append_query_results([vec![detail(SIMPLE_UUID, InventoryBasetype::Simple, 2)]])
+            .into_connection();
+
+        let err = InventoryService::adjust(&db, 1, "tester", "WIDGET:C001", SIMPLE_UUID, -3, "")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, InventoryError::NegativeQuantity(_)));
+    }
+
+    #[tokio::test]
+    async fn test_move_records_both_basetypes() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("ONORDER", 12)]])
+            .append_query_results([vec![detail("PO-1", InventoryBasetype::Onorder, 12)]])
+            .append_exec_results([ok(), ok(), ok()])
+            .into_connection();
+
+        InventoryService::set_basetype(&db, 1, "tester", "WIDGET:C001", "PO-1", InventoryBasetype::Simple, "")
+            .await
+            .unwrap();
+
+        let log = db.into_transaction_log();
+        let txn = log[0].statements();
+        assert!(txn[4].to_string().contains(r#"CAST('SIMPLE' AS "inventory_basetype_enum")"#), "{}", txn[4]);
+        assert!(txn[5].to_string().contains("'ONORDER to SIMPLE'"), "{}", txn[5]);
+        let counters = txn[6].to_string();
+        assert!(counters.contains(r#""qty_onorder" = 0"#) && counters.contains(r#""qty_onshelf" = 12"#), "{}", counters);
+    }
+
+    #[tokio::test]
+    async fn test_allocate_refuses_more_than_available() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("SIMPLE", 5), total("PICK", 4)]])
+            .into_connection();
+        let lines = [StockLine { sku: "WIDGET:C001".to_string(), qty: 2 }];
+
+        let err = InventoryService::allocate(&db, 1, "checkout", "2025-01-00042", &lines)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, InventoryError::OutOfStock(ref sku) if sku == "WIDGET:C001"));
+    }
+}
@@ -0,0 +1,683 @@
+//! Suppliers and purchase orders
+//!
+//! A purchase order is drafted as NEW and its lines can be replaced until
+//! it is sent. [`PurchaseOrderService::submit`] moves it to ONORDER and
+//! puts an ONORDER row in the ledger for each line, tagged with the
+//! supplier (`vendor`, `supplier_id`), the PO (`vendor_order_dbid`) and the
+//! supplier's part number (`vendor_sku`, `supplier_sku`); those rows count
+//! towards `sku_lookup.qty_onorder`. The supplier confirming the order
+//! moves the PO and its rows to CONFIRMED.
+//!
+//! [`PurchaseOrderService::receive`] moves delivered units off a line's
+//! ONORDER row onto the shelf, or into a bin. Deliveries may come in parts;
+//! a row is RECEIVED once its line is complete, and the PO once every line
+//! is. Cancelling takes whatever is still outstanding off order.
+
+use ::entity::inventory_detail::Column;
+use ::entity::prelude::{
+    InventoryDetails, PurchaseOrder, PurchaseOrderLine, PurchaseOrderLines, PurchaseOrders, SkuLookup, Supplier,
+    Suppliers,
+};
+use ::entity::sea_orm_active_enums::InventoryVendorStatus;
+use ::entity::{purchase_order_lines, purchase_orders, sku_lookup, suppliers};
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use std::collections::HashMap;
+
+use crate::error::{InventoryError, Result};
+use crate::rollup::Rollup;
+use crate::wms::{add_to_bin, location_part, Location};
+use crate::{
+    add_to_shelf, clip, find_detail, lock_sku, max_len, update_detail, vendor_status_expr, write_log, Action,
+    InventoryBasetype, NewDetail,
+};
+
+/// Supplier details other than its code
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SupplierInput {
+    pub name: String,
+    pub email: String,
+    pub phone: String,
+    pub note: String,
+    pub lead_days: i32,
+}
+
+impl SupplierInput {
+    fn validated(self) -> Result<Self> {
+        if self.name.trim().is_empty() {
+            return Err(InventoryError::InvalidDetail {
+                field: "name",
+                message: "must not be empty".to_string(),
+            });
+        }
+        max_len("name", &self.name, 100)?;
+        max_len("email", &self.email, 100)?;
+        max_len("phone", &self.phone, 30)?;
+        max_len("note", &self.note, 255)?;
+        if self.lead_days < 0 {
+            return Err(InventoryError::InvalidDetail {
+                field: "lead_days",
+                message: "can't be negative".to_string(),
+            });
+        }
+        Ok(self)
+    }
+}
+
+/// A SKU to order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderLine {
+    pub sku: String,
+    pub qty: i32,
+    /// Unit cost in integer cents
+    pub cost_i: i32,
+    /// Supplier's part number; the SKU's `supplierid` when left out
+    pub vendor_sku: Option<String>,
+}
+
+/// Units of a SKU delivered, into a bin or onto the shelf without one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub sku: String,
+    pub qty: i32,
+    pub location: Option<Location>,
+}
+
+/// A purchase order with its supplier and lines in SKU order
+#[derive(Debug, Clone)]
+pub struct PurchaseOrderSheet {
+    pub order: PurchaseOrder,
+    pub supplier: Supplier,
+    pub lines: Vec<PurchaseOrderLine>,
+}
+
+/// Supplier service
+pub struct SupplierService;
+
+impl SupplierService {
+    /// A merchant's suppliers in code order
+    pub async fn list(db: &DatabaseConnection, mid: i32) -> Result<Vec<Supplier>> {
+        Ok(Suppliers::find()
+            .filter(suppliers::Column::Mid.eq(mid))
+            .order_by_asc(suppliers::Column::Code)
+            .all(db)
+            .await?)
+    }
+
+    pub async fn get(db: &DatabaseConnection, mid: i32, code: &str) -> Result<Supplier> {
+        find_supplier(db, mid, code).await
+    }
+
+    /// Add a supplier; codes are upper-cased
+    pub async fn create(db: &DatabaseConnection, mid: i32, code: &str, input: SupplierInput) -> Result<Supplier> {
+        let code = location_part("code", code, 6)?;
+        let input = input.validated()?;
+        let now = Utc::now().timestamp() as i32;
+
+        suppliers::ActiveModel {
+            mid: Set(mid),
+            code: Set(code.clone()),
+            name: Set(input.name),
+            email: Set(input.email),
+            phone: Set(input.phone),
+            note: Set(input.note),
+            lead_days: Set(input.lead_days),
+            created_gmt: Set(now),
+            modified_gmt: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(|e| match e.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => InventoryError::DuplicateSupplier(code),
+            _ => e.into(),
+        })
+    }
+
+    pub async fn update(db: &DatabaseConnection, mid: i32, code: &str, input: SupplierInput) -> Result<Supplier> {
+        let input = input.validated()?;
+        let mut active: suppliers::ActiveModel = find_supplier(db, mid, code).await?.into();
+        active.name = Set(input.name);
+        active.email = Set(input.email);
+        active.phone = Set(input.phone);
+        active.note = Set(input.note);
+        active.lead_days = Set(input.lead_days);
+        active.modified_gmt = Set(Utc::now().timestamp() as i32);
+        Ok(active.update(db).await?)
+    }
+
+    /// Remove a supplier that has never been sent an order
+    pub async fn delete(db: &DatabaseConnection, mid: i32, code: &str) -> Result<()> {
+        let supplier = find_supplier(db, mid, code).await?;
+        let orders = PurchaseOrders::find()
+            .filter(purchase_orders::Column::SupplierId.eq(supplier.id))
+            .count(db)
+            .await?;
+        if orders > 0 {
+            return Err(InventoryError::SupplierInUse(supplier.code));
+        }
+        Suppliers::delete_by_id(supplier.id).exec(db).await?;
+        Ok(())
+    }
+}
+
+/// Purchase order service
+pub struct PurchaseOrderService;
+
+impl PurchaseOrderService {
+    /// Draft a NEW purchase order
+    pub async fn create(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        supplier: &str,
+        note: &str,
+        lines: &[OrderLine],
+    ) -> Result<PurchaseOrderSheet> {
+        max_len("note", note, 255)?;
+        let supplier = find_supplier(db, mid, supplier).await?;
+
+        let txn = db.begin().await?;
+        let order = purchase_orders::ActiveModel {
+            mid: Set(mid),
+            supplier_id: Set(supplier.id),
+            status: Set(InventoryVendorStatus::New),
+            reference: Set(String::new()),
+            note: Set(note.to_string()),
+            created_by: Set(clip(luser, 10)),
+            created_gmt: Set(Utc::now().timestamp() as i32),
+            ordered_gmt: Set(0),
+            confirmed_gmt: Set(0),
+            closed_gmt: Set(0),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        insert_lines(&txn, mid, order.id, lines).await?;
+        let lines = lines_of(&txn, order.id).await?;
+        txn.commit().await?;
+
+        Ok(PurchaseOrderSheet { order, supplier, lines })
+    }
+
+    /// A merchant's purchase orders with their suppliers, newest first
+    pub async fn list(
+        db: &DatabaseConnection,
+        mid: i32,
+        status: Option<InventoryVendorStatus>,
+        supplier: Option<&str>,
+    ) -> Result<Vec<(PurchaseOrder, Supplier)>> {
+        let mut query = PurchaseOrders::find()
+            .find_also_related(Suppliers)
+            .filter(purchase_orders::Column::Mid.eq(mid));
+        if let Some(status) = status {
+            query = query.filter(purchase_orders::Column::Status.eq(status));
+        }
+        if let Some(code) = supplier {
+            query = query.filter(suppliers::Column::Code.eq(code.trim().to_uppercase()));
+        }
+
+        let orders = query.order_by_desc(purchase_orders::Column::Id).all(db).await?;
+        Ok(orders.into_iter().filter_map(|(order, supplier)| Some((order, supplier?))).collect())
+    }
+
+    pub async fn get(db: &DatabaseConnection, mid: i32, id: i32) -> Result<PurchaseOrderSheet> {
+        let order = PurchaseOrders::find_by_id(id)
+            .filter(purchase_orders::Column::Mid.eq(mid))
+            .one(db)
+            .await?
+            .ok_or(InventoryError::PurchaseOrderNotFound)?;
+        sheet(db, order).await
+    }
+
+    /// Replace the lines of a NEW purchase order
+    pub async fn set_lines(db: &DatabaseConnection, mid: i32, id: i32, lines: &[OrderLine]) -> Result<PurchaseOrderSheet> {
+        let txn = db.begin().await?;
+        let order = locked(&txn, mid, id, &[InventoryVendorStatus::New]).await?;
+        PurchaseOrderLines::delete_many()
+            .filter(purchase_order_lines::Column::PurchaseOrderId.eq(id))
+            .exec(&txn)
+            .await?;
+        insert_lines(&txn, mid, id, lines).await?;
+        let sheet = sheet(&txn, order).await?;
+        txn.commit().await?;
+        Ok(sheet)
+    }
+
+    /// Send a NEW purchase order, putting its lines on order in the ledger
+    pub async fn submit(db: &DatabaseConnection, mid: i32, luser: &str, id: i32) -> Result<PurchaseOrderSheet> {
+        let txn = db.begin().await?;
+        let order = locked(&txn, mid, id, &[InventoryVendorStatus::New]).await?;
+        let supplier = supplier_of(&txn, &order).await?;
+        let note = format!("PO {}", id);
+
+        for line in lines_of(&txn, id).await? {
+            let sku = lock_sku(&txn, mid, &line.sku).await?;
+            let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+
+            let mut input = NewDetail::new(&sku.sku, InventoryBasetype::Onorder, line.qty);
+            input.uuid = format!("PO-{}-{}", id, line.id);
+            input.cost_i = line.cost_i;
+            input.note = clip(&note, 25);
+            input.origin = "PO".to_string();
+            input.supplier_id = Some(supplier.code.clone());
+            input.supplier_sku = line.vendor_sku.clone();
+            let uuid = input.uuid.clone();
+            let mut active = input.validated()?.into_active_model(mid, &sku, luser);
+            active.vendor = Set(supplier.code.clone());
+            active.vendor_status = Set(Some(InventoryVendorStatus::Onorder));
+            active.vendor_order_dbid = Set(id);
+            active.vendor_sku = Set(line.vendor_sku.clone());
+            let row = active.insert(&txn).await.map_err(|e| InventoryError::from_write(e, &uuid))?;
+            write_log(&txn, mid, luser, &row, 0, Action::Add, &note).await?;
+            rollup.apply(Some(&InventoryBasetype::Onorder), line.qty);
+            rollup.store(&txn, sku.id).await?;
+
+            PurchaseOrderLines::update_many()
+                .col_expr(purchase_order_lines::Column::Uuid, Expr::value(uuid))
+                .filter(purchase_order_lines::Column::Id.eq(line.id))
+                .exec(&txn)
+                .await?;
+        }
+
+        let mut active: purchase_orders::ActiveModel = order.into();
+        active.status = Set(InventoryVendorStatus::Onorder);
+        active.ordered_gmt = Set(Utc::now().timestamp() as i32);
+        let order = active.update(&txn).await?;
+        let sheet = sheet(&txn, order).await?;
+        txn.commit().await?;
+        Ok(sheet)
+    }
+
+    /// Record the supplier's confirmation of an order
+    pub async fn confirm(
+        db: &DatabaseConnection,
+        mid: i32,
+        id: i32,
+        reference: &str,
+    ) -> Result<PurchaseOrderSheet> {
+        max_len("reference", reference, 30)?;
+        let txn = db.begin().await?;
+        let order = locked(&txn, mid, id, &[InventoryVendorStatus::Onorder]).await?;
+        let supplier = supplier_of(&txn, &order).await?;
+
+        InventoryDetails::update_many()
+            .col_expr(Column::VendorStatus, vendor_status_expr(InventoryVendorStatus::Confirmed))
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Vendor.eq(&supplier.code))
+            .filter(Column::VendorOrderDbid.eq(id))
+            .filter(Column::VendorStatus.eq(InventoryVendorStatus::Onorder))
+            .exec(&txn)
+            .await?;
+
+        let mut active: purchase_orders::ActiveModel = order.into();
+        active.status = Set(InventoryVendorStatus::Confirmed);
+        active.reference = Set(reference.to_string());
+        active.confirmed_gmt = Set(Utc::now().timestamp() as i32);
+        let order = active.update(&txn).await?;
+        let sheet = sheet(&txn, order).await?;
+        txn.commit().await?;
+        Ok(sheet)
+    }
+
+    /// Take delivery of units against an order's lines
+    pub async fn receive(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        id: i32,
+        receipts: &[Receipt],
+    ) -> Result<PurchaseOrderSheet> {
+        if receipts.is_empty() {
+            return Err(InventoryError::InvalidDetail {
+                field: "receipts",
+                message: "must hold at least one delivery".to_string(),
+            });
+        }
+
+        let txn = db.begin().await?;
+        let order = locked(&txn, mid, id, &[InventoryVendorStatus::Onorder, InventoryVendorStatus::Confirmed]).await?;
+        let lines = lines_of(&txn, id).await?;
+
+        let mut delivered: HashMap<&str, i32> = HashMap::new();
+        for receipt in receipts {
+            if receipt.qty <= 0 {
+                return Err(InventoryError::InvalidDetail {
+                    field: "qty",
+                    message: "must be greater than zero".to_string(),
+                });
+            }
+            *delivered.entry(&receipt.sku).or_default() += receipt.qty;
+        }
+        for (sku, qty) in &delivered {
+            let Some(line) = lines.iter().find(|l| l.sku == *sku) else {
+                return Err(InventoryError::InvalidDetail {
+                    field: "sku",
+                    message: format!("{} isn't on purchase order {}", sku, id),
+                });
+            };
+            let outstanding = line.qty - line.qty_received;
+            if *qty > outstanding {
+                return Err(InventoryError::InvalidDetail {
+                    field: "qty",
+                    message: format!("only {} of {} outstanding", outstanding, sku),
+                });
+            }
+        }
+
+        let note = format!("PO {}", id);
+        let mut receipts: Vec<&Receipt> = receipts.iter().collect();
+        receipts.sort_by(|a, b| a.sku.cmp(&b.sku));
+        for group in receipts.chunk_by(|a, b| a.sku == b.sku) {
+            let line = lines.iter().find(|l| l.sku == group[0].sku).expect("checked above");
+            let sku = lock_sku(&txn, mid, &line.sku).await?;
+            let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+            let mut row = find_detail(&txn, mid, &sku.sku, &line.uuid).await?;
+
+            for receipt in group {
+                if row.qty < receipt.qty {
+                    return Err(InventoryError::NegativeQuantity(sku.sku.clone()));
+                }
+                let after = update_detail(&txn, luser, &row, row.qty - receipt.qty, row.basetype).await?;
+                write_log(&txn, mid, luser, &after, row.qty, Action::Receive, &note).await?;
+                rollup.apply(row.basetype.as_ref(), -receipt.qty);
+                row = after;
+
+                match &receipt.location {
+                    Some(at) => {
+                        add_to_bin(&txn, mid, luser, &sku, at, receipt.qty, Action::Receive, &note).await?;
+                        rollup.apply(Some(&InventoryBasetype::Wms), receipt.qty);
+                    }
+                    None => {
+                        add_to_shelf(&txn, mid, luser, &sku, receipt.qty, Action::Receive, &note).await?;
+                        rollup.apply(Some(&InventoryBasetype::Simple), receipt.qty);
+                    }
+                }
+            }
+            rollup.store(&txn, sku.id).await?;
+
+            let received = line.qty_received + delivered[line.sku.as_str()];
+            PurchaseOrderLines::update_many()
+                .col_expr(purchase_order_lines::Column::QtyReceived, Expr::value(received))
+                .filter(purchase_order_lines::Column::Id.eq(line.id))
+                .exec(&txn)
+                .await?;
+            if received == line.qty {
+                set_vendor_status(&txn, row.id, InventoryVendorStatus::Received).await?;
+            }
+        }
+
+        let complete = lines
+            .iter()
+            .all(|l| l.qty_received + delivered.get(l.sku.as_str()).copied().unwrap_or(0) >= l.qty);
+        let order = if complete {
+            let mut active: purchase_orders::ActiveModel = order.into();
+            active.status = Set(InventoryVendorStatus::Received);
+            active.closed_gmt = Set(Utc::now().timestamp() as i32);
+            active.update(&txn).await?
+        } else {
+            order
+        };
+        let sheet = sheet(&txn, order).await?;
+        txn.commit().await?;
+        Ok(sheet)
+    }
+
+    /// Cancel an order, taking what hasn't been delivered off order
+    pub async fn cancel(db: &DatabaseConnection, mid: i32, luser: &str, id: i32) -> Result<PurchaseOrderSheet> {
+        use InventoryVendorStatus::*;
+
+        let txn = db.begin().await?;
+        let order = locked(&txn, mid, id, &[New, Onorder, Confirmed]).await?;
+        if order.status != New {
+            let note = format!("PO {} cancelled", id);
+            for line in lines_of(&txn, id).await?.iter().filter(|l| l.qty_received < l.qty) {
+                let sku = lock_sku(&txn, mid, &line.sku).await?;
+                let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+                let row = find_detail(&txn, mid, &sku.sku, &line.uuid).await?;
+
+                let after = update_detail(&txn, luser, &row, 0, row.basetype).await?;
+                write_log(&txn, mid, luser, &after, row.qty, Action::Remove, &note).await?;
+                set_vendor_status(&txn, row.id, Cancelled).await?;
+                rollup.apply(row.basetype.as_ref(), -row.qty);
+                rollup.store(&txn, sku.id).await?;
+            }
+        }
+
+        let mut active: purchase_orders::ActiveModel = order.into();
+        active.status = Set(Cancelled);
+        active.closed_gmt = Set(Utc::now().timestamp() as i32);
+        let order = active.update(&txn).await?;
+        let sheet = sheet(&txn, order).await?;
+        txn.commit().await?;
+        Ok(sheet)
+    }
+}
+
+async fn find_supplier<C: ConnectionTrait>(db: &C, mid: i32, code: &str) -> Result<Supplier> {
+    Suppliers::find()
+        .filter(suppliers::Column::Mid.eq(mid))
+        .filter(suppliers::Column::Code.eq(code.trim().to_uppercase()))
+        .one(db)
+        .await?
+        .ok_or(InventoryError::SupplierNotFound)
+}
+
+async fn supplier_of<C: ConnectionTrait>(db: &C, order: &PurchaseOrder) -> Result<Supplier> {
+    Suppliers::find_by_id(order.supplier_id)
+        .one(db)
+        .await?
+        .ok_or(InventoryError::SupplierNotFound)
+}
+
+/// Lock an order, which must be in one of `allowed`
+async fn locked<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    id: i32,
+    allowed: &[InventoryVendorStatus],
+) -> Result<PurchaseOrder> {
+    let order = PurchaseOrders::find_by_id(id)
+        .filter(purchase_orders::Column::Mid.eq(mid))
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or(InventoryError::PurchaseOrderNotFound)?;
+    if !allowed.contains(&order.status) {
+        return Err(InventoryError::PurchaseOrderStatus {
+            id,
+            status: order.status.to_value(),
+        });
+    }
+    Ok(order)
+}
+
+async fn lines_of<C: ConnectionTrait>(db: &C, id: i32) -> Result<Vec<PurchaseOrderLine>> {
+    Ok(PurchaseOrderLines::find()
+        .filter(purchase_order_lines::Column::PurchaseOrderId.eq(id))
+        .order_by_asc(purchase_order_lines::Column::Sku)
+        .all(db)
+        .await?)
+}
+
+async fn sheet<C: ConnectionTrait>(db: &C, order: PurchaseOrder) -> Result<PurchaseOrderSheet> {
+    let supplier = supplier_of(db, &order).await?;
+    let lines = lines_of(db, order.id).await?;
+    Ok(PurchaseOrderSheet { order, supplier, lines })
+}
+
+/// Check and store an order's lines; SKUs must exist and appear once
+async fn insert_lines<C: ConnectionTrait>(db: &C, mid: i32, id: i32, lines: &[OrderLine]) -> Result<()> {
+    if lines.is_empty() {
+        return Err(InventoryError::InvalidDetail {
+            field: "lines",
+            message: "must hold at least one SKU".to_string(),
+        });
+    }
+    let mut seen: Vec<&str> = Vec::with_capacity(lines.len());
+    for line in lines {
+        if line.qty <= 0 {
+            return Err(InventoryError::InvalidDetail {
+                field: "qty",
+                message: "must be greater than zero".to_string(),
+            });
+        }
+        if line.cost_i < 0 {
+            return Err(InventoryError::InvalidDetail {
+                field: "cost_i",
+                message: "can't be negative".to_string(),
+            });
+        }
+        if seen.contains(&line.sku.as_str()) {
+            return Err(InventoryError::InvalidDetail {
+                field: "lines",
+                message: format!("{} is on more than one line", line.sku),
+            });
+        }
+        seen.push(&line.sku);
+    }
+
+    let skus: HashMap<String, String> = SkuLookup::find()
+        .filter(sku_lookup::Column::Mid.eq(mid))
+        .filter(sku_lookup::Column::Sku.is_in(seen.iter().copied()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|s| (s.sku, s.supplierid))
+        .collect();
+
+    let mut models = Vec::with_capacity(lines.len());
+    for line in lines {
+        let Some(supplierid) = skus.get(&line.sku) else {
+            return Err(InventoryError::UnknownSku(line.sku.clone()));
+        };
+        let vendor_sku = line.vendor_sku.clone().unwrap_or_else(|| supplierid.clone());
+        max_len("vendor_sku", &vendor_sku, 25)?;
+        models.push(purchase_order_lines::ActiveModel {
+            purchase_order_id: Set(id),
+            sku: Set(line.sku.clone()),
+            vendor_sku: Set(vendor_sku),
+            qty: Set(line.qty),
+            qty_received: Set(0),
+            cost_i: Set(line.cost_i),
+            uuid: Set(String::new()),
+            ..Default::default()
+        });
+    }
+    PurchaseOrderLines::insert_many(models).exec_without_returning(db).await?;
+    Ok(())
+}
+
+async fn set_vendor_status<C: ConnectionTrait>(db: &C, row_id: i64, status: InventoryVendorStatus) -> Result<()> {
+    InventoryDetails::update_many()
+        .col_expr(Column::VendorStatus, vendor_status_expr(status))
+        .filter(Column::Id.eq(row_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{detail, ok, sku, total};
+
+    fn supplier() -> Supplier {
+        Supplier {
+            id: 2,
+            mid: 1,
+            code: "ACME".to_string(),
+            name: "Acme Tools".to_string(),
+            email: String::new(),
+            phone: String::new(),
+            note: String::new(),
+            lead_days: 10,
+            created_gmt: 0,
+            modified_gmt: 0,
+        }
+    }
+
+    fn order(status: InventoryVendorStatus) -> PurchaseOrder {
+        PurchaseOrder {
+            id: 7,
+            mid: 1,
+            supplier_id: 2,
+            status,
+            reference: String::new(),
+            note: String::new(),
+            created_by: "tester".to_string(),
+            created_gmt: 0,
+            ordered_gmt: 0,
+            confirmed_gmt: 0,
+            closed_gmt: 0,
+        }
+    }
+
+    fn line(qty: i32, qty_received: i32) -> PurchaseOrderLine {
+        PurchaseOrderLine {
+            id: 3,
+            purchase_order_id: 7,
+            sku: "WIDGET:C001".to_string(),
+            vendor_sku: "AC-100".to_string(),
+            qty,
+            qty_received,
+            cost_i: 1250,
+            uuid: "PO-7-3".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_puts_lines_on_order() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![order(InventoryVendorStatus::New)]])
+            .append_query_results([vec![supplier()]])
+            .append_query_results([vec![line(5, 0)]])
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("ONORDER", 2)]])
+            .append_query_results([vec![detail("PO-7-3", InventoryBasetype::Onorder, 5)]])
+            .append_query_results([vec![order(InventoryVendorStatus::Onorder)]])
+            .append_query_results([vec![supplier()]])
+            .append_query_results([vec![line(5, 0)]])
+            .append_exec_results([ok(), ok(), ok()])
+            .into_connection();
+
+        let sheet = PurchaseOrderService::submit(&db, 1, "tester", 7).await.unwrap();
+        assert_eq!(sheet.order.status, InventoryVendorStatus::Onorder);
+
+        let log = db.into_transaction_log();
+        let txn: Vec<String> = log[0].statements().iter().map(|s| s.to_string()).collect();
+        let row = &txn[6];
+        assert!(row.starts_with(r#"INSERT INTO "inventory_detail""#), "{txn:?}");
+        assert!(row.contains("'PO-7-3'") && row.contains("'ACME'") && row.contains("'AC-100'"), "{row}");
+        assert!(row.contains(r#"CAST('ONORDER' AS "inventory_vendor_status_enum")"#), "{row}");
+        assert!(txn[7].contains("0, 'ADD'"), "{txn:?}");
+        assert!(txn[8].contains(r#""qty_onorder" = 7"#), "{txn:?}");
+        assert!(txn[9].contains(r#""uuid" = 'PO-7-3'"#), "{txn:?}");
+    }
+
+    #[tokio::test]
+    async fn test_receive_refuses_more_than_outstanding() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![order(InventoryVendorStatus::Confirmed)]])
+            .append_query_results([vec![line(5, 3)]])
+            .into_connection();
+        let receipt = Receipt { sku: "WIDGET:C001".to_string(), qty: 3, location: None };
+
+        let err = PurchaseOrderService::receive(&db, 1, "tester", 7, &[receipt]).await.unwrap_err();
+        match err {
+            InventoryError::InvalidDetail { field: "qty", message } => assert_eq!(message, "only 2 of WIDGET:C001 outstanding"),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_received_orders_are_closed() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![order(InventoryVendorStatus::Received)]])
+            .into_connection();
+
+        let err = PurchaseOrderService::cancel(&db, 1, "tester", 7).await.unwrap_err();
+        assert!(matches!(err, InventoryError::PurchaseOrderStatus { id: 7, ref status } if status == "RECEIVED"), "{err:?}");
+    }
+}
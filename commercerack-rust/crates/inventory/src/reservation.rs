@@ -0,0 +1,372 @@
+//! Cart holds
+//!
+//! A cart claims stock with one HOLD row per SKU, keyed by the cart ID in
+//! `uuid` and lapsing at `hold_expires_ts`. Holds count as reserved, so once
+//! one cart holds the last unit no other cart can get it. Every change locks
+//! the SKU's `sku_lookup` row and checks availability under that lock.
+//!
+//! At checkout [`ReservationService::allocate`] gives the cart's holds back
+//! and reserves the order's lines in the same transaction. Holds of a deleted
+//! cart go with [`ReservationService::release`]; lapsed ones with
+//! [`ReservationService::release_expired`], or as soon as their SKU is held
+//! or allocated again.
+
+use ::entity::inventory_detail::Column;
+use ::entity::prelude::{InventoryDetail, InventoryDetails};
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use std::time::Duration;
+
+use crate::error::{InventoryError, Result};
+use crate::rollup::Rollup;
+use crate::{lock_sku, reserve_line, update_detail, write_log, Action, InventoryBasetype, NewDetail, StockLine};
+
+/// How long a cart holds stock after its last change
+pub const DEFAULT_HOLD_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// `luser` and `origin` of holds made for carts
+const LUSER: &str = "cart";
+
+/// Cart reservations
+pub struct ReservationService;
+
+impl ReservationService {
+    /// A cart's holds in SKU order
+    pub async fn holds<C: ConnectionTrait>(db: &C, mid: i32, cart_id: &str) -> Result<Vec<InventoryDetail>> {
+        let holds = InventoryDetails::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Uuid.eq(cart_id))
+            .filter(Column::Basetype.eq(InventoryBasetype::Hold))
+            .order_by_asc(Column::Sku)
+            .all(db)
+            .await?;
+
+        Ok(holds)
+    }
+
+    /// Hold `qty` units of `sku` for a cart until `ttl` from now
+    ///
+    /// Any change counts as activity on the whole cart, so its holds on
+    /// other SKUs are extended to the same time.
+    ///
+    /// `qty` is all the cart wants of the SKU; what it already holds counts
+    /// towards it, and zero gives the hold back. Returns the hold, if any.
+    pub async fn hold(
+        db: &DatabaseConnection,
+        mid: i32,
+        cart_id: &str,
+        sku: &str,
+        qty: i32,
+        ttl: Duration,
+    ) -> Result<Option<InventoryDetail>> {
+        if qty < 0 {
+            return Err(InventoryError::NegativeQuantity(sku.to_string()));
+        }
+        let now = Utc::now().naive_utc();
+        let expires = now + TimeDelta::from_std(ttl).unwrap_or(TimeDelta::MAX);
+
+        let txn = db.begin().await?;
+        let sku = lock_sku(&txn, mid, sku).await?;
+        let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+        let (held, lapsed): (Vec<_>, Vec<_>) = stale_holds(&txn, mid, &sku.sku, Some(cart_id), now)
+            .await?
+            .into_iter()
+            .partition(|h| h.uuid == cart_id);
+        release_rows(&txn, mid, LUSER, &lapsed, &mut rollup, now).await?;
+
+        let held = held.into_iter().next();
+        let wanted = qty - held.as_ref().map_or(0, |h| h.qty);
+        if wanted > 0 && sku.inv_is_bo == 0 && rollup.available() < wanted {
+            return Err(InventoryError::OutOfStock(sku.sku));
+        }
+
+        let detail = match held {
+            None if qty == 0 => None,
+            Some(held) if qty == 0 => {
+                release_rows(&txn, mid, LUSER, &[held], &mut rollup, now).await?;
+                None
+            }
+            Some(held) => {
+                let mut updated = update_detail(&txn, LUSER, &held, qty, held.basetype).await?;
+                updated.hold_expires_ts = Some(expires);
+                write_log(&txn, mid, LUSER, &updated, held.qty, Action::Hold, "").await?;
+                rollup.apply(updated.basetype.as_ref(), wanted);
+                Some(updated)
+            }
+            None => {
+                let mut input = NewDetail::new(&sku.sku, InventoryBasetype::Hold, qty);
+                input.uuid = cart_id.to_string();
+                input.origin = LUSER.to_string();
+                let mut active = input.validated()?.into_active_model(mid, &sku, LUSER);
+                active.hold_expires_ts = Set(Some(expires));
+                let detail = active
+                    .insert(&txn)
+                    .await
+                    .map_err(|e| InventoryError::from_write(e, cart_id))?;
+                write_log(&txn, mid, LUSER, &detail, 0, Action::Hold, "").await?;
+                rollup.apply(detail.basetype.as_ref(), detail.qty);
+                Some(detail)
+            }
+        };
+        InventoryDetails::update_many()
+            .col_expr(Column::HoldExpiresTs, Expr::value(expires))
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Uuid.eq(cart_id))
+            .filter(Column::Basetype.eq(InventoryBasetype::Hold))
+            .exec(&txn)
+            .await?;
+        rollup.store(&txn, sku.id).await?;
+        txn.commit().await?;
+
+        Ok(detail)
+    }
+
+    /// Give back all of a cart's holds, e.g. when it's emptied or deleted
+    ///
+    /// Returns the number of holds released.
+    pub async fn release(db: &DatabaseConnection, mid: i32, cart_id: &str) -> Result<usize> {
+        let holds = Self::holds(db, mid, cart_id).await?;
+        if holds.is_empty() {
+            return Ok(0);
+        }
+        let now = Utc::now().naive_utc();
+
+        let txn = db.begin().await?;
+        let mut released = 0;
+        for hold in holds {
+            let sku = lock_sku(&txn, mid, &hold.sku).await?;
+            let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+            // Re-read under the lock; the hold may have changed since
+            let (held, lapsed): (Vec<_>, Vec<_>) = stale_holds(&txn, mid, &sku.sku, Some(cart_id), now)
+                .await?
+                .into_iter()
+                .partition(|h| h.uuid == cart_id);
+            released += held.len();
+            release_rows(&txn, mid, LUSER, &[held, lapsed].concat(), &mut rollup, now).await?;
+            rollup.store(&txn, sku.id).await?;
+        }
+        txn.commit().await?;
+
+        Ok(released)
+    }
+
+    /// Give back holds that have lapsed, for any merchant
+    ///
+    /// Works through at most `limit` SKUs, one transaction each. Returns the
+    /// number of holds released.
+    pub async fn release_expired(db: &DatabaseConnection, limit: u64) -> Result<usize> {
+        let now = Utc::now().naive_utc();
+        let skus: Vec<(i32, String)> = InventoryDetails::find()
+            .select_only()
+            .column(Column::Mid)
+            .column(Column::Sku)
+            .distinct()
+            .filter(Column::Basetype.eq(InventoryBasetype::Hold))
+            .filter(Column::HoldExpiresTs.lt(now))
+            .order_by_asc(Column::Mid)
+            .order_by_asc(Column::Sku)
+            .limit(limit)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        let mut released = 0;
+        for (mid, sku) in skus {
+            let txn = db.begin().await?;
+            match lock_sku(&txn, mid, &sku).await {
+                Ok(sku) => {
+                    let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+                    let lapsed = stale_holds(&txn, mid, &sku.sku, None, now).await?;
+                    release_rows(&txn, mid, LUSER, &lapsed, &mut rollup, now).await?;
+                    rollup.store(&txn, sku.id).await?;
+                    released += lapsed.len();
+                }
+                // 🤓 the SKU was deleted under its holds; no counters left to fix
+                Err(InventoryError::UnknownSku(_)) => {
+                    let deleted = InventoryDetails::delete_many()
+                        .filter(Column::Mid.eq(mid))
+                        .filter(Column::Sku.eq(&sku))
+                        .filter(Column::Basetype.eq(InventoryBasetype::Hold))
+                        .filter(Column::HoldExpiresTs.lt(now))
+                        .exec(&txn)
+                        .await?;
+                    released += deleted.rows_affected as usize;
+                }
+                Err(err) => return Err(err),
+            }
+            txn.commit().await?;
+        }
+
+        Ok(released)
+    }
+
+    /// Reserve an order's lines as UNPAID rows in place of the cart's holds
+    ///
+    /// Runs in the caller's transaction. Whatever the cart holds of a SKU
+    /// covers its line first, so only the rest has to be available; holds on
+    /// SKUs the order doesn't take are given back. SKUs are locked in code
+    /// order, as in [`crate::InventoryService::allocate`].
+    pub async fn allocate<C: ConnectionTrait>(
+        db: &C,
+        mid: i32,
+        luser: &str,
+        cart_id: &str,
+        orderid: &str,
+        lines: &[StockLine],
+    ) -> Result<()> {
+        let now = Utc::now().naive_utc();
+        let holds = Self::holds(db, mid, cart_id).await?;
+        let mut skus: Vec<&str> = lines.iter().map(|l| l.sku.as_str()).collect();
+        skus.extend(holds.iter().map(|h| h.sku.as_str()));
+        skus.sort_unstable();
+        skus.dedup();
+
+        for code in skus {
+            let sku = lock_sku(db, mid, code).await?;
+            let mut rollup = Rollup::load(db, mid, &sku.sku).await?;
+            let stale = stale_holds(db, mid, &sku.sku, Some(cart_id), now).await?;
+            release_rows(db, mid, luser, &stale, &mut rollup, now).await?;
+
+            let qty: i32 = lines.iter().filter(|l| l.sku == sku.sku).map(|l| l.qty).sum();
+            if qty > 0 {
+                reserve_line(db, mid, luser, orderid, &sku, &mut rollup, qty).await?;
+            }
+            rollup.store(db, sku.id).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A SKU's holds that have lapsed, plus `cart_id`'s whether lapsed or not
+async fn stale_holds<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    sku: &str,
+    cart_id: Option<&str>,
+    now: NaiveDateTime,
+) -> Result<Vec<InventoryDetail>> {
+    let mut stale = Condition::any().add(Column::HoldExpiresTs.lt(now));
+    if let Some(cart_id) = cart_id {
+        stale = stale.add(Column::Uuid.eq(cart_id));
+    }
+
+    let holds = InventoryDetails::find()
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Sku.eq(sku))
+        .filter(Column::Basetype.eq(InventoryBasetype::Hold))
+        .filter(stale)
+        .order_by_asc(Column::Id)
+        .all(db)
+        .await?;
+
+    Ok(holds)
+}
+
+/// Delete holds of one locked SKU, logging each
+async fn release_rows<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    luser: &str,
+    holds: &[InventoryDetail],
+    rollup: &mut Rollup,
+    now: NaiveDateTime,
+) -> Result<()> {
+    if holds.is_empty() {
+        return Ok(());
+    }
+
+    InventoryDetails::delete_many()
+        .filter(Column::Id.is_in(holds.iter().map(|h| h.id)))
+        .exec(db)
+        .await?;
+    for hold in holds {
+        let lapsed = hold.hold_expires_ts.is_some_and(|ts| ts < now);
+        let released = InventoryDetail { qty: 0, ..hold.clone() };
+        write_log(db, mid, luser, &released, hold.qty, Action::Release, if lapsed { "expired" } else { "" }).await?;
+        rollup.apply(hold.basetype.as_ref(), -hold.qty);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{detail, ok, sku, total};
+
+    const CART: &str = "7f0c3a52-0d5e-4a8e-9a57-1b1f0c2d9e44";
+
+    fn cart_hold(qty: i32) -> InventoryDetail {
+        let mut hold = detail(CART, InventoryBasetype::Hold, qty);
+        hold.hold_expires_ts = Some(Utc::now().naive_utc() + TimeDelta::minutes(5));
+        hold
+    }
+
+    #[tokio::test]
+    async fn test_hold_refuses_stock_held_by_another_cart() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("SIMPLE", 1), total("HOLD", 1)]])
+            .append_query_results([Vec::<InventoryDetail>::new()])
+            .into_connection();
+
+        let err = ReservationService::hold(&db, 1, CART, "WIDGET:C001", 1, DEFAULT_HOLD_TTL)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, InventoryError::OutOfStock(_)));
+    }
+
+    #[tokio::test]
+    async fn test_raising_a_hold_only_needs_the_difference() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("SIMPLE", 3), total("HOLD", 2)]])
+            .append_query_results([vec![cart_hold(2)]])
+            .append_exec_results([ok(), ok(), ok(), ok()])
+            .into_connection();
+
+        let hold = ReservationService::hold(&db, 1, CART, "WIDGET:C001", 3, DEFAULT_HOLD_TTL)
+            .await
+            .unwrap()
+            .expect("hold kept");
+        assert_eq!(hold.qty, 3);
+
+        let log = db.into_transaction_log();
+        let txn = log[0].statements();
+        assert!(txn[3].to_string().contains(r#""uuid" = '7f0c3a52"#), "{}", txn[3]);
+        assert!(txn[5].to_string().contains("3, 2, 'HOLD', 'cart'"), "{}", txn[5]);
+        // The whole cart's holds are extended, not just this SKU's
+        let extend = txn[6].to_string();
+        assert!(extend.contains(r#"SET "hold_expires_ts" = "#), "{}", extend);
+        assert!(extend.contains(r#""uuid" = '7f0c3a52"#) && !extend.contains(r#""sku""#), "{}", extend);
+        let counters = txn[7].to_string();
+        assert!(counters.contains(r#""inv_available" = 0"#), "{}", counters);
+        assert!(counters.contains(r#""qty_reserved" = 3"#), "{}", counters);
+    }
+
+    #[tokio::test]
+    async fn test_allocate_turns_the_hold_into_an_order_reservation() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![cart_hold(2)]])
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("SIMPLE", 2), total("HOLD", 2)]])
+            .append_query_results([vec![cart_hold(2)]])
+            .append_exec_results([ok(), ok(), ok(), ok(), ok()])
+            .into_connection();
+        let lines = [StockLine { sku: "WIDGET:C001".to_string(), qty: 2 }];
+
+        ReservationService::allocate(&db, 1, "checkout", CART, "2025-01-00042", &lines)
+            .await
+            .unwrap();
+
+        let log = db.into_transaction_log();
+        let statements: Vec<String> = log.iter().map(|s| s.statements()[0].to_string()).collect();
+        assert!(statements[4].starts_with(r#"DELETE FROM "inventory_detail""#), "{statements:?}");
+        assert!(statements[5].contains("0, 2, 'RELEASE', 'checkout'"), "{statements:?}");
+        assert!(statements[6].contains("'UNPAID'") && statements[6].contains("'2025-01-00042'"), "{statements:?}");
+        assert!(statements[7].contains("'RESERVE'"), "{statements:?}");
+        assert!(statements[8].contains(r#""inv_available" = 0"#), "{statements:?}");
+        assert!(statements[8].contains(r#""qty_reserved" = 2"#), "{statements:?}");
+    }
+}
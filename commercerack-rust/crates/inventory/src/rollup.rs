@@ -0,0 +1,145 @@
+//! Stock counters on `sku_lookup`, derived from `inventory_detail`
+//!
+//! | basetype                                        | counts towards            |
+//! |-------------------------------------------------|---------------------------|
+//! | SIMPLE, WMS                                     | on shelf, sellable        |
+//! | SUPPLIER, CONSTANT                              | sellable (not our shelf)  |
+//! | ONORDER                                         | on order                  |
+//! | UNPAID, HOLD                                    | reserved                  |
+//! | PICK, PICKED, BACKORDER, PREORDER, OVERSOLD     | reserved, needs shipping  |
+//! | MARKET                                          | listed on a marketplace   |
+//!
+//! Everything else (SHIPPED, DONE, CANCEL, RETURN, ...) is history and
+//! counts for nothing. Available to sell is sellable minus reserved minus
+//! marketplace listings, and may go negative for backorderable SKUs.
+
+use ::entity::prelude::{InventoryDetails, SkuLookup};
+use ::entity::sea_orm_active_enums::InventoryBasetype;
+use ::entity::{inventory_detail, sku_lookup};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+
+use crate::error::Result;
+
+/// Quantity totals of one SKU
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rollup {
+    pub on_shelf: i32,
+    /// Sellable stock held elsewhere, e.g. at a dropship supplier
+    pub off_shelf: i32,
+    pub on_order: i32,
+    pub reserved: i32,
+    pub need_ship: i32,
+    pub markets: i32,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct BasetypeTotal {
+    basetype: Option<InventoryBasetype>,
+    qty: i64,
+}
+
+impl Rollup {
+    /// Available to sell
+    pub fn available(&self) -> i32 {
+        self.on_shelf + self.off_shelf - self.reserved - self.markets
+    }
+
+    /// Count `qty` units of `basetype`; negative to take them away
+    pub fn apply(&mut self, basetype: Option<&InventoryBasetype>, qty: i32) {
+        use InventoryBasetype::*;
+
+        let Some(basetype) = basetype else { return };
+        match basetype {
+            Simple | Wms => self.on_shelf += qty,
+            Supplier | Constant => self.off_shelf += qty,
+            Onorder => self.on_order += qty,
+            Unpaid | Hold => self.reserved += qty,
+            Pick | Picked | Backorder | Preorder | Oversold => {
+                self.reserved += qty;
+                self.need_ship += qty;
+            }
+            Market => self.markets += qty,
+            _ => {}
+        }
+    }
+
+    /// Totals of a SKU's detail rows
+    pub async fn load<C: ConnectionTrait>(db: &C, mid: i32, sku: &str) -> Result<Self> {
+        use inventory_detail::Column;
+
+        let totals = InventoryDetails::find()
+            .select_only()
+            .column(Column::Basetype)
+            .column_as(Expr::col(Column::Qty).sum(), "qty")
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Sku.eq(sku))
+            .group_by(Column::Basetype)
+            .into_model::<BasetypeTotal>()
+            .all(db)
+            .await?;
+
+        let mut rollup = Self::default();
+        for total in totals {
+            rollup.apply(total.basetype.as_ref(), i32::try_from(total.qty).unwrap_or(i32::MAX));
+        }
+        Ok(rollup)
+    }
+
+    /// Write the counters to the SKU's `sku_lookup` row
+    ///
+    /// `inv_on_shelf` and `inv_on_order` are legacy copies kept in step.
+    pub async fn store<C: ConnectionTrait>(&self, db: &C, sku_id: i64) -> Result<()> {
+        use sku_lookup::Column;
+
+        SkuLookup::update_many()
+            .col_expr(Column::InvAvailable, Expr::value(self.available()))
+            .col_expr(Column::QtyOnshelf, Expr::value(self.on_shelf))
+            .col_expr(Column::InvOnShelf, Expr::value(self.on_shelf))
+            .col_expr(Column::QtyOnorder, Expr::value(self.on_order))
+            .col_expr(Column::InvOnOrder, Expr::value(self.on_order))
+            .col_expr(Column::QtyReserved, Expr::value(self.reserved))
+            .col_expr(Column::QtyNeedship, Expr::value(self.need_ship))
+            .col_expr(Column::QtyMarkets, Expr::value(self.markets))
+            .filter(Column::Id.eq(sku_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InventoryBasetype::*;
+
+    #[test]
+    fn test_available_nets_out_reservations() {
+        let mut rollup = Rollup::default();
+        for (basetype, qty) in [
+            (Simple, 10),
+            (Wms, 5),
+            (Supplier, 3),
+            (Onorder, 20),
+            (Unpaid, 2),
+            (Picked, 4),
+            (Market, 1),
+            (Shipped, 7),
+        ] {
+            rollup.apply(Some(&basetype), qty);
+        }
+        rollup.apply(None, 99);
+
+        assert_eq!(rollup.on_shelf, 15);
+        assert_eq!(rollup.on_order, 20);
+        assert_eq!(rollup.reserved, 6);
+        assert_eq!(rollup.need_ship, 4);
+        assert_eq!(rollup.available(), 15 + 3 - 6 - 1);
+
+        // Moving units from PICKED to SHIPPED releases the reservation
+        rollup.apply(Some(&Picked), -4);
+        rollup.apply(Some(&Shipped), 4);
+        assert_eq!(rollup.reserved, 2);
+        assert_eq!(rollup.need_ship, 0);
+    }
+}
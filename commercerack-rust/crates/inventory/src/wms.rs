@@ -0,0 +1,799 @@
+//! Warehouse bins and picking
+//!
+//! Stock kept in bins is one WMS row per SKU and bin, located by `wms_geo`
+//! (the warehouse), `wms_zone` and `wms_pos`, with [`Location::uuid`] as its
+//! `uuid`. [`WmsService::put_away`] shelves stock into a bin and
+//! [`WmsService::relocate`] moves it between bins.
+//!
+//! Paid orders are picked in batches. [`WmsService::create_batch`] turns
+//! their UNPAID rows into PICK rows sharing one `pick_batchid`, each naming
+//! the bin to take its units from (route WMS) or the plain shelf (route
+//! SIMPLE), splitting rows that span several. Pickers confirm rows from PICK
+//! to PICKED, and [`WmsService::ship`] moves an order's picked rows to
+//! SHIPPED, taking the units off the bin or shelf rows they came from.
+
+use ::entity::inventory_detail::{self, Column};
+use ::entity::orders;
+use ::entity::prelude::{InventoryDetail, InventoryDetails, Orders};
+use ::entity::sea_orm_active_enums::InventoryPickRoute;
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::error::{InventoryError, Result};
+use crate::rollup::Rollup;
+use crate::{clip, find_detail, lock_sku, update_detail, write_log, Action, InventoryBasetype, NewDetail};
+
+/// A bin: warehouse, zone and position in the zone
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Location {
+    pub geo: String,
+    pub zone: String,
+    pub pos: String,
+}
+
+impl Location {
+    /// Upper-cased and checked against the column widths
+    pub fn new(geo: &str, zone: &str, pos: &str) -> Result<Self> {
+        Ok(Self {
+            geo: location_part("wms_geo", geo, 3)?,
+            zone: location_part("wms_zone", zone, 3)?,
+            pos: location_part("wms_pos", pos, 12)?,
+        })
+    }
+
+    /// `uuid` of the WMS row holding a SKU in this bin
+    pub fn uuid(&self) -> String {
+        format!("WMS:{}", self)
+    }
+
+    /// The bin a row names, if any
+    pub fn of(detail: &InventoryDetail) -> Option<Self> {
+        Some(Self {
+            geo: detail.wms_geo.clone()?,
+            zone: detail.wms_zone.clone()?,
+            pos: detail.wms_pos.clone()?,
+        })
+    }
+}
+
+/// Parses the `GEO:ZONE:POS` form [`Location`] displays as
+impl FromStr for Location {
+    type Err = InventoryError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split(':').collect::<Vec<_>>()[..] {
+            [geo, zone, pos] => Self::new(geo, zone, pos),
+            _ => Err(InventoryError::InvalidDetail {
+                field: "location",
+                message: "must be warehouse:zone:position".to_string(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.geo, self.zone, self.pos)
+    }
+}
+
+/// A pick batch and its rows in walking order
+#[derive(Debug, Clone)]
+pub struct PickBatch {
+    pub batchid: String,
+    pub lines: Vec<InventoryDetail>,
+}
+
+/// Somewhere units of a SKU can be picked from
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Source {
+    route: InventoryPickRoute,
+    location: Option<Location>,
+    /// On hand less what is already on a pick batch
+    free: i32,
+}
+
+/// Units of a SKU on pick batches but not yet shipped, per place
+#[derive(Debug, FromQueryResult)]
+struct Picking {
+    pick_route: Option<InventoryPickRoute>,
+    wms_geo: Option<String>,
+    wms_zone: Option<String>,
+    wms_pos: Option<String>,
+    qty: i64,
+}
+
+/// Warehouse service
+pub struct WmsService;
+
+impl WmsService {
+    /// Bins holding stock, optionally in one warehouse and zone
+    pub async fn bins(
+        db: &DatabaseConnection,
+        mid: i32,
+        geo: Option<&str>,
+        zone: Option<&str>,
+    ) -> Result<Vec<InventoryDetail>> {
+        let mut query = InventoryDetails::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Basetype.eq(InventoryBasetype::Wms))
+            .filter(Column::Qty.gt(0));
+        if let Some(geo) = geo {
+            query = query.filter(Column::WmsGeo.eq(geo.trim().to_uppercase()));
+        }
+        if let Some(zone) = zone {
+            query = query.filter(Column::WmsZone.eq(zone.trim().to_uppercase()));
+        }
+
+        let bins = query
+            .order_by_asc(Column::WmsGeo)
+            .order_by_asc(Column::WmsZone)
+            .order_by_asc(Column::WmsPos)
+            .order_by_asc(Column::Sku)
+            .all(db)
+            .await?;
+        Ok(bins)
+    }
+
+    /// Shelve `qty` units of a SKU into a bin
+    ///
+    /// With `from`, the units come off that SIMPLE or ONORDER row (stock
+    /// being put away after receiving); otherwise they are new to the
+    /// ledger. Returns the bin's row.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn put_away(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        sku: &str,
+        to: &Location,
+        qty: i32,
+        from: Option<&str>,
+        note: &str,
+    ) -> Result<InventoryDetail> {
+        positive(qty)?;
+
+        let txn = db.begin().await?;
+        let sku = lock_sku(&txn, mid, sku).await?;
+        let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+
+        if let Some(from) = from {
+            let source = find_detail(&txn, mid, &sku.sku, from).await?;
+            if !matches!(source.basetype, Some(InventoryBasetype::Simple | InventoryBasetype::Onorder)) {
+                return Err(InventoryError::InvalidDetail {
+                    field: "from",
+                    message: "only SIMPLE and ONORDER rows can be put away".to_string(),
+                });
+            }
+            if source.qty < qty {
+                return Err(InventoryError::ShelfShort {
+                    sku: sku.sku,
+                    location: source.uuid,
+                    free: source.qty,
+                });
+            }
+            let updated = update_detail(&txn, luser, &source, source.qty - qty, source.basetype).await?;
+            write_log(&txn, mid, luser, &updated, source.qty, Action::Putaway, &format!("to {}", to)).await?;
+            rollup.apply(source.basetype.as_ref(), -qty);
+        }
+
+        let bin = add_to_bin(&txn, mid, luser, &sku, to, qty, Action::Putaway, note).await?;
+        rollup.apply(bin.basetype.as_ref(), qty);
+        rollup.store(&txn, sku.id).await?;
+        txn.commit().await?;
+
+        Ok(bin)
+    }
+
+    /// Move `qty` units of a SKU from one bin to another
+    ///
+    /// Units already on a pick batch stay put. Returns the destination row.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn relocate(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        sku: &str,
+        from: &Location,
+        to: &Location,
+        qty: i32,
+        note: &str,
+    ) -> Result<InventoryDetail> {
+        positive(qty)?;
+
+        let txn = db.begin().await?;
+        let sku = lock_sku(&txn, mid, sku).await?;
+        let source = find_detail(&txn, mid, &sku.sku, &from.uuid()).await?;
+        let picking = picking(&txn, mid, &sku.sku).await?;
+        let free = source.qty - taken(&picking, InventoryPickRoute::Wms, Some(from));
+        if free < qty {
+            return Err(InventoryError::ShelfShort {
+                sku: sku.sku,
+                location: from.to_string(),
+                free: free.max(0),
+            });
+        }
+
+        let updated = update_detail(&txn, luser, &source, source.qty - qty, source.basetype).await?;
+        write_log(&txn, mid, luser, &updated, source.qty, Action::Relocate, &format!("to {}", to)).await?;
+        let bin = add_to_bin(&txn, mid, luser, &sku, to, qty, Action::Relocate, note).await?;
+        txn.commit().await?;
+
+        Ok(bin)
+    }
+
+    /// Put the UNPAID rows of paid, unshipped orders on a new pick batch
+    ///
+    /// Each row is taken from bins first, in zone and position order, then
+    /// from the shelf; a row that can't be covered fails the whole batch.
+    pub async fn create_batch(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        orderids: &[String],
+    ) -> Result<PickBatch> {
+        if orderids.is_empty() {
+            return Err(InventoryError::InvalidDetail {
+                field: "orderids",
+                message: "must name at least one order".to_string(),
+            });
+        }
+        let ready: Vec<String> = Orders::find()
+            .select_only()
+            .column(orders::Column::Orderid)
+            .filter(orders::Column::Mid.eq(mid))
+            .filter(orders::Column::Orderid.is_in(orderids.iter().cloned()))
+            .filter(orders::Column::PaidGmt.gt(0))
+            .filter(orders::Column::ShippedGmt.eq(0))
+            .into_tuple()
+            .all(db)
+            .await?;
+        if let Some(orderid) = orderids.iter().find(|id| !ready.contains(id)) {
+            return Err(InventoryError::OrderNotReady(orderid.clone()));
+        }
+
+        let which = Condition::all()
+            .add(Column::Mid.eq(mid))
+            .add(Column::Basetype.eq(InventoryBasetype::Unpaid))
+            .add(Column::OurOrderid.is_in(orderids.iter().cloned()))
+            .add(Column::Qty.gt(0));
+        let batchid = new_batchid();
+
+        let txn = db.begin().await?;
+        let unpaid = InventoryDetails::find().filter(which.clone()).all(&txn).await?;
+        if unpaid.is_empty() {
+            return Err(InventoryError::NothingToPick);
+        }
+        for code in skus_of(&unpaid) {
+            let sku = lock_sku(&txn, mid, &code).await?;
+            let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+            let mut sources = sources(&txn, mid, &sku.sku).await?;
+            let rows = InventoryDetails::find()
+                .filter(which.clone())
+                .filter(Column::Sku.eq(&sku.sku))
+                .order_by_asc(Column::OurOrderid)
+                .order_by_asc(Column::Id)
+                .all(&txn)
+                .await?;
+
+            for row in rows {
+                let parts = take(&mut sources, row.qty).ok_or_else(|| InventoryError::OutOfStock(sku.sku.clone()))?;
+                for (i, (route, location, qty)) in parts.into_iter().enumerate() {
+                    let (pick, qty_before) = if i == 0 {
+                        let pick = save(&txn, luser, &row, |a| {
+                            a.basetype = Set(Some(InventoryBasetype::Pick));
+                            a.qty = Set(qty);
+                            on_batch(a, &batchid, route, location.as_ref());
+                        })
+                        .await?;
+                        (pick, row.qty)
+                    } else {
+                        let mut input = NewDetail::new(&sku.sku, InventoryBasetype::Pick, qty);
+                        input.cost_i = row.cost_i;
+                        input.origin = row.origin.clone();
+                        input.our_orderid = row.our_orderid.clone();
+                        let mut active = input.validated()?.into_active_model(mid, &sku, luser);
+                        on_batch(&mut active, &batchid, route, location.as_ref());
+                        (active.insert(&txn).await?, 0)
+                    };
+                    write_log(&txn, mid, luser, &pick, qty_before, Action::Pick, &batchid).await?;
+                }
+                rollup.apply(Some(&InventoryBasetype::Unpaid), -row.qty);
+                rollup.apply(Some(&InventoryBasetype::Pick), row.qty);
+            }
+            rollup.store(&txn, sku.id).await?;
+        }
+        txn.commit().await?;
+
+        let lines = Self::pick_list(db, mid, &batchid).await?;
+        Ok(PickBatch { batchid, lines })
+    }
+
+    /// A batch's rows in walking order: warehouse, zone, position, then SKU
+    ///
+    /// Shelf rows without a bin come last.
+    pub async fn pick_list<C: ConnectionTrait>(db: &C, mid: i32, batchid: &str) -> Result<Vec<InventoryDetail>> {
+        let lines = InventoryDetails::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::PickBatchid.eq(batchid))
+            .order_by_asc(Column::WmsGeo)
+            .order_by_asc(Column::WmsZone)
+            .order_by_asc(Column::WmsPos)
+            .order_by_asc(Column::Sku)
+            .order_by_asc(Column::OurOrderid)
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
+        if lines.is_empty() {
+            return Err(InventoryError::PickBatchNotFound);
+        }
+
+        Ok(lines)
+    }
+
+    /// Confirm rows of a batch as picked (PICK to PICKED)
+    ///
+    /// Confirms the rows named by `uuids`, or all of the batch's PICK rows
+    /// when it is empty. Counters don't change; both count as reserved and
+    /// waiting to ship.
+    pub async fn confirm_picks(
+        db: &DatabaseConnection,
+        mid: i32,
+        luser: &str,
+        batchid: &str,
+        uuids: &[String],
+    ) -> Result<Vec<InventoryDetail>> {
+        let mut which = Condition::all()
+            .add(Column::Mid.eq(mid))
+            .add(Column::PickBatchid.eq(batchid))
+            .add(Column::Basetype.eq(InventoryBasetype::Pick));
+        if !uuids.is_empty() {
+            which = which.add(Column::Uuid.is_in(uuids.iter().cloned()));
+        }
+
+        let txn = db.begin().await?;
+        let rows = InventoryDetails::find().filter(which.clone()).all(&txn).await?;
+        if rows.is_empty() && uuids.is_empty() {
+            Self::pick_list(&txn, mid, batchid).await?;
+        }
+        if uuids.iter().any(|uuid| !rows.iter().any(|r| &r.uuid == uuid)) {
+            return Err(InventoryError::DetailNotFound);
+        }
+
+        let now = Utc::now().naive_utc();
+        let mut picked = Vec::with_capacity(rows.len());
+        for code in skus_of(&rows) {
+            lock_sku(&txn, mid, &code).await?;
+            let rows = InventoryDetails::find()
+                .filter(which.clone())
+                .filter(Column::Sku.eq(&code))
+                .order_by_asc(Column::Id)
+                .all(&txn)
+                .await?;
+            for row in rows {
+                let done = save(&txn, luser, &row, |a| {
+                    a.basetype = Set(Some(InventoryBasetype::Picked));
+                    a.pick_done_ts = Set(Some(now));
+                })
+                .await?;
+                write_log(&txn, mid, luser, &done, row.qty, Action::Picked, batchid).await?;
+                picked.push(done);
+            }
+        }
+        txn.commit().await?;
+
+        Ok(picked)
+    }
+
+    /// Ship an order's picked rows (PICKED to SHIPPED)
+    ///
+    /// The units come off the bin or shelf rows they were picked from. Once
+    /// nothing is left to pick or ship, the order is marked shipped.
+    pub async fn ship(db: &DatabaseConnection, mid: i32, luser: &str, orderid: &str) -> Result<Vec<InventoryDetail>> {
+        let which = Condition::all()
+            .add(Column::Mid.eq(mid))
+            .add(Column::OurOrderid.eq(orderid))
+            .add(Column::Basetype.eq(InventoryBasetype::Picked));
+
+        let txn = db.begin().await?;
+        let picked = InventoryDetails::find().filter(which.clone()).all(&txn).await?;
+        if picked.is_empty() {
+            return Err(InventoryError::NothingToShip(orderid.to_string()));
+        }
+
+        let mut shipped = Vec::with_capacity(picked.len());
+        for code in skus_of(&picked) {
+            let sku = lock_sku(&txn, mid, &code).await?;
+            let mut rollup = Rollup::load(&txn, mid, &sku.sku).await?;
+            let rows = InventoryDetails::find()
+                .filter(which.clone())
+                .filter(Column::Sku.eq(&sku.sku))
+                .order_by_asc(Column::Id)
+                .all(&txn)
+                .await?;
+            for row in rows {
+                take_off_shelf(&txn, mid, luser, &row, &mut rollup).await?;
+                let gone = update_detail(&txn, luser, &row, row.qty, Some(InventoryBasetype::Shipped)).await?;
+                write_log(&txn, mid, luser, &gone, row.qty, Action::Ship, "").await?;
+                rollup.apply(row.basetype.as_ref(), -row.qty);
+                shipped.push(gone);
+            }
+            rollup.store(&txn, sku.id).await?;
+        }
+
+        let open = InventoryDetails::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::OurOrderid.eq(orderid))
+            .filter(Column::Basetype.is_in([
+                InventoryBasetype::Unpaid,
+                InventoryBasetype::Pick,
+                InventoryBasetype::Picked,
+            ]))
+            .count(&txn)
+            .await?;
+        if open == 0 {
+            Orders::update_many()
+                .col_expr(orders::Column::ShippedGmt, Expr::value(Utc::now().timestamp() as i32))
+                .filter(orders::Column::Mid.eq(mid))
+                .filter(orders::Column::Orderid.eq(orderid))
+                .filter(orders::Column::ShippedGmt.eq(0))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        Ok(shipped)
+    }
+}
+
+/// One part of a location, upper-cased and checked against its column width
+pub(crate) fn location_part(field: &'static str, value: &str, max: usize) -> Result<String> {
+    let value = value.trim().to_uppercase();
+    let valid = value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if value.is_empty() || value.chars().count() > max || !valid {
+        return Err(InventoryError::InvalidDetail {
+            field,
+            message: format!("must be 1 to {} letters, digits or dashes", max),
+        });
+    }
+    Ok(value)
+}
+
+fn positive(qty: i32) -> Result<()> {
+    if qty <= 0 {
+        return Err(InventoryError::InvalidDetail {
+            field: "qty",
+            message: "must be greater than zero".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// 🤓 `pick_batchid` is varchar(8)
+fn new_batchid() -> String {
+    Uuid::new_v4().simple().to_string()[..8].to_uppercase()
+}
+
+/// Distinct SKUs of `rows` in lock order
+fn skus_of(rows: &[InventoryDetail]) -> Vec<String> {
+    let mut skus: Vec<String> = rows.iter().map(|r| r.sku.clone()).collect();
+    skus.sort_unstable();
+    skus.dedup();
+    skus
+}
+
+/// Add `qty` units to a SKU's row in a bin, creating it the first time
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn add_to_bin<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    luser: &str,
+    sku: &::entity::prelude::Sku,
+    to: &Location,
+    qty: i32,
+    action: Action,
+    note: &str,
+) -> Result<InventoryDetail> {
+    match find_detail(db, mid, &sku.sku, &to.uuid()).await {
+        Ok(bin) => {
+            let updated = update_detail(db, luser, &bin, bin.qty + qty, bin.basetype).await?;
+            write_log(db, mid, luser, &updated, bin.qty, action, note).await?;
+            Ok(updated)
+        }
+        Err(InventoryError::DetailNotFound) => {
+            let mut input = NewDetail::new(&sku.sku, InventoryBasetype::Wms, qty);
+            input.uuid = to.uuid();
+            input.note = clip(note, 25);
+            let mut active = input.validated()?.into_active_model(mid, sku, luser);
+            active.wms_geo = Set(Some(to.geo.clone()));
+            active.wms_zone = Set(Some(to.zone.clone()));
+            active.wms_pos = Set(Some(to.pos.clone()));
+            let bin = active.insert(db).await?;
+            write_log(db, mid, luser, &bin, 0, action, note).await?;
+            Ok(bin)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Write changes to a row, stamping it as modified by `luser`
+async fn save<C: ConnectionTrait>(
+    db: &C,
+    luser: &str,
+    before: &InventoryDetail,
+    change: impl FnOnce(&mut inventory_detail::ActiveModel),
+) -> Result<InventoryDetail> {
+    let mut active: inventory_detail::ActiveModel = before.clone().into();
+    change(&mut active);
+    active.modified_ts = Set(Some(Utc::now().naive_utc()));
+    active.modified_by = Set(clip(luser, 10));
+    active.modified_inc = Set(before.modified_inc + 1);
+    active.modified_qty_was = Set(before.qty);
+
+    Ok(active.update(db).await?)
+}
+
+fn on_batch(
+    active: &mut inventory_detail::ActiveModel,
+    batchid: &str,
+    route: InventoryPickRoute,
+    location: Option<&Location>,
+) {
+    active.pick_batchid = Set(batchid.to_string());
+    active.pick_route = Set(Some(route));
+    active.wms_geo = Set(location.map(|l| l.geo.clone()));
+    active.wms_zone = Set(location.map(|l| l.zone.clone()));
+    active.wms_pos = Set(location.map(|l| l.pos.clone()));
+}
+
+/// What is on pick batches for a SKU and not yet shipped
+async fn picking<C: ConnectionTrait>(db: &C, mid: i32, sku: &str) -> Result<Vec<Picking>> {
+    let picking = InventoryDetails::find()
+        .select_only()
+        .column(Column::PickRoute)
+        .column(Column::WmsGeo)
+        .column(Column::WmsZone)
+        .column(Column::WmsPos)
+        .column_as(Expr::col(Column::Qty).sum(), "qty")
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Sku.eq(sku))
+        .filter(Column::Basetype.is_in([InventoryBasetype::Pick, InventoryBasetype::Picked]))
+        .group_by(Column::PickRoute)
+        .group_by(Column::WmsGeo)
+        .group_by(Column::WmsZone)
+        .group_by(Column::WmsPos)
+        .into_model::<Picking>()
+        .all(db)
+        .await?;
+
+    Ok(picking)
+}
+
+fn taken(picking: &[Picking], route: InventoryPickRoute, location: Option<&Location>) -> i32 {
+    picking
+        .iter()
+        .filter(|p| p.pick_route == Some(route))
+        .filter(|p| {
+            location.is_none_or(|l| {
+                p.wms_geo.as_ref() == Some(&l.geo)
+                    && p.wms_zone.as_ref() == Some(&l.zone)
+                    && p.wms_pos.as_ref() == Some(&l.pos)
+            })
+        })
+        .map(|p| i32::try_from(p.qty).unwrap_or(i32::MAX))
+        .sum()
+}
+
+/// A SKU's bins in zone and position order, then its shelf
+async fn sources<C: ConnectionTrait>(db: &C, mid: i32, sku: &str) -> Result<Vec<Source>> {
+    let stock = InventoryDetails::find()
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Sku.eq(sku))
+        .filter(Column::Basetype.is_in([InventoryBasetype::Wms, InventoryBasetype::Simple]))
+        .order_by_asc(Column::WmsGeo)
+        .order_by_asc(Column::WmsZone)
+        .order_by_asc(Column::WmsPos)
+        .all(db)
+        .await?;
+    let picking = picking(db, mid, sku).await?;
+
+    let mut sources: Vec<Source> = stock
+        .iter()
+        .filter(|d| d.basetype == Some(InventoryBasetype::Wms))
+        .filter_map(|d| {
+            let location = Location::of(d)?;
+            let free = d.qty - taken(&picking, InventoryPickRoute::Wms, Some(&location));
+            Some(Source { route: InventoryPickRoute::Wms, location: Some(location), free })
+        })
+        .collect();
+    let shelf: i32 = stock
+        .iter()
+        .filter(|d| d.basetype == Some(InventoryBasetype::Simple))
+        .map(|d| d.qty)
+        .sum();
+    sources.push(Source {
+        route: InventoryPickRoute::Simple,
+        location: None,
+        free: shelf - taken(&picking, InventoryPickRoute::Simple, None),
+    });
+
+    Ok(sources)
+}
+
+/// Split `qty` over `sources` in order; `None` when they can't cover it
+fn take(sources: &mut [Source], mut qty: i32) -> Option<Vec<(InventoryPickRoute, Option<Location>, i32)>> {
+    let free: i32 = sources.iter().map(|s| s.free.max(0)).sum();
+    if free < qty {
+        return None;
+    }
+
+    let mut parts = Vec::new();
+    for source in sources.iter_mut().filter(|s| s.free > 0) {
+        if qty == 0 {
+            break;
+        }
+        let n = source.free.min(qty);
+        source.free -= n;
+        qty -= n;
+        parts.push((source.route, source.location.clone(), n));
+    }
+    Some(parts)
+}
+
+/// Take a picked row's units off the bin or shelf rows it was picked from
+async fn take_off_shelf<C: ConnectionTrait>(
+    db: &C,
+    mid: i32,
+    luser: &str,
+    row: &InventoryDetail,
+    rollup: &mut Rollup,
+) -> Result<()> {
+    let (rows, location) = match (row.pick_route, Location::of(row)) {
+        (Some(InventoryPickRoute::Wms), Some(location)) => {
+            let bin = InventoryDetails::find()
+                .filter(Column::Mid.eq(mid))
+                .filter(Column::Sku.eq(&row.sku))
+                .filter(Column::Uuid.eq(location.uuid()))
+                .all(db)
+                .await?;
+            (bin, location.to_string())
+        }
+        _ => {
+            let shelf = InventoryDetails::find()
+                .filter(Column::Mid.eq(mid))
+                .filter(Column::Sku.eq(&row.sku))
+                .filter(Column::Basetype.eq(InventoryBasetype::Simple))
+                .order_by_asc(Column::Id)
+                .all(db)
+                .await?;
+            (shelf, "SIMPLE".to_string())
+        }
+    };
+
+    let on_hand: i32 = rows.iter().map(|r| r.qty).sum();
+    if on_hand < row.qty {
+        return Err(InventoryError::ShelfShort {
+            sku: row.sku.clone(),
+            location,
+            free: on_hand.max(0),
+        });
+    }
+
+    let mut left = row.qty;
+    for source in rows.iter().filter(|r| r.qty > 0) {
+        if left == 0 {
+            break;
+        }
+        let n = source.qty.min(left);
+        left -= n;
+        let updated = update_detail(db, luser, source, source.qty - n, source.basetype).await?;
+        write_log(db, mid, luser, &updated, source.qty, Action::Ship, &row.our_orderid).await?;
+        rollup.apply(source.basetype.as_ref(), -n);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{detail, ok, sku, total};
+    use std::collections::BTreeMap;
+
+    fn bin(pos: &str, qty: i32) -> InventoryDetail {
+        let location = Location::new("MN", "A", pos).unwrap();
+        let mut bin = detail(&location.uuid(), InventoryBasetype::Wms, qty);
+        bin.wms_geo = Some(location.geo);
+        bin.wms_zone = Some(location.zone);
+        bin.wms_pos = Some(location.pos);
+        bin
+    }
+
+    fn picking_at(pos: &str, qty: i64) -> BTreeMap<String, Value> {
+        BTreeMap::from([
+            ("pick_route".to_string(), Value::from("WMS")),
+            ("wms_geo".to_string(), Value::from("MN")),
+            ("wms_zone".to_string(), Value::from("A")),
+            ("wms_pos".to_string(), Value::from(pos)),
+            ("qty".to_string(), Value::from(qty)),
+        ])
+    }
+
+    #[test]
+    fn test_location_is_normalised_and_checked() {
+        let location = Location::new(" mn", "a", "03-2 ").unwrap();
+        assert_eq!(location.uuid(), "WMS:MN:A:03-2");
+        assert!(Location::new("MAIN", "A", "1").is_err());
+        assert!(Location::new("MN", "", "1").is_err());
+        assert!(Location::new("MN", "A", "1/2").is_err());
+    }
+
+    #[test]
+    fn test_take_walks_sources_in_order() {
+        let a1 = Location::new("MN", "A", "1").unwrap();
+        let a2 = Location::new("MN", "A", "2").unwrap();
+        let mut sources = vec![
+            Source { route: InventoryPickRoute::Wms, location: Some(a1.clone()), free: 2 },
+            Source { route: InventoryPickRoute::Wms, location: Some(a2), free: 0 },
+            Source { route: InventoryPickRoute::Simple, location: None, free: 5 },
+        ];
+
+        let parts = take(&mut sources, 3).unwrap();
+        assert_eq!(
+            parts,
+            vec![(InventoryPickRoute::Wms, Some(a1), 2), (InventoryPickRoute::Simple, None, 1)]
+        );
+        assert!(take(&mut sources, 5).is_none(), "only 4 left on the shelf");
+        assert_eq!(take(&mut sources, 4).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_relocate_leaves_units_on_pick_batches() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![bin("1", 5)]])
+            .append_query_results([vec![picking_at("1", 4)]])
+            .into_connection();
+        let from = Location::new("MN", "A", "1").unwrap();
+        let to = Location::new("MN", "B", "7").unwrap();
+
+        let err = WmsService::relocate(&db, 1, "tester", "WIDGET:C001", &from, &to, 2, "")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, InventoryError::ShelfShort { free: 1, .. }), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn test_ship_takes_units_off_the_bin() {
+        let mut picked = bin("1", 2);
+        picked.uuid = "pick-1".to_string();
+        picked.basetype = Some(InventoryBasetype::Picked);
+        picked.pick_route = Some(InventoryPickRoute::Wms);
+        picked.our_orderid = "2025-01-00042".to_string();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![picked.clone()]])
+            .append_query_results([vec![sku()]])
+            .append_query_results([vec![total("WMS", 5), total("PICKED", 2)]])
+            .append_query_results([vec![picked]])
+            .append_query_results([vec![bin("1", 5)]])
+            .append_query_results([vec![BTreeMap::from([("num_items".to_string(), Value::from(0i64))])]])
+            .append_exec_results([ok(), ok(), ok(), ok(), ok(), ok()])
+            .into_connection();
+
+        let shipped = WmsService::ship(&db, 1, "tester", "2025-01-00042").await.unwrap();
+        assert_eq!(shipped[0].basetype, Some(InventoryBasetype::Shipped));
+
+        let log = db.into_transaction_log();
+        let txn: Vec<String> = log[0].statements().iter().map(|s| s.to_string()).collect();
+        assert!(txn[6].contains(r#""qty" = 3"#), "{txn:?}");
+        assert!(txn[7].contains("3, 5, 'SHIP'"), "{txn:?}");
+        assert!(txn[8].contains(r#"CAST('SHIPPED' AS "inventory_basetype_enum")"#), "{txn:?}");
+        let counters = &txn[10];
+        assert!(counters.contains(r#""qty_onshelf" = 3"#), "{counters}");
+        assert!(counters.contains(r#""qty_reserved" = 0"#) && counters.contains(r#""qty_needship" = 0"#), "{counters}");
+        assert!(txn[12].starts_with(r#"UPDATE "orders""#), "{txn:?}");
+    }
+}
@@ -0,0 +1,393 @@
+//! Merchant API keys for server-to-server integrations
+//!
+//! Keys look like `crk_<43 url-safe characters>`. Only a SHA-256 of each key
+//! is stored, plus its first 12 characters so listings can tell keys apart.
+//! Rotating a key issues a replacement with the same name and scopes and
+//! lets the old key live on for a grace period while callers switch over.
+
+use ::entity::merchant_api_keys::{self, Column};
+use ::entity::prelude::{MerchantApiKey, MerchantApiKeys};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::error::{MerchantError, Result};
+
+const KEY_PREFIX: &str = "crk_";
+
+/// Characters of a key kept in `key_prefix`
+const DISPLAY_LEN: usize = 12;
+
+/// Seconds between `last_used_gmt` writes for a busy key
+const TOUCH_INTERVAL: i32 = 60;
+
+/// What an API key may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ApiScope {
+    #[serde(rename = "catalog:read")]
+    CatalogRead,
+    /// Products, SKUs and their relations
+    #[serde(rename = "catalog:write")]
+    CatalogWrite,
+    /// Order lookups for reporting and ERP feeds
+    #[serde(rename = "orders:read")]
+    OrdersRead,
+    #[serde(rename = "orders:write")]
+    OrdersWrite,
+    #[serde(rename = "inventory:write")]
+    InventoryWrite,
+    /// Support notes and customer timelines
+    #[serde(rename = "customers:notes")]
+    CustomerNotes,
+    /// Account administration such as unlocking logins
+    #[serde(rename = "customers:admin")]
+    CustomersAdmin,
+    /// Merchant settings such as the order ID format
+    #[serde(rename = "merchant:settings")]
+    MerchantSettings,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 8] = [
+        Self::CatalogRead,
+        Self::CatalogWrite,
+        Self::OrdersRead,
+        Self::OrdersWrite,
+        Self::InventoryWrite,
+        Self::CustomerNotes,
+        Self::CustomersAdmin,
+        Self::MerchantSettings,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CatalogRead => "catalog:read",
+            Self::CatalogWrite => "catalog:write",
+            Self::OrdersRead => "orders:read",
+            Self::OrdersWrite => "orders:write",
+            Self::InventoryWrite => "inventory:write",
+            Self::CustomerNotes => "customers:notes",
+            Self::CustomersAdmin => "customers:admin",
+            Self::MerchantSettings => "merchant:settings",
+        }
+    }
+
+    /// Parse a comma-separated scope list
+    pub fn parse_list(scopes: &str) -> Result<Vec<ApiScope>> {
+        let mut parsed: Vec<ApiScope> = Vec::new();
+        for scope in scopes.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let scope = scope.parse()?;
+            if !parsed.contains(&scope) {
+                parsed.push(scope);
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Scopes granted to a stored key; unknown names are ignored
+    pub fn of(key: &MerchantApiKey) -> Vec<ApiScope> {
+        key.scopes.split(',').filter_map(|s| s.parse().ok()).collect()
+    }
+
+    fn join(scopes: &[ApiScope]) -> String {
+        scopes.iter().map(ApiScope::as_str).collect::<Vec<_>>().join(",")
+    }
+}
+
+impl fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApiScope {
+    type Err = MerchantError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| MerchantError::UnknownScope(s.to_string()))
+    }
+}
+
+/// A new API key and its stored record
+#[derive(Debug, Clone)]
+pub struct IssuedApiKey {
+    /// Plaintext key; shown to the merchant once and never stored
+    pub key: String,
+    pub record: MerchantApiKey,
+}
+
+/// API key service
+pub struct ApiKeyService;
+
+impl ApiKeyService {
+    /// Issue a key for merchant `mid`; `ttl` of `None` never expires
+    pub async fn create(
+        db: &DatabaseConnection,
+        mid: i32,
+        name: &str,
+        scopes: &[ApiScope],
+        ttl: Option<Duration>,
+    ) -> Result<IssuedApiKey> {
+        let expires_gmt = ttl.map_or(0, |ttl| expires_after(now_gmt(), ttl));
+        Self::issue(db, mid, name, &ApiScope::join(scopes), expires_gmt).await
+    }
+
+    /// Keys of merchant `mid`, newest first
+    pub async fn list(db: &DatabaseConnection, mid: i32) -> Result<Vec<MerchantApiKey>> {
+        let keys = MerchantApiKeys::find()
+            .filter(Column::Mid.eq(mid))
+            .order_by_desc(Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(keys)
+    }
+
+    /// Replace key `id` with a new one; the old key keeps working for `grace`
+    pub async fn rotate(
+        db: &DatabaseConnection,
+        mid: i32,
+        id: i32,
+        grace: Duration,
+    ) -> Result<IssuedApiKey> {
+        let txn = db.begin().await?;
+        let old = MerchantApiKeys::find_by_id(id)
+            .filter(Column::Mid.eq(mid))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .filter(|key| is_active(key, now_gmt()) && key.replaced_by == 0)
+            .ok_or(MerchantError::ApiKeyNotFound)?;
+
+        let now = now_gmt();
+        // 🤓 the replacement gets the same lifetime the old key was issued with
+        let expires_gmt = match old.expires_gmt {
+            0 => 0,
+            expires => now.saturating_add(expires - old.created_gmt),
+        };
+        let issued = Self::issue(&txn, mid, &old.name, &old.scopes, expires_gmt).await?;
+
+        let retire_at = expires_after(now, grace);
+        let mut retired: merchant_api_keys::ActiveModel = old.clone().into();
+        retired.replaced_by = Set(issued.record.id);
+        if old.expires_gmt == 0 || old.expires_gmt > retire_at {
+            retired.expires_gmt = Set(retire_at);
+        }
+        retired.update(&txn).await?;
+
+        txn.commit().await?;
+        Ok(issued)
+    }
+
+    /// Revoke key `id` immediately
+    pub async fn revoke(db: &DatabaseConnection, mid: i32, id: i32) -> Result<()> {
+        let result = MerchantApiKeys::update_many()
+            .col_expr(Column::RevokedGmt, Expr::value(now_gmt()))
+            .filter(Column::Id.eq(id))
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::RevokedGmt.eq(0))
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(MerchantError::ApiKeyNotFound);
+        }
+        Ok(())
+    }
+
+    /// The active key matching `key`, recording that it was used
+    pub async fn authenticate(db: &DatabaseConnection, key: &str) -> Result<MerchantApiKey> {
+        if !key.starts_with(KEY_PREFIX) {
+            return Err(MerchantError::InvalidApiKey);
+        }
+
+        let now = now_gmt();
+        let mut record = MerchantApiKeys::find()
+            .filter(Column::KeyHash.eq(hash_key(key)))
+            .one(db)
+            .await?
+            .filter(|record| is_active(record, now))
+            .ok_or(MerchantError::InvalidApiKey)?;
+
+        // 🤓 busy integrations would otherwise write this row on every request
+        if record.last_used_gmt <= now - TOUCH_INTERVAL {
+            MerchantApiKeys::update_many()
+                .col_expr(Column::LastUsedGmt, Expr::value(now))
+                .filter(Column::Id.eq(record.id))
+                .filter(Column::LastUsedGmt.lte(now - TOUCH_INTERVAL))
+                .exec(db)
+                .await?;
+            record.last_used_gmt = now;
+        }
+
+        Ok(record)
+    }
+
+    async fn issue<C: ConnectionTrait>(
+        db: &C,
+        mid: i32,
+        name: &str,
+        scopes: &str,
+        expires_gmt: i32,
+    ) -> Result<IssuedApiKey> {
+        let key = generate_key();
+
+        let record = merchant_api_keys::ActiveModel {
+            mid: Set(mid),
+            name: Set(name.chars().take(60).collect()),
+            key_prefix: Set(key[..DISPLAY_LEN].to_string()),
+            key_hash: Set(hash_key(&key)),
+            scopes: Set(scopes.to_string()),
+            created_gmt: Set(now_gmt()),
+            expires_gmt: Set(expires_gmt),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(IssuedApiKey { key, record })
+    }
+}
+
+fn is_active(key: &MerchantApiKey, now: i32) -> bool {
+    key.revoked_gmt == 0 && (key.expires_gmt == 0 || key.expires_gmt > now)
+}
+
+/// 256 random bits, URL-safe, behind a recognisable prefix
+fn generate_key() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{}{}", KEY_PREFIX, URL_SAFE_NO_PAD.encode(bytes))
+}
+
+fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+fn expires_after(now: i32, ttl: Duration) -> i32 {
+    now.saturating_add(i32::try_from(ttl.as_secs()).unwrap_or(i32::MAX))
+}
+
+fn now_gmt() -> i32 {
+    Utc::now().timestamp() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn record(key: &str) -> MerchantApiKey {
+        MerchantApiKey {
+            id: 1,
+            mid: 1,
+            name: "erp".to_string(),
+            key_prefix: key[..DISPLAY_LEN].to_string(),
+            key_hash: hash_key(key),
+            scopes: "catalog:read,orders:write".to_string(),
+            created_gmt: now_gmt() - 3600,
+            expires_gmt: 0,
+            last_used_gmt: 0,
+            revoked_gmt: 0,
+            replaced_by: 0,
+        }
+    }
+
+    #[test]
+    fn test_scopes_round_trip() {
+        let scopes = ApiScope::parse_list("orders:write, catalog:read,orders:write").unwrap();
+        assert_eq!(scopes, [ApiScope::OrdersWrite, ApiScope::CatalogRead]);
+        assert_eq!(ApiScope::join(&scopes), "orders:write,catalog:read");
+        assert!(matches!(
+            ApiScope::parse_list("catalog:delete"),
+            Err(MerchantError::UnknownScope(s)) if s == "catalog:delete"
+        ));
+    }
+
+    #[test]
+    fn test_keys_are_prefixed_and_hashed() {
+        let key = generate_key();
+        assert!(key.starts_with("crk_"));
+        assert_eq!(key.len(), 47);
+        assert_ne!(generate_key(), key);
+        assert_eq!(hash_key(&key).len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_records_last_use() {
+        let key = generate_key();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![record(&key)]])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .into_connection();
+
+        let found = ApiKeyService::authenticate(&db, &key).await.unwrap();
+        assert_eq!(ApiScope::of(&found), [ApiScope::CatalogRead, ApiScope::OrdersWrite]);
+        assert!(found.last_used_gmt > 0);
+
+        let log = db.into_transaction_log();
+        assert!(log[1].statements()[0].sql.contains(r#""last_used_gmt""#));
+    }
+
+    #[tokio::test]
+    async fn test_recently_used_key_is_not_rewritten() {
+        let key = generate_key();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![MerchantApiKey { last_used_gmt: now_gmt() - 5, ..record(&key) }]])
+            .into_connection();
+
+        ApiKeyService::authenticate(&db, &key).await.unwrap();
+        assert_eq!(db.into_transaction_log().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_expired_and_revoked_keys_are_rejected() {
+        let key = generate_key();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![MerchantApiKey { expires_gmt: now_gmt() - 1, ..record(&key) }]])
+            .append_query_results([vec![MerchantApiKey { revoked_gmt: now_gmt() - 1, ..record(&key) }]])
+            .into_connection();
+
+        for _ in 0..2 {
+            let err = ApiKeyService::authenticate(&db, &key).await.unwrap_err();
+            assert!(matches!(err, MerchantError::InvalidApiKey));
+        }
+        let err = ApiKeyService::authenticate(&db, "not-a-key").await.unwrap_err();
+        assert!(matches!(err, MerchantError::InvalidApiKey));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_keeps_old_key_for_grace_period() {
+        let old_key = generate_key();
+        let new_key = generate_key();
+        let old = record(&old_key);
+        let new = MerchantApiKey { id: 2, created_gmt: now_gmt(), ..record(&new_key) };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![old.clone()]])
+            .append_query_results([vec![new]])
+            .append_query_results([vec![MerchantApiKey { replaced_by: 2, ..old }]])
+            .into_connection();
+
+        let issued = ApiKeyService::rotate(&db, 1, 1, Duration::from_secs(3600)).await.unwrap();
+        assert_eq!(issued.record.id, 2);
+
+        let log = db.into_transaction_log();
+        let txn = log[0].statements();
+        assert!(txn[1].sql.contains("FOR UPDATE"), "{}", txn[1].sql);
+        assert!(txn[2].sql.starts_with(r#"INSERT INTO "merchant_api_keys""#));
+        let retire = txn[3].to_string();
+        assert!(retire.contains(r#""replaced_by" = 2"#), "{retire}");
+        assert!(retire.contains(r#""expires_gmt""#), "{retire}");
+    }
+}
@@ -0,0 +1,37 @@
+//! Merchant service error types
+
+use sea_orm::{DbErr, SqlErr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MerchantError {
+    #[error("Merchant not found")]
+    NotFound,
+
+    #[error("Merchant {0} already exists")]
+    DuplicateUsername(String),
+
+    #[error("API key not found")]
+    ApiKeyNotFound,
+
+    #[error("API key is invalid, expired or revoked")]
+    InvalidApiKey,
+
+    #[error("Unknown API scope {0:?}")]
+    UnknownScope(String),
+
+    #[error(transparent)]
+    Db(#[from] DbErr),
+}
+
+impl MerchantError {
+    /// Map a unique violation on insert to [`MerchantError::DuplicateUsername`]
+    pub(crate) fn from_write(err: DbErr, username: &str) -> Self {
+        match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => Self::DuplicateUsername(username.to_string()),
+            _ => Self::Db(err),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, MerchantError>;
@@ -0,0 +1,45 @@
+//! Merchant accounts (`zusers`) and their API keys
+
+use ::entity::prelude::*;
+use ::entity::zusers::{self, Column};
+use chrono::Utc;
+use sea_orm::*;
+
+pub mod api_key;
+pub mod error;
+
+pub use api_key::{ApiKeyService, ApiScope, IssuedApiKey};
+pub use error::{MerchantError, Result};
+
+/// Merchant account service
+pub struct MerchantService;
+
+impl MerchantService {
+    /// Create a merchant account
+    pub async fn create(db: &DatabaseConnection, username: &str, email: &str) -> Result<Zuser> {
+        zusers::ActiveModel {
+            username: Set(username.to_string()),
+            email: Set(email.to_string()),
+            created: Set(Some(Utc::now().naive_utc())),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(|e| MerchantError::from_write(e, username))
+    }
+
+    /// Find merchant by ID
+    pub async fn find_by_id(db: &DatabaseConnection, mid: i32) -> Result<Option<Zuser>> {
+        Ok(Zusers::find_by_id(mid).one(db).await?)
+    }
+
+    /// Find merchant by username
+    pub async fn find_by_username(db: &DatabaseConnection, username: &str) -> Result<Option<Zuser>> {
+        let merchant = Zusers::find()
+            .filter(Column::Username.eq(username))
+            .one(db)
+            .await?;
+
+        Ok(merchant)
+    }
+}
@@ -0,0 +1,283 @@
+//! Order ID allocation from `order_counters`
+//!
+//! Each merchant has one counter row. Incrementing it is a single
+//! `UPDATE ... RETURNING`, so concurrent checkouts on any server get distinct
+//! numbers; the row lock is held until the caller's transaction ends, and a
+//! rolled-back order gives its number back.
+//!
+//! IDs are rendered from the merchant's template, where `{YYYY}`, `{YY}`,
+//! `{MM}` and `{DD}` are the allocation date (UTC), `{MID}` the merchant and
+//! `{N}` the counter, zero-padded with `{N:5}`.
+
+use ::entity::order_counters::{ActiveModel, Column};
+use ::entity::prelude::{OrderCounter, OrderCounters, Zusers};
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{Expr, Func, OnConflict};
+use sea_orm::*;
+use std::fmt::Write;
+use std::sync::OnceLock;
+
+use crate::error::{OrderError, Result};
+
+/// Legacy CommerceRack order IDs, e.g. `2025-01-00042`
+pub const DEFAULT_FORMAT: &str = "{YYYY}-{MM}-{N:5}";
+
+/// `orders.orderid` is varchar(30)
+const MAX_ORDERID_LEN: usize = 30;
+
+/// Allocates per-merchant sequential order IDs
+pub struct OrderIdAllocator;
+
+impl OrderIdAllocator {
+    /// Next order ID for merchant `mid`
+    pub async fn next<C: ConnectionTrait>(db: &C, mid: i32) -> Result<String> {
+        let row = Self::increment(db, mid).await?;
+        let format = match row.orderid_format.as_str() {
+            "" => DEFAULT_FORMAT,
+            format => format,
+        };
+
+        render_orderid(format, Utc::now(), mid, row.counter.unwrap_or_default())
+    }
+
+    /// Use `format` for the merchant's future order IDs; empty restores the
+    /// legacy format
+    pub async fn set_format<C: ConnectionTrait>(db: &C, mid: i32, format: &str) -> Result<()> {
+        if !format.is_empty() {
+            validate_format(format)?;
+        }
+
+        OrderCounters::insert(ActiveModel {
+            mid: Set(mid),
+            merchant: Set(merchant_name(db, mid).await?),
+            counter: Set(Some(0)),
+            orderid_format: Set(format.to_string()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(Column::Mid)
+                .update_column(Column::OrderidFormat)
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Bump the merchant's counter, creating the row on first use
+    async fn increment<C: ConnectionTrait>(db: &C, mid: i32) -> Result<OrderCounter> {
+        let next = Expr::expr(Func::coalesce([
+            Expr::col((OrderCounters, Column::Counter)).into(),
+            Expr::val(0).into(),
+        ]))
+        .add(1);
+        let pid = std::process::id() as i32;
+
+        let updated = OrderCounters::update_many()
+            .col_expr(Column::Counter, next.clone())
+            .col_expr(Column::LastPid, Expr::value(pid))
+            .col_expr(Column::LastServer, Expr::value(server_name()))
+            .filter(Column::Mid.eq(mid))
+            .exec_with_returning(db)
+            .await?;
+        if let Some(row) = updated.into_iter().next() {
+            return Ok(row);
+        }
+
+        let row = OrderCounters::insert(ActiveModel {
+            mid: Set(mid),
+            merchant: Set(merchant_name(db, mid).await?),
+            counter: Set(Some(1)),
+            last_pid: Set(Some(pid)),
+            last_server: Set(server_name().to_string()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(Column::Mid)
+                .value(Column::Counter, next)
+                .update_columns([Column::LastPid, Column::LastServer])
+                .to_owned(),
+        )
+        .exec_with_returning(db)
+        .await?;
+
+        Ok(row)
+    }
+}
+
+/// Name for a new counter row; `order_counters.merchant` is unique, so it
+/// can't be left blank
+async fn merchant_name<C: ConnectionTrait>(db: &C, mid: i32) -> Result<String> {
+    let name = Zusers::find_by_id(mid)
+        .one(db)
+        .await?
+        .map(|z| z.username)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| mid.to_string());
+
+    Ok(name)
+}
+
+/// Host recorded in `order_counters.last_server`
+fn server_name() -> &'static str {
+    static NAME: OnceLock<String> = OnceLock::new();
+    NAME.get_or_init(|| {
+        std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            // 🤓 legacy column width: last_server varchar(25)
+            .map(|name| name.trim().chars().take(25).collect())
+            .unwrap_or_default()
+    })
+}
+
+/// Check that `format` renders every possible counter into a valid order ID
+pub fn validate_format(format: &str) -> Result<()> {
+    render_orderid(format, Utc::now(), i32::MAX, i32::MAX).map(|_| ())
+}
+
+/// Render the order ID for `counter` from `format`
+pub fn render_orderid(format: &str, at: DateTime<Utc>, mid: i32, counter: i32) -> Result<String> {
+    let invalid = |reason| OrderError::InvalidFormat {
+        format: format.to_string(),
+        reason,
+    };
+
+    let mut orderid = String::new();
+    let mut has_counter = false;
+    let mut rest = format;
+    while let Some(open) = rest.find('{') {
+        orderid.push_str(&rest[..open]);
+        let close = rest[open..].find('}').ok_or(invalid("unclosed '{'"))? + open;
+
+        // 🤓 writing to a String cannot fail
+        let _ = match &rest[open + 1..close] {
+            "YYYY" => write!(orderid, "{}", at.format("%Y")),
+            "YY" => write!(orderid, "{}", at.format("%y")),
+            "MM" => write!(orderid, "{}", at.format("%m")),
+            "DD" => write!(orderid, "{}", at.format("%d")),
+            "MID" => write!(orderid, "{}", mid),
+            "N" => {
+                has_counter = true;
+                write!(orderid, "{}", counter)
+            }
+            token => {
+                let width = token
+                    .strip_prefix("N:")
+                    .and_then(|w| w.parse::<usize>().ok())
+                    .filter(|w| (1..=10).contains(w))
+                    .ok_or(invalid("unknown placeholder"))?;
+                has_counter = true;
+                write!(orderid, "{:0width$}", counter, width = width)
+            }
+        };
+        rest = &rest[close + 1..];
+    }
+    orderid.push_str(rest);
+
+    if !has_counter {
+        return Err(invalid("must contain {N}"));
+    }
+    if orderid.len() > MAX_ORDERID_LEN {
+        return Err(invalid("renders longer than 30 characters"));
+    }
+    Ok(orderid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    fn counter(value: i32, format: &str) -> OrderCounter {
+        OrderCounter {
+            mid: 1,
+            merchant: "demo".to_string(),
+            counter: Some(value),
+            last_pid: Some(0),
+            last_server: String::new(),
+            orderid_format: format.to_string(),
+        }
+    }
+
+    fn january() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_default_format_is_legacy() {
+        assert_eq!(render_orderid(DEFAULT_FORMAT, january(), 1, 42).unwrap(), "2025-01-00042");
+        assert_eq!(render_orderid(DEFAULT_FORMAT, january(), 1, 123_456).unwrap(), "2025-01-123456");
+    }
+
+    #[test]
+    fn test_custom_formats() {
+        assert_eq!(render_orderid("WEB{YY}{MM}{DD}-{N}", january(), 1, 7).unwrap(), "WEB250115-7");
+        assert_eq!(render_orderid("{MID}/{N:8}", january(), 12, 7).unwrap(), "12/00000007");
+    }
+
+    #[test]
+    fn test_invalid_formats() {
+        for format in ["{YYYY}-{MM}", "{N", "{N:0}", "{N:x}", "{SKU}-{N}", "ORDER-NUMBER-{YYYY}{MM}{DD}-{N:9}"] {
+            assert!(
+                matches!(validate_format(format), Err(OrderError::InvalidFormat { .. })),
+                "{format} should be rejected"
+            );
+        }
+        assert!(validate_format(DEFAULT_FORMAT).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_next_increments_existing_counter() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![counter(42, "")]])
+            .into_connection();
+
+        let orderid = OrderIdAllocator::next(&db, 1).await.unwrap();
+        assert!(orderid.ends_with("-00042"), "{orderid}");
+
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 1);
+        let sql = &log[0].statements()[0].sql;
+        assert!(sql.starts_with(r#"UPDATE "order_counters""#), "{sql}");
+        assert!(sql.contains(r#""last_server""#), "{sql}");
+        assert!(sql.contains("RETURNING"), "{sql}");
+    }
+
+    #[tokio::test]
+    async fn test_next_uses_merchant_format() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![counter(7, "WEB-{N:4}")]])
+            .into_connection();
+
+        assert_eq!(OrderIdAllocator::next(&db, 1).await.unwrap(), "WEB-0007");
+    }
+
+    #[tokio::test]
+    async fn test_next_creates_counter_for_new_merchant() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<OrderCounter>::new()])
+            .append_query_results([Vec::<::entity::prelude::Zuser>::new()])
+            .append_query_results([vec![counter(1, "")]])
+            .into_connection();
+
+        let orderid = OrderIdAllocator::next(&db, 1).await.unwrap();
+        assert!(orderid.ends_with("-00001"), "{orderid}");
+
+        let log = db.into_transaction_log();
+        let sql = &log[2].statements()[0].sql;
+        assert!(sql.starts_with(r#"INSERT INTO "order_counters""#), "{sql}");
+        assert!(sql.contains("ON CONFLICT"), "{sql}");
+    }
+
+    #[tokio::test]
+    async fn test_set_format_rejects_invalid_template() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+        let err = OrderIdAllocator::set_format(&db, 1, "{YYYY}").await.unwrap_err();
+        assert!(matches!(err, OrderError::InvalidFormat { .. }));
+        assert!(db.into_transaction_log().is_empty());
+    }
+}
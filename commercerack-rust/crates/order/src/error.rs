@@ -0,0 +1,39 @@
+//! Order service error types
+
+use ::entity::sea_orm_active_enums::OrderPool;
+use commercerack_db::pagination::CursorError;
+use sea_orm::{DbErr, SqlErr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum OrderError {
+    #[error("Order not found")]
+    NotFound,
+
+    #[error("Order {0} already exists")]
+    DuplicateOrder(String),
+
+    #[error("Order cannot move from {from} to {to}")]
+    IllegalTransition { from: OrderPool, to: OrderPool },
+
+    #[error("Invalid order ID format {format:?}: {reason}")]
+    InvalidFormat { format: String, reason: &'static str },
+
+    #[error(transparent)]
+    InvalidCursor(#[from] CursorError),
+
+    #[error(transparent)]
+    Db(#[from] DbErr),
+}
+
+impl OrderError {
+    /// Map a unique violation on insert to [`OrderError::DuplicateOrder`]
+    pub(crate) fn from_write(err: DbErr, orderid: &str) -> Self {
+        match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => Self::DuplicateOrder(orderid.to_string()),
+            _ => Self::Db(err),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, OrderError>;
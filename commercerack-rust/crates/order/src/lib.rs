@@ -1,42 +1,116 @@
 //! Order management module using SeaORM
 
-use anyhow::Result;
 use chrono::Utc;
-use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr, Set};
-use ::entity::prelude::{Orders, Order as OrderModel};
+use sea_orm::{entity::*, query::*, ConnectionTrait, DatabaseConnection, DbErr, Set};
+use ::entity::prelude::{Orders, Order as OrderModel, OrderEvent};
 use rust_decimal::Decimal;
+use commercerack_db::pagination::{Cursor, Page, PageRequest, SortDirection};
+
+pub mod counter;
+pub mod error;
+pub mod pool;
+pub mod query;
+
+pub use counter::OrderIdAllocator;
+pub use error::{OrderError, Result};
+pub use pool::OrderPool;
+pub use query::{OrderFilter, OrderSort};
+
+/// Fields of an order about to be inserted
+#[derive(Debug, Clone)]
+pub struct NewOrder {
+    pub mid: i32,
+    pub orderid: String,
+    pub cartid: String,
+    pub customer: i32,
+    pub pool: OrderPool,
+    pub total: Decimal,
+    /// Units across all lines
+    pub items: i16,
+    pub bill_name: String,
+    pub bill_email: String,
+    pub bill_phone: String,
+    pub bill_zone: String,
+    pub ship_name: String,
+    pub ship_zone: String,
+    pub sdomain: Option<String>,
+    /// Full order document (lines, addresses, totals)
+    pub yaml: String,
+}
 
 /// Order service for managing order operations
 pub struct OrderService;
 
 impl OrderService {
-    /// Create new order
+    /// Create new order under the merchant's next order ID
     pub async fn create(
         db: &DatabaseConnection,
         mid: i32,
-        orderid: &str,
         cartid: &str,
         customer: i32,
-        pool: &str,
+        pool: OrderPool,
         total: Decimal,
     ) -> Result<OrderModel> {
+        let txn = db.begin().await?;
+        let order = NewOrder {
+            mid,
+            orderid: OrderIdAllocator::next(&txn, mid).await?,
+            cartid: cartid.to_string(),
+            customer,
+            pool,
+            total,
+            items: 0,
+            bill_name: String::new(),
+            bill_email: String::new(),
+            bill_phone: String::new(),
+            bill_zone: String::new(),
+            ship_name: String::new(),
+            ship_zone: String::new(),
+            sdomain: None,
+            yaml: String::new(),
+        };
+
+        let order = Self::insert(&txn, order).await?;
+        txn.commit().await?;
+        Ok(order)
+    }
+
+    /// Insert `order`, on a connection or inside a caller's transaction
+    ///
+    /// A second order for the same `orderid` or `cartid` is rejected with
+    /// [`OrderError::DuplicateOrder`].
+    pub async fn insert<C: ConnectionTrait>(db: &C, order: NewOrder) -> Result<OrderModel> {
         let now = Utc::now().timestamp() as i32;
+        let orderid = order.orderid;
 
-        let order = ::entity::orders::ActiveModel {
-            mid: Set(mid),
-            orderid: Set(orderid.to_string()),
-            cartid: Set(cartid.to_string()),
-            customer: Set(customer),
-            pool: Set(pool.to_string()),
-            total: Set(total),
+        let active = ::entity::orders::ActiveModel {
+            mid: Set(order.mid),
+            orderid: Set(orderid.clone()),
+            cartid: Set(Some(order.cartid)),
+            customer: Set(order.customer),
+            pool: Set(order.pool),
+            order_total: Set(order.total),
+            items: Set(order.items),
             created_gmt: Set(now),
-            paid_gmt: Set(None),
-            shipped_gmt: Set(None),
+            modified_gmt: Set(now),
+            // 🤓 legacy summary column widths; the yaml keeps the full values
+            order_bill_name: Set(order.bill_name.chars().take(30).collect()),
+            order_bill_email: Set(order.bill_email.chars().take(30).collect()),
+            order_bill_phone: Set(order.bill_phone.chars().take(12).collect()),
+            order_bill_zone: Set(order.bill_zone.chars().take(12).collect()),
+            order_ship_name: Set(order.ship_name.chars().take(30).collect()),
+            order_ship_zone: Set(order.ship_zone.chars().take(12).collect()),
+            order_payment_status: Set(String::new()),
+            mkt: Set(Some(0)),
+            sdomain: Set(order.sdomain),
+            yaml: Set(order.yaml),
             ..Default::default()
         };
 
-        let result = order.insert(db).await?;
-        Ok(result)
+        active
+            .insert(db)
+            .await
+            .map_err(|e| OrderError::from_write(e, &orderid))
     }
 
     /// Find order by ID
@@ -84,20 +158,24 @@ impl OrderService {
         Ok(order)
     }
 
-    /// List orders by customer
+    /// List a customer's orders, newest first, optionally only those created before `before`
     pub async fn list_by_customer(
         db: &DatabaseConnection,
         mid: i32,
         customer: i32,
         limit: u64,
-        offset: u64,
+        before: Option<i32>,
     ) -> Result<Vec<OrderModel>> {
-        let orders = Orders::find()
+        let mut query = Orders::find()
             .filter(::entity::orders::Column::Mid.eq(mid))
-            .filter(::entity::orders::Column::Customer.eq(customer))
+            .filter(::entity::orders::Column::Customer.eq(customer));
+        if let Some(before) = before {
+            query = query.filter(::entity::orders::Column::CreatedGmt.lt(before));
+        }
+
+        let orders = query
             .order_by_desc(::entity::orders::Column::CreatedGmt)
             .limit(limit)
-            .offset(offset)
             .all(db)
             .await?;
 
@@ -108,7 +186,7 @@ impl OrderService {
     pub async fn list_by_pool(
         db: &DatabaseConnection,
         mid: i32,
-        pool: &str,
+        pool: OrderPool,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<OrderModel>> {
@@ -124,14 +202,132 @@ impl OrderService {
         Ok(orders)
     }
 
-    /// Update order
+    /// Search orders matching `filter`, one keyset page at a time
+    ///
+    /// Rows are ordered by the requested sort column with `id` as the
+    /// tiebreaker, so paging stays stable while new orders arrive.
+    pub async fn search(
+        db: &DatabaseConnection,
+        mid: i32,
+        filter: &OrderFilter,
+        page: &PageRequest<OrderSort>,
+    ) -> Result<Page<OrderModel>> {
+        use ::entity::orders::Column;
+
+        let sort = page.sort;
+        let after = page
+            .cursor
+            .as_deref()
+            .map(|token| Cursor::decode(token, sort.as_str()))
+            .transpose()?;
+
+        let condition = filter.condition(mid);
+        let total = Orders::find()
+            .filter(condition.clone())
+            .count(db)
+            .await?;
+        let page_size = page.page_size();
+
+        let select = Orders::find().filter(condition);
+        let rows = match sort.column() {
+            None => {
+                let mut cursor = select.cursor_by(Column::Id);
+                if let Some(after) = &after {
+                    cursor.after(after.id as i32);
+                }
+                apply_direction(&mut cursor, page.direction);
+                cursor.first(page_size + 1).all(db).await?
+            }
+            Some(column) => {
+                let mut cursor = select.cursor_by((column, Column::Id));
+                if let Some(after) = &after {
+                    cursor.after((sort.key_value(after)?, after.id as i32));
+                }
+                apply_direction(&mut cursor, page.direction);
+                cursor.first(page_size + 1).all(db).await?
+            }
+        };
+
+        Ok(Page::from_rows(rows, page_size, total, |o| {
+            Cursor::new(sort.as_str(), sort.key_of(o), o.id as i64)
+        }))
+    }
+
+    /// Move an order to another pool, recording who moved it and why
+    ///
+    /// The order row is locked for the duration of the move and the pool
+    /// change and its `order_events` row commit together.
+    pub async fn transition(
+        db: &DatabaseConnection,
+        mid: i32,
+        id: i32,
+        to: OrderPool,
+        actor: &str,
+        reason: &str,
+    ) -> Result<(OrderModel, OrderEvent)> {
+        let txn = db.begin().await?;
+
+        let order = Orders::find()
+            .filter(::entity::orders::Column::Mid.eq(mid))
+            .filter(::entity::orders::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(OrderError::NotFound)?;
+
+        let from = order.pool;
+        if !pool::can_transition(from, to) {
+            return Err(OrderError::IllegalTransition { from, to });
+        }
+
+        let now = Utc::now().timestamp() as i32;
+        let orderid = order.orderid.clone();
+        let mut active: ::entity::orders::ActiveModel = order.into();
+        active.pool = Set(to);
+        active.modified_gmt = Set(now);
+        let order = active.update(&txn).await?;
+
+        let note = if reason.is_empty() {
+            format!("{} -> {}", from, to)
+        } else {
+            format!("{} -> {}: {}", from, to, reason)
+        };
+        let event = ::entity::order_events::ActiveModel {
+            created_gmt: Set(now),
+            mid: Set(mid),
+            // 🤓 legacy column widths: username varchar(20), event varchar(10)
+            username: Set(actor.chars().take(20).collect()),
+            prt: Set(0),
+            orderid: Set(orderid),
+            event: Set(to.as_str().to_string()),
+            lock_id: Set(0),
+            lock_gmt: Set(0),
+            attempts: Set(0),
+            note: Set(Some(note)),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        txn.commit().await?;
+        Ok((order, event))
+    }
+
+    /// Update an order of merchant `mid`
     pub async fn update(
         db: &DatabaseConnection,
+        mid: i32,
         order: OrderModel,
     ) -> Result<OrderModel> {
         let active: ::entity::orders::ActiveModel = order.into();
-        let result = active.update(db).await?;
-        Ok(result)
+        Orders::update(active)
+            .filter(::entity::orders::Column::Mid.eq(mid))
+            .exec(db)
+            .await
+            .map_err(|e| match e {
+                DbErr::RecordNotUpdated => OrderError::NotFound,
+                e => e.into(),
+            })
     }
 
     /// Mark order as paid
@@ -141,10 +337,10 @@ impl OrderService {
         id: i32,
     ) -> Result<OrderModel> {
         let order = Self::find_by_id(db, mid, id).await?
-            .ok_or_else(|| anyhow::anyhow!("Order not found"))?;
+            .ok_or(OrderError::NotFound)?;
 
         let mut active: ::entity::orders::ActiveModel = order.into();
-        active.paid_gmt = Set(Some(Utc::now().timestamp() as i32));
+        active.paid_gmt = Set(Utc::now().timestamp() as i32);
 
         let result = active.update(db).await?;
         Ok(result)
@@ -157,10 +353,10 @@ impl OrderService {
         id: i32,
     ) -> Result<OrderModel> {
         let order = Self::find_by_id(db, mid, id).await?
-            .ok_or_else(|| anyhow::anyhow!("Order not found"))?;
+            .ok_or(OrderError::NotFound)?;
 
         let mut active: ::entity::orders::ActiveModel = order.into();
-        active.shipped_gmt = Set(Some(Utc::now().timestamp() as i32));
+        active.shipped_gmt = Set(Utc::now().timestamp() as i32);
 
         let result = active.update(db).await?;
         Ok(result)
@@ -182,10 +378,120 @@ impl OrderService {
     }
 }
 
+fn apply_direction<S: sea_orm::SelectorTrait>(cursor: &mut sea_orm::Cursor<S>, direction: SortDirection) {
+    match direction {
+        SortDirection::Asc => cursor.asc(),
+        SortDirection::Desc => cursor.desc(),
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    fn order(pool: OrderPool) -> OrderModel {
+        OrderModel {
+            id: 9,
+            merchant: "demo".to_string(),
+            mid: 1,
+            prt: 0,
+            orderid: "2025-01-00009".to_string(),
+            bs_settlement: 0,
+            v: Some(0),
+            created_gmt: 1_700_000_000,
+            modified_gmt: 1_700_000_000,
+            paid_gmt: 0,
+            paid_txn: String::new(),
+            inv_gmt: 0,
+            shipped_gmt: 0,
+            synced_gmt: 0,
+            customer: 7,
+            pool,
+            order_bill_name: String::new(),
+            order_bill_email: String::new(),
+            order_bill_zone: String::new(),
+            order_bill_phone: String::new(),
+            order_ship_name: String::new(),
+            order_ship_zone: String::new(),
+            review_status: String::new(),
+            order_payment_status: String::new(),
+            order_payment_method: String::new(),
+            order_payment_lookup: String::new(),
+            order_erefid: None,
+            order_total: Decimal::new(1999, 2),
+            order_special: String::new(),
+            ship_method: String::new(),
+            mkt: Some(0),
+            mkt_bitstr: String::new(),
+            flags: 0,
+            items: 1,
+            yaml: String::new(),
+            cartid: Some("CART9".to_string()),
+            sdomain: None,
+        }
+    }
 
-    // Tests will be added when we have a test database setup
-    // For now, compilation success validates the API design
+    fn event(note: &str) -> OrderEvent {
+        OrderEvent {
+            id: 1,
+            created_gmt: 1_700_000_100,
+            mid: 1,
+            username: "admin".to_string(),
+            prt: 0,
+            orderid: "2025-01-00009".to_string(),
+            event: "HOLD".to_string(),
+            lock_id: 0,
+            lock_gmt: 0,
+            attempts: 0,
+            note: Some(note.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transition_updates_pool_and_records_event() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![order(OrderPool::Recent)]])
+            .append_query_results([vec![order(OrderPool::Hold)]])
+            .append_query_results([vec![event("RECENT -> HOLD: fraud check")]])
+            .into_connection();
+        let started = Utc::now().timestamp() as i32;
+
+        let (order, event) = OrderService::transition(&db, 1, 9, OrderPool::Hold, "admin", "fraud check")
+            .await
+            .unwrap();
+        assert_eq!(order.pool, OrderPool::Hold);
+        assert_eq!(event.event, "HOLD");
+
+        // One transaction: locked select, pool update, event insert
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 1);
+        let statements: Vec<String> = log[0].statements().iter().map(|s| s.sql.clone()).collect();
+        assert_eq!(statements.len(), 5, "{statements:?}");
+        assert!(statements[1].ends_with("FOR UPDATE"));
+        assert!(statements[2].starts_with(r#"UPDATE "orders""#));
+        assert!(statements[2].contains(r#""modified_gmt" = "#), "{}", statements[2]);
+        let stamped = log[0].statements()[2]
+            .values
+            .iter()
+            .flat_map(|v| v.0.iter())
+            .any(|v| matches!(v, sea_orm::Value::Int(Some(gmt)) if *gmt >= started));
+        assert!(stamped, "{:?}", log[0].statements()[2]);
+        assert!(statements[3].starts_with(r#"INSERT INTO "order_events""#));
+    }
+
+    #[tokio::test]
+    async fn test_illegal_transition_is_rejected() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![order(OrderPool::Completed)]])
+            .into_connection();
+
+        let err = OrderService::transition(&db, 1, 9, OrderPool::Recent, "admin", "")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OrderError::IllegalTransition { from: OrderPool::Completed, to: OrderPool::Recent }
+        ));
+    }
 }
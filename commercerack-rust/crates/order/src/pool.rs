@@ -0,0 +1,64 @@
+//! Order pool transition table
+//!
+//! Orders only move along the edges listed here; anything else (for
+//! example `COMPLETED` back to `RECENT`) is rejected by
+//! [`crate::OrderService::transition`].
+
+pub use ::entity::sea_orm_active_enums::OrderPool;
+
+/// Pools an order in `from` may be moved to
+pub fn allowed_transitions(from: OrderPool) -> &'static [OrderPool] {
+    use OrderPool::*;
+
+    match from {
+        Quote => &[Recent, Pending, Deleted, Archive],
+        Recent => &[
+            Review, Hold, Pending, Approved, Process, Backorder, Preorder, Completed, Deleted,
+        ],
+        Review | Hold | Pending => &[
+            Recent, Review, Hold, Pending, Approved, Process, Deleted,
+        ],
+        Approved => &[Hold, Process, Backorder, Preorder, Completed, Deleted],
+        Process => &[Hold, Backorder, Completed, Deleted],
+        Backorder | Preorder => &[Hold, Approved, Process, Backorder, Preorder, Completed, Deleted],
+        Completed => &[Archive],
+        // 🤓 deleted orders can be restored for review, but never straight into fulfilment
+        Deleted => &[Recent, Archive],
+        Archive => &[],
+    }
+}
+
+/// Whether an order may move from `from` to `to`; staying put is not a move
+pub fn can_transition(from: OrderPool, to: OrderPool) -> bool {
+    from != to && allowed_transitions(from).contains(&to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::Iterable;
+
+    #[test]
+    fn test_completed_cannot_return_to_recent() {
+        assert!(!can_transition(OrderPool::Completed, OrderPool::Recent));
+        assert!(can_transition(OrderPool::Completed, OrderPool::Archive));
+    }
+
+    #[test]
+    fn test_no_self_transitions_and_archive_is_terminal() {
+        for pool in OrderPool::iter() {
+            assert!(!can_transition(pool, pool), "{pool} -> {pool}");
+            assert!(!can_transition(OrderPool::Archive, pool));
+        }
+    }
+
+    #[test]
+    fn test_every_pool_except_archive_can_leave() {
+        for pool in OrderPool::iter().filter(|p| *p != OrderPool::Archive) {
+            assert!(
+                OrderPool::iter().any(|to| can_transition(pool, to)),
+                "{pool} is a dead end"
+            );
+        }
+    }
+}
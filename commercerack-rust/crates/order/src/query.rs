@@ -0,0 +1,177 @@
+//! Order list filters and sort options
+
+use ::entity::orders::{Column, Model as OrderModel};
+use ::entity::sea_orm_active_enums::OrderPool;
+use commercerack_db::pagination::{Cursor, CursorError};
+use sea_orm::sea_query::{Expr, ExprTrait, LikeExpr};
+use sea_orm::{ColumnTrait, Condition, Value};
+use serde::{Deserialize, Serialize};
+
+/// Filters for [`crate::OrderService::search`]; unset fields match everything
+///
+/// Each filter leads with `mid` so Postgres can use the matching
+/// `idx_orders_mid_*` index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderFilter {
+    pub pool: Option<OrderPool>,
+    /// Customer ID
+    pub customer: Option<i32>,
+    pub created_from: Option<i32>,
+    pub created_to: Option<i32>,
+    pub paid_from: Option<i32>,
+    pub paid_to: Option<i32>,
+    pub shipped_from: Option<i32>,
+    pub shipped_to: Option<i32>,
+    /// Payment status code or prefix, e.g. `0` for every paid status
+    pub payment_status: Option<String>,
+    /// Exact billing email
+    pub bill_email: Option<String>,
+    /// Marketplace bitmask; matches orders synced to any of the given marketplaces
+    pub marketplace: Option<i32>,
+}
+
+impl OrderFilter {
+    /// SQL condition for this filter within merchant `mid`
+    pub fn condition(&self, mid: i32) -> Condition {
+        let mut cond = Condition::all().add(Column::Mid.eq(mid));
+
+        if let Some(pool) = self.pool {
+            cond = cond.add(Column::Pool.eq(pool));
+        }
+        if let Some(customer) = self.customer {
+            cond = cond.add(Column::Customer.eq(customer));
+        }
+
+        // 🤓 paid_gmt/shipped_gmt are 0 until set, so an upper bound on its own
+        // must not match every unpaid or unshipped order.
+        for (column, from, to, zero_is_unset) in [
+            (Column::CreatedGmt, self.created_from, self.created_to, false),
+            (Column::PaidGmt, self.paid_from, self.paid_to, true),
+            (Column::ShippedGmt, self.shipped_from, self.shipped_to, true),
+        ] {
+            if let Some(from) = from {
+                cond = cond.add(column.gte(from));
+            }
+            if let Some(to) = to {
+                cond = cond.add(column.lte(to));
+                if zero_is_unset && from.is_none() {
+                    cond = cond.add(column.gt(0));
+                }
+            }
+        }
+
+        if let Some(status) = self.payment_status.as_deref().filter(|s| !s.is_empty()) {
+            let pattern = format!("{}%", escape_like(status));
+            cond = cond.add(
+                Expr::col(Column::OrderPaymentStatus).like(LikeExpr::new(pattern).escape('\\')),
+            );
+        }
+        if let Some(email) = &self.bill_email {
+            cond = cond.add(Column::OrderBillEmail.eq(email.as_str()));
+        }
+        if let Some(mask) = self.marketplace.filter(|m| *m != 0) {
+            cond = cond.add(Expr::col(Column::Mkt).bit_and(mask).ne(0));
+        }
+
+        cond
+    }
+}
+
+/// Sort order for order listings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderSort {
+    /// Internal order ID (insertion order)
+    #[default]
+    Id,
+    Created,
+    Orderid,
+}
+
+impl OrderSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Created => "created",
+            Self::Orderid => "orderid",
+        }
+    }
+
+    /// Sort column ahead of the `id` tiebreaker, `None` when sorting by `id`
+    pub(crate) fn column(&self) -> Option<Column> {
+        match self {
+            Self::Id => None,
+            Self::Created => Some(Column::CreatedGmt),
+            Self::Orderid => Some(Column::Orderid),
+        }
+    }
+
+    /// Sort key of `order` as stored in a cursor
+    pub(crate) fn key_of(&self, order: &OrderModel) -> serde_json::Value {
+        match self {
+            Self::Id => order.id.into(),
+            Self::Created => order.created_gmt.into(),
+            Self::Orderid => order.orderid.clone().into(),
+        }
+    }
+
+    /// Sort key of `cursor` as a column value
+    pub(crate) fn key_value(&self, cursor: &Cursor) -> Result<Value, CursorError> {
+        Ok(match self {
+            Self::Id | Self::Created => {
+                let key = i32::try_from(cursor.key_i64()?).map_err(|_| CursorError::Malformed)?;
+                key.into()
+            }
+            Self::Orderid => cursor.key_str()?.to_string().into(),
+        })
+    }
+}
+
+/// Escape LIKE wildcards so user input matches literally
+fn escape_like(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
+
+    fn sql(filter: &OrderFilter) -> String {
+        ::entity::orders::Entity::find()
+            .filter(filter.condition(3))
+            .build(DbBackend::Postgres)
+            .to_string()
+    }
+
+    #[test]
+    fn test_empty_filter_scopes_by_merchant_only() {
+        assert!(sql(&OrderFilter::default()).ends_with(r#"WHERE "orders"."mid" = 3"#));
+    }
+
+    #[test]
+    fn test_filters_build_expected_predicates() {
+        let sql = sql(&OrderFilter {
+            pool: Some(OrderPool::Recent),
+            customer: Some(42),
+            paid_from: Some(100),
+            shipped_to: Some(200),
+            payment_status: Some("0".to_string()),
+            bill_email: Some("a@b.com".to_string()),
+            marketplace: Some(4),
+            ..Default::default()
+        });
+
+        assert!(sql.contains(r#""pool" = (CAST('RECENT' AS "order_pool_enum"))"#), "{sql}");
+        assert!(sql.contains(r#""customer" = 42"#));
+        assert!(sql.contains(r#""paid_gmt" >= 100"#));
+        assert!(sql.contains(r#""shipped_gmt" <= 200"#));
+        assert!(sql.contains(r#""shipped_gmt" > 0"#));
+        assert!(sql.contains(r#""order_payment_status" LIKE '0%'"#), "{sql}");
+        assert!(sql.contains(r#""order_bill_email" = 'a@b.com'"#));
+        assert!(sql.contains(r#"("mkt" & 4) <> 0"#), "{sql}");
+    }
+}
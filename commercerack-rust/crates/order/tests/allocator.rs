@@ -0,0 +1,97 @@
+//! Order ID allocation under concurrency
+//!
+//! Several connection pools stand in for several API servers, all
+//! allocating for the same merchant at once.
+//!
+//! Needs a Postgres server: `TEST_DATABASE_URL=postgres://user@host cargo test -p commercerack-order`
+
+use commercerack_order::OrderIdAllocator;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectionTrait, Database, TransactionTrait};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+const SERVERS: usize = 4;
+const ORDERS_PER_SERVER: usize = 25;
+
+/// Same server as `url`, different database
+fn with_database(url: &str, name: &str) -> String {
+    let (base, query) = url.split_once('?').map_or((url, None), |(b, q)| (b, Some(q)));
+    let authority = base.find("://").map_or(0, |i| i + 3);
+    let server = match base[authority..].find('/') {
+        Some(i) => &base[..authority + i],
+        None => base,
+    };
+    match query {
+        Some(q) => format!("{}/{}?{}", server, name, q),
+        None => format!("{}/{}", server, name),
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_allocation_is_gapless_and_unique() {
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("Skipping test: TEST_DATABASE_URL not set");
+        return;
+    };
+
+    let name = format!("order_allocator_{}", uuid::Uuid::new_v4().simple());
+    let admin = Database::connect(url.as_str()).await.expect("connect to server");
+    admin
+        .execute_unprepared(&format!("CREATE DATABASE {}", name))
+        .await
+        .expect("create throwaway database");
+    let db_url = with_database(&url, &name);
+
+    let db = Database::connect(db_url.as_str()).await.expect("connect to throwaway database");
+    Migrator::up(&db, None).await.expect("migrations apply");
+
+    let mut servers = Vec::new();
+    for _ in 0..SERVERS {
+        let pool = Arc::new(Database::connect(db_url.as_str()).await.expect("connect server pool"));
+        servers.push(tokio::spawn(async move {
+            let mut tasks = Vec::new();
+            for _ in 0..ORDERS_PER_SERVER {
+                let pool = Arc::clone(&pool);
+                tasks.push(tokio::spawn(async move {
+                    // 🤓 allocate inside a transaction, as checkout does
+                    let txn = pool.begin().await.unwrap();
+                    let orderid = OrderIdAllocator::next(&txn, 1).await.unwrap();
+                    txn.commit().await.unwrap();
+                    orderid
+                }));
+            }
+            let mut ids = Vec::new();
+            for task in tasks {
+                ids.push(task.await.unwrap());
+            }
+            ids
+        }));
+    }
+
+    let mut ids = Vec::new();
+    for server in servers {
+        ids.extend(server.await.unwrap());
+    }
+
+    // A rolled-back allocation hands its number back
+    let txn = db.begin().await.unwrap();
+    OrderIdAllocator::next(&txn, 1).await.unwrap();
+    txn.rollback().await.unwrap();
+    let after = OrderIdAllocator::next(&db, 1).await.unwrap();
+
+    db.close().await.ok();
+    admin
+        .execute_unprepared(&format!("DROP DATABASE {} WITH (FORCE)", name))
+        .await
+        .expect("drop throwaway database");
+
+    let total = SERVERS * ORDERS_PER_SERVER;
+    let counters: HashSet<usize> = ids
+        .iter()
+        .map(|id| id.rsplit('-').next().unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(ids.len(), total);
+    assert_eq!(counters, (1..=total).collect(), "{ids:?}");
+    assert!(after.ends_with(&format!("-{:05}", total + 1)), "{after}");
+}
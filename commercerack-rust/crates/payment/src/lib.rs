@@ -1 +1 @@
-fn main() {}
+//! Payment gateway integrations (to be implemented)
@@ -0,0 +1,60 @@
+//! Product service error types
+
+use sea_orm::{DbErr, SqlErr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ProductError {
+    #[error("Product not found")]
+    NotFound,
+
+    #[error("Product {0} already exists")]
+    DuplicateProduct(String),
+
+    #[error("SKU not found")]
+    SkuNotFound,
+
+    #[error("SKU {0} already exists")]
+    DuplicateSku(String),
+
+    #[error("Invalid {field}: {message}")]
+    InvalidSku { field: &'static str, message: String },
+
+    #[error("Product relation not found")]
+    RelationNotFound,
+
+    #[error("{child_pid} contains {pid}, so it can't also be a component of it")]
+    RelationCycle { pid: String, child_pid: String },
+
+    #[error("Components of {0} are nested too deeply")]
+    ComponentsTooDeep(String),
+
+    #[error("Invalid {field}: {message}")]
+    InvalidRelation { field: &'static str, message: String },
+
+    #[error(transparent)]
+    Db(#[from] DbErr),
+}
+
+impl ProductError {
+    /// Map a unique violation on insert/update to [`ProductError::DuplicateProduct`]
+    pub(crate) fn from_write(err: DbErr, product_id: &str) -> Self {
+        match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => {
+                Self::DuplicateProduct(product_id.to_string())
+            }
+            _ => Self::Db(err),
+        }
+    }
+
+    /// Map a unique violation on a SKU insert to [`ProductError::DuplicateSku`]
+    pub(crate) fn from_sku_write(err: DbErr, sku: &str) -> Self {
+        match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => Self::DuplicateSku(sku.to_string()),
+            _ if matches!(err, DbErr::RecordNotUpdated) => Self::SkuNotFound,
+            _ => Self::Db(err),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ProductError>;
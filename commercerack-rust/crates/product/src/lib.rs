@@ -1,18 +1,24 @@
 //! Product management module using SeaORM
 
-use anyhow::Result;
 use chrono::Utc;
 use sea_orm::*;
 use ::entity::prelude::*;
 use rust_decimal::Decimal;
 
+pub mod error;
+pub mod relation;
 pub mod sku;
 
+pub use error::{ProductError, Result};
+pub use relation::{ProductRelationService, RelatedProduct, RelationKind, SkuQty};
+pub use sku::{OptionGroup, OptionValue, SkuInput, SkuService};
+
 /// Product service for managing product operations
 pub struct ProductService;
 
 impl ProductService {
     /// Create new product
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
         db: &DatabaseConnection,
         mid: i32,
@@ -32,18 +38,16 @@ impl ProductService {
             ts: Set(now),
             product_name: Set(product_name.to_string()),
             category: Set(category.to_string()),
-            base_price: Set(base_price),
-            base_cost: Set(base_cost),
-            supplier: Set(String::new()),
-            supplier_id: Set(String::new()),
-            upc: Set(String::new()),
+            base_price: Set(Some(base_price)),
+            base_cost: Set(Some(base_cost)),
             created_gmt: Set(now),
-            lastsold_gmt: Set(None),
             ..Default::default()
         };
 
-        let result = product.insert(db).await?;
-        Ok(result)
+        product
+            .insert(db)
+            .await
+            .map_err(|e| ProductError::from_write(e, product_id))
     }
 
     /// Find product by ID
@@ -94,16 +98,23 @@ impl ProductService {
         Ok(products)
     }
 
-    /// Update product
+    /// Update a product of merchant `mid`
     pub async fn update(
         db: &DatabaseConnection,
+        mid: i32,
         product: Product,
     ) -> Result<Product> {
         let mut active: ::entity::products::ActiveModel = product.into();
         active.ts = Set(Utc::now().timestamp() as i32);
 
-        let result = active.update(db).await?;
-        Ok(result)
+        Products::update(active)
+            .filter(::entity::products::Column::Mid.eq(mid))
+            .exec(db)
+            .await
+            .map_err(|e| match e {
+                DbErr::RecordNotUpdated => ProductError::NotFound,
+                e => e.into(),
+            })
     }
 
     /// Delete product
@@ -130,12 +141,12 @@ impl ProductService {
         base_cost: Option<Decimal>,
     ) -> Result<Product> {
         let product = Self::find_by_id(db, mid, id).await?
-            .ok_or_else(|| anyhow::anyhow!("Product not found"))?;
+            .ok_or(ProductError::NotFound)?;
 
         let mut active: ::entity::products::ActiveModel = product.into();
-        active.base_price = Set(base_price);
+        active.base_price = Set(Some(base_price));
         if let Some(cost) = base_cost {
-            active.base_cost = Set(cost);
+            active.base_cost = Set(Some(cost));
         }
         active.ts = Set(Utc::now().timestamp() as i32);
 
@@ -150,10 +161,10 @@ impl ProductService {
         id: i32,
     ) -> Result<Product> {
         let product = Self::find_by_id(db, mid, id).await?
-            .ok_or_else(|| anyhow::anyhow!("Product not found"))?;
+            .ok_or(ProductError::NotFound)?;
 
         let mut active: ::entity::products::ActiveModel = product.into();
-        active.lastsold_gmt = Set(Some(Utc::now().timestamp() as i32));
+        active.lastsold_gmt = Set(Utc::now().timestamp() as i32);
 
         let result = active.update(db).await?;
         Ok(result)
@@ -162,8 +173,6 @@ impl ProductService {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     // Tests will be added when we have a test database setup
     // For now, compilation success validates the API design
 }
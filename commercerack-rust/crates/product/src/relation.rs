@@ -0,0 +1,583 @@
+//! Typed links between products (`product_relations`)
+//!
+//! A relation points from a parent product (`pid`) to a child product
+//! (`child_pid`). Accessories and related products are merchandising lists
+//! for the storefront; bundle components and assembly parts make up the
+//! parent, and selling the parent consumes their stock instead. Each list is
+//! ordered by `list_pos`.
+//!
+//! Components must not loop back to their parent, directly or through other
+//! bundles and assemblies, or expanding the parent would never end.
+
+use ::entity::prelude::{Product, ProductRelation, ProductRelations, Products};
+use ::entity::product_relations::{self, Column};
+use chrono::Utc;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{ProductError, Result};
+
+/// Longest list kept for one product and kind
+pub const MAX_RELATED: usize = 100;
+
+/// Bundles nested deeper than this are refused when expanding
+pub const MAX_COMPONENT_DEPTH: usize = 8;
+
+/// Namespace of the advisory lock serialising component changes per merchant
+const COMPONENT_LOCK: i32 = 0x5052_4c4e;
+
+/// What a relation means; stored as an upper-case code in `relation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelationKind {
+    /// Add-on offered alongside the parent ("customers also need")
+    Accessory,
+    /// Alternative or similar product
+    Related,
+    /// Sold together as a kit under the parent's SKU
+    Bundle,
+    /// Part the parent is built from
+    Assembly,
+}
+
+impl RelationKind {
+    pub const ALL: [RelationKind; 4] = [Self::Accessory, Self::Related, Self::Bundle, Self::Assembly];
+
+    /// Kinds whose children make up the parent
+    pub const COMPONENTS: [RelationKind; 2] = [Self::Bundle, Self::Assembly];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Accessory => "ACCESSORY",
+            Self::Related => "RELATED",
+            Self::Bundle => "BUNDLE",
+            Self::Assembly => "ASSEMBLY",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accessory => "accessory",
+            Self::Related => "related",
+            Self::Bundle => "bundle",
+            Self::Assembly => "assembly",
+        }
+    }
+
+    /// Kind of a stored relation; `None` for codes this service doesn't know
+    pub fn of(relation: &ProductRelation) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.code() == relation.relation)
+    }
+
+    pub fn is_component(&self) -> bool {
+        Self::COMPONENTS.contains(self)
+    }
+}
+
+impl fmt::Display for RelationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RelationKind {
+    type Err = ProductError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| invalid("kind", format!("unknown relation kind {}", s)))
+    }
+}
+
+/// One entry of a relation list
+#[derive(Debug, Clone)]
+pub struct RelatedProduct {
+    pub child_pid: String,
+    /// Units of the child per unit of the parent
+    pub qty: i16,
+    /// Inactive entries are kept but not shown or expanded
+    pub is_active: bool,
+}
+
+/// A quantity of one SKU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkuQty {
+    pub sku: String,
+    pub qty: i32,
+}
+
+/// Product relation service
+pub struct ProductRelationService;
+
+impl ProductRelationService {
+    /// A product's relations of one kind, in list order
+    pub async fn list(
+        db: &DatabaseConnection,
+        mid: i32,
+        pid: &str,
+        kind: RelationKind,
+    ) -> Result<Vec<ProductRelation>> {
+        Self::list_in(db, mid, pid, kind).await
+    }
+
+    /// Replace a product's list of one kind
+    ///
+    /// Children must be existing products. Bundle and assembly children must
+    /// not have options, since their SKU is the product ID, and must not
+    /// contain the parent. Returns the new list.
+    pub async fn set(
+        db: &DatabaseConnection,
+        mid: i32,
+        pid: &str,
+        kind: RelationKind,
+        children: &[RelatedProduct],
+    ) -> Result<Vec<ProductRelation>> {
+        validate_children(pid, children)?;
+
+        let txn = db.begin().await?;
+        if kind.is_component() {
+            // Row locks on the products involved can't stop two edits from
+            // closing a loop through products neither of them touches
+            txn.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT pg_advisory_xact_lock($1, $2)",
+                [COMPONENT_LOCK.into(), mid.into()],
+            ))
+            .await?;
+        }
+        Products::find()
+            .filter(::entity::products::Column::Mid.eq(mid))
+            .filter(::entity::products::Column::Product.eq(pid))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(ProductError::NotFound)?;
+
+        let pids: Vec<&str> = children.iter().map(|c| c.child_pid.as_str()).collect();
+        let found: HashMap<String, Product> = Products::find()
+            .filter(::entity::products::Column::Mid.eq(mid))
+            .filter(::entity::products::Column::Product.is_in(pids.iter().copied()))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|p| (p.product.clone(), p))
+            .collect();
+        for child in children {
+            let product = found
+                .get(&child.child_pid)
+                .ok_or_else(|| invalid("child_pid", format!("unknown product {}", child.child_pid)))?;
+            if kind.is_component() && product.options != 0 {
+                return Err(invalid(
+                    "child_pid",
+                    format!("{} has options; only products without options can be components", child.child_pid),
+                ));
+            }
+        }
+        if kind.is_component() {
+            check_cycle(&txn, mid, pid, &pids).await?;
+        }
+
+        let existing = Self::list_in(&txn, mid, pid, kind).await?;
+        let created: HashMap<&str, i32> =
+            existing.iter().map(|r| (r.child_pid.as_str(), r.created_gmt)).collect();
+        let now = Utc::now().timestamp() as i32;
+        let rows: Vec<product_relations::ActiveModel> = children
+            .iter()
+            .enumerate()
+            .map(|(pos, child)| product_relations::ActiveModel {
+                mid: Set(mid),
+                pid: Set(pid.to_string()),
+                child_pid: Set(child.child_pid.clone()),
+                relation: Set(kind.code().to_string()),
+                qty: Set(child.qty),
+                is_active: Set(child.is_active as i16),
+                list_pos: Set(pos as i16),
+                created_gmt: Set(created.get(child.child_pid.as_str()).copied().unwrap_or(now)),
+            })
+            .collect();
+
+        ProductRelations::delete_many()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Pid.eq(pid))
+            .filter(Column::Relation.eq(kind.code()))
+            .exec(&txn)
+            .await?;
+        if !rows.is_empty() {
+            ProductRelations::insert_many(rows).exec_without_returning(&txn).await?;
+        }
+        txn.commit().await?;
+
+        Self::list(db, mid, pid, kind).await
+    }
+
+    /// Take one product off a list; the rest keep their order
+    pub async fn remove(
+        db: &DatabaseConnection,
+        mid: i32,
+        pid: &str,
+        kind: RelationKind,
+        child_pid: &str,
+    ) -> Result<()> {
+        let result = ProductRelations::delete_many()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Pid.eq(pid))
+            .filter(Column::Relation.eq(kind.code()))
+            .filter(Column::ChildPid.eq(child_pid))
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(ProductError::RelationNotFound);
+        }
+        Ok(())
+    }
+
+    /// Replace bundles and assemblies with the SKUs they consume
+    ///
+    /// Quantities multiply through nested bundles and are summed per SKU, in
+    /// order of first appearance. SKUs with options and products without
+    /// active components are passed through unchanged; this is what
+    /// inventory should reserve and ship for an order.
+    pub async fn expand(db: &DatabaseConnection, mid: i32, lines: &[SkuQty]) -> Result<Vec<SkuQty>> {
+        let mut expanded: Vec<SkuQty> = Vec::new();
+        let mut add = |sku: &str, qty: i32| match expanded.iter_mut().find(|l| l.sku == sku) {
+            Some(line) => line.qty += qty,
+            None => expanded.push(SkuQty { sku: sku.to_string(), qty }),
+        };
+
+        let mut frontier: Vec<SkuQty> = Vec::new();
+        for line in lines {
+            if line.sku.contains(':') {
+                add(&line.sku, line.qty);
+            } else {
+                frontier.push(line.clone());
+            }
+        }
+
+        let mut depth = 0;
+        while !frontier.is_empty() {
+            if depth > MAX_COMPONENT_DEPTH {
+                return Err(ProductError::ComponentsTooDeep(frontier[0].sku.clone()));
+            }
+            depth += 1;
+
+            let pids: HashSet<&str> = frontier.iter().map(|l| l.sku.as_str()).collect();
+            let mut components: HashMap<String, Vec<ProductRelation>> = HashMap::new();
+            for relation in component_query(mid, pids).filter(Column::IsActive.ne(0)).all(db).await? {
+                components.entry(relation.pid.clone()).or_default().push(relation);
+            }
+
+            let mut next = Vec::new();
+            for line in frontier {
+                match components.get(&line.sku) {
+                    Some(children) => next.extend(children.iter().map(|c| SkuQty {
+                        sku: c.child_pid.clone(),
+                        qty: line.qty * i32::from(c.qty),
+                    })),
+                    None => add(&line.sku, line.qty),
+                }
+            }
+            frontier = next;
+        }
+
+        Ok(expanded)
+    }
+
+    /// Active accessories of `pids` for a storefront "customers also need"
+    ///
+    /// Products already in `pids` are left out. Each parent's list order is
+    /// kept, parents in the order given.
+    pub async fn also_need(
+        db: &DatabaseConnection,
+        mid: i32,
+        pids: &[&str],
+        limit: usize,
+    ) -> Result<Vec<Product>> {
+        if pids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut relations = ProductRelations::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Pid.is_in(pids.iter().copied()))
+            .filter(Column::Relation.eq(RelationKind::Accessory.code()))
+            .filter(Column::IsActive.ne(0))
+            .order_by_asc(Column::ListPos)
+            .all(db)
+            .await?;
+        relations.sort_by_key(|r| pids.iter().position(|p| *p == r.pid));
+
+        let mut seen: HashSet<&str> = pids.iter().copied().collect();
+        let wanted: Vec<&str> = relations
+            .iter()
+            .map(|r| r.child_pid.as_str())
+            .filter(|child| seen.insert(child))
+            .take(limit)
+            .collect();
+        if wanted.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut products = Products::find()
+            .filter(::entity::products::Column::Mid.eq(mid))
+            .filter(::entity::products::Column::Product.is_in(wanted.iter().copied()))
+            .all(db)
+            .await?;
+        products.sort_by_key(|p| wanted.iter().position(|w| *w == p.product));
+        Ok(products)
+    }
+
+    async fn list_in<C: ConnectionTrait>(
+        db: &C,
+        mid: i32,
+        pid: &str,
+        kind: RelationKind,
+    ) -> Result<Vec<ProductRelation>> {
+        let relations = ProductRelations::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Pid.eq(pid))
+            .filter(Column::Relation.eq(kind.code()))
+            .order_by_asc(Column::ListPos)
+            .order_by_asc(Column::ChildPid)
+            .all(db)
+            .await?;
+
+        Ok(relations)
+    }
+}
+
+/// Bundle and assembly rows under any of `pids`
+fn component_query<'a>(mid: i32, pids: impl IntoIterator<Item = &'a str>) -> Select<ProductRelations> {
+    ProductRelations::find()
+        .filter(Column::Mid.eq(mid))
+        .filter(Column::Pid.is_in(pids))
+        .filter(Column::Relation.is_in(RelationKind::COMPONENTS.map(|k| k.code())))
+        .order_by_asc(Column::ListPos)
+}
+
+/// Fail if `pid` is reachable from any of `children` through components
+///
+/// Inactive rows count too, so switching one back on can't close a loop.
+async fn check_cycle<C: ConnectionTrait>(db: &C, mid: i32, pid: &str, children: &[&str]) -> Result<()> {
+    // Which of the new children each visited product was reached from
+    let mut via: HashMap<String, String> =
+        children.iter().map(|c| (String::from(*c), String::from(*c))).collect();
+    let mut frontier: Vec<String> = children.iter().map(|c| String::from(*c)).collect();
+
+    while !frontier.is_empty() {
+        let rows = component_query(mid, frontier.iter().map(String::as_str)).all(db).await?;
+        frontier = Vec::new();
+        for row in rows {
+            let origin = via[&row.pid].clone();
+            if row.child_pid == pid {
+                return Err(ProductError::RelationCycle {
+                    pid: pid.to_string(),
+                    child_pid: origin,
+                });
+            }
+            if !via.contains_key(&row.child_pid) {
+                via.insert(row.child_pid.clone(), origin);
+                frontier.push(row.child_pid);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_children(pid: &str, children: &[RelatedProduct]) -> Result<()> {
+    if children.len() > MAX_RELATED {
+        return Err(invalid("children", format!("must have at most {} products", MAX_RELATED)));
+    }
+
+    let mut seen = HashSet::new();
+    for child in children {
+        if child.child_pid.is_empty() || child.child_pid.chars().count() > 20 {
+            return Err(invalid("child_pid", "must be 1 to 20 characters"));
+        }
+        if child.child_pid == pid {
+            return Err(invalid("child_pid", "a product can't be related to itself"));
+        }
+        if !seen.insert(child.child_pid.as_str()) {
+            return Err(invalid("child_pid", format!("{} is listed twice", child.child_pid)));
+        }
+        if child.qty < 1 {
+            return Err(invalid("qty", "must be at least 1"));
+        }
+    }
+    Ok(())
+}
+
+fn invalid(field: &'static str, message: impl Into<String>) -> ProductError {
+    ProductError::InvalidRelation {
+        field,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(pid: &str, options: i32) -> Product {
+        Product {
+            id: 1,
+            mid: 1,
+            merchant: "demo".to_string(),
+            product: pid.to_string(),
+            ts: 0,
+            product_name: pid.to_string(),
+            category: String::new(),
+            data: String::new(),
+            salesrank: 0,
+            created_gmt: 0,
+            lastsold_gmt: 0,
+            base_price: None,
+            base_cost: None,
+            supplier: None,
+            supplier_id: None,
+            mfg: None,
+            mfg_id: None,
+            upc: String::new(),
+            options,
+            profile: String::new(),
+            mkt: 0,
+            prod_is: 0,
+            mkt_bitstr: String::new(),
+            mkterr_bitstr: String::new(),
+        }
+    }
+
+    fn relation(pid: &str, child: &str, kind: RelationKind, qty: i16) -> ProductRelation {
+        ProductRelation {
+            mid: 1,
+            pid: pid.to_string(),
+            child_pid: child.to_string(),
+            relation: kind.code().to_string(),
+            qty,
+            is_active: 1,
+            list_pos: 0,
+            created_gmt: 0,
+        }
+    }
+
+    fn child(pid: &str, qty: i16) -> RelatedProduct {
+        RelatedProduct {
+            child_pid: pid.to_string(),
+            qty,
+            is_active: true,
+        }
+    }
+
+    fn line(sku: &str, qty: i32) -> SkuQty {
+        SkuQty { sku: sku.to_string(), qty }
+    }
+
+    #[test]
+    fn test_children_are_validated() {
+        assert!(validate_children("KIT", &[child("A", 1), child("B", 2)]).is_ok());
+        assert!(validate_children("KIT", &[child("KIT", 1)]).is_err());
+        assert!(validate_children("KIT", &[child("A", 1), child("A", 1)]).is_err());
+        assert!(matches!(
+            validate_children("KIT", &[child("A", 0)]),
+            Err(ProductError::InvalidRelation { field: "qty", .. })
+        ));
+        assert_eq!("bundle".parse::<RelationKind>().unwrap(), RelationKind::Bundle);
+        assert!("kit".parse::<RelationKind>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_set_rejects_assembly_cycle() {
+        // KIT -> DRILL, while DRILL -> MOTOR -> KIT already exists
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .append_query_results([vec![product("KIT", 0)]])
+            .append_query_results([vec![product("DRILL", 0)]])
+            .append_query_results([vec![relation("DRILL", "MOTOR", RelationKind::Assembly, 1)]])
+            .append_query_results([vec![relation("MOTOR", "KIT", RelationKind::Bundle, 1)]])
+            .into_connection();
+
+        let err = ProductRelationService::set(&db, 1, "KIT", RelationKind::Assembly, &[child("DRILL", 1)])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, ProductError::RelationCycle { pid, child_pid } if pid == "KIT" && child_pid == "DRILL"),
+            "{:?}",
+            err
+        );
+
+        let log = db.into_transaction_log();
+        let txn = log[0].statements();
+        assert!(txn[1].sql.contains("pg_advisory_xact_lock"), "{}", txn[1].sql);
+        assert!(txn[2].sql.ends_with("FOR UPDATE"), "{}", txn[2].sql);
+    }
+
+    #[tokio::test]
+    async fn test_components_must_not_have_options() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .append_query_results([vec![product("KIT", 0)]])
+            .append_query_results([vec![product("TSHIRT", 3)]])
+            .into_connection();
+
+        let err = ProductRelationService::set(&db, 1, "KIT", RelationKind::Bundle, &[child("TSHIRT", 1)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProductError::InvalidRelation { field: "child_pid", .. }));
+    }
+
+    #[tokio::test]
+    async fn test_expand_multiplies_nested_components() {
+        // KIT = 2 x DRILL + 1 x CASE, DRILL = 1 x MOTOR + 4 x BIT
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![
+                relation("KIT", "DRILL", RelationKind::Bundle, 2),
+                relation("KIT", "CASE", RelationKind::Bundle, 1),
+            ]])
+            .append_query_results([vec![
+                relation("DRILL", "MOTOR", RelationKind::Assembly, 1),
+                relation("DRILL", "BIT", RelationKind::Assembly, 4),
+            ]])
+            .append_query_results([Vec::<ProductRelation>::new()])
+            .into_connection();
+
+        let expanded = ProductRelationService::expand(
+            &db,
+            1,
+            &[line("KIT", 3), line("BIT", 1), line("TSHIRT:C001", 2)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            expanded,
+            [line("TSHIRT:C001", 2), line("BIT", 25), line("CASE", 3), line("MOTOR", 6)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_also_need_skips_products_already_given() {
+        let mut second = relation("DRILL", "BIT", RelationKind::Accessory, 1);
+        second.list_pos = 1;
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![
+                relation("SAW", "BLADE", RelationKind::Accessory, 1),
+                relation("DRILL", "SAW", RelationKind::Accessory, 1),
+                second,
+                relation("SAW", "BIT", RelationKind::Accessory, 1),
+            ]])
+            .append_query_results([vec![product("BLADE", 0), product("BIT", 0)]])
+            .into_connection();
+
+        let products = ProductRelationService::also_need(&db, 1, &["DRILL", "SAW"], 10).await.unwrap();
+        let pids: Vec<&str> = products.iter().map(|p| p.product.as_str()).collect();
+        assert_eq!(pids, ["BIT", "BLADE"]);
+
+        let log = db.into_transaction_log();
+        let children = log[1].statements()[0].to_string();
+        assert!(children.contains("'BIT', 'BLADE'"), "{}", children);
+    }
+}
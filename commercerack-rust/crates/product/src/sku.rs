@@ -1,31 +1,664 @@
-//! SKU management (placeholder - to be implemented with SeaORM)
+//! SKU catalog lookups (`sku_lookup`)
 //!
-//! TODO: Implement SKU entity and service layer
-//! For now, this is a stub to allow compilation
+//! A SKU is one purchasable variant of a product and carries its own price
+//! and stock counters. Its code is the product ID followed by `invopts`, one
+//! `:GGVV` block per option group (`GG` the group, `VV` the chosen value),
+//! so a product without options has a single SKU equal to its ID.
 
-use serde::{Deserialize, Serialize};
+use ::entity::prelude::{Product, Products, Sku, SkuLookup};
+use ::entity::sku_lookup::{self, Column};
+use rust_decimal::Decimal;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use std::collections::HashSet;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SKU {
-    pub id: i32,
-    pub pid: i32,
-    pub mid: i32,
-    pub sku: String,
+use crate::error::{ProductError, Result};
+
+/// Option groups fit in `invopts` (varchar 15) at five characters each
+pub const MAX_OPTION_GROUPS: usize = 3;
+
+/// Most SKUs one set of option groups may generate
+pub const MAX_VARIANTS: usize = 1000;
+
+/// Rows per generated-variant `INSERT`, keeping each statement far below
+/// Postgres's 65,535 bind parameters
+const INSERT_CHUNK: usize = 500;
+
+/// Editable fields of a SKU; stock counters belong to the inventory service
+#[derive(Debug, Clone, Default)]
+pub struct SkuInput {
     pub title: String,
-    pub price: rust_decimal::Decimal,
-    pub cost: rust_decimal::Decimal,
+    pub price: Decimal,
+    pub cost: Decimal,
     pub upc: String,
-    pub inv_available: i32,
-    pub qty_onshelf: i32,
-}
-
-// TODO: Implement SKUService with SeaORM
-// pub struct SKUService;
-//
-// impl SKUService {
-//     pub async fn create(db: &DatabaseConnection, sku: SKU) -> Result<SKU> { ... }
-//     pub async fn find_by_id(db: &DatabaseConnection, mid: i32, id: i32) -> Result<Option<SKU>> { ... }
-//     pub async fn find_by_product(db: &DatabaseConnection, mid: i32, pid: i32) -> Result<Vec<SKU>> { ... }
-//     pub async fn update(db: &DatabaseConnection, sku: SKU) -> Result<SKU> { ... }
-//     pub async fn delete(db: &DatabaseConnection, mid: i32, id: i32) -> Result<()> { ... }
-// }
+    pub mfgid: String,
+    pub supplierid: String,
+    pub amz_asin: String,
+}
+
+/// A product option that selects a variant, e.g. colour
+#[derive(Debug, Clone)]
+pub struct OptionGroup {
+    /// Two characters, unique within the product
+    pub id: String,
+    pub name: String,
+    pub values: Vec<OptionValue>,
+}
+
+/// One choice within an [`OptionGroup`]
+#[derive(Debug, Clone)]
+pub struct OptionValue {
+    /// Two characters, unique within the group
+    pub id: String,
+    pub name: String,
+    /// Added to the product's base price
+    pub price_delta: Decimal,
+}
+
+/// SKU service for catalog lookups
+pub struct SkuService;
+
+impl SkuService {
+    /// Find a SKU by its code
+    pub async fn find_by_sku(
+        db: &DatabaseConnection,
+        mid: i32,
+        sku: &str,
+    ) -> Result<Option<Sku>> {
+        let sku = SkuLookup::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Sku.eq(sku))
+            .one(db)
+            .await?;
+
+        Ok(sku)
+    }
+
+    /// Find several SKUs by code; unknown codes are left out
+    pub async fn find_many(
+        db: &DatabaseConnection,
+        mid: i32,
+        skus: &[&str],
+    ) -> Result<Vec<Sku>> {
+        if skus.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let skus = SkuLookup::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Sku.is_in(skus.iter().copied()))
+            .all(db)
+            .await?;
+
+        Ok(skus)
+    }
+
+    /// SKUs with a UPC/EAN barcode
+    pub async fn find_by_upc(db: &DatabaseConnection, mid: i32, upc: &str) -> Result<Vec<Sku>> {
+        Self::find_by_column(db, mid, Column::Upc, upc).await
+    }
+
+    /// SKUs listed under an Amazon ASIN
+    pub async fn find_by_asin(db: &DatabaseConnection, mid: i32, asin: &str) -> Result<Vec<Sku>> {
+        Self::find_by_column(db, mid, Column::AmzAsin, asin).await
+    }
+
+    /// SKUs with a manufacturer part number
+    pub async fn find_by_mfgid(db: &DatabaseConnection, mid: i32, mfgid: &str) -> Result<Vec<Sku>> {
+        Self::find_by_column(db, mid, Column::Mfgid, mfgid).await
+    }
+
+    /// All SKUs of a product, in code order
+    pub async fn list_by_product(db: &DatabaseConnection, mid: i32, pid: &str) -> Result<Vec<Sku>> {
+        let skus = SkuLookup::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Pid.eq(pid))
+            .order_by_asc(Column::Sku)
+            .all(db)
+            .await?;
+
+        Ok(skus)
+    }
+
+    /// Find a SKU of a product
+    pub async fn find(db: &DatabaseConnection, mid: i32, pid: &str, sku: &str) -> Result<Option<Sku>> {
+        let sku = SkuLookup::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Pid.eq(pid))
+            .filter(Column::Sku.eq(sku))
+            .one(db)
+            .await?;
+
+        Ok(sku)
+    }
+
+    /// Add a SKU to product `pid`; its code is `pid` followed by `invopts`
+    pub async fn create(
+        db: &DatabaseConnection,
+        mid: i32,
+        pid: &str,
+        invopts: &str,
+        input: SkuInput,
+    ) -> Result<Sku> {
+        validate_invopts(invopts)?;
+        let input = input.validated()?;
+        find_product(db, mid, pid).await?;
+
+        let code = format!("{}{}", pid, invopts);
+        let mut active = input.into_active_model();
+        active.mid = Set(mid);
+        active.pid = Set(pid.to_string());
+        active.invopts = Set(invopts.to_string());
+        active.sku = Set(code.clone());
+        active
+            .insert(db)
+            .await
+            .map_err(|e| ProductError::from_sku_write(e, &code))
+    }
+
+    /// Replace the editable fields of a SKU
+    pub async fn update(
+        db: &DatabaseConnection,
+        mid: i32,
+        pid: &str,
+        sku: &str,
+        input: SkuInput,
+    ) -> Result<Sku> {
+        let input = input.validated()?;
+        let current = Self::find(db, mid, pid, sku)
+            .await?
+            .ok_or(ProductError::SkuNotFound)?;
+
+        let mut active = input.into_active_model();
+        active.id = Unchanged(current.id);
+        active
+            .update(db)
+            .await
+            .map_err(|e| ProductError::from_sku_write(e, sku))
+    }
+
+    /// Delete a SKU of a product
+    pub async fn delete(db: &DatabaseConnection, mid: i32, pid: &str, sku: &str) -> Result<()> {
+        let deleted = SkuLookup::delete_many()
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Pid.eq(pid))
+            .filter(Column::Sku.eq(sku))
+            .exec(db)
+            .await?;
+        if deleted.rows_affected == 0 {
+            return Err(ProductError::SkuNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Create a SKU for every combination of option values
+    ///
+    /// Variants that already exist are left as they are, so this can be
+    /// rerun after adding a value. The product's `options` mask gets one bit
+    /// per group. Returns all of the product's SKUs.
+    pub async fn generate_variants(
+        db: &DatabaseConnection,
+        mid: i32,
+        pid: &str,
+        groups: &[OptionGroup],
+    ) -> Result<Vec<Sku>> {
+        validate_groups(groups)?;
+
+        let txn = db.begin().await?;
+        let product = Products::find()
+            .filter(::entity::products::Column::Mid.eq(mid))
+            .filter(::entity::products::Column::Product.eq(pid))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(ProductError::NotFound)?;
+
+        let existing: HashSet<String> = SkuLookup::find()
+            .select_only()
+            .column(Column::Sku)
+            .filter(Column::Mid.eq(mid))
+            .filter(Column::Pid.eq(pid))
+            .into_tuple::<String>()
+            .all(&txn)
+            .await?
+            .into_iter()
+            .collect();
+
+        let missing: Vec<sku_lookup::ActiveModel> = variants(&product, groups)
+            .into_iter()
+            .filter(|v| !existing.contains(&v.sku))
+            .map(|v| {
+                let mut active = v.input.into_active_model();
+                active.mid = Set(mid);
+                active.pid = Set(pid.to_string());
+                active.invopts = Set(v.invopts);
+                active.sku = Set(v.sku);
+                active
+            })
+            .collect();
+        for chunk in missing.chunks(INSERT_CHUNK) {
+            SkuLookup::insert_many(chunk.to_vec()).exec_without_returning(&txn).await?;
+        }
+
+        Products::update_many()
+            .col_expr(::entity::products::Column::Options, Expr::value((1i32 << groups.len()) - 1))
+            .filter(::entity::products::Column::Id.eq(product.id))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        Self::list_by_product(db, mid, pid).await
+    }
+
+    async fn find_by_column(db: &DatabaseConnection, mid: i32, column: Column, value: &str) -> Result<Vec<Sku>> {
+        if value.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let skus = SkuLookup::find()
+            .filter(Column::Mid.eq(mid))
+            .filter(column.eq(value))
+            .order_by_asc(Column::Sku)
+            .all(db)
+            .await?;
+
+        Ok(skus)
+    }
+}
+
+/// Units of `sku` that can still be sold; `None` when backorders are allowed
+pub fn sellable_quantity(sku: &Sku) -> Option<i32> {
+    (sku.inv_is_bo == 0).then_some(sku.inv_available)
+}
+
+impl SkuInput {
+    fn validated(mut self) -> Result<Self> {
+        self.title = self.title.trim().to_string();
+        self.upc = self.upc.trim().to_string();
+        self.mfgid = self.mfgid.trim().to_string();
+        self.supplierid = self.supplierid.trim().to_string();
+        self.amz_asin = self.amz_asin.trim().to_uppercase();
+
+        max_len("title", &self.title, 80)?;
+        max_len("mfgid", &self.mfgid, 25)?;
+        max_len("supplierid", &self.supplierid, 25)?;
+        max_len("amz_asin", &self.amz_asin, 15)?;
+        if !matches!(self.upc.len(), 0 | 12 | 13) {
+            return Err(invalid("upc", "must be a 12-digit UPC or 13-digit EAN"));
+        }
+        if !self.upc.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid("upc", "must be digits only"));
+        }
+        if self.price.is_sign_negative() {
+            return Err(invalid("price", "must not be negative"));
+        }
+        if self.cost.is_sign_negative() {
+            return Err(invalid("cost", "must not be negative"));
+        }
+        Ok(self)
+    }
+
+    fn into_active_model(self) -> sku_lookup::ActiveModel {
+        sku_lookup::ActiveModel {
+            title: Set(self.title),
+            price: Set(self.price),
+            cost: Set(self.cost),
+            upc: Set(self.upc),
+            mfgid: Set(self.mfgid),
+            supplierid: Set(self.supplierid),
+            amz_asin: Set(self.amz_asin),
+            ..Default::default()
+        }
+    }
+}
+
+struct Variant {
+    invopts: String,
+    sku: String,
+    input: SkuInput,
+}
+
+/// Every combination of one value per group, in group order
+fn variants(product: &Product, groups: &[OptionGroup]) -> Vec<Variant> {
+    let mut combos: Vec<Vec<(&OptionGroup, &OptionValue)>> = vec![Vec::new()];
+    for group in groups {
+        combos = combos
+            .into_iter()
+            .flat_map(|combo| {
+                group.values.iter().map(move |value| {
+                    let mut next = combo.clone();
+                    next.push((group, value));
+                    next
+                })
+            })
+            .collect();
+    }
+
+    combos
+        .into_iter()
+        .map(|combo| {
+            let invopts: String = combo
+                .iter()
+                .map(|(g, v)| format!(":{}{}", g.id, v.id))
+                .collect();
+            let names: Vec<&str> = combo.iter().map(|(_, v)| v.name.as_str()).collect();
+            let price = combo
+                .iter()
+                .fold(product.base_price.unwrap_or_default(), |p, (_, v)| p + v.price_delta);
+            Variant {
+                sku: format!("{}{}", product.product, invopts),
+                invopts,
+                input: SkuInput {
+                    // 🤓 legacy column is varchar(80)
+                    title: format!("{} ({})", product.product_name, names.join(", "))
+                        .chars()
+                        .take(80)
+                        .collect(),
+                    price: price.max(Decimal::ZERO),
+                    cost: product.base_cost.unwrap_or_default(),
+                    upc: String::new(),
+                    mfgid: product.mfg_id.clone().unwrap_or_default().chars().take(25).collect(),
+                    supplierid: product.supplier_id.clone().unwrap_or_default().chars().take(25).collect(),
+                    amz_asin: String::new(),
+                },
+            }
+        })
+        .collect()
+}
+
+async fn find_product(db: &DatabaseConnection, mid: i32, pid: &str) -> Result<Product> {
+    Products::find()
+        .filter(::entity::products::Column::Mid.eq(mid))
+        .filter(::entity::products::Column::Product.eq(pid))
+        .one(db)
+        .await?
+        .ok_or(ProductError::NotFound)
+}
+
+fn validate_groups(groups: &[OptionGroup]) -> Result<()> {
+    if groups.is_empty() || groups.len() > MAX_OPTION_GROUPS {
+        return Err(invalid("groups", format!("must have 1 to {} option groups", MAX_OPTION_GROUPS)));
+    }
+
+    let mut group_ids = HashSet::new();
+    for group in groups {
+        if !is_option_id(&group.id) || !group_ids.insert(group.id.as_str()) {
+            return Err(invalid("groups", format!("group id {:?} must be two unique letters or digits", group.id)));
+        }
+        if group.values.is_empty() {
+            return Err(invalid("groups", format!("group {} has no values", group.id)));
+        }
+        let mut value_ids = HashSet::new();
+        for value in &group.values {
+            if !is_option_id(&value.id) || !value_ids.insert(value.id.as_str()) {
+                return Err(invalid(
+                    "groups",
+                    format!("value id {:?} in group {} must be two unique letters or digits", value.id, group.id),
+                ));
+            }
+        }
+    }
+
+    let combinations = groups.iter().try_fold(1usize, |n, g| n.checked_mul(g.values.len()));
+    if combinations.is_none_or(|n| n > MAX_VARIANTS) {
+        return Err(invalid("groups", format!("must make at most {} combinations", MAX_VARIANTS)));
+    }
+    Ok(())
+}
+
+/// Empty, or up to three `:GGVV` blocks
+fn validate_invopts(invopts: &str) -> Result<()> {
+    let blocks: Vec<&str> = invopts.split(':').skip(1).collect();
+    let well_formed = (invopts.is_empty() || invopts.starts_with(':'))
+        && blocks.len() <= MAX_OPTION_GROUPS
+        && blocks.iter().all(|b| b.len() == 4 && b.is_ascii() && is_option_id(&b[..2]) && is_option_id(&b[2..]));
+    if !well_formed {
+        return Err(invalid("invopts", "must be up to three :GGVV option blocks"));
+    }
+    Ok(())
+}
+
+fn is_option_id(id: &str) -> bool {
+    id.len() == 2 && id.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+}
+
+fn max_len(field: &'static str, value: &str, max: usize) -> Result<()> {
+    if value.chars().count() > max {
+        return Err(invalid(field, format!("must be at most {} characters", max)));
+    }
+    Ok(())
+}
+
+fn invalid(field: &'static str, message: impl Into<String>) -> ProductError {
+    ProductError::InvalidSku {
+        field,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn product() -> Product {
+        Product {
+            id: 4,
+            mid: 1,
+            merchant: "demo".to_string(),
+            product: "TSHIRT".to_string(),
+            ts: 0,
+            product_name: "T-shirt".to_string(),
+            category: String::new(),
+            data: String::new(),
+            salesrank: 0,
+            created_gmt: 0,
+            lastsold_gmt: 0,
+            base_price: Some(Decimal::new(1500, 2)),
+            base_cost: Some(Decimal::new(600, 2)),
+            supplier: None,
+            supplier_id: None,
+            mfg: None,
+            mfg_id: Some("TS-100".to_string()),
+            upc: String::new(),
+            options: 0,
+            profile: String::new(),
+            mkt: 0,
+            prod_is: 0,
+            mkt_bitstr: String::new(),
+            mkterr_bitstr: String::new(),
+        }
+    }
+
+    fn sku(code: &str, invopts: &str) -> Sku {
+        Sku {
+            id: 1,
+            mid: 1,
+            pid: "TSHIRT".to_string(),
+            invopts: invopts.to_string(),
+            grp_parent: String::new(),
+            sku: code.to_string(),
+            title: String::new(),
+            cost: Decimal::ZERO,
+            price: Decimal::ZERO,
+            upc: String::new(),
+            mfgid: String::new(),
+            supplierid: String::new(),
+            prodasm: None,
+            assembly: None,
+            inv_available: 0,
+            qty_onshelf: 0,
+            qty_onorder: 0,
+            qty_needship: 0,
+            qty_markets: 0,
+            qty_legacy: 0,
+            qty_reserved: 0,
+            amz_asin: String::new(),
+            amz_feeds_done: 0,
+            amz_feeds_todo: 0,
+            amz_feeds_sent: 0,
+            amz_feeds_wait: 0,
+            amz_feeds_warn: 0,
+            amz_feeds_error: 0,
+            amz_productdb_gmt: 0,
+            amz_error: String::new(),
+            inv_on_shelf: 0,
+            inv_on_order: 0,
+            inv_is_bo: 0,
+            inv_reorder: 0,
+            inv_is_rsvp: 0,
+            dss_agent: String::new(),
+            dss_run: None,
+            dss_mood: None,
+            dss_config: None,
+        }
+    }
+
+    fn value(id: &str, name: &str, cents: i64) -> OptionValue {
+        OptionValue {
+            id: id.to_string(),
+            name: name.to_string(),
+            price_delta: Decimal::new(cents, 2),
+        }
+    }
+
+    fn groups() -> Vec<OptionGroup> {
+        vec![
+            OptionGroup {
+                id: "C0".to_string(),
+                name: "Colour".to_string(),
+                values: vec![value("01", "Red", 0), value("02", "Blue", 0)],
+            },
+            OptionGroup {
+                id: "S0".to_string(),
+                name: "Size".to_string(),
+                values: vec![value("0S", "Small", 0), value("0L", "Large", 200)],
+            },
+        ]
+    }
+
+    /// Groups `ids`, each with `count` values
+    fn many_values(ids: &[&str], count: usize) -> Vec<OptionGroup> {
+        ids.iter()
+            .map(|id| OptionGroup {
+                id: String::from(*id),
+                name: String::from(*id),
+                values: (0..count).map(|i| value(&format!("{:02}", i), &i.to_string(), 0)).collect(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_variants_cover_every_combination() {
+        let variants = variants(&product(), &groups());
+        let codes: Vec<&str> = variants.iter().map(|v| v.sku.as_str()).collect();
+        assert_eq!(
+            codes,
+            ["TSHIRT:C001:S00S", "TSHIRT:C001:S00L", "TSHIRT:C002:S00S", "TSHIRT:C002:S00L"]
+        );
+        assert_eq!(variants[1].input.title, "T-shirt (Red, Large)");
+        assert_eq!(variants[1].input.price, Decimal::new(1700, 2));
+        assert_eq!(variants[1].input.mfgid, "TS-100");
+    }
+
+    #[test]
+    fn test_invopts_and_groups_are_validated() {
+        assert!(validate_invopts("").is_ok());
+        assert!(validate_invopts(":C001:S00L").is_ok());
+        assert!(validate_invopts("C001").is_err());
+        assert!(validate_invopts(":C01").is_err());
+        assert!(validate_invopts(":A000:B000:C000:D000").is_err());
+        assert!(validate_invopts(":Cé1").is_err());
+
+        let mut dup = groups();
+        dup[1].id = "C0".to_string();
+        assert!(matches!(validate_groups(&dup), Err(ProductError::InvalidSku { field: "groups", .. })));
+        assert!(validate_groups(&[]).is_err());
+
+        let mut big = many_values(&["C0", "S0"], 32);
+        assert!(validate_groups(&big).is_err(), "1024 combinations");
+        big[1].values.pop();
+        assert!(validate_groups(&big).is_ok(), "992 combinations");
+    }
+
+    #[tokio::test]
+    async fn test_generate_variants_keeps_existing_skus() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![product()]])
+            .append_query_results([vec![
+                std::collections::BTreeMap::from([("sku".to_string(), Value::from("TSHIRT:C001:S00S"))]),
+            ]])
+            .append_exec_results([
+                MockExecResult { last_insert_id: 9, rows_affected: 3 },
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+            ])
+            .append_query_results([vec![sku("TSHIRT:C001:S00S", ":C001:S00S")]])
+            .into_connection();
+
+        SkuService::generate_variants(&db, 1, "TSHIRT", &groups()).await.unwrap();
+
+        let log = db.into_transaction_log();
+        let txn = log[0].statements();
+        assert!(txn[1].sql.ends_with("FOR UPDATE"), "{}", txn[1].sql);
+        let insert = txn[3].to_string();
+        assert!(insert.starts_with(r#"INSERT INTO "sku_lookup""#), "{}", insert);
+        assert!(!insert.contains("'TSHIRT:C001:S00S'"));
+        assert!(insert.contains("'TSHIRT:C002:S00L'"));
+        assert!(txn[4].to_string().contains(r#""options" = 3"#), "{}", txn[4]);
+    }
+
+    #[tokio::test]
+    async fn test_generated_variants_insert_in_chunks() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![product()]])
+            .append_query_results([Vec::<std::collections::BTreeMap<String, Value>>::new()])
+            .append_exec_results([
+                MockExecResult { last_insert_id: 0, rows_affected: 500 },
+                MockExecResult { last_insert_id: 0, rows_affected: 400 },
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+            ])
+            .append_query_results([Vec::<Sku>::new()])
+            .into_connection();
+
+        SkuService::generate_variants(&db, 1, "TSHIRT", &many_values(&["C0", "S0"], 30))
+            .await
+            .unwrap();
+
+        let log = db.into_transaction_log();
+        let inserts = log[0]
+            .statements()
+            .iter()
+            .filter(|s| s.sql.starts_with(r#"INSERT INTO "sku_lookup""#))
+            .count();
+        assert_eq!(inserts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_create_derives_code_from_product_and_invopts() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![product()]])
+            .append_query_results([vec![sku("TSHIRT:C001", ":C001")]])
+            .into_connection();
+        let input = SkuInput {
+            title: "Red".to_string(),
+            upc: "012345678905".to_string(),
+            ..Default::default()
+        };
+
+        let created = SkuService::create(&db, 1, "TSHIRT", ":C001", input).await.unwrap();
+        assert_eq!(created.sku, "TSHIRT:C001");
+
+        let log = db.into_transaction_log();
+        assert!(log[1].statements()[0].to_string().contains("'TSHIRT:C001'"));
+    }
+
+    #[tokio::test]
+    async fn test_bad_upc_is_rejected() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let input = SkuInput { upc: "12345".to_string(), ..Default::default() };
+
+        let err = SkuService::update(&db, 1, "TSHIRT", "TSHIRT", input).await.unwrap_err();
+        assert!(matches!(err, ProductError::InvalidSku { field: "upc", .. }));
+    }
+}
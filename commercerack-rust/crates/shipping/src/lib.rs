@@ -1 +1 @@
-fn main() {}
+//! Shipping calculations (to be implemented)
@@ -0,0 +1,36 @@
+//! Amazon document entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "amazon_docs")]
+pub struct Model {
+    pub username: String,
+    pub mid: i32,
+    pub prt: i16,
+    pub doctype: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub docid: i64,
+    pub docbody: String,
+    pub created_gmt: i32,
+    pub retrieved_gmt: i32,
+    pub response_docid: i64,
+    pub response_body: Option<String>,
+    pub resent_docid: Option<i64>,
+    pub attempts: i16,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::amazon_document_contents::Entity")]
+    AmazonDocumentContents,
+}
+
+impl Related<super::amazon_document_contents::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AmazonDocumentContents.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,40 @@
+//! Amazon document content entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::sea_orm_active_enums::AmazonFeed;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "amazon_document_contents")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub mid: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub docid: i64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub msgid: i32,
+    pub feed: Option<AmazonFeed>,
+    pub sku: String,
+    pub created_ts: Option<DateTime>,
+    pub debug: Option<String>,
+    pub ack_gmt: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::amazon_docs::Entity",
+        from = "Column::Docid",
+        to = "super::amazon_docs::Column::Docid"
+    )]
+    AmazonDocs,
+}
+
+impl Related<super::amazon_docs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AmazonDocs.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
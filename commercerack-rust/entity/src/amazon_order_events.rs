@@ -0,0 +1,41 @@
+//! Amazon order event entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::sea_orm_active_enums::AmazonOrderEventType;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "amazon_order_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub username: String,
+    pub mid: i32,
+    pub created: Option<DateTime>,
+    pub r#type: AmazonOrderEventType,
+    pub orderid: String,
+    pub data: Option<String>,
+    pub lock_gmt: i32,
+    pub processed_gmt: i32,
+    pub processed_docid: i32,
+    pub attempts: i16,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid"
+    )]
+    Zusers,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,49 @@
+//! Amazon order entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::sea_orm_active_enums::AmazonShippingMethod;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "amazon_orders")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub mid: i32,
+    pub prt: i16,
+    pub docid: Option<i64>,
+    pub amazon_orderid: String,
+    pub our_orderid: String,
+    pub created_gmt: i32,
+    pub ack_gmt: i32,
+    pub track_gmt: Option<i32>,
+    pub has_tracking: i16,
+    pub order_total: Option<Decimal>,
+    pub dirty: i16,
+    pub posted_gmt: i32,
+    pub shipping_method: Option<AmazonShippingMethod>,
+    pub neworder_ack_processed_gmt: i32,
+    pub neworder_ack_docid: i64,
+    pub fulfillment_ack_requested_gmt: i32,
+    pub fulfillment_ack_processed_gmt: i32,
+    pub fulfillment_ack_docid: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::orders::Entity",
+        from = "(Column::Mid, Column::OurOrderid)",
+        to = "(super::orders::Column::Mid, super::orders::Column::Orderid)"
+    )]
+    Orders,
+}
+
+impl Related<super::orders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Orders.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
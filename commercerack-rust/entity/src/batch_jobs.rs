@@ -0,0 +1,73 @@
+//! Batch job entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::sea_orm_active_enums::BatchJobStatus;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "batch_jobs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub username: String,
+    pub lusername: String,
+    pub mid: i32,
+    pub prt: i16,
+    pub guid: String,
+    pub job_type: String,
+    pub version: Decimal,
+    pub batch_exec: String,
+    pub parameters_uuid: String,
+    pub batch_vars: String,
+    pub created_ts: Option<DateTime>,
+    pub queued_ts: Option<DateTime>,
+    pub start_ts: Option<DateTime>,
+    pub estdone_ts: Option<DateTime>,
+    pub end_ts: Option<DateTime>,
+    pub archived_ts: Option<DateTime>,
+    pub aborted_ts: Option<DateTime>,
+    pub title: String,
+    pub status: Option<BatchJobStatus>,
+    pub status_msg: String,
+    pub records_done: i32,
+    pub records_total: i32,
+    pub records_warn: i32,
+    pub records_error: i32,
+    pub has_slog: i16,
+    pub output_file: String,
+    pub is_running: i32,
+    pub is_crashed: i32,
+    pub is_abortable: i32,
+    pub job_cost_cycles: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid"
+    )]
+    Zusers,
+    #[sea_orm(
+        belongs_to = "super::batch_parameters::Entity",
+        from = "(Column::Mid, Column::ParametersUuid)",
+        to = "(super::batch_parameters::Column::Mid, super::batch_parameters::Column::Uuid)"
+    )]
+    BatchParameters,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl Related<super::batch_parameters::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BatchParameters.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,37 @@
+//! Batch parameters entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "batch_parameters")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub uuid: String,
+    pub mid: i32,
+    pub username: String,
+    pub luser: String,
+    pub title: Option<String>,
+    pub created_ts: Option<DateTime>,
+    pub created_by: String,
+    pub lastrun_ts: Option<DateTime>,
+    pub lastjob_id: i32,
+    pub batch_exec: String,
+    pub apiversion: i32,
+    pub yaml: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::batch_jobs::Entity")]
+    BatchJobs,
+}
+
+impl Related<super::batch_jobs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BatchJobs.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,45 @@
+//! Campaign recipient entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "campaign_recipients")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub mid: i32,
+    pub cid: i64,
+    pub cpg: i32,
+    pub created_gmt: i32,
+    pub sent_gmt: i32,
+    pub opened: i16,
+    pub clicked_gmt: i32,
+    pub opened_gmt: i32,
+    pub unsubscribed: i16,
+    pub bounced: i16,
+    pub locked_gmt: i32,
+    pub locked_pid: i32,
+    pub clicked: i32,
+    pub purchased: i32,
+    pub total_sales: i32,
+    pub purchased_gmt: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::customers::Entity",
+        from = "(Column::Mid, Column::Cid)",
+        to = "(super::customers::Column::Mid, super::customers::Column::Cid)"
+    )]
+    Customers,
+}
+
+impl Related<super::customers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
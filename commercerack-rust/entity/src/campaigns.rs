@@ -0,0 +1,52 @@
+//! Campaign entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::sea_orm_active_enums::{CampaignQueueMode, CampaignStatus};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "campaigns")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub campaignid: String,
+    pub username: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub mid: i32,
+    pub subject: String,
+    pub prt: i16,
+    pub created_ts: Option<DateTime>,
+    pub template_origin: String,
+    pub recipients: Option<String>,
+    pub send_email: i16,
+    pub send_appleios: i16,
+    pub send_android: i16,
+    pub send_facebook: i16,
+    pub send_twitter: i16,
+    pub send_sms: i16,
+    pub queue_mode: Option<CampaignQueueMode>,
+    pub expires: Option<DateTime>,
+    pub coupon: String,
+    pub rss_data: String,
+    pub status: Option<CampaignStatus>,
+    pub starttime: Option<DateTime>,
+    pub jobid: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid"
+    )]
+    Zusers,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
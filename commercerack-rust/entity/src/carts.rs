@@ -0,0 +1,26 @@
+//! Cart entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "carts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub cartid: String,
+    pub mid: i32,
+    /// Serialized cart lines
+    #[sea_orm(column_type = "JsonBinary")]
+    pub items: Json,
+    /// Bumped on every save; used for optimistic concurrency
+    pub version: i64,
+    pub created_gmt: i32,
+    pub modified_gmt: i32,
+    /// Abandoned after this time; see `CartRepository::purge_expired`
+    pub expires_gmt: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,42 @@
+//! Checkout entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::sea_orm_active_enums::CheckoutAssist;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "checkouts")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub mid: i32,
+    pub username: String,
+    pub sdomain: String,
+    pub assist: CheckoutAssist,
+    pub cartid: String,
+    pub cid: i32,
+    pub created_gmt: i32,
+    pub handled_gmt: i32,
+    pub closed_gmt: i32,
+    pub assistid: String,
+    pub checkout_stage: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::customers::Entity",
+        from = "(Column::Mid, Column::Cid)",
+        to = "(super::customers::Column::Mid, super::customers::Column::Cid)"
+    )]
+    Customers,
+}
+
+impl Related<super::customers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
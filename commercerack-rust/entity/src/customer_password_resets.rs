@@ -0,0 +1,38 @@
+//! Customer password reset token entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "customer_password_resets")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub mid: i32,
+    pub cid: i32,
+    /// Hex SHA-256 of the reset token
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub created_gmt: i32,
+    pub expires_gmt: i32,
+    /// Set once the token was redeemed or superseded
+    pub used_gmt: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::customers::Entity",
+        from = "(Column::Mid, Column::Cid)",
+        to = "(super::customers::Column::Mid, super::customers::Column::Cid)"
+    )]
+    Customers,
+}
+
+impl Related<super::customers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
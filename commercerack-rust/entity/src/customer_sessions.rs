@@ -0,0 +1,41 @@
+//! Customer session (refresh token) entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "customer_sessions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub mid: i32,
+    pub cid: i32,
+    /// Shared by every token rotated from the same login
+    pub family: String,
+    /// Hex SHA-256 of the refresh token
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub created_gmt: i32,
+    pub expires_gmt: i32,
+    /// Set once the token was exchanged for a new one
+    pub rotated_gmt: i32,
+    pub revoked_gmt: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::customers::Entity",
+        from = "(Column::Mid, Column::Cid)",
+        to = "(super::customers::Column::Mid, super::customers::Column::Cid)"
+    )]
+    Customers,
+}
+
+impl Related<super::customers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
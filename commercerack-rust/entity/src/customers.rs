@@ -3,22 +3,104 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "customers")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub cid: i32,
+    pub orgid: i32,
     pub mid: i32,
+    pub username: String,
+    pub prt: i16,
     pub email: String,
+    /// Legacy plaintext password; cleared when the password is rehashed
+    pub password: String,
+    /// Argon2 PHC string, or a legacy salted digest
+    pub passhash: String,
+    /// Salt of a legacy digest; Argon2 hashes carry their own
+    pub passsalt: String,
     pub firstname: String,
     pub lastname: String,
+    pub phone: String,
     pub created_gmt: i32,
     pub modified_gmt: i32,
-    pub passhash: String,
-    pub passsalt: String,
+    pub lastlogin_gmt: i32,
+    pub lastorder_gmt: i32,
+    pub order_count: i16,
+    /// Newsletter subscription bitmask
+    pub newsletter: Option<i32>,
+    pub optin_gmt: i32,
+    pub hint_num: i16,
+    pub hint_answer: String,
+    /// Failed logins since the last successful one or lockout
+    pub hint_attempts: i16,
+    /// IPv4 address packed into an integer
+    pub ip: i32,
+    pub origin: i16,
+    pub schedule: String,
+    pub has_notes: i16,
+    pub reward_balance: Option<i32>,
+    pub is_affiliate: i16,
+    /// Locked by an admin until unlocked
+    pub is_locked: i16,
+    /// Locked after too many failed logins until this time; 0 when not
+    pub locked_until_gmt: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid"
+    )]
+    Zusers,
+    #[sea_orm(has_many = "super::customer_addrs::Entity")]
+    CustomerAddrs,
+    #[sea_orm(has_many = "super::customer_notes::Entity")]
+    CustomerNotes,
+    #[sea_orm(has_many = "super::orders::Entity")]
+    Orders,
+    #[sea_orm(has_many = "super::customer_sessions::Entity")]
+    CustomerSessions,
+    #[sea_orm(has_many = "super::customer_password_resets::Entity")]
+    CustomerPasswordResets,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl Related<super::customer_addrs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CustomerAddrs.def()
+    }
+}
+
+impl Related<super::customer_notes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CustomerNotes.def()
+    }
+}
+
+impl Related<super::orders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Orders.def()
+    }
+}
+
+impl Related<super::customer_sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CustomerSessions.def()
+    }
+}
+
+impl Related<super::customer_password_resets::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CustomerPasswordResets.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,42 @@
+//! Inventory count line entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "inventory_count_lines")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub count_id: i32,
+    pub sku: String,
+    /// Bin as `GEO:ZONE:POS`, empty for the plain shelf
+    pub location: String,
+    /// Ledger quantity when counted
+    pub expected: i32,
+    /// `None` until counted
+    pub counted: Option<i32>,
+    pub counted_by: String,
+    pub counted_gmt: i32,
+    /// Units written back to the ledger on approval; `None` if not applied
+    pub adjusted: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::inventory_counts::Entity",
+        from = "Column::CountId",
+        to = "super::inventory_counts::Column::Id",
+        on_delete = "Cascade"
+    )]
+    InventoryCounts,
+}
+
+impl Related<super::inventory_counts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::InventoryCounts.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,53 @@
+//! Inventory count (cycle count session) entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "inventory_counts")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub mid: i32,
+    /// OPEN, APPROVED or CANCELLED
+    pub status: String,
+    /// Warehouse and zone counted, for a zone count
+    pub wms_geo: Option<String>,
+    pub wms_zone: Option<String>,
+    /// SKUs counted, for a SKU count
+    #[sea_orm(column_type = "JsonBinary")]
+    pub skus: Json,
+    pub note: String,
+    pub created_by: String,
+    pub created_gmt: i32,
+    pub closed_by: String,
+    /// 0 while open
+    pub closed_gmt: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid",
+        on_delete = "Cascade"
+    )]
+    Zusers,
+    #[sea_orm(has_many = "super::inventory_count_lines::Entity")]
+    InventoryCountLines,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl Related<super::inventory_count_lines::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::InventoryCountLines.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
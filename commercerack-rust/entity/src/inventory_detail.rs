@@ -0,0 +1,72 @@
+//! Inventory detail entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::sea_orm_active_enums::{InventoryBasetype, InventoryPickRoute, InventoryVendorStatus};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "inventory_detail")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub uuid: String,
+    pub mid: i32,
+    pub pid: String,
+    pub sku: String,
+    pub wms_geo: Option<String>,
+    pub wms_zone: Option<String>,
+    pub wms_pos: Option<String>,
+    pub qty: i32,
+    /// Unit cost in integer cents
+    pub cost_i: i32,
+    pub note: String,
+    pub container: String,
+    pub origin: String,
+    pub basetype: Option<InventoryBasetype>,
+    pub supplier_id: Option<String>,
+    pub supplier_sku: String,
+    pub market_dst: Option<String>,
+    pub market_refid: String,
+    pub market_ends_ts: Option<DateTime>,
+    pub market_sold_qty: i32,
+    pub market_sale_ts: Option<DateTime>,
+    pub preference: i16,
+    pub created_ts: Option<DateTime>,
+    pub modified_ts: Option<DateTime>,
+    pub modified_by: String,
+    pub modified_inc: i64,
+    pub modified_qty_was: i32,
+    pub verify_ts: Option<DateTime>,
+    pub verify_inc: i32,
+    pub our_orderid: String,
+    pub pick_batchid: String,
+    pub pick_route: Option<InventoryPickRoute>,
+    pub pick_done_ts: Option<DateTime>,
+    pub grpasm_ref: Option<String>,
+    pub description: String,
+    pub vendor_status: Option<InventoryVendorStatus>,
+    pub vendor: String,
+    pub vendor_order_dbid: i32,
+    pub vendor_sku: String,
+    /// When a cart's HOLD row lapses
+    pub hold_expires_ts: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::sku_lookup::Entity",
+        from = "(Column::Mid, Column::Sku)",
+        to = "(super::sku_lookup::Column::Mid, super::sku_lookup::Column::Sku)"
+    )]
+    SkuLookup,
+}
+
+impl Related<super::sku_lookup::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SkuLookup.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,40 @@
+//! Inventory log entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "inventory_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub mid: i32,
+    pub pid: String,
+    pub sku: String,
+    pub created_gmt: i32,
+    pub qty: i32,
+    pub qty_before: i32,
+    pub action: String,
+    pub luser: String,
+    pub note: String,
+    pub orderid: String,
+    pub uuid: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::sku_lookup::Entity",
+        from = "(Column::Mid, Column::Sku)",
+        to = "(super::sku_lookup::Column::Mid, super::sku_lookup::Column::Sku)"
+    )]
+    SkuLookup,
+}
+
+impl Related<super::sku_lookup::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SkuLookup.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -3,8 +3,37 @@
 //! This crate contains all database entity definitions for CommerceRack.
 
 pub mod customers;
+pub mod customer_addrs;
+pub mod customer_notes;
+pub mod customer_password_resets;
+pub mod customer_sessions;
 pub mod products;
+pub mod product_relations;
+pub mod sku_lookup;
 pub mod orders;
+pub mod order_events;
+pub mod order_counters;
+pub mod checkouts;
+pub mod carts;
+pub mod inventory_detail;
+pub mod inventory_log;
+pub mod inventory_counts;
+pub mod inventory_count_lines;
+pub mod suppliers;
+pub mod purchase_orders;
+pub mod purchase_order_lines;
+pub mod zusers;
+pub mod merchant_api_keys;
+pub mod amazon_docs;
+pub mod amazon_document_contents;
+pub mod amazon_orders;
+pub mod amazon_order_events;
+pub mod batch_jobs;
+pub mod batch_parameters;
+pub mod campaigns;
+pub mod campaign_recipients;
+pub mod projects;
+pub mod sea_orm_active_enums;
 
 pub mod prelude;
 
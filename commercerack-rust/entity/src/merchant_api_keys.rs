@@ -0,0 +1,46 @@
+//! Merchant API key entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "merchant_api_keys")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub mid: i32,
+    pub name: String,
+    /// Leading characters of the key, safe to show in listings
+    pub key_prefix: String,
+    /// Hex SHA-256 of the key
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    /// Comma-separated scopes, e.g. `catalog:read,orders:write`
+    pub scopes: String,
+    pub created_gmt: i32,
+    /// 0 = never expires
+    pub expires_gmt: i32,
+    pub last_used_gmt: i32,
+    pub revoked_gmt: i32,
+    /// Key issued by rotating this one, 0 if not rotated
+    pub replaced_by: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid",
+        on_delete = "Cascade"
+    )]
+    Zusers,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+//! Order counter entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "order_counters")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub mid: i32,
+    pub merchant: String,
+    pub counter: Option<i32>,
+    pub last_pid: Option<i32>,
+    pub last_server: String,
+    /// Order ID template, e.g. `{YYYY}-{MM}-{N:5}`; empty for the legacy format
+    pub orderid_format: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid"
+    )]
+    Zusers,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,41 @@
+//! Order event entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "order_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_gmt: i32,
+    pub mid: i32,
+    /// User or process that raised the event
+    pub username: String,
+    pub prt: i16,
+    pub orderid: String,
+    pub event: String,
+    pub lock_id: i16,
+    pub lock_gmt: i32,
+    pub attempts: i16,
+    /// Free text, e.g. the reason for a pool transition
+    pub note: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::orders::Entity",
+        from = "(Column::Mid, Column::Orderid)",
+        to = "(super::orders::Column::Mid, super::orders::Column::Orderid)"
+    )]
+    Orders,
+}
+
+impl Related<super::orders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Orders.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -3,23 +3,96 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+use super::sea_orm_active_enums::OrderPool;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "orders")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
+    pub merchant: String,
     pub mid: i32,
+    pub prt: i16,
     pub orderid: String,
-    pub cartid: String,
-    pub customer: i32,
-    pub pool: String,
-    pub total: Decimal,
+    pub bs_settlement: i32,
+    pub v: Option<i16>,
     pub created_gmt: i32,
-    pub paid_gmt: Option<i32>,
-    pub shipped_gmt: Option<i32>,
+    pub modified_gmt: i32,
+    pub paid_gmt: i32,
+    pub paid_txn: String,
+    pub inv_gmt: i32,
+    pub shipped_gmt: i32,
+    pub synced_gmt: i32,
+    pub customer: i32,
+    pub pool: OrderPool,
+    pub order_bill_name: String,
+    pub order_bill_email: String,
+    pub order_bill_zone: String,
+    pub order_bill_phone: String,
+    pub order_ship_name: String,
+    pub order_ship_zone: String,
+    pub review_status: String,
+    /// Legacy three-character payment status code; first digit 0 means paid
+    pub order_payment_status: String,
+    pub order_payment_method: String,
+    pub order_payment_lookup: String,
+    pub order_erefid: Option<String>,
+    pub order_total: Decimal,
+    pub order_special: String,
+    pub ship_method: String,
+    /// Marketplace bitmask
+    pub mkt: Option<i32>,
+    pub mkt_bitstr: String,
+    pub flags: i32,
+    pub items: i16,
+    /// Serialized order data (items, addresses, etc)
+    pub yaml: String,
+    pub cartid: Option<String>,
+    pub sdomain: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid"
+    )]
+    Zusers,
+    #[sea_orm(
+        belongs_to = "super::customers::Entity",
+        from = "(Column::Mid, Column::Customer)",
+        to = "(super::customers::Column::Mid, super::customers::Column::Cid)"
+    )]
+    Customers,
+    #[sea_orm(has_many = "super::order_events::Entity")]
+    OrderEvents,
+    #[sea_orm(has_many = "super::amazon_orders::Entity")]
+    AmazonOrders,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl Related<super::customers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customers.def()
+    }
+}
+
+impl Related<super::order_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrderEvents.def()
+    }
+}
+
+impl Related<super::amazon_orders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AmazonOrders.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
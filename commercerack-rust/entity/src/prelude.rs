@@ -1,5 +1,34 @@
 //! Entity prelude - re-exports commonly used types
 
 pub use super::customers::{Entity as Customers, Model as Customer};
+pub use super::customer_addrs::{Entity as CustomerAddrs, Model as CustomerAddr};
+pub use super::customer_notes::{Entity as CustomerNotes, Model as CustomerNote};
+pub use super::customer_password_resets::{Entity as CustomerPasswordResets, Model as CustomerPasswordReset};
+pub use super::customer_sessions::{Entity as CustomerSessions, Model as CustomerSession};
 pub use super::products::{Entity as Products, Model as Product};
+pub use super::product_relations::{Entity as ProductRelations, Model as ProductRelation};
+pub use super::sku_lookup::{Entity as SkuLookup, Model as Sku};
 pub use super::orders::{Entity as Orders, Model as Order};
+pub use super::order_events::{Entity as OrderEvents, Model as OrderEvent};
+pub use super::order_counters::{Entity as OrderCounters, Model as OrderCounter};
+pub use super::carts::{Entity as Carts, Model as Cart};
+pub use super::checkouts::{Entity as Checkouts, Model as Checkout};
+pub use super::inventory_detail::{Entity as InventoryDetails, Model as InventoryDetail};
+pub use super::inventory_log::{Entity as InventoryLog, Model as InventoryLogEntry};
+pub use super::inventory_counts::{Entity as InventoryCounts, Model as InventoryCount};
+pub use super::inventory_count_lines::{Entity as InventoryCountLines, Model as InventoryCountLine};
+pub use super::suppliers::{Entity as Suppliers, Model as Supplier};
+pub use super::purchase_orders::{Entity as PurchaseOrders, Model as PurchaseOrder};
+pub use super::purchase_order_lines::{Entity as PurchaseOrderLines, Model as PurchaseOrderLine};
+pub use super::zusers::{Entity as Zusers, Model as Zuser};
+pub use super::merchant_api_keys::{Entity as MerchantApiKeys, Model as MerchantApiKey};
+pub use super::amazon_docs::{Entity as AmazonDocs, Model as AmazonDoc};
+pub use super::amazon_document_contents::{Entity as AmazonDocumentContents, Model as AmazonDocumentContent};
+pub use super::amazon_orders::{Entity as AmazonOrders, Model as AmazonOrder};
+pub use super::amazon_order_events::{Entity as AmazonOrderEvents, Model as AmazonOrderEvent};
+pub use super::batch_jobs::{Entity as BatchJobs, Model as BatchJob};
+pub use super::batch_parameters::{Entity as BatchParameters, Model as BatchParameter};
+pub use super::campaigns::{Entity as Campaigns, Model as Campaign};
+pub use super::campaign_recipients::{Entity as CampaignRecipients, Model as CampaignRecipient};
+pub use super::projects::{Entity as Projects, Model as Project};
+pub use super::sea_orm_active_enums::*;
@@ -0,0 +1,39 @@
+//! Product relation entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "product_relations")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub mid: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub pid: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub child_pid: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub relation: String,
+    pub qty: i16,
+    pub is_active: i16,
+    pub list_pos: i16,
+    pub created_gmt: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::products::Entity",
+        from = "(Column::Mid, Column::Pid)",
+        to = "(super::products::Column::Mid, super::products::Column::Product)"
+    )]
+    Products,
+}
+
+impl Related<super::products::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Products.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -3,7 +3,7 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "products")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -14,16 +14,57 @@ pub struct Model {
     pub ts: i32,
     pub product_name: String,
     pub category: String,
-    pub base_price: Decimal,
-    pub base_cost: Decimal,
-    pub supplier: String,
-    pub supplier_id: String,
-    pub upc: String,
+    /// YAML serialized product data
+    pub data: String,
+    pub salesrank: i32,
     pub created_gmt: i32,
-    pub lastsold_gmt: Option<i32>,
+    pub lastsold_gmt: i32,
+    pub base_price: Option<Decimal>,
+    pub base_cost: Option<Decimal>,
+    pub supplier: Option<String>,
+    pub supplier_id: Option<String>,
+    pub mfg: Option<String>,
+    pub mfg_id: Option<String>,
+    pub upc: String,
+    /// Option group bitmask; non-zero when the product has SKU variants
+    pub options: i32,
+    pub profile: String,
+    pub mkt: i64,
+    pub prod_is: i32,
+    pub mkt_bitstr: String,
+    pub mkterr_bitstr: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid"
+    )]
+    Zusers,
+    #[sea_orm(has_many = "super::sku_lookup::Entity")]
+    SkuLookup,
+    #[sea_orm(has_many = "super::product_relations::Entity")]
+    ProductRelations,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl Related<super::sku_lookup::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SkuLookup.def()
+    }
+}
+
+impl Related<super::product_relations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProductRelations.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,48 @@
+//! Project entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::sea_orm_active_enums::ProjectType;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "projects")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_ts: Option<DateTime>,
+    pub updated_ts: Option<DateTime>,
+    pub mid: i32,
+    pub username: String,
+    pub title: String,
+    pub uuid: String,
+    pub secret: String,
+    pub r#type: ProjectType,
+    pub github_repo: String,
+    pub github_branch: String,
+    pub github_txlog: String,
+    pub app_release: String,
+    pub app_version: String,
+    pub app_seo: String,
+    pub app_expire: String,
+    pub app_force_secure: i16,
+    pub app_root: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid"
+    )]
+    Zusers,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
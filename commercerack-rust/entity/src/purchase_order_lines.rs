@@ -0,0 +1,40 @@
+//! Purchase order line entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "purchase_order_lines")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub purchase_order_id: i32,
+    pub sku: String,
+    /// Supplier's part number
+    pub vendor_sku: String,
+    pub qty: i32,
+    pub qty_received: i32,
+    /// Unit cost in integer cents
+    pub cost_i: i32,
+    /// ONORDER row in `inventory_detail` once ordered, empty before
+    pub uuid: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::purchase_orders::Entity",
+        from = "Column::PurchaseOrderId",
+        to = "super::purchase_orders::Column::Id",
+        on_delete = "Cascade"
+    )]
+    PurchaseOrders,
+}
+
+impl Related<super::purchase_orders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PurchaseOrders.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,65 @@
+//! Purchase order entity definition
+
+use super::sea_orm_active_enums::InventoryVendorStatus;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "purchase_orders")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub mid: i32,
+    pub supplier_id: i32,
+    /// NEW while drafted, then ONORDER, CONFIRMED and RECEIVED, or CANCELLED
+    pub status: InventoryVendorStatus,
+    /// Supplier's own order or confirmation number
+    pub reference: String,
+    pub note: String,
+    pub created_by: String,
+    pub created_gmt: i32,
+    pub ordered_gmt: i32,
+    pub confirmed_gmt: i32,
+    /// When fully received or cancelled
+    pub closed_gmt: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid",
+        on_delete = "Cascade"
+    )]
+    Zusers,
+    #[sea_orm(
+        belongs_to = "super::suppliers::Entity",
+        from = "Column::SupplierId",
+        to = "super::suppliers::Column::Id",
+        on_delete = "Restrict"
+    )]
+    Suppliers,
+    #[sea_orm(has_many = "super::purchase_order_lines::Entity")]
+    PurchaseOrderLines,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl Related<super::suppliers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Suppliers.def()
+    }
+}
+
+impl Related<super::purchase_order_lines::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PurchaseOrderLines.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
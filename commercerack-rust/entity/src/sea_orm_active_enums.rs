@@ -0,0 +1,382 @@
+//! Postgres enum types shared by several entities
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Workflow pool an order sits in (`order_pool_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "order_pool_enum")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OrderPool {
+    #[sea_orm(string_value = "RECENT")]
+    Recent,
+    #[sea_orm(string_value = "REVIEW")]
+    Review,
+    #[sea_orm(string_value = "HOLD")]
+    Hold,
+    #[sea_orm(string_value = "PENDING")]
+    Pending,
+    #[sea_orm(string_value = "APPROVED")]
+    Approved,
+    #[sea_orm(string_value = "PROCESS")]
+    Process,
+    #[sea_orm(string_value = "COMPLETED")]
+    Completed,
+    #[sea_orm(string_value = "DELETED")]
+    Deleted,
+    #[sea_orm(string_value = "QUOTE")]
+    Quote,
+    #[sea_orm(string_value = "BACKORDER")]
+    Backorder,
+    #[sea_orm(string_value = "PREORDER")]
+    Preorder,
+    #[sea_orm(string_value = "ARCHIVE")]
+    Archive,
+}
+
+impl OrderPool {
+    /// Name as stored in the database, e.g. `RECENT`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Recent => "RECENT",
+            Self::Review => "REVIEW",
+            Self::Hold => "HOLD",
+            Self::Pending => "PENDING",
+            Self::Approved => "APPROVED",
+            Self::Process => "PROCESS",
+            Self::Completed => "COMPLETED",
+            Self::Deleted => "DELETED",
+            Self::Quote => "QUOTE",
+            Self::Backorder => "BACKORDER",
+            Self::Preorder => "PREORDER",
+            Self::Archive => "ARCHIVE",
+        }
+    }
+}
+
+impl std::fmt::Display for OrderPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Amazon feed a document line belongs to (`amazon_feed_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "amazon_feed_enum")]
+#[serde(rename_all = "lowercase")]
+pub enum AmazonFeed {
+    #[sea_orm(string_value = "init")]
+    Init,
+    #[sea_orm(string_value = "products")]
+    Products,
+    #[sea_orm(string_value = "prices")]
+    Prices,
+    #[sea_orm(string_value = "images")]
+    Images,
+    #[sea_orm(string_value = "inventory")]
+    Inventory,
+    #[sea_orm(string_value = "relations")]
+    Relations,
+    #[sea_orm(string_value = "shipping")]
+    Shipping,
+    #[sea_orm(string_value = "deleted")]
+    Deleted,
+}
+
+/// Acknowledgement queued for an Amazon order (`amazon_order_event_type_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "amazon_order_event_type_enum")]
+#[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+pub enum AmazonOrderEventType {
+    #[sea_orm(string_value = "ORDER-ACK")]
+    OrderAck,
+    #[sea_orm(string_value = "FULFILL-ACK")]
+    FulfillAck,
+    #[sea_orm(string_value = "")]
+    #[serde(rename = "")]
+    Blank,
+}
+
+/// Amazon shipping service level (`amazon_shipping_method_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "amazon_shipping_method_enum")]
+pub enum AmazonShippingMethod {
+    #[sea_orm(string_value = "Standard")]
+    Standard,
+    #[sea_orm(string_value = "Expedited")]
+    Expedited,
+    #[sea_orm(string_value = "Scheduled")]
+    Scheduled,
+    #[sea_orm(string_value = "NextDay")]
+    NextDay,
+    #[sea_orm(string_value = "SecondDay")]
+    SecondDay,
+    #[sea_orm(string_value = "Unknown")]
+    Unknown,
+}
+
+/// Batch job lifecycle state (`batch_job_status_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "batch_job_status_enum")]
+#[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+pub enum BatchJobStatus {
+    #[sea_orm(string_value = "NEW")]
+    New,
+    #[sea_orm(string_value = "HOLD")]
+    Hold,
+    #[sea_orm(string_value = "QUEUED")]
+    Queued,
+    #[sea_orm(string_value = "RUNNING")]
+    Running,
+    #[sea_orm(string_value = "ABORTING")]
+    Aborting,
+    #[sea_orm(string_value = "END")]
+    End,
+    #[sea_orm(string_value = "END-ABORT")]
+    EndAbort,
+    #[sea_orm(string_value = "END-SUCCESS")]
+    EndSuccess,
+    #[sea_orm(string_value = "END-WARNINGS")]
+    EndWarnings,
+    #[sea_orm(string_value = "END-ERRORS")]
+    EndErrors,
+    #[sea_orm(string_value = "END-CRASHED")]
+    EndCrashed,
+}
+
+/// Where a campaign is queued relative to others (`campaign_queue_mode_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "campaign_queue_mode_enum")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CampaignQueueMode {
+    #[sea_orm(string_value = "FRONT")]
+    Front,
+    #[sea_orm(string_value = "BACK")]
+    Back,
+    #[sea_orm(string_value = "SINGLE")]
+    Single,
+}
+
+/// Campaign send state (`campaign_status_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "campaign_status_enum")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CampaignStatus {
+    #[sea_orm(string_value = "NEW")]
+    New,
+    #[sea_orm(string_value = "WAITING")]
+    Waiting,
+    #[sea_orm(string_value = "SENDING")]
+    Sending,
+    #[sea_orm(string_value = "FINISHED")]
+    Finished,
+}
+
+/// Assistance requested during checkout (`checkout_assist_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "checkout_assist_enum")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CheckoutAssist {
+    #[sea_orm(string_value = "NONE")]
+    None,
+    #[sea_orm(string_value = "CALL")]
+    Call,
+    #[sea_orm(string_value = "CHAT")]
+    Chat,
+    #[sea_orm(string_value = "")]
+    #[serde(rename = "")]
+    Blank,
+}
+
+/// Dynamic sourcing agent mood (`dss_mood_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "dss_mood_enum")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DssMood {
+    #[sea_orm(string_value = "WINNING")]
+    Winning,
+    #[sea_orm(string_value = "HAPPY")]
+    Happy,
+    #[sea_orm(string_value = "ZEN")]
+    Zen,
+    #[sea_orm(string_value = "MEDITATING")]
+    Meditating,
+    #[sea_orm(string_value = "SLEEPY")]
+    Sleepy,
+    #[sea_orm(string_value = "GRUMPY")]
+    Grumpy,
+    #[sea_orm(string_value = "DEPRESSED")]
+    Depressed,
+    #[sea_orm(string_value = "UNHAPPY")]
+    Unhappy,
+    #[sea_orm(string_value = "ANGRY")]
+    Angry,
+    #[sea_orm(string_value = "SUICIDAL")]
+    Suicidal,
+}
+
+/// Dynamic sourcing run state (a MySQL SET in the legacy schema) (`dss_run_set`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "dss_run_set")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DssRun {
+    #[sea_orm(string_value = "ENABLED")]
+    Enabled,
+    #[sea_orm(string_value = "UNLEASHED")]
+    Unleashed,
+    #[sea_orm(string_value = "PAUSED")]
+    Paused,
+    #[sea_orm(string_value = "HALTED")]
+    Halted,
+}
+
+/// Inventory record state / lifecycle stage (`inventory_basetype_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "inventory_basetype_enum")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum InventoryBasetype {
+    #[sea_orm(string_value = "SIMPLE")]
+    Simple,
+    #[sea_orm(string_value = "RETURN")]
+    Return,
+    #[sea_orm(string_value = "WMS")]
+    Wms,
+    #[sea_orm(string_value = "SUPPLIER")]
+    Supplier,
+    #[sea_orm(string_value = "ITEM")]
+    Item,
+    #[sea_orm(string_value = "UNPAID")]
+    Unpaid,
+    #[sea_orm(string_value = "PURCHASE")]
+    Purchase,
+    #[sea_orm(string_value = "HOLD")]
+    Hold,
+    #[sea_orm(string_value = "PICK")]
+    Pick,
+    #[sea_orm(string_value = "PICKED")]
+    Picked,
+    #[sea_orm(string_value = "DONE")]
+    Done,
+    #[sea_orm(string_value = "SHIPPED")]
+    Shipped,
+    #[sea_orm(string_value = "CANCEL")]
+    Cancel,
+    #[sea_orm(string_value = "OVERSOLD")]
+    Oversold,
+    #[sea_orm(string_value = "BACKORDER")]
+    Backorder,
+    #[sea_orm(string_value = "ERROR")]
+    Error,
+    #[sea_orm(string_value = "PREORDER")]
+    Preorder,
+    #[sea_orm(string_value = "ONORDER")]
+    Onorder,
+    #[sea_orm(string_value = "MARKET")]
+    Market,
+    #[sea_orm(string_value = "CLAIM")]
+    Claim,
+    #[sea_orm(string_value = "CONSTANT")]
+    Constant,
+    #[sea_orm(string_value = "_ASM_")]
+    #[serde(rename = "_ASM_")]
+    Asm,
+}
+
+/// How an inventory record is picked (`inventory_pick_route_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "inventory_pick_route_enum")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum InventoryPickRoute {
+    #[sea_orm(string_value = "")]
+    #[serde(rename = "")]
+    Blank,
+    #[sea_orm(string_value = "NEW")]
+    New,
+    #[sea_orm(string_value = "TBD")]
+    Tbd,
+    #[sea_orm(string_value = "SIMPLE")]
+    Simple,
+    #[sea_orm(string_value = "WMS")]
+    Wms,
+    #[sea_orm(string_value = "SUPPLIER")]
+    Supplier,
+    #[sea_orm(string_value = "PARTNER")]
+    Partner,
+}
+
+/// Supplier order state of an inventory record (`inventory_vendor_status_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "inventory_vendor_status_enum")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InventoryVendorStatus {
+    #[sea_orm(string_value = "NEW")]
+    New,
+    #[sea_orm(string_value = "MANUAL_DISPATCH")]
+    ManualDispatch,
+    #[sea_orm(string_value = "ADDED")]
+    Added,
+    #[sea_orm(string_value = "ONORDER")]
+    Onorder,
+    #[sea_orm(string_value = "CONFIRMED")]
+    Confirmed,
+    #[sea_orm(string_value = "RECEIVED")]
+    Received,
+    #[sea_orm(string_value = "RETURNED")]
+    Returned,
+    #[sea_orm(string_value = "FINISHED")]
+    Finished,
+    #[sea_orm(string_value = "CANCELLED")]
+    Cancelled,
+    #[sea_orm(string_value = "CORRUPT")]
+    Corrupt,
+}
+
+/// Kind of app/storefront project (`project_type_enum`)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "project_type_enum")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ProjectType {
+    #[sea_orm(string_value = "APP")]
+    App,
+    #[sea_orm(string_value = "VSTORE")]
+    Vstore,
+    #[sea_orm(string_value = "ADMIN")]
+    Admin,
+    #[sea_orm(string_value = "CHECKOUT")]
+    Checkout,
+    #[sea_orm(string_value = "DSS")]
+    Dss,
+    #[sea_orm(string_value = "TEMPLATE")]
+    Template,
+    #[sea_orm(string_value = "")]
+    #[serde(rename = "")]
+    Blank,
+}
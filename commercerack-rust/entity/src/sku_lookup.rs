@@ -0,0 +1,86 @@
+//! SKU lookup entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::sea_orm_active_enums::{DssMood, DssRun};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "sku_lookup")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub mid: i32,
+    pub pid: String,
+    pub invopts: String,
+    pub grp_parent: String,
+    pub sku: String,
+    pub title: String,
+    pub cost: Decimal,
+    pub price: Decimal,
+    pub upc: String,
+    pub mfgid: String,
+    pub supplierid: String,
+    pub prodasm: Option<String>,
+    pub assembly: Option<String>,
+    pub inv_available: i32,
+    pub qty_onshelf: i32,
+    pub qty_onorder: i32,
+    pub qty_needship: i32,
+    pub qty_markets: i32,
+    pub qty_legacy: i32,
+    pub qty_reserved: i32,
+    pub amz_asin: String,
+    pub amz_feeds_done: i16,
+    pub amz_feeds_todo: i16,
+    pub amz_feeds_sent: i16,
+    pub amz_feeds_wait: i16,
+    pub amz_feeds_warn: i16,
+    pub amz_feeds_error: i16,
+    pub amz_productdb_gmt: i32,
+    pub amz_error: String,
+    pub inv_on_shelf: i32,
+    pub inv_on_order: i32,
+    pub inv_is_bo: i32,
+    pub inv_reorder: i32,
+    pub inv_is_rsvp: i32,
+    pub dss_agent: String,
+    /// MySQL SET in the legacy schema; only one value is kept here
+    pub dss_run: Option<DssRun>,
+    pub dss_mood: Option<DssMood>,
+    pub dss_config: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::products::Entity",
+        from = "(Column::Mid, Column::Pid)",
+        to = "(super::products::Column::Mid, super::products::Column::Product)"
+    )]
+    Products,
+    #[sea_orm(has_many = "super::inventory_detail::Entity")]
+    InventoryDetail,
+    #[sea_orm(has_many = "super::inventory_log::Entity")]
+    InventoryLog,
+}
+
+impl Related<super::products::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Products.def()
+    }
+}
+
+impl Related<super::inventory_detail::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::InventoryDetail.def()
+    }
+}
+
+impl Related<super::inventory_log::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::InventoryLog.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,49 @@
+//! Supplier entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "suppliers")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub mid: i32,
+    /// Short code, stored as `inventory_detail.vendor` and `products.supplier`
+    pub code: String,
+    pub name: String,
+    pub email: String,
+    pub phone: String,
+    pub note: String,
+    /// Usual days from ordering to delivery
+    pub lead_days: i32,
+    pub created_gmt: i32,
+    pub modified_gmt: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::zusers::Entity",
+        from = "Column::Mid",
+        to = "super::zusers::Column::Mid",
+        on_delete = "Cascade"
+    )]
+    Zusers,
+    #[sea_orm(has_many = "super::purchase_orders::Entity")]
+    PurchaseOrders,
+}
+
+impl Related<super::zusers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zusers.def()
+    }
+}
+
+impl Related<super::purchase_orders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PurchaseOrders.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,87 @@
+//! Merchant account (zuser) entity definition
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "zusers")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub mid: i32,
+    pub username: String,
+    pub password: String,
+    pub password_changed: Option<DateTime>,
+    pub reseller: String,
+    pub created: Option<DateTime>,
+    pub last_login: Option<DateTime>,
+    pub logins: i32,
+    pub cached_flags: String,
+    pub email: String,
+    pub phone: String,
+    pub salesperson: String,
+    pub tech_contact: String,
+    pub overduenotify_gmt: i32,
+    pub ipaddr: String,
+    pub data: String,
+    pub sugarguid: String,
+    pub bill_day: i16,
+    pub bill_package: String,
+    pub bill_provisioned: Option<Date>,
+    pub bill_nextrun: Option<Date>,
+    pub bill_lastexec: Option<DateTime>,
+    pub bill_orderdate: Option<DateTime>,
+    pub invoice_count: i16,
+    pub bill_lock_id: i32,
+    pub bill_lock_gmt: i32,
+    pub bill_customrates: String,
+    pub bill_pricing_revision: i16,
+    pub bpp_member: i16,
+    pub bpp_lastcheck_gmt: i32,
+    pub published_file: String,
+    pub published_gmt: i32,
+    pub bpp_review_count: i16,
+    pub cluster: String,
+    pub bs_returndays: i16,
+    pub tkts_available: i16,
+    pub tkts_used: i16,
+    pub tkts_lastused_gmt: i32,
+    pub is_newbie: i16,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::customers::Entity")]
+    Customers,
+    #[sea_orm(has_many = "super::products::Entity")]
+    Products,
+    #[sea_orm(has_many = "super::orders::Entity")]
+    Orders,
+    #[sea_orm(has_many = "super::merchant_api_keys::Entity")]
+    MerchantApiKeys,
+}
+
+impl Related<super::customers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customers.def()
+    }
+}
+
+impl Related<super::products::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Products.def()
+    }
+}
+
+impl Related<super::orders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Orders.def()
+    }
+}
+
+impl Related<super::merchant_api_keys::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MerchantApiKeys.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,186 @@
+//! Entity ↔ migration drift check
+//!
+//! Runs every migration against a throwaway database and verifies that each
+//! entity column exists with a compatible type and nullability.
+//!
+//! Needs a Postgres server: `TEST_DATABASE_URL=postgres://user@host cargo test -p entity`
+
+use entity::*;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{
+    ColumnTrait, ColumnType, ConnectionTrait, Database, DatabaseConnection, IdenStatic,
+    EntityTrait, FromQueryResult, Iterable, Statement,
+};
+use std::collections::HashMap;
+
+#[derive(Debug, FromQueryResult)]
+struct DbColumn {
+    table_name: String,
+    column_name: String,
+    udt_name: String,
+    is_nullable: String,
+}
+
+/// Same server as `url`, different database
+fn with_database(url: &str, name: &str) -> String {
+    let (base, query) = url.split_once('?').map_or((url, None), |(b, q)| (b, Some(q)));
+    let authority = base.find("://").map_or(0, |i| i + 3);
+    let server = match base[authority..].find('/') {
+        Some(i) => &base[..authority + i],
+        None => base,
+    };
+    match query {
+        Some(q) => format!("{}/{}?{}", server, name, q),
+        None => format!("{}/{}", server, name),
+    }
+}
+
+/// Postgres `udt_name`s a SeaORM column type can be read from and written to
+fn compatible_udts(column_type: &ColumnType) -> Vec<String> {
+    let names: &[&str] = match column_type {
+        ColumnType::SmallInteger => &["int2"],
+        ColumnType::Integer => &["int4"],
+        ColumnType::BigInteger => &["int8"],
+        ColumnType::String(_) | ColumnType::Char(_) | ColumnType::Text => {
+            &["varchar", "bpchar", "text"]
+        }
+        ColumnType::Decimal(_) | ColumnType::Money(_) => &["numeric"],
+        ColumnType::DateTime | ColumnType::Timestamp => &["timestamp"],
+        ColumnType::Date => &["date"],
+        ColumnType::Uuid => &["uuid"],
+        ColumnType::Json | ColumnType::JsonBinary => &["json", "jsonb"],
+        ColumnType::Enum { name, .. } => return vec![name.to_string()],
+        _ => &[],
+    };
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+fn check_entity<E: EntityTrait>(
+    entity: E,
+    db_columns: &HashMap<(String, String), DbColumn>,
+    problems: &mut Vec<String>,
+) {
+    let table = entity.table_name();
+    for column in E::Column::iter() {
+        let name = column.as_str();
+        let Some(db_column) = db_columns.get(&(table.to_string(), name.to_string())) else {
+            problems.push(format!("{}.{}: column missing", table, name));
+            continue;
+        };
+
+        let def = column.def();
+        let udts = compatible_udts(def.get_column_type());
+        if !udts.contains(&db_column.udt_name) {
+            problems.push(format!(
+                "{}.{}: entity {:?} vs database {}",
+                table,
+                name,
+                def.get_column_type(),
+                db_column.udt_name
+            ));
+        }
+
+        let db_nullable = db_column.is_nullable == "YES";
+        if def.is_null() != db_nullable {
+            problems.push(format!(
+                "{}.{}: entity nullable={} vs database nullable={}",
+                table,
+                name,
+                def.is_null(),
+                db_nullable
+            ));
+        }
+    }
+}
+
+async fn check_all(db: &DatabaseConnection) -> Vec<String> {
+    let rows = DbColumn::find_by_statement(Statement::from_string(
+        db.get_database_backend(),
+        "SELECT table_name::text, column_name::text, udt_name::text, is_nullable::text \
+         FROM information_schema.columns WHERE table_schema = 'public'",
+    ))
+    .all(db)
+    .await
+    .expect("read information_schema");
+
+    let db_columns: HashMap<_, _> = rows
+        .into_iter()
+        .map(|c| ((c.table_name.clone(), c.column_name.clone()), c))
+        .collect();
+
+    let mut problems = Vec::new();
+    check_entity(zusers::Entity, &db_columns, &mut problems);
+    check_entity(merchant_api_keys::Entity, &db_columns, &mut problems);
+    check_entity(customers::Entity, &db_columns, &mut problems);
+    check_entity(customer_addrs::Entity, &db_columns, &mut problems);
+    check_entity(customer_notes::Entity, &db_columns, &mut problems);
+    check_entity(customer_password_resets::Entity, &db_columns, &mut problems);
+    check_entity(customer_sessions::Entity, &db_columns, &mut problems);
+    check_entity(products::Entity, &db_columns, &mut problems);
+    check_entity(product_relations::Entity, &db_columns, &mut problems);
+    check_entity(sku_lookup::Entity, &db_columns, &mut problems);
+    check_entity(orders::Entity, &db_columns, &mut problems);
+    check_entity(order_events::Entity, &db_columns, &mut problems);
+    check_entity(order_counters::Entity, &db_columns, &mut problems);
+    check_entity(checkouts::Entity, &db_columns, &mut problems);
+    check_entity(carts::Entity, &db_columns, &mut problems);
+    check_entity(inventory_detail::Entity, &db_columns, &mut problems);
+    check_entity(inventory_log::Entity, &db_columns, &mut problems);
+    check_entity(inventory_counts::Entity, &db_columns, &mut problems);
+    check_entity(inventory_count_lines::Entity, &db_columns, &mut problems);
+    check_entity(suppliers::Entity, &db_columns, &mut problems);
+    check_entity(purchase_orders::Entity, &db_columns, &mut problems);
+    check_entity(purchase_order_lines::Entity, &db_columns, &mut problems);
+    check_entity(amazon_docs::Entity, &db_columns, &mut problems);
+    check_entity(amazon_document_contents::Entity, &db_columns, &mut problems);
+    check_entity(amazon_orders::Entity, &db_columns, &mut problems);
+    check_entity(amazon_order_events::Entity, &db_columns, &mut problems);
+    check_entity(batch_jobs::Entity, &db_columns, &mut problems);
+    check_entity(batch_parameters::Entity, &db_columns, &mut problems);
+    check_entity(campaigns::Entity, &db_columns, &mut problems);
+    check_entity(campaign_recipients::Entity, &db_columns, &mut problems);
+    check_entity(projects::Entity, &db_columns, &mut problems);
+    problems
+}
+
+#[test]
+fn test_with_database() {
+    assert_eq!(
+        with_database("postgres://u:p@localhost:5432/dev?sslmode=disable", "t1"),
+        "postgres://u:p@localhost:5432/t1?sslmode=disable"
+    );
+    assert_eq!(with_database("postgres://localhost", "t1"), "postgres://localhost/t1");
+}
+
+#[tokio::test]
+async fn test_entities_match_migrated_schema() {
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("Skipping test: TEST_DATABASE_URL not set");
+        return;
+    };
+
+    let name = format!("entity_schema_{}", uuid::Uuid::new_v4().simple());
+    let admin = Database::connect(url.as_str()).await.expect("connect to server");
+    admin
+        .execute_unprepared(&format!("CREATE DATABASE {}", name))
+        .await
+        .expect("create throwaway database");
+
+    let db = Database::connect(with_database(&url, &name))
+        .await
+        .expect("connect to throwaway database");
+    let migrated = Migrator::up(&db, None).await;
+    let problems = match &migrated {
+        Ok(()) => check_all(&db).await,
+        Err(_) => Vec::new(),
+    };
+    db.close().await.ok();
+
+    admin
+        .execute_unprepared(&format!("DROP DATABASE {} WITH (FORCE)", name))
+        .await
+        .expect("drop throwaway database");
+
+    migrated.expect("migrations apply to an empty database");
+    assert!(problems.is_empty(), "schema drift:\n{}", problems.join("\n"));
+}
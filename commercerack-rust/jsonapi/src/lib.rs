@@ -1 +1 @@
-fn main() {}
+//! JSON API server (to be implemented)
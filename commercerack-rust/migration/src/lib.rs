@@ -1,4 +1,5 @@
 pub use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
 
 mod m20251117_000001_create_zusers;
 mod m20251117_000002_create_customers;
@@ -22,6 +23,23 @@ mod m20251117_000019_create_campaigns;
 mod m20251117_000020_create_campaign_recipients;
 mod m20251117_000021_create_projects;
 mod m20251117_000022_create_checkouts;
+mod m20251118_000001_order_pool_enum;
+mod m20251118_000002_reconcile_schema;
+mod m20251119_000001_create_carts;
+mod m20251119_000002_add_cart_mid;
+mod m20251120_000001_widen_orders_cartid;
+mod m20251120_000002_add_orderid_format;
+mod m20251121_000001_create_customer_sessions;
+mod m20251121_000002_create_merchant_api_keys;
+mod m20251122_000001_single_default_customer_addr;
+mod m20251122_000002_customer_login_security;
+mod m20251122_000003_widen_customer_passhash;
+mod m20251123_000001_index_sku_lookup_mfgid;
+mod m20251124_000001_seed_inventory_ledger;
+mod m20251125_000001_inventory_hold_expiry;
+mod m20251126_000001_create_inventory_counts;
+mod m20251127_000001_create_purchasing;
+mod m20251128_000001_drop_zero_date_defaults;
 
 pub struct Migrator;
 
@@ -51,6 +69,33 @@ impl MigratorTrait for Migrator {
             Box::new(m20251117_000020_create_campaign_recipients::Migration),
             Box::new(m20251117_000021_create_projects::Migration),
             Box::new(m20251117_000022_create_checkouts::Migration),
+            Box::new(m20251118_000001_order_pool_enum::Migration),
+            Box::new(m20251118_000002_reconcile_schema::Migration),
+            Box::new(m20251119_000001_create_carts::Migration),
+            Box::new(m20251119_000002_add_cart_mid::Migration),
+            Box::new(m20251120_000001_widen_orders_cartid::Migration),
+            Box::new(m20251120_000002_add_orderid_format::Migration),
+            Box::new(m20251121_000001_create_customer_sessions::Migration),
+            Box::new(m20251121_000002_create_merchant_api_keys::Migration),
+            Box::new(m20251122_000001_single_default_customer_addr::Migration),
+            Box::new(m20251122_000002_customer_login_security::Migration),
+            Box::new(m20251122_000003_widen_customer_passhash::Migration),
+            Box::new(m20251123_000001_index_sku_lookup_mfgid::Migration),
+            Box::new(m20251124_000001_seed_inventory_ledger::Migration),
+            Box::new(m20251125_000001_inventory_hold_expiry::Migration),
+            Box::new(m20251126_000001_create_inventory_counts::Migration),
+            Box::new(m20251127_000001_create_purchasing::Migration),
+            Box::new(m20251128_000001_drop_zero_date_defaults::Migration),
         ]
     }
 }
+
+/// MySQL's zero date, the legacy "never" default. Postgres rejects it, so
+/// there the column is created without one, as
+/// m20251128_000001_drop_zero_date_defaults leaves it everywhere.
+pub(crate) fn zero_date(manager: &SchemaManager, zero: &str) -> SimpleExpr {
+    match manager.get_database_backend() {
+        DbBackend::MySql => zero.into(),
+        _ => SimpleExpr::Keyword(Keyword::Null),
+    }
+}
@@ -1,5 +1,7 @@
 use sea_orm_migration::prelude::*;
 
+use crate::zero_date;
+
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -25,7 +27,7 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(Zusers::PasswordChanged)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(Zusers::Reseller)
@@ -36,7 +38,7 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(Zusers::LastLogin)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(Zusers::Logins)
@@ -102,25 +104,25 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(Zusers::BillProvisioned)
                             .date()
                             .null()
-                            .default("0000-00-00")
+                            .default(zero_date(manager, "0000-00-00"))
                     )
                     .col(
                         ColumnDef::new(Zusers::BillNextrun)
                             .date()
                             .null()
-                            .default("0000-00-00")
+                            .default(zero_date(manager, "0000-00-00"))
                     )
                     .col(
                         ColumnDef::new(Zusers::BillLastexec)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(Zusers::BillOrderdate)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(Zusers::InvoiceCount)
@@ -1,5 +1,7 @@
 use sea_orm_migration::prelude::*;
 
+use crate::zero_date;
+
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -100,7 +102,7 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(InventoryDetail::MarketEndsTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(InventoryDetail::MarketSoldQty)
@@ -111,7 +113,7 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(InventoryDetail::MarketSaleTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(InventoryDetail::Preference)
@@ -122,7 +124,7 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(InventoryDetail::ModifiedTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(InventoryDetail::ModifiedBy)
@@ -143,7 +145,7 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(InventoryDetail::VerifyTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(InventoryDetail::VerifyInc)
@@ -169,7 +171,7 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(InventoryDetail::PickDoneTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(InventoryDetail::GrpasmRef)
@@ -1,5 +1,7 @@
 use sea_orm_migration::prelude::*;
 
+use crate::zero_date;
+
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -66,37 +68,37 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(BatchJobs::QueuedTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(BatchJobs::StartTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(BatchJobs::EstdoneTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(BatchJobs::EndTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(BatchJobs::ArchivedTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(BatchJobs::AbortedTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(BatchJobs::Title)
@@ -1,5 +1,7 @@
 use sea_orm_migration::prelude::*;
 
+use crate::zero_date;
+
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -40,7 +42,7 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(BatchParameters::LastrunTs)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(BatchParameters::LastjobId)
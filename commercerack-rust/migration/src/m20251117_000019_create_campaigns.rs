@@ -1,5 +1,7 @@
 use sea_orm_migration::prelude::*;
 
+use crate::zero_date;
+
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -85,7 +87,7 @@ impl MigrationTrait for Migration {
                         ColumnDef::new(Campaigns::Expires)
                             .timestamp()
                             .null()
-                            .default("0000-00-00 00:00:00")
+                            .default(zero_date(manager, "0000-00-00 00:00:00"))
                     )
                     .col(
                         ColumnDef::new(Campaigns::Coupon)
@@ -0,0 +1,134 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Pools an order moves through, in `order_pool_enum` declaration order
+const POOLS: [OrderPoolEnum; 12] = [
+    OrderPoolEnum::Recent,
+    OrderPoolEnum::Review,
+    OrderPoolEnum::Hold,
+    OrderPoolEnum::Pending,
+    OrderPoolEnum::Approved,
+    OrderPoolEnum::Process,
+    OrderPoolEnum::Completed,
+    OrderPoolEnum::Deleted,
+    OrderPoolEnum::Quote,
+    OrderPoolEnum::Backorder,
+    OrderPoolEnum::Preorder,
+    OrderPoolEnum::Archive,
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(OrderPoolEnum::Enum)
+                    .values(POOLS)
+                    .to_owned(),
+            )
+            .await?;
+
+        // orders.pool was created as a plain string; blank legacy values become RECENT
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"ALTER TABLE "orders"
+                ALTER COLUMN "pool" TYPE order_pool_enum
+                    USING COALESCE(NULLIF("pool", ''), 'RECENT')::order_pool_enum,
+                ALTER COLUMN "pool" SET DEFAULT 'RECENT',
+                ALTER COLUMN "pool" SET NOT NULL"#,
+        )
+        .await?;
+
+        // Pool transitions are recorded in order_events, which needs a key,
+        // a timestamp and somewhere to keep the reason for the move
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderEvents::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(OrderEvents::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .add_column_if_not_exists(
+                        ColumnDef::new(OrderEvents::CreatedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column_if_not_exists(ColumnDef::new(OrderEvents::Note).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderEvents::Table)
+                    .drop_column(OrderEvents::Note)
+                    .drop_column(OrderEvents::CreatedGmt)
+                    .drop_column(OrderEvents::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"ALTER TABLE "orders"
+                ALTER COLUMN "pool" DROP NOT NULL,
+                ALTER COLUMN "pool" DROP DEFAULT,
+                ALTER COLUMN "pool" TYPE varchar USING "pool"::text"#,
+        )
+        .await?;
+
+        manager
+            .drop_type(Type::drop().name(OrderPoolEnum::Enum).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OrderEvents {
+    Table,
+    Id,
+    CreatedGmt,
+    Note,
+}
+
+#[derive(DeriveIden)]
+enum OrderPoolEnum {
+    #[sea_orm(iden = "order_pool_enum")]
+    Enum,
+    #[sea_orm(iden = "RECENT")]
+    Recent,
+    #[sea_orm(iden = "REVIEW")]
+    Review,
+    #[sea_orm(iden = "HOLD")]
+    Hold,
+    #[sea_orm(iden = "PENDING")]
+    Pending,
+    #[sea_orm(iden = "APPROVED")]
+    Approved,
+    #[sea_orm(iden = "PROCESS")]
+    Process,
+    #[sea_orm(iden = "COMPLETED")]
+    Completed,
+    #[sea_orm(iden = "DELETED")]
+    Deleted,
+    #[sea_orm(iden = "QUOTE")]
+    Quote,
+    #[sea_orm(iden = "BACKORDER")]
+    Backorder,
+    #[sea_orm(iden = "PREORDER")]
+    Preorder,
+    #[sea_orm(iden = "ARCHIVE")]
+    Archive,
+}
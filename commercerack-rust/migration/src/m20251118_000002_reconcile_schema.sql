@@ -0,0 +1,943 @@
+-- Reconcile the tables created by m20251117_000001..000022 with
+-- migrations/001_initial_schema.sql.
+--
+-- The generated create-table migrations dropped primary keys, serial ids,
+-- created_* columns, NOT NULL constraints, defaults, enum and decimal types,
+-- and every index. This brings each table back in line with the legacy
+-- schema so the SeaORM entities map 1:1 onto it. Existing rows are kept:
+-- NULLs are backfilled with the column default before NOT NULL is applied.
+--
+-- Known differences from 001_initial_schema.sql:
+--   * order_pool_enum has no '' label (see m20251118_000001)
+--   * order_events.note holds the reason for pool transitions
+--   * order_counters.mid is the primary key
+
+-- Enum types from 001_initial_schema.sql (order_pool_enum already exists)
+CREATE TYPE amazon_feed_enum AS ENUM ('init', 'products', 'prices', 'images', 'inventory', 'relations', 'shipping', 'deleted');
+CREATE TYPE amazon_order_event_type_enum AS ENUM ('ORDER-ACK', 'FULFILL-ACK', '');
+CREATE TYPE amazon_shipping_method_enum AS ENUM ('Standard', 'Expedited', 'Scheduled', 'NextDay', 'SecondDay', 'Unknown');
+CREATE TYPE batch_job_status_enum AS ENUM ('NEW', 'HOLD', 'QUEUED', 'RUNNING', 'ABORTING', 'END', 'END-ABORT', 'END-SUCCESS', 'END-WARNINGS', 'END-ERRORS', 'END-CRASHED');
+CREATE TYPE campaign_queue_mode_enum AS ENUM ('FRONT', 'BACK', 'SINGLE');
+CREATE TYPE campaign_status_enum AS ENUM ('NEW', 'WAITING', 'SENDING', 'FINISHED');
+CREATE TYPE checkout_assist_enum AS ENUM ('NONE', 'CALL', 'CHAT', '');
+CREATE TYPE dss_mood_enum AS ENUM ('WINNING', 'HAPPY', 'ZEN', 'MEDITATING', 'SLEEPY', 'GRUMPY', 'DEPRESSED', 'UNHAPPY', 'ANGRY', 'SUICIDAL');
+CREATE TYPE dss_run_set AS ENUM ('ENABLED', 'UNLEASHED', 'PAUSED', 'HALTED');
+CREATE TYPE inventory_basetype_enum AS ENUM ('SIMPLE', 'RETURN', 'WMS', 'SUPPLIER', 'ITEM', 'UNPAID', 'PURCHASE', 'HOLD', 'PICK', 'PICKED', 'DONE', 'SHIPPED', 'CANCEL', 'OVERSOLD', 'BACKORDER', 'ERROR', 'PREORDER', 'ONORDER', 'MARKET', 'CLAIM', 'CONSTANT', '_ASM_');
+CREATE TYPE inventory_pick_route_enum AS ENUM ('', 'NEW', 'TBD', 'SIMPLE', 'WMS', 'SUPPLIER', 'PARTNER');
+CREATE TYPE inventory_vendor_status_enum AS ENUM ('NEW', 'MANUAL_DISPATCH', 'ADDED', 'ONORDER', 'CONFIRMED', 'RECEIVED', 'RETURNED', 'FINISHED', 'CANCELLED', 'CORRUPT');
+CREATE TYPE project_type_enum AS ENUM ('APP', 'VSTORE', 'ADMIN', 'CHECKOUT', 'DSS', 'TEMPLATE', '');
+
+-- amazon_docs
+ALTER TABLE amazon_docs
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0;
+UPDATE amazon_docs SET
+    username = COALESCE(username, ''),
+    mid = COALESCE(mid, 0),
+    prt = COALESCE(prt, 0),
+    doctype = COALESCE(doctype, ''),
+    docid = COALESCE(docid, 0),
+    docbody = COALESCE(docbody, ''),
+    retrieved_gmt = COALESCE(retrieved_gmt, 0),
+    response_docid = COALESCE(response_docid, 0),
+    attempts = COALESCE(attempts, 0);
+ALTER TABLE amazon_docs
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN prt SET DEFAULT 0, ALTER COLUMN prt SET NOT NULL,
+    ALTER COLUMN doctype SET DEFAULT '', ALTER COLUMN doctype SET NOT NULL,
+    ALTER COLUMN docid SET DEFAULT 0, ALTER COLUMN docid SET NOT NULL,
+    ALTER COLUMN docbody SET DEFAULT '', ALTER COLUMN docbody SET NOT NULL,
+    ALTER COLUMN retrieved_gmt SET DEFAULT 0, ALTER COLUMN retrieved_gmt SET NOT NULL,
+    ALTER COLUMN response_docid SET DEFAULT 0, ALTER COLUMN response_docid SET NOT NULL,
+    ALTER COLUMN attempts SET DEFAULT 0, ALTER COLUMN attempts SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_amazon_docs_docid ON amazon_docs (docid);
+CREATE INDEX IF NOT EXISTS idx_amazon_docs_mid_retrieved ON amazon_docs (mid, retrieved_gmt);
+
+-- amazon_document_contents
+ALTER TABLE amazon_document_contents
+    ALTER COLUMN feed TYPE amazon_feed_enum USING NULLIF(feed, '')::amazon_feed_enum,
+    ADD COLUMN created_ts TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+UPDATE amazon_document_contents SET
+    mid = COALESCE(mid, 0),
+    docid = COALESCE(docid, 0),
+    msgid = COALESCE(msgid, 0),
+    sku = COALESCE(sku, ''),
+    ack_gmt = COALESCE(ack_gmt, 0);
+ALTER TABLE amazon_document_contents
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN docid SET DEFAULT 0, ALTER COLUMN docid SET NOT NULL,
+    ALTER COLUMN msgid SET DEFAULT 0, ALTER COLUMN msgid SET NOT NULL,
+    ALTER COLUMN sku SET DEFAULT '', ALTER COLUMN sku SET NOT NULL,
+    ALTER COLUMN ack_gmt SET DEFAULT 0, ALTER COLUMN ack_gmt SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_amazon_document_contents_unique ON amazon_document_contents (mid, docid, msgid);
+
+-- amazon_order_events
+ALTER TABLE amazon_order_events
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ADD COLUMN created TIMESTAMP,
+    ALTER COLUMN type TYPE amazon_order_event_type_enum USING type::amazon_order_event_type_enum;
+UPDATE amazon_order_events SET
+    username = COALESCE(username, ''),
+    mid = COALESCE(mid, 0),
+    type = COALESCE(type, ''),
+    orderid = COALESCE(orderid, ''),
+    lock_gmt = COALESCE(lock_gmt, 0),
+    processed_gmt = COALESCE(processed_gmt, 0),
+    processed_docid = COALESCE(processed_docid, 0),
+    attempts = COALESCE(attempts, 0);
+ALTER TABLE amazon_order_events
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN type SET DEFAULT '', ALTER COLUMN type SET NOT NULL,
+    ALTER COLUMN orderid SET DEFAULT '', ALTER COLUMN orderid SET NOT NULL,
+    ALTER COLUMN lock_gmt SET DEFAULT 0, ALTER COLUMN lock_gmt SET NOT NULL,
+    ALTER COLUMN processed_gmt SET DEFAULT 0, ALTER COLUMN processed_gmt SET NOT NULL,
+    ALTER COLUMN processed_docid SET DEFAULT 0, ALTER COLUMN processed_docid SET NOT NULL,
+    ALTER COLUMN attempts SET DEFAULT 0, ALTER COLUMN attempts SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_amazon_order_events_lock ON amazon_order_events (lock_gmt);
+CREATE INDEX IF NOT EXISTS idx_amazon_order_events_processed ON amazon_order_events (processed_gmt);
+
+-- amazon_orders
+ALTER TABLE amazon_orders
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0,
+    ALTER COLUMN order_total TYPE DECIMAL(10,2) USING NULLIF(TRIM(order_total), '')::numeric,
+    ALTER COLUMN shipping_method TYPE amazon_shipping_method_enum USING NULLIF(shipping_method, '')::amazon_shipping_method_enum;
+UPDATE amazon_orders SET
+    mid = COALESCE(mid, 0),
+    prt = COALESCE(prt, 0),
+    amazon_orderid = COALESCE(amazon_orderid, ''),
+    our_orderid = COALESCE(our_orderid, ''),
+    ack_gmt = COALESCE(ack_gmt, 0),
+    has_tracking = COALESCE(has_tracking, 0),
+    dirty = COALESCE(dirty, 0),
+    posted_gmt = COALESCE(posted_gmt, 0),
+    neworder_ack_processed_gmt = COALESCE(neworder_ack_processed_gmt, 0),
+    neworder_ack_docid = COALESCE(neworder_ack_docid, 0),
+    fulfillment_ack_requested_gmt = COALESCE(fulfillment_ack_requested_gmt, 0),
+    fulfillment_ack_processed_gmt = COALESCE(fulfillment_ack_processed_gmt, 0),
+    fulfillment_ack_docid = COALESCE(fulfillment_ack_docid, 0);
+ALTER TABLE amazon_orders
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN prt SET DEFAULT 0, ALTER COLUMN prt SET NOT NULL,
+    ALTER COLUMN amazon_orderid SET DEFAULT '', ALTER COLUMN amazon_orderid SET NOT NULL,
+    ALTER COLUMN our_orderid SET DEFAULT '', ALTER COLUMN our_orderid SET NOT NULL,
+    ALTER COLUMN ack_gmt SET DEFAULT 0, ALTER COLUMN ack_gmt SET NOT NULL,
+    ALTER COLUMN track_gmt SET DEFAULT 0,
+    ALTER COLUMN has_tracking SET DEFAULT 0, ALTER COLUMN has_tracking SET NOT NULL,
+    ALTER COLUMN dirty SET DEFAULT 0, ALTER COLUMN dirty SET NOT NULL,
+    ALTER COLUMN posted_gmt SET DEFAULT 0, ALTER COLUMN posted_gmt SET NOT NULL,
+    ALTER COLUMN shipping_method SET DEFAULT 'Unknown',
+    ALTER COLUMN neworder_ack_processed_gmt SET DEFAULT 0, ALTER COLUMN neworder_ack_processed_gmt SET NOT NULL,
+    ALTER COLUMN neworder_ack_docid SET DEFAULT 0, ALTER COLUMN neworder_ack_docid SET NOT NULL,
+    ALTER COLUMN fulfillment_ack_requested_gmt SET DEFAULT 0, ALTER COLUMN fulfillment_ack_requested_gmt SET NOT NULL,
+    ALTER COLUMN fulfillment_ack_processed_gmt SET DEFAULT 0, ALTER COLUMN fulfillment_ack_processed_gmt SET NOT NULL,
+    ALTER COLUMN fulfillment_ack_docid SET DEFAULT 0, ALTER COLUMN fulfillment_ack_docid SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_amazon_orders_amazon_orderid ON amazon_orders (amazon_orderid);
+CREATE INDEX IF NOT EXISTS idx_amazon_orders_fulfillment_ack ON amazon_orders (fulfillment_ack_processed_gmt, fulfillment_ack_requested_gmt);
+CREATE INDEX IF NOT EXISTS idx_amazon_orders_mid_prt_docid ON amazon_orders (mid, prt, docid);
+CREATE INDEX IF NOT EXISTS idx_amazon_orders_mid_prt_orderid ON amazon_orders (mid, prt, our_orderid);
+CREATE INDEX IF NOT EXISTS idx_amazon_orders_neworder_ack ON amazon_orders (neworder_ack_processed_gmt);
+
+-- batch_jobs
+ALTER TABLE batch_jobs
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ALTER COLUMN version DROP DEFAULT,
+    ALTER COLUMN version TYPE DECIMAL(6,0) USING NULLIF(TRIM(version), '')::numeric,
+    ADD COLUMN created_ts TIMESTAMP,
+    ALTER COLUMN status TYPE batch_job_status_enum USING NULLIF(status, '')::batch_job_status_enum;
+UPDATE batch_jobs SET
+    username = COALESCE(username, ''),
+    lusername = COALESCE(lusername, ''),
+    mid = COALESCE(mid, 0),
+    prt = COALESCE(prt, 0),
+    guid = COALESCE(guid, ''),
+    job_type = COALESCE(job_type, ''),
+    batch_exec = COALESCE(batch_exec, ''),
+    parameters_uuid = COALESCE(parameters_uuid, ''),
+    batch_vars = COALESCE(batch_vars, ''),
+    title = COALESCE(title, ''),
+    status_msg = COALESCE(status_msg, ''),
+    records_done = COALESCE(records_done, 0),
+    records_total = COALESCE(records_total, 0),
+    records_warn = COALESCE(records_warn, 0),
+    records_error = COALESCE(records_error, 0),
+    has_slog = COALESCE(has_slog, 0),
+    output_file = COALESCE(output_file, ''),
+    is_running = COALESCE(is_running, 0),
+    is_crashed = COALESCE(is_crashed, 0),
+    is_abortable = COALESCE(is_abortable, 0),
+    job_cost_cycles = COALESCE(job_cost_cycles, 0);
+ALTER TABLE batch_jobs
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN lusername SET DEFAULT '', ALTER COLUMN lusername SET NOT NULL,
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN prt SET DEFAULT 0, ALTER COLUMN prt SET NOT NULL,
+    ALTER COLUMN guid SET DEFAULT '', ALTER COLUMN guid SET NOT NULL,
+    ALTER COLUMN job_type SET DEFAULT '', ALTER COLUMN job_type SET NOT NULL,
+    ALTER COLUMN batch_exec SET DEFAULT '', ALTER COLUMN batch_exec SET NOT NULL,
+    ALTER COLUMN version SET DEFAULT 0,
+    ALTER COLUMN parameters_uuid SET DEFAULT '', ALTER COLUMN parameters_uuid SET NOT NULL,
+    ALTER COLUMN batch_vars SET DEFAULT '', ALTER COLUMN batch_vars SET NOT NULL,
+    ALTER COLUMN title SET DEFAULT '', ALTER COLUMN title SET NOT NULL,
+    ALTER COLUMN status_msg SET DEFAULT '', ALTER COLUMN status_msg SET NOT NULL,
+    ALTER COLUMN records_done SET DEFAULT 0, ALTER COLUMN records_done SET NOT NULL,
+    ALTER COLUMN records_total SET DEFAULT 0, ALTER COLUMN records_total SET NOT NULL,
+    ALTER COLUMN records_warn SET DEFAULT 0, ALTER COLUMN records_warn SET NOT NULL,
+    ALTER COLUMN records_error SET DEFAULT 0, ALTER COLUMN records_error SET NOT NULL,
+    ALTER COLUMN has_slog SET DEFAULT 0, ALTER COLUMN has_slog SET NOT NULL,
+    ALTER COLUMN output_file SET DEFAULT '', ALTER COLUMN output_file SET NOT NULL,
+    ALTER COLUMN is_running SET DEFAULT 0, ALTER COLUMN is_running SET NOT NULL,
+    ALTER COLUMN is_crashed SET DEFAULT 0, ALTER COLUMN is_crashed SET NOT NULL,
+    ALTER COLUMN is_abortable SET DEFAULT 0, ALTER COLUMN is_abortable SET NOT NULL,
+    ALTER COLUMN job_cost_cycles SET DEFAULT 0, ALTER COLUMN job_cost_cycles SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_batch_jobs_created_status ON batch_jobs (created_ts, status);
+CREATE INDEX IF NOT EXISTS idx_batch_jobs_mid_exec_end ON batch_jobs (mid, batch_exec, end_ts);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_batch_jobs_username_guid ON batch_jobs (username, guid);
+
+-- batch_parameters
+ALTER TABLE batch_parameters
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ADD COLUMN created_ts TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN created_by VARCHAR(10) NOT NULL DEFAULT '';
+UPDATE batch_parameters SET
+    uuid = COALESCE(uuid, ''),
+    mid = COALESCE(mid, 0),
+    username = COALESCE(username, ''),
+    luser = COALESCE(luser, ''),
+    lastjob_id = COALESCE(lastjob_id, 0),
+    batch_exec = COALESCE(batch_exec, ''),
+    apiversion = COALESCE(apiversion, 0),
+    yaml = COALESCE(yaml, '');
+ALTER TABLE batch_parameters
+    ALTER COLUMN uuid SET DEFAULT '', ALTER COLUMN uuid SET NOT NULL,
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN luser SET DEFAULT '', ALTER COLUMN luser SET NOT NULL,
+    ALTER COLUMN lastjob_id SET DEFAULT 0, ALTER COLUMN lastjob_id SET NOT NULL,
+    ALTER COLUMN batch_exec SET DEFAULT '', ALTER COLUMN batch_exec SET NOT NULL,
+    ALTER COLUMN apiversion SET DEFAULT 0, ALTER COLUMN apiversion SET NOT NULL,
+    ALTER COLUMN yaml SET DEFAULT '', ALTER COLUMN yaml SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_batch_parameters_mid_luser ON batch_parameters (mid, luser);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_batch_parameters_mid_uuid ON batch_parameters (mid, uuid);
+
+-- campaign_recipients
+ALTER TABLE campaign_recipients
+    ADD COLUMN id BIGSERIAL PRIMARY KEY,
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0;
+UPDATE campaign_recipients SET
+    mid = COALESCE(mid, 0),
+    cid = COALESCE(cid, 0),
+    cpg = COALESCE(cpg, 0),
+    sent_gmt = COALESCE(sent_gmt, 0),
+    opened = COALESCE(opened, 0),
+    clicked_gmt = COALESCE(clicked_gmt, 0),
+    opened_gmt = COALESCE(opened_gmt, 0),
+    unsubscribed = COALESCE(unsubscribed, 0),
+    bounced = COALESCE(bounced, 0),
+    locked_gmt = COALESCE(locked_gmt, 0),
+    locked_pid = COALESCE(locked_pid, 0),
+    clicked = COALESCE(clicked, 0),
+    purchased = COALESCE(purchased, 0),
+    total_sales = COALESCE(total_sales, 0);
+ALTER TABLE campaign_recipients
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN cid SET DEFAULT 0, ALTER COLUMN cid SET NOT NULL,
+    ALTER COLUMN cpg SET DEFAULT 0, ALTER COLUMN cpg SET NOT NULL,
+    ALTER COLUMN sent_gmt SET DEFAULT 0, ALTER COLUMN sent_gmt SET NOT NULL,
+    ALTER COLUMN opened SET DEFAULT 0, ALTER COLUMN opened SET NOT NULL,
+    ALTER COLUMN clicked_gmt SET DEFAULT 0, ALTER COLUMN clicked_gmt SET NOT NULL,
+    ALTER COLUMN opened_gmt SET DEFAULT 0, ALTER COLUMN opened_gmt SET NOT NULL,
+    ALTER COLUMN unsubscribed SET DEFAULT 0, ALTER COLUMN unsubscribed SET NOT NULL,
+    ALTER COLUMN bounced SET DEFAULT 0, ALTER COLUMN bounced SET NOT NULL,
+    ALTER COLUMN locked_gmt SET DEFAULT 0, ALTER COLUMN locked_gmt SET NOT NULL,
+    ALTER COLUMN locked_pid SET DEFAULT 0, ALTER COLUMN locked_pid SET NOT NULL,
+    ALTER COLUMN clicked SET DEFAULT 0, ALTER COLUMN clicked SET NOT NULL,
+    ALTER COLUMN purchased SET DEFAULT 0, ALTER COLUMN purchased SET NOT NULL,
+    ALTER COLUMN total_sales SET DEFAULT 0, ALTER COLUMN total_sales SET NOT NULL,
+    ALTER COLUMN purchased_gmt SET DEFAULT 0;
+CREATE INDEX IF NOT EXISTS idx_campaign_recipients_created_sent ON campaign_recipients (created_gmt, sent_gmt);
+CREATE INDEX IF NOT EXISTS idx_campaign_recipients_locked_gmt ON campaign_recipients (locked_gmt);
+CREATE INDEX IF NOT EXISTS idx_campaign_recipients_locked_pid ON campaign_recipients (locked_pid, locked_gmt);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_campaign_recipients_unique ON campaign_recipients (cpg, cid, mid);
+
+-- campaigns
+ALTER TABLE campaigns
+    ADD COLUMN created_ts TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    ALTER COLUMN queue_mode TYPE campaign_queue_mode_enum USING NULLIF(queue_mode, '')::campaign_queue_mode_enum,
+    ALTER COLUMN status TYPE campaign_status_enum USING NULLIF(status, '')::campaign_status_enum;
+UPDATE campaigns SET
+    campaignid = COALESCE(campaignid, ''),
+    username = COALESCE(username, ''),
+    mid = COALESCE(mid, 0),
+    subject = COALESCE(subject, ''),
+    prt = COALESCE(prt, 0),
+    template_origin = COALESCE(template_origin, ''),
+    send_email = COALESCE(send_email, 0),
+    send_appleios = COALESCE(send_appleios, 0),
+    send_android = COALESCE(send_android, 0),
+    send_facebook = COALESCE(send_facebook, 0),
+    send_twitter = COALESCE(send_twitter, 0),
+    send_sms = COALESCE(send_sms, 0),
+    coupon = COALESCE(coupon, ''),
+    rss_data = COALESCE(rss_data, ''),
+    jobid = COALESCE(jobid, 0);
+ALTER TABLE campaigns
+    ALTER COLUMN campaignid SET DEFAULT '', ALTER COLUMN campaignid SET NOT NULL,
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN subject SET DEFAULT '', ALTER COLUMN subject SET NOT NULL,
+    ALTER COLUMN prt SET DEFAULT 0, ALTER COLUMN prt SET NOT NULL,
+    ALTER COLUMN template_origin SET DEFAULT '', ALTER COLUMN template_origin SET NOT NULL,
+    ALTER COLUMN send_email SET DEFAULT 0, ALTER COLUMN send_email SET NOT NULL,
+    ALTER COLUMN send_appleios SET DEFAULT 0, ALTER COLUMN send_appleios SET NOT NULL,
+    ALTER COLUMN send_android SET DEFAULT 0, ALTER COLUMN send_android SET NOT NULL,
+    ALTER COLUMN send_facebook SET DEFAULT 0, ALTER COLUMN send_facebook SET NOT NULL,
+    ALTER COLUMN send_twitter SET DEFAULT 0, ALTER COLUMN send_twitter SET NOT NULL,
+    ALTER COLUMN send_sms SET DEFAULT 0, ALTER COLUMN send_sms SET NOT NULL,
+    ALTER COLUMN queue_mode SET DEFAULT 'FRONT',
+    ALTER COLUMN coupon SET DEFAULT '', ALTER COLUMN coupon SET NOT NULL,
+    ALTER COLUMN rss_data SET DEFAULT '', ALTER COLUMN rss_data SET NOT NULL,
+    ALTER COLUMN status SET DEFAULT 'NEW',
+    ALTER COLUMN starttime SET DEFAULT CURRENT_TIMESTAMP,
+    ALTER COLUMN jobid SET DEFAULT 0, ALTER COLUMN jobid SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_campaigns_mid_campaignid ON campaigns (mid, campaignid);
+
+-- checkouts
+ALTER TABLE checkouts
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ADD COLUMN assist checkout_assist_enum NOT NULL DEFAULT '',
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN checkout_stage VARCHAR(8) NOT NULL DEFAULT '';
+UPDATE checkouts SET
+    mid = COALESCE(mid, 0),
+    username = COALESCE(username, ''),
+    sdomain = COALESCE(sdomain, ''),
+    cartid = COALESCE(cartid, ''),
+    cid = COALESCE(cid, 0),
+    handled_gmt = COALESCE(handled_gmt, 0),
+    closed_gmt = COALESCE(closed_gmt, 0),
+    assistid = COALESCE(assistid, '');
+ALTER TABLE checkouts
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN sdomain SET DEFAULT '', ALTER COLUMN sdomain SET NOT NULL,
+    ALTER COLUMN cartid SET DEFAULT '', ALTER COLUMN cartid SET NOT NULL,
+    ALTER COLUMN cid SET DEFAULT 0, ALTER COLUMN cid SET NOT NULL,
+    ALTER COLUMN handled_gmt SET DEFAULT 0, ALTER COLUMN handled_gmt SET NOT NULL,
+    ALTER COLUMN closed_gmt SET DEFAULT 0, ALTER COLUMN closed_gmt SET NOT NULL,
+    ALTER COLUMN assistid SET DEFAULT '', ALTER COLUMN assistid SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_checkouts_mid_sdomain_handled ON checkouts (mid, sdomain, handled_gmt);
+
+-- customer_addrs
+ALTER TABLE customer_addrs
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN address1 VARCHAR(50) NOT NULL DEFAULT '',
+    ADD COLUMN address2 VARCHAR(30) NOT NULL DEFAULT '';
+UPDATE customer_addrs SET
+    mid = COALESCE(mid, 0),
+    cid = COALESCE(cid, 0),
+    guid = COALESCE(guid, ''),
+    is_default = COALESCE(is_default, 0),
+    label = COALESCE(label, ''),
+    firstname = COALESCE(firstname, ''),
+    lastname = COALESCE(lastname, ''),
+    city = COALESCE(city, ''),
+    state = COALESCE(state, ''),
+    zip = COALESCE(zip, ''),
+    country = COALESCE(country, ''),
+    phone = COALESCE(phone, ''),
+    company = COALESCE(company, '');
+ALTER TABLE customer_addrs
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN cid SET DEFAULT 0, ALTER COLUMN cid SET NOT NULL,
+    ALTER COLUMN guid SET DEFAULT '', ALTER COLUMN guid SET NOT NULL,
+    ALTER COLUMN is_default SET DEFAULT 0, ALTER COLUMN is_default SET NOT NULL,
+    ALTER COLUMN label SET DEFAULT '', ALTER COLUMN label SET NOT NULL,
+    ALTER COLUMN firstname SET DEFAULT '', ALTER COLUMN firstname SET NOT NULL,
+    ALTER COLUMN lastname SET DEFAULT '', ALTER COLUMN lastname SET NOT NULL,
+    ALTER COLUMN city SET DEFAULT '', ALTER COLUMN city SET NOT NULL,
+    ALTER COLUMN state SET DEFAULT '', ALTER COLUMN state SET NOT NULL,
+    ALTER COLUMN zip SET DEFAULT '', ALTER COLUMN zip SET NOT NULL,
+    ALTER COLUMN country SET DEFAULT '', ALTER COLUMN country SET NOT NULL,
+    ALTER COLUMN phone SET DEFAULT '', ALTER COLUMN phone SET NOT NULL,
+    ALTER COLUMN company SET DEFAULT '', ALTER COLUMN company SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_customer_addrs_mid_cid ON customer_addrs (mid, cid);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_customer_addrs_mid_guid ON customer_addrs (mid, guid);
+
+-- customer_notes
+ALTER TABLE customer_notes
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0;
+UPDATE customer_notes SET
+    mid = COALESCE(mid, 0),
+    username = COALESCE(username, ''),
+    cid = COALESCE(cid, 0),
+    luser = COALESCE(luser, ''),
+    note = COALESCE(note, ''),
+    type = COALESCE(type, '');
+ALTER TABLE customer_notes
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN cid SET DEFAULT 0, ALTER COLUMN cid SET NOT NULL,
+    ALTER COLUMN luser SET DEFAULT '', ALTER COLUMN luser SET NOT NULL,
+    ALTER COLUMN note SET DEFAULT '', ALTER COLUMN note SET NOT NULL,
+    ALTER COLUMN type SET DEFAULT '', ALTER COLUMN type SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_customer_notes_mid_cid ON customer_notes (mid, cid);
+
+-- customers
+ALTER TABLE customers
+    ADD COLUMN cid SERIAL PRIMARY KEY,
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0;
+UPDATE customers SET
+    orgid = COALESCE(orgid, 0),
+    mid = COALESCE(mid, 0),
+    username = COALESCE(username, ''),
+    prt = COALESCE(prt, 0),
+    email = COALESCE(email, ''),
+    password = COALESCE(password, ''),
+    passhash = COALESCE(passhash, ''),
+    passsalt = COALESCE(passsalt, ''),
+    firstname = COALESCE(firstname, ''),
+    lastname = COALESCE(lastname, ''),
+    phone = COALESCE(phone, ''),
+    modified_gmt = COALESCE(modified_gmt, 0),
+    lastlogin_gmt = COALESCE(lastlogin_gmt, 0),
+    lastorder_gmt = COALESCE(lastorder_gmt, 0),
+    order_count = COALESCE(order_count, 0),
+    optin_gmt = COALESCE(optin_gmt, 0),
+    hint_num = COALESCE(hint_num, 0),
+    hint_answer = COALESCE(hint_answer, ''),
+    hint_attempts = COALESCE(hint_attempts, 0),
+    ip = COALESCE(ip, 0),
+    origin = COALESCE(origin, 0),
+    schedule = COALESCE(schedule, ''),
+    has_notes = COALESCE(has_notes, 0),
+    is_affiliate = COALESCE(is_affiliate, 0),
+    is_locked = COALESCE(is_locked, 0);
+ALTER TABLE customers
+    ALTER COLUMN orgid SET DEFAULT 0, ALTER COLUMN orgid SET NOT NULL,
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN prt SET DEFAULT 0, ALTER COLUMN prt SET NOT NULL,
+    ALTER COLUMN email SET DEFAULT '', ALTER COLUMN email SET NOT NULL,
+    ALTER COLUMN password SET DEFAULT '', ALTER COLUMN password SET NOT NULL,
+    ALTER COLUMN passhash SET DEFAULT '', ALTER COLUMN passhash SET NOT NULL,
+    ALTER COLUMN passsalt SET DEFAULT '', ALTER COLUMN passsalt SET NOT NULL,
+    ALTER COLUMN firstname SET DEFAULT '', ALTER COLUMN firstname SET NOT NULL,
+    ALTER COLUMN lastname SET DEFAULT '', ALTER COLUMN lastname SET NOT NULL,
+    ALTER COLUMN phone SET DEFAULT '', ALTER COLUMN phone SET NOT NULL,
+    ALTER COLUMN modified_gmt SET DEFAULT 0, ALTER COLUMN modified_gmt SET NOT NULL,
+    ALTER COLUMN lastlogin_gmt SET DEFAULT 0, ALTER COLUMN lastlogin_gmt SET NOT NULL,
+    ALTER COLUMN lastorder_gmt SET DEFAULT 0, ALTER COLUMN lastorder_gmt SET NOT NULL,
+    ALTER COLUMN order_count SET DEFAULT 0, ALTER COLUMN order_count SET NOT NULL,
+    ALTER COLUMN newsletter SET DEFAULT 1,
+    ALTER COLUMN optin_gmt SET DEFAULT 0, ALTER COLUMN optin_gmt SET NOT NULL,
+    ALTER COLUMN hint_num SET DEFAULT 0, ALTER COLUMN hint_num SET NOT NULL,
+    ALTER COLUMN hint_answer SET DEFAULT '', ALTER COLUMN hint_answer SET NOT NULL,
+    ALTER COLUMN hint_attempts SET DEFAULT 0, ALTER COLUMN hint_attempts SET NOT NULL,
+    ALTER COLUMN ip SET DEFAULT 0, ALTER COLUMN ip SET NOT NULL,
+    ALTER COLUMN origin SET DEFAULT 0, ALTER COLUMN origin SET NOT NULL,
+    ALTER COLUMN schedule SET DEFAULT '', ALTER COLUMN schedule SET NOT NULL,
+    ALTER COLUMN has_notes SET DEFAULT 0, ALTER COLUMN has_notes SET NOT NULL,
+    ALTER COLUMN is_affiliate SET DEFAULT 0, ALTER COLUMN is_affiliate SET NOT NULL,
+    ALTER COLUMN is_locked SET DEFAULT 0, ALTER COLUMN is_locked SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_customers_mid_cid ON customers (mid, cid);
+CREATE INDEX IF NOT EXISTS idx_customers_mid_modified ON customers (mid, modified_gmt);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_customers_mid_prt_email ON customers (mid, prt, email);
+
+-- inventory_detail
+ALTER TABLE inventory_detail
+    ADD COLUMN id BIGSERIAL PRIMARY KEY,
+    ALTER COLUMN basetype TYPE inventory_basetype_enum USING NULLIF(basetype, '')::inventory_basetype_enum,
+    ADD COLUMN created_ts TIMESTAMP,
+    ALTER COLUMN pick_route TYPE inventory_pick_route_enum USING pick_route::inventory_pick_route_enum,
+    ALTER COLUMN vendor_status TYPE inventory_vendor_status_enum USING NULLIF(vendor_status, '')::inventory_vendor_status_enum;
+UPDATE inventory_detail SET
+    uuid = COALESCE(uuid, ''),
+    mid = COALESCE(mid, 0),
+    pid = COALESCE(pid, ''),
+    sku = COALESCE(sku, ''),
+    qty = COALESCE(qty, 0),
+    cost_i = COALESCE(cost_i, 0),
+    note = COALESCE(note, ''),
+    container = COALESCE(container, ''),
+    origin = COALESCE(origin, ''),
+    supplier_sku = COALESCE(supplier_sku, ''),
+    market_refid = COALESCE(market_refid, ''),
+    market_sold_qty = COALESCE(market_sold_qty, 0),
+    preference = COALESCE(preference, 0),
+    modified_by = COALESCE(modified_by, ''),
+    modified_inc = COALESCE(modified_inc, 0),
+    modified_qty_was = COALESCE(modified_qty_was, 0),
+    verify_inc = COALESCE(verify_inc, 0),
+    our_orderid = COALESCE(our_orderid, ''),
+    pick_batchid = COALESCE(pick_batchid, ''),
+    description = COALESCE(description, ''),
+    vendor = COALESCE(vendor, ''),
+    vendor_order_dbid = COALESCE(vendor_order_dbid, 0),
+    vendor_sku = COALESCE(vendor_sku, '');
+ALTER TABLE inventory_detail
+    ALTER COLUMN uuid SET DEFAULT '', ALTER COLUMN uuid SET NOT NULL,
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN pid SET DEFAULT '', ALTER COLUMN pid SET NOT NULL,
+    ALTER COLUMN sku SET DEFAULT '', ALTER COLUMN sku SET NOT NULL,
+    ALTER COLUMN qty SET DEFAULT 0, ALTER COLUMN qty SET NOT NULL,
+    ALTER COLUMN cost_i SET DEFAULT 0, ALTER COLUMN cost_i SET NOT NULL,
+    ALTER COLUMN note SET DEFAULT '', ALTER COLUMN note SET NOT NULL,
+    ALTER COLUMN container SET DEFAULT '', ALTER COLUMN container SET NOT NULL,
+    ALTER COLUMN origin SET DEFAULT '', ALTER COLUMN origin SET NOT NULL,
+    ALTER COLUMN basetype SET DEFAULT 'ERROR',
+    ALTER COLUMN supplier_sku SET DEFAULT '', ALTER COLUMN supplier_sku SET NOT NULL,
+    ALTER COLUMN market_refid SET DEFAULT '', ALTER COLUMN market_refid SET NOT NULL,
+    ALTER COLUMN market_sold_qty SET DEFAULT 0, ALTER COLUMN market_sold_qty SET NOT NULL,
+    ALTER COLUMN preference SET DEFAULT 0, ALTER COLUMN preference SET NOT NULL,
+    ALTER COLUMN modified_by SET DEFAULT '', ALTER COLUMN modified_by SET NOT NULL,
+    ALTER COLUMN modified_inc SET DEFAULT 0, ALTER COLUMN modified_inc SET NOT NULL,
+    ALTER COLUMN modified_qty_was SET DEFAULT 0, ALTER COLUMN modified_qty_was SET NOT NULL,
+    ALTER COLUMN verify_inc SET DEFAULT 0, ALTER COLUMN verify_inc SET NOT NULL,
+    ALTER COLUMN our_orderid SET DEFAULT '', ALTER COLUMN our_orderid SET NOT NULL,
+    ALTER COLUMN pick_batchid SET DEFAULT '', ALTER COLUMN pick_batchid SET NOT NULL,
+    ALTER COLUMN description SET DEFAULT '', ALTER COLUMN description SET NOT NULL,
+    ALTER COLUMN vendor SET DEFAULT '', ALTER COLUMN vendor SET NOT NULL,
+    ALTER COLUMN vendor_order_dbid SET DEFAULT 0, ALTER COLUMN vendor_order_dbid SET NOT NULL,
+    ALTER COLUMN vendor_sku SET DEFAULT '', ALTER COLUMN vendor_sku SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_inventory_detail_mid_container ON inventory_detail (mid, container);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_inventory_detail_mid_market ON inventory_detail (mid, market_dst, market_refid);
+CREATE INDEX IF NOT EXISTS idx_inventory_detail_mid_modified ON inventory_detail (mid, modified_ts);
+CREATE INDEX IF NOT EXISTS idx_inventory_detail_mid_orderid ON inventory_detail (mid, our_orderid);
+CREATE INDEX IF NOT EXISTS idx_inventory_detail_mid_pid ON inventory_detail (mid, pid);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_inventory_detail_mid_sku_uuid ON inventory_detail (mid, sku, uuid);
+CREATE INDEX IF NOT EXISTS idx_inventory_detail_mid_supplier ON inventory_detail (mid, supplier_id, sku);
+CREATE INDEX IF NOT EXISTS idx_inventory_detail_mid_vendor_order ON inventory_detail (mid, vendor, vendor_order_dbid);
+CREATE INDEX IF NOT EXISTS idx_inventory_detail_mid_vendor_status ON inventory_detail (mid, vendor_status);
+CREATE INDEX IF NOT EXISTS idx_inventory_detail_mid_wms ON inventory_detail (mid, wms_zone);
+
+-- inventory_log
+ALTER TABLE inventory_log
+    ADD COLUMN id BIGSERIAL PRIMARY KEY,
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0;
+UPDATE inventory_log SET
+    mid = COALESCE(mid, 0),
+    pid = COALESCE(pid, ''),
+    sku = COALESCE(sku, ''),
+    qty = COALESCE(qty, 0),
+    qty_before = COALESCE(qty_before, 0),
+    action = COALESCE(action, ''),
+    luser = COALESCE(luser, ''),
+    note = COALESCE(note, ''),
+    orderid = COALESCE(orderid, ''),
+    uuid = COALESCE(uuid, '');
+ALTER TABLE inventory_log
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN pid SET DEFAULT '', ALTER COLUMN pid SET NOT NULL,
+    ALTER COLUMN sku SET DEFAULT '', ALTER COLUMN sku SET NOT NULL,
+    ALTER COLUMN qty SET DEFAULT 0, ALTER COLUMN qty SET NOT NULL,
+    ALTER COLUMN qty_before SET DEFAULT 0, ALTER COLUMN qty_before SET NOT NULL,
+    ALTER COLUMN action SET DEFAULT '', ALTER COLUMN action SET NOT NULL,
+    ALTER COLUMN luser SET DEFAULT '', ALTER COLUMN luser SET NOT NULL,
+    ALTER COLUMN note SET DEFAULT '', ALTER COLUMN note SET NOT NULL,
+    ALTER COLUMN orderid SET DEFAULT '', ALTER COLUMN orderid SET NOT NULL,
+    ALTER COLUMN uuid SET DEFAULT '', ALTER COLUMN uuid SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_inventory_log_mid_created ON inventory_log (mid, created_gmt);
+CREATE INDEX IF NOT EXISTS idx_inventory_log_mid_sku ON inventory_log (mid, sku);
+
+-- order_counters
+-- Deviation: one counter row per merchant, so mid is the primary key rather
+-- than a nullable column with a unique index (idx_order_counters_mid)
+DELETE FROM order_counters WHERE mid IS NULL;
+UPDATE order_counters SET
+    merchant = COALESCE(merchant, ''),
+    last_server = COALESCE(last_server, '');
+ALTER TABLE order_counters
+    ADD PRIMARY KEY (mid),
+    ALTER COLUMN merchant SET DEFAULT '', ALTER COLUMN merchant SET NOT NULL,
+    ALTER COLUMN counter SET DEFAULT 0,
+    ALTER COLUMN last_pid SET DEFAULT 0,
+    ALTER COLUMN last_server SET DEFAULT '', ALTER COLUMN last_server SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_order_counters_merchant ON order_counters (merchant);
+
+-- order_events
+UPDATE order_events SET
+    mid = COALESCE(mid, 0),
+    username = COALESCE(username, ''),
+    prt = COALESCE(prt, 0),
+    orderid = COALESCE(orderid, ''),
+    event = COALESCE(event, ''),
+    lock_id = COALESCE(lock_id, 0),
+    lock_gmt = COALESCE(lock_gmt, 0),
+    attempts = COALESCE(attempts, 0);
+ALTER TABLE order_events
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN prt SET DEFAULT 0, ALTER COLUMN prt SET NOT NULL,
+    ALTER COLUMN orderid SET DEFAULT '', ALTER COLUMN orderid SET NOT NULL,
+    ALTER COLUMN event SET DEFAULT '', ALTER COLUMN event SET NOT NULL,
+    ALTER COLUMN lock_id SET DEFAULT 0, ALTER COLUMN lock_id SET NOT NULL,
+    ALTER COLUMN lock_gmt SET DEFAULT 0, ALTER COLUMN lock_gmt SET NOT NULL,
+    ALTER COLUMN attempts SET DEFAULT 0, ALTER COLUMN attempts SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_order_events_lock ON order_events (lock_gmt, lock_id);
+
+-- orders
+ALTER TABLE orders
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0,
+    ALTER COLUMN order_total DROP DEFAULT,
+    ALTER COLUMN order_total TYPE DECIMAL(10,2) USING NULLIF(TRIM(order_total), '')::numeric;
+UPDATE orders SET
+    merchant = COALESCE(merchant, ''),
+    mid = COALESCE(mid, 0),
+    prt = COALESCE(prt, 0),
+    orderid = COALESCE(orderid, ''),
+    bs_settlement = COALESCE(bs_settlement, 0),
+    modified_gmt = COALESCE(modified_gmt, 0),
+    paid_gmt = COALESCE(paid_gmt, 0),
+    paid_txn = COALESCE(paid_txn, ''),
+    inv_gmt = COALESCE(inv_gmt, 0),
+    shipped_gmt = COALESCE(shipped_gmt, 0),
+    synced_gmt = COALESCE(synced_gmt, 0),
+    customer = COALESCE(customer, 0),
+    order_bill_name = COALESCE(order_bill_name, ''),
+    order_bill_email = COALESCE(order_bill_email, ''),
+    order_bill_zone = COALESCE(order_bill_zone, ''),
+    order_bill_phone = COALESCE(order_bill_phone, ''),
+    order_ship_name = COALESCE(order_ship_name, ''),
+    order_ship_zone = COALESCE(order_ship_zone, ''),
+    review_status = COALESCE(review_status, ''),
+    order_payment_status = COALESCE(order_payment_status, ''),
+    order_payment_method = COALESCE(order_payment_method, ''),
+    order_payment_lookup = COALESCE(order_payment_lookup, ''),
+    order_special = COALESCE(order_special, ''),
+    ship_method = COALESCE(ship_method, ''),
+    mkt_bitstr = COALESCE(mkt_bitstr, ''),
+    flags = COALESCE(flags, 0),
+    items = COALESCE(items, 0),
+    yaml = COALESCE(yaml, '');
+ALTER TABLE orders
+    ALTER COLUMN merchant SET DEFAULT '', ALTER COLUMN merchant SET NOT NULL,
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN prt SET DEFAULT 0, ALTER COLUMN prt SET NOT NULL,
+    ALTER COLUMN orderid SET DEFAULT '', ALTER COLUMN orderid SET NOT NULL,
+    ALTER COLUMN bs_settlement SET DEFAULT 0, ALTER COLUMN bs_settlement SET NOT NULL,
+    ALTER COLUMN v SET DEFAULT 0,
+    ALTER COLUMN modified_gmt SET DEFAULT 0, ALTER COLUMN modified_gmt SET NOT NULL,
+    ALTER COLUMN paid_gmt SET DEFAULT 0, ALTER COLUMN paid_gmt SET NOT NULL,
+    ALTER COLUMN paid_txn SET DEFAULT '', ALTER COLUMN paid_txn SET NOT NULL,
+    ALTER COLUMN inv_gmt SET DEFAULT 0, ALTER COLUMN inv_gmt SET NOT NULL,
+    ALTER COLUMN shipped_gmt SET DEFAULT 0, ALTER COLUMN shipped_gmt SET NOT NULL,
+    ALTER COLUMN synced_gmt SET DEFAULT 0, ALTER COLUMN synced_gmt SET NOT NULL,
+    ALTER COLUMN customer SET DEFAULT 0, ALTER COLUMN customer SET NOT NULL,
+    ALTER COLUMN order_bill_name SET DEFAULT '', ALTER COLUMN order_bill_name SET NOT NULL,
+    ALTER COLUMN order_bill_email SET DEFAULT '', ALTER COLUMN order_bill_email SET NOT NULL,
+    ALTER COLUMN order_bill_zone SET DEFAULT '', ALTER COLUMN order_bill_zone SET NOT NULL,
+    ALTER COLUMN order_bill_phone SET DEFAULT '', ALTER COLUMN order_bill_phone SET NOT NULL,
+    ALTER COLUMN order_ship_name SET DEFAULT '', ALTER COLUMN order_ship_name SET NOT NULL,
+    ALTER COLUMN order_ship_zone SET DEFAULT '', ALTER COLUMN order_ship_zone SET NOT NULL,
+    ALTER COLUMN review_status SET DEFAULT '', ALTER COLUMN review_status SET NOT NULL,
+    ALTER COLUMN order_payment_status SET DEFAULT '', ALTER COLUMN order_payment_status SET NOT NULL,
+    ALTER COLUMN order_payment_method SET DEFAULT '', ALTER COLUMN order_payment_method SET NOT NULL,
+    ALTER COLUMN order_payment_lookup SET DEFAULT '', ALTER COLUMN order_payment_lookup SET NOT NULL,
+    ALTER COLUMN order_erefid SET DEFAULT '',
+    ALTER COLUMN order_total SET DEFAULT 0.00,
+    ALTER COLUMN order_special SET DEFAULT '', ALTER COLUMN order_special SET NOT NULL,
+    ALTER COLUMN ship_method SET DEFAULT '', ALTER COLUMN ship_method SET NOT NULL,
+    ALTER COLUMN mkt SET DEFAULT 0,
+    ALTER COLUMN mkt_bitstr SET DEFAULT '', ALTER COLUMN mkt_bitstr SET NOT NULL,
+    ALTER COLUMN flags SET DEFAULT 0, ALTER COLUMN flags SET NOT NULL,
+    ALTER COLUMN items SET DEFAULT 0, ALTER COLUMN items SET NOT NULL,
+    ALTER COLUMN yaml SET DEFAULT '', ALTER COLUMN yaml SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_orders_mid_bill_email ON orders (mid, order_bill_email);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_orders_mid_cartid ON orders (mid, cartid);
+CREATE INDEX IF NOT EXISTS idx_orders_mid_created ON orders (mid, created_gmt);
+CREATE INDEX IF NOT EXISTS idx_orders_mid_customer ON orders (mid, customer);
+CREATE INDEX IF NOT EXISTS idx_orders_mid_erefid ON orders (mid, order_erefid);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_orders_mid_orderid ON orders (mid, orderid);
+CREATE INDEX IF NOT EXISTS idx_orders_mid_paid_shipped ON orders (mid, paid_gmt, shipped_gmt);
+CREATE INDEX IF NOT EXISTS idx_orders_mid_pool ON orders (mid, pool);
+CREATE INDEX IF NOT EXISTS idx_orders_mid_synced ON orders (mid, synced_gmt);
+
+-- product_relations
+ALTER TABLE product_relations
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0;
+UPDATE product_relations SET
+    mid = COALESCE(mid, 0),
+    pid = COALESCE(pid, ''),
+    child_pid = COALESCE(child_pid, ''),
+    relation = COALESCE(relation, ''),
+    qty = COALESCE(qty, 0),
+    is_active = COALESCE(is_active, 0),
+    list_pos = COALESCE(list_pos, 0);
+ALTER TABLE product_relations
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN pid SET DEFAULT '', ALTER COLUMN pid SET NOT NULL,
+    ALTER COLUMN child_pid SET DEFAULT '', ALTER COLUMN child_pid SET NOT NULL,
+    ALTER COLUMN relation SET DEFAULT '', ALTER COLUMN relation SET NOT NULL,
+    ALTER COLUMN qty SET DEFAULT 0, ALTER COLUMN qty SET NOT NULL,
+    ALTER COLUMN is_active SET DEFAULT 0, ALTER COLUMN is_active SET NOT NULL,
+    ALTER COLUMN list_pos SET DEFAULT 0, ALTER COLUMN list_pos SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_product_relations_child ON product_relations (mid, child_pid, relation);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_product_relations_unique ON product_relations (mid, pid, relation, child_pid);
+
+-- products
+ALTER TABLE products
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ADD COLUMN created_gmt INTEGER NOT NULL DEFAULT 0,
+    ALTER COLUMN base_price TYPE DECIMAL(10,2) USING NULLIF(TRIM(base_price), '')::numeric,
+    ALTER COLUMN base_cost TYPE DECIMAL(10,2) USING NULLIF(TRIM(base_cost), '')::numeric;
+UPDATE products SET
+    mid = COALESCE(mid, 0),
+    merchant = COALESCE(merchant, ''),
+    product = COALESCE(product, ''),
+    ts = COALESCE(ts, 0),
+    product_name = COALESCE(product_name, ''),
+    category = COALESCE(category, ''),
+    data = COALESCE(data, ''),
+    salesrank = COALESCE(salesrank, 0),
+    lastsold_gmt = COALESCE(lastsold_gmt, 0),
+    upc = COALESCE(upc, ''),
+    options = COALESCE(options, 0),
+    profile = COALESCE(profile, ''),
+    mkt = COALESCE(mkt, 0),
+    prod_is = COALESCE(prod_is, 0),
+    mkt_bitstr = COALESCE(mkt_bitstr, ''),
+    mkterr_bitstr = COALESCE(mkterr_bitstr, '');
+ALTER TABLE products
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN merchant SET DEFAULT '', ALTER COLUMN merchant SET NOT NULL,
+    ALTER COLUMN product SET DEFAULT '', ALTER COLUMN product SET NOT NULL,
+    ALTER COLUMN ts SET DEFAULT 0, ALTER COLUMN ts SET NOT NULL,
+    ALTER COLUMN product_name SET DEFAULT '', ALTER COLUMN product_name SET NOT NULL,
+    ALTER COLUMN category SET DEFAULT '', ALTER COLUMN category SET NOT NULL,
+    ALTER COLUMN data SET DEFAULT '', ALTER COLUMN data SET NOT NULL,
+    ALTER COLUMN salesrank SET DEFAULT 0, ALTER COLUMN salesrank SET NOT NULL,
+    ALTER COLUMN lastsold_gmt SET DEFAULT 0, ALTER COLUMN lastsold_gmt SET NOT NULL,
+    ALTER COLUMN upc SET DEFAULT '', ALTER COLUMN upc SET NOT NULL,
+    ALTER COLUMN options SET DEFAULT 0, ALTER COLUMN options SET NOT NULL,
+    ALTER COLUMN profile SET DEFAULT '', ALTER COLUMN profile SET NOT NULL,
+    ALTER COLUMN mkt SET DEFAULT 0, ALTER COLUMN mkt SET NOT NULL,
+    ALTER COLUMN prod_is SET DEFAULT 0, ALTER COLUMN prod_is SET NOT NULL,
+    ALTER COLUMN mkt_bitstr SET DEFAULT '', ALTER COLUMN mkt_bitstr SET NOT NULL,
+    ALTER COLUMN mkterr_bitstr SET DEFAULT '', ALTER COLUMN mkterr_bitstr SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_products_mid_product ON products (mid, product);
+CREATE INDEX IF NOT EXISTS idx_products_mid_supplierid ON products (mid, supplier_id);
+CREATE INDEX IF NOT EXISTS idx_products_mid_ts ON products (mid, ts);
+
+-- projects
+ALTER TABLE projects
+    ADD COLUMN id SERIAL PRIMARY KEY,
+    ADD COLUMN created_ts TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    ALTER COLUMN type TYPE project_type_enum USING type::project_type_enum;
+UPDATE projects SET
+    mid = COALESCE(mid, 0),
+    username = COALESCE(username, ''),
+    title = COALESCE(title, ''),
+    uuid = COALESCE(uuid, ''),
+    secret = COALESCE(secret, ''),
+    type = COALESCE(type, ''),
+    github_repo = COALESCE(github_repo, ''),
+    github_branch = COALESCE(github_branch, ''),
+    github_txlog = COALESCE(github_txlog, ''),
+    app_release = COALESCE(app_release, '0'),
+    app_version = COALESCE(app_version, ''),
+    app_seo = COALESCE(app_seo, ''),
+    app_expire = COALESCE(app_expire, ''),
+    app_force_secure = COALESCE(app_force_secure, 0),
+    app_root = COALESCE(app_root, '');
+ALTER TABLE projects
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN title SET DEFAULT '', ALTER COLUMN title SET NOT NULL,
+    ALTER COLUMN uuid SET DEFAULT '', ALTER COLUMN uuid SET NOT NULL,
+    ALTER COLUMN secret SET DEFAULT '', ALTER COLUMN secret SET NOT NULL,
+    ALTER COLUMN type SET DEFAULT '', ALTER COLUMN type SET NOT NULL,
+    ALTER COLUMN github_repo SET DEFAULT '', ALTER COLUMN github_repo SET NOT NULL,
+    ALTER COLUMN github_branch SET DEFAULT '', ALTER COLUMN github_branch SET NOT NULL,
+    ALTER COLUMN github_txlog SET DEFAULT '', ALTER COLUMN github_txlog SET NOT NULL,
+    ALTER COLUMN app_release SET DEFAULT '0', ALTER COLUMN app_release SET NOT NULL,
+    ALTER COLUMN app_version SET DEFAULT '', ALTER COLUMN app_version SET NOT NULL,
+    ALTER COLUMN app_seo SET DEFAULT '', ALTER COLUMN app_seo SET NOT NULL,
+    ALTER COLUMN app_expire SET DEFAULT '', ALTER COLUMN app_expire SET NOT NULL,
+    ALTER COLUMN app_force_secure SET DEFAULT 0, ALTER COLUMN app_force_secure SET NOT NULL,
+    ALTER COLUMN app_root SET DEFAULT '', ALTER COLUMN app_root SET NOT NULL;
+
+-- sku_lookup
+ALTER TABLE sku_lookup
+    ADD COLUMN id BIGSERIAL PRIMARY KEY,
+    ALTER COLUMN cost DROP DEFAULT,
+    ALTER COLUMN cost TYPE DECIMAL(10,2) USING NULLIF(TRIM(cost), '')::numeric,
+    ALTER COLUMN price DROP DEFAULT,
+    ALTER COLUMN price TYPE DECIMAL(10,2) USING NULLIF(TRIM(price), '')::numeric,
+    ALTER COLUMN dss_run TYPE dss_run_set USING NULLIF(dss_run, '')::dss_run_set,
+    ALTER COLUMN dss_mood TYPE dss_mood_enum USING NULLIF(dss_mood, '')::dss_mood_enum;
+UPDATE sku_lookup SET
+    mid = COALESCE(mid, 0),
+    pid = COALESCE(pid, ''),
+    invopts = COALESCE(invopts, ''),
+    grp_parent = COALESCE(grp_parent, ''),
+    title = COALESCE(title, '0'),
+    upc = COALESCE(upc, ''),
+    mfgid = COALESCE(mfgid, ''),
+    supplierid = COALESCE(supplierid, ''),
+    inv_available = COALESCE(inv_available, 0),
+    qty_onshelf = COALESCE(qty_onshelf, 0),
+    qty_onorder = COALESCE(qty_onorder, 0),
+    qty_needship = COALESCE(qty_needship, 0),
+    qty_markets = COALESCE(qty_markets, 0),
+    qty_legacy = COALESCE(qty_legacy, 0),
+    qty_reserved = COALESCE(qty_reserved, 0),
+    amz_asin = COALESCE(amz_asin, ''),
+    amz_feeds_done = COALESCE(amz_feeds_done, 0),
+    amz_feeds_todo = COALESCE(amz_feeds_todo, 0),
+    amz_feeds_sent = COALESCE(amz_feeds_sent, 0),
+    amz_feeds_wait = COALESCE(amz_feeds_wait, 0),
+    amz_feeds_warn = COALESCE(amz_feeds_warn, 0),
+    amz_feeds_error = COALESCE(amz_feeds_error, 0),
+    amz_productdb_gmt = COALESCE(amz_productdb_gmt, 0),
+    amz_error = COALESCE(amz_error, ''),
+    inv_on_shelf = COALESCE(inv_on_shelf, 0),
+    inv_on_order = COALESCE(inv_on_order, 0),
+    inv_is_bo = COALESCE(inv_is_bo, 0),
+    inv_reorder = COALESCE(inv_reorder, 0),
+    inv_is_rsvp = COALESCE(inv_is_rsvp, 0),
+    dss_agent = COALESCE(dss_agent, '');
+ALTER TABLE sku_lookup
+    ALTER COLUMN mid SET DEFAULT 0, ALTER COLUMN mid SET NOT NULL,
+    ALTER COLUMN pid SET DEFAULT '', ALTER COLUMN pid SET NOT NULL,
+    ALTER COLUMN invopts SET DEFAULT '', ALTER COLUMN invopts SET NOT NULL,
+    ALTER COLUMN grp_parent SET DEFAULT '', ALTER COLUMN grp_parent SET NOT NULL,
+    ALTER COLUMN sku SET NOT NULL,
+    ALTER COLUMN title SET DEFAULT '0', ALTER COLUMN title SET NOT NULL,
+    ALTER COLUMN cost SET DEFAULT 0.00,
+    ALTER COLUMN price SET DEFAULT 0.00,
+    ALTER COLUMN upc SET DEFAULT '', ALTER COLUMN upc SET NOT NULL,
+    ALTER COLUMN mfgid SET DEFAULT '', ALTER COLUMN mfgid SET NOT NULL,
+    ALTER COLUMN supplierid SET DEFAULT '', ALTER COLUMN supplierid SET NOT NULL,
+    ALTER COLUMN inv_available SET DEFAULT 0, ALTER COLUMN inv_available SET NOT NULL,
+    ALTER COLUMN qty_onshelf SET DEFAULT 0, ALTER COLUMN qty_onshelf SET NOT NULL,
+    ALTER COLUMN qty_onorder SET DEFAULT 0, ALTER COLUMN qty_onorder SET NOT NULL,
+    ALTER COLUMN qty_needship SET DEFAULT 0, ALTER COLUMN qty_needship SET NOT NULL,
+    ALTER COLUMN qty_markets SET DEFAULT 0, ALTER COLUMN qty_markets SET NOT NULL,
+    ALTER COLUMN qty_legacy SET DEFAULT 0, ALTER COLUMN qty_legacy SET NOT NULL,
+    ALTER COLUMN qty_reserved SET DEFAULT 0, ALTER COLUMN qty_reserved SET NOT NULL,
+    ALTER COLUMN amz_asin SET DEFAULT '', ALTER COLUMN amz_asin SET NOT NULL,
+    ALTER COLUMN amz_feeds_done SET DEFAULT 0, ALTER COLUMN amz_feeds_done SET NOT NULL,
+    ALTER COLUMN amz_feeds_todo SET DEFAULT 0, ALTER COLUMN amz_feeds_todo SET NOT NULL,
+    ALTER COLUMN amz_feeds_sent SET DEFAULT 0, ALTER COLUMN amz_feeds_sent SET NOT NULL,
+    ALTER COLUMN amz_feeds_wait SET DEFAULT 0, ALTER COLUMN amz_feeds_wait SET NOT NULL,
+    ALTER COLUMN amz_feeds_warn SET DEFAULT 0, ALTER COLUMN amz_feeds_warn SET NOT NULL,
+    ALTER COLUMN amz_feeds_error SET DEFAULT 0, ALTER COLUMN amz_feeds_error SET NOT NULL,
+    ALTER COLUMN amz_productdb_gmt SET DEFAULT 0, ALTER COLUMN amz_productdb_gmt SET NOT NULL,
+    ALTER COLUMN amz_error SET DEFAULT '', ALTER COLUMN amz_error SET NOT NULL,
+    ALTER COLUMN inv_on_shelf SET DEFAULT 0, ALTER COLUMN inv_on_shelf SET NOT NULL,
+    ALTER COLUMN inv_on_order SET DEFAULT 0, ALTER COLUMN inv_on_order SET NOT NULL,
+    ALTER COLUMN inv_is_bo SET DEFAULT 0, ALTER COLUMN inv_is_bo SET NOT NULL,
+    ALTER COLUMN inv_reorder SET DEFAULT 0, ALTER COLUMN inv_reorder SET NOT NULL,
+    ALTER COLUMN inv_is_rsvp SET DEFAULT 0, ALTER COLUMN inv_is_rsvp SET NOT NULL,
+    ALTER COLUMN dss_agent SET DEFAULT '', ALTER COLUMN dss_agent SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_sku_lookup_mid_asin ON sku_lookup (mid, amz_asin);
+CREATE INDEX IF NOT EXISTS idx_sku_lookup_mid_pid ON sku_lookup (mid, pid);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sku_lookup_mid_sku ON sku_lookup (mid, sku);
+CREATE INDEX IF NOT EXISTS idx_sku_lookup_mid_upc ON sku_lookup (mid, upc);
+
+-- zusers
+ALTER TABLE zusers
+    ADD COLUMN mid SERIAL PRIMARY KEY,
+    ADD COLUMN created TIMESTAMP,
+    ADD COLUMN bpp_lastcheck_gmt INTEGER NOT NULL DEFAULT 0;
+UPDATE zusers SET
+    username = COALESCE(username, ''),
+    password = COALESCE(password, ''),
+    reseller = COALESCE(reseller, ''),
+    logins = COALESCE(logins, 0),
+    cached_flags = COALESCE(cached_flags, ''),
+    email = COALESCE(email, ''),
+    phone = COALESCE(phone, ''),
+    salesperson = COALESCE(salesperson, ''),
+    tech_contact = COALESCE(tech_contact, ''),
+    overduenotify_gmt = COALESCE(overduenotify_gmt, 0),
+    ipaddr = COALESCE(ipaddr, ''),
+    data = COALESCE(data, ''),
+    sugarguid = COALESCE(sugarguid, ''),
+    bill_day = COALESCE(bill_day, 0),
+    bill_package = COALESCE(bill_package, ''),
+    invoice_count = COALESCE(invoice_count, 0),
+    bill_lock_id = COALESCE(bill_lock_id, 0),
+    bill_lock_gmt = COALESCE(bill_lock_gmt, 0),
+    bill_customrates = COALESCE(bill_customrates, ''),
+    bill_pricing_revision = COALESCE(bill_pricing_revision, 0),
+    bpp_member = COALESCE(bpp_member, 0),
+    published_file = COALESCE(published_file, ''),
+    published_gmt = COALESCE(published_gmt, 0),
+    bpp_review_count = COALESCE(bpp_review_count, 0),
+    cluster = COALESCE(cluster, 'beast'),
+    bs_returndays = COALESCE(bs_returndays, 0),
+    tkts_available = COALESCE(tkts_available, 0),
+    tkts_used = COALESCE(tkts_used, 0),
+    tkts_lastused_gmt = COALESCE(tkts_lastused_gmt, 0),
+    is_newbie = COALESCE(is_newbie, 1);
+ALTER TABLE zusers
+    ALTER COLUMN username SET DEFAULT '', ALTER COLUMN username SET NOT NULL,
+    ALTER COLUMN password SET DEFAULT '', ALTER COLUMN password SET NOT NULL,
+    ALTER COLUMN reseller SET DEFAULT '', ALTER COLUMN reseller SET NOT NULL,
+    ALTER COLUMN logins SET DEFAULT 0, ALTER COLUMN logins SET NOT NULL,
+    ALTER COLUMN cached_flags SET DEFAULT '', ALTER COLUMN cached_flags SET NOT NULL,
+    ALTER COLUMN email SET DEFAULT '', ALTER COLUMN email SET NOT NULL,
+    ALTER COLUMN phone SET DEFAULT '', ALTER COLUMN phone SET NOT NULL,
+    ALTER COLUMN salesperson SET DEFAULT '', ALTER COLUMN salesperson SET NOT NULL,
+    ALTER COLUMN tech_contact SET DEFAULT '', ALTER COLUMN tech_contact SET NOT NULL,
+    ALTER COLUMN overduenotify_gmt SET DEFAULT 0, ALTER COLUMN overduenotify_gmt SET NOT NULL,
+    ALTER COLUMN ipaddr SET DEFAULT '', ALTER COLUMN ipaddr SET NOT NULL,
+    ALTER COLUMN data SET DEFAULT '', ALTER COLUMN data SET NOT NULL,
+    ALTER COLUMN sugarguid SET DEFAULT '', ALTER COLUMN sugarguid SET NOT NULL,
+    ALTER COLUMN bill_day SET DEFAULT 0, ALTER COLUMN bill_day SET NOT NULL,
+    ALTER COLUMN bill_package SET DEFAULT '', ALTER COLUMN bill_package SET NOT NULL,
+    ALTER COLUMN invoice_count SET DEFAULT 0, ALTER COLUMN invoice_count SET NOT NULL,
+    ALTER COLUMN bill_lock_id SET DEFAULT 0, ALTER COLUMN bill_lock_id SET NOT NULL,
+    ALTER COLUMN bill_lock_gmt SET DEFAULT 0, ALTER COLUMN bill_lock_gmt SET NOT NULL,
+    ALTER COLUMN bill_customrates SET DEFAULT '', ALTER COLUMN bill_customrates SET NOT NULL,
+    ALTER COLUMN bill_pricing_revision SET DEFAULT 0, ALTER COLUMN bill_pricing_revision SET NOT NULL,
+    ALTER COLUMN bpp_member SET DEFAULT 0, ALTER COLUMN bpp_member SET NOT NULL,
+    ALTER COLUMN published_file SET DEFAULT '', ALTER COLUMN published_file SET NOT NULL,
+    ALTER COLUMN published_gmt SET DEFAULT 0, ALTER COLUMN published_gmt SET NOT NULL,
+    ALTER COLUMN bpp_review_count SET DEFAULT 0, ALTER COLUMN bpp_review_count SET NOT NULL,
+    ALTER COLUMN cluster SET DEFAULT 'beast', ALTER COLUMN cluster SET NOT NULL,
+    ALTER COLUMN bs_returndays SET DEFAULT 0, ALTER COLUMN bs_returndays SET NOT NULL,
+    ALTER COLUMN tkts_available SET DEFAULT 0, ALTER COLUMN tkts_available SET NOT NULL,
+    ALTER COLUMN tkts_used SET DEFAULT 0, ALTER COLUMN tkts_used SET NOT NULL,
+    ALTER COLUMN tkts_lastused_gmt SET DEFAULT 0, ALTER COLUMN tkts_lastused_gmt SET NOT NULL,
+    ALTER COLUMN is_newbie SET DEFAULT 1, ALTER COLUMN is_newbie SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_zusers_phone ON zusers (phone);
+CREATE INDEX IF NOT EXISTS idx_zusers_reseller ON zusers (reseller);
+CREATE INDEX IF NOT EXISTS idx_zusers_salesperson ON zusers (salesperson);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_zusers_sugarguid ON zusers (sugarguid);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_zusers_username ON zusers (username);
+
+-- ON UPDATE CURRENT_TIMESTAMP emulation, as in 001_initial_schema.sql
+CREATE OR REPLACE FUNCTION update_modified_timestamp()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.created_ts = CURRENT_TIMESTAMP;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_amazon_document_contents_timestamp
+    BEFORE UPDATE ON amazon_document_contents
+    FOR EACH ROW
+    EXECUTE FUNCTION update_modified_timestamp();
+
+CREATE TRIGGER trigger_batch_parameters_timestamp
+    BEFORE UPDATE ON batch_parameters
+    FOR EACH ROW
+    EXECUTE FUNCTION update_modified_timestamp();
+
+CREATE TRIGGER trigger_campaigns_timestamp
+    BEFORE UPDATE ON campaigns
+    FOR EACH ROW
+    EXECUTE FUNCTION update_modified_timestamp();
+
+CREATE TRIGGER trigger_projects_timestamp
+    BEFORE UPDATE ON projects
+    FOR EACH ROW
+    EXECUTE FUNCTION update_modified_timestamp();
@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Carts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Carts::Cartid)
+                            .string_len(36)
+                            .not_null()
+                            .primary_key()
+                    )
+                    .col(
+                        ColumnDef::new(Carts::Items)
+                            .json_binary()
+                            .not_null()
+                            .default(Expr::cust("'[]'::jsonb"))
+                    )
+                    .col(
+                        ColumnDef::new(Carts::Version)
+                            .big_integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(Carts::CreatedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(Carts::ModifiedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(Carts::ExpiresGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_carts_expires_gmt")
+                    .table(Carts::Table)
+                    .col(Carts::ExpiresGmt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Carts::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Carts {
+    Table,
+    Cartid,
+    Items,
+    Version,
+    CreatedGmt,
+    ModifiedGmt,
+    ExpiresGmt,
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+/// Per-merchant order ID template; empty means the legacy `YYYY-MM-NNNNN`
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderCounters::Table)
+                    .add_column(
+                        ColumnDef::new(OrderCounters::OrderidFormat)
+                            .string_len(40)
+                            .not_null()
+                            .default("")
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderCounters::Table)
+                    .drop_column(OrderCounters::OrderidFormat)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OrderCounters {
+    Table,
+    OrderidFormat,
+}
@@ -0,0 +1,114 @@
+use sea_orm_migration::prelude::*;
+
+/// Server-side refresh tokens; only a SHA-256 of each token is stored
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CustomerSessions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CustomerSessions::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key()
+                    )
+                    .col(ColumnDef::new(CustomerSessions::Mid).integer().not_null())
+                    .col(ColumnDef::new(CustomerSessions::Cid).integer().not_null())
+                    .col(
+                        ColumnDef::new(CustomerSessions::Family)
+                            .string_len(36)
+                            .not_null()
+                    )
+                    .col(
+                        ColumnDef::new(CustomerSessions::TokenHash)
+                            .string_len(64)
+                            .not_null()
+                    )
+                    .col(
+                        ColumnDef::new(CustomerSessions::CreatedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(CustomerSessions::ExpiresGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(CustomerSessions::RotatedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(CustomerSessions::RevokedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_customer_sessions_token_hash")
+                    .table(CustomerSessions::Table)
+                    .col(CustomerSessions::TokenHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_customer_sessions_mid_cid")
+                    .table(CustomerSessions::Table)
+                    .col(CustomerSessions::Mid)
+                    .col(CustomerSessions::Cid)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_customer_sessions_family")
+                    .table(CustomerSessions::Table)
+                    .col(CustomerSessions::Family)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CustomerSessions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CustomerSessions {
+    Table,
+    Id,
+    Mid,
+    Cid,
+    Family,
+    TokenHash,
+    CreatedGmt,
+    ExpiresGmt,
+    RotatedGmt,
+    RevokedGmt,
+}
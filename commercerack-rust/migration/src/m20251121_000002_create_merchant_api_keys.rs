@@ -0,0 +1,136 @@
+use sea_orm_migration::prelude::*;
+
+/// Merchant API keys for server-to-server callers; only a SHA-256 of each
+/// key is stored
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MerchantApiKeys::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key()
+                    )
+                    .col(ColumnDef::new(MerchantApiKeys::Mid).integer().not_null())
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::Name)
+                            .string_len(60)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::KeyPrefix)
+                            .string_len(16)
+                            .not_null()
+                    )
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::KeyHash)
+                            .string_len(64)
+                            .not_null()
+                    )
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::Scopes)
+                            .string_len(255)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::CreatedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::ExpiresGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::LastUsedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::RevokedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(MerchantApiKeys::ReplacedBy)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_merchant_api_keys_mid")
+                            .from(MerchantApiKeys::Table, MerchantApiKeys::Mid)
+                            .to(Zusers::Table, Zusers::Mid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_merchant_api_keys_key_hash")
+                    .table(MerchantApiKeys::Table)
+                    .col(MerchantApiKeys::KeyHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_merchant_api_keys_mid")
+                    .table(MerchantApiKeys::Table)
+                    .col(MerchantApiKeys::Mid)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MerchantApiKeys::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MerchantApiKeys {
+    Table,
+    Id,
+    Mid,
+    Name,
+    KeyPrefix,
+    KeyHash,
+    Scopes,
+    CreatedGmt,
+    ExpiresGmt,
+    LastUsedGmt,
+    RevokedGmt,
+    ReplacedBy,
+}
+
+#[derive(DeriveIden)]
+enum Zusers {
+    Table,
+    Mid,
+}
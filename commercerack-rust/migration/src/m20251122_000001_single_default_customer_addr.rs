@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+/// At most one default address per customer
+///
+/// Legacy data can hold several defaults; the oldest keeps the flag.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "UPDATE customer_addrs a SET is_default = 0 \
+             WHERE a.is_default <> 0 AND EXISTS ( \
+                 SELECT 1 FROM customer_addrs b \
+                 WHERE b.mid = a.mid AND b.cid = a.cid AND b.is_default <> 0 AND b.id < a.id)",
+        )
+        .await?;
+        // 🤓 sea-query can't express a partial index
+        db.execute_unprepared(
+            "CREATE UNIQUE INDEX idx_customer_addrs_default \
+             ON customer_addrs (mid, cid) WHERE is_default <> 0",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_customer_addrs_default")
+                    .table(CustomerAddrs::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CustomerAddrs {
+    Table,
+}
@@ -0,0 +1,121 @@
+use sea_orm_migration::prelude::*;
+
+/// Temporary lockout after repeated failed logins, and password reset tokens
+/// (only a SHA-256 of each token is stored)
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Customers::Table)
+                    .add_column(
+                        ColumnDef::new(Customers::LockedUntilGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(CustomerPasswordResets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CustomerPasswordResets::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key()
+                    )
+                    .col(ColumnDef::new(CustomerPasswordResets::Mid).integer().not_null())
+                    .col(ColumnDef::new(CustomerPasswordResets::Cid).integer().not_null())
+                    .col(
+                        ColumnDef::new(CustomerPasswordResets::TokenHash)
+                            .string_len(64)
+                            .not_null()
+                    )
+                    .col(
+                        ColumnDef::new(CustomerPasswordResets::CreatedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(CustomerPasswordResets::ExpiresGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(CustomerPasswordResets::UsedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_customer_password_resets_token_hash")
+                    .table(CustomerPasswordResets::Table)
+                    .col(CustomerPasswordResets::TokenHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_customer_password_resets_mid_cid")
+                    .table(CustomerPasswordResets::Table)
+                    .col(CustomerPasswordResets::Mid)
+                    .col(CustomerPasswordResets::Cid)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CustomerPasswordResets::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Customers::Table)
+                    .drop_column(Customers::LockedUntilGmt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Customers {
+    Table,
+    LockedUntilGmt,
+}
+
+#[derive(DeriveIden)]
+enum CustomerPasswordResets {
+    Table,
+    Id,
+    Mid,
+    Cid,
+    TokenHash,
+    CreatedGmt,
+    ExpiresGmt,
+    UsedGmt,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+/// Argon2id PHC strings are about 100 characters; the legacy column held 30
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Customers::Table)
+                    .modify_column(
+                        ColumnDef::new(Customers::Passhash)
+                            .string_len(255)
+                            .not_null()
+                            .default("")
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Customers::Table)
+                    .modify_column(
+                        ColumnDef::new(Customers::Passhash)
+                            .string_len(30)
+                            .not_null()
+                            .default("")
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Customers {
+    Table,
+    Passhash,
+}
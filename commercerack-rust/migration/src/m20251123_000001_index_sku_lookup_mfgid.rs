@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+/// SKUs are looked up by manufacturer part number as well as UPC and ASIN
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_sku_lookup_mid_mfgid")
+                    .table(SkuLookup::Table)
+                    .col(SkuLookup::Mid)
+                    .col(SkuLookup::Mfgid)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_sku_lookup_mid_mfgid")
+                    .table(SkuLookup::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SkuLookup {
+    Table,
+    Mid,
+    Mfgid,
+}
@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+/// Opening `inventory_detail` rows for SKUs counted before the ledger
+///
+/// Stock counters on `sku_lookup` are now derived from detail rows, so SKUs
+/// without any get an on-shelf row, plus a reserved and an on-order row
+/// where their counters say so. SKUs that already have detail rows are left
+/// alone. Counters of the seeded SKUs are brought in line with their rows.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const SEED: &str = "\
+INSERT INTO inventory_detail \
+    (uuid, mid, pid, sku, qty, basetype, note, origin, created_ts, modified_ts, modified_by) \
+SELECT b.uuid, s.mid, left(s.pid, 20), s.sku, b.qty, b.basetype::inventory_basetype_enum, \
+       'opening balance', 'migration', now(), now(), 'migration' \
+FROM sku_lookup s \
+CROSS JOIN LATERAL (VALUES \
+    ('SIMPLE', 'SIMPLE', GREATEST(s.qty_onshelf, s.inv_available + s.qty_reserved)), \
+    ('LEGACY-RESERVED', 'UNPAID', s.qty_reserved), \
+    ('LEGACY-ONORDER', 'ONORDER', s.qty_onorder) \
+) AS b(uuid, basetype, qty) \
+WHERE b.qty > 0 \
+  AND length(s.sku) <= 35 \
+  AND NOT EXISTS ( \
+      SELECT 1 FROM inventory_detail d WHERE d.mid = s.mid AND d.sku = s.sku)";
+
+const ROLLUP: &str = "\
+UPDATE sku_lookup s SET \
+    qty_onshelf = GREATEST(s.qty_onshelf, s.inv_available + s.qty_reserved), \
+    inv_on_shelf = GREATEST(s.qty_onshelf, s.inv_available + s.qty_reserved), \
+    inv_available = GREATEST(s.qty_onshelf, s.inv_available + s.qty_reserved) - s.qty_reserved, \
+    inv_on_order = s.qty_onorder \
+WHERE EXISTS ( \
+    SELECT 1 FROM inventory_detail d \
+    WHERE d.mid = s.mid AND d.sku = s.sku AND d.origin = 'migration')";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(SEED).await?;
+        db.execute_unprepared(ROLLUP).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM inventory_detail WHERE origin = 'migration'")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+/// Cart holds (HOLD rows in `inventory_detail`) expire; the sweep finds
+/// them by basetype and expiry
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InventoryDetail::Table)
+                    .add_column(ColumnDef::new(InventoryDetail::HoldExpiresTs).timestamp().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_inventory_detail_basetype_hold_expires")
+                    .table(InventoryDetail::Table)
+                    .col(InventoryDetail::Basetype)
+                    .col(InventoryDetail::HoldExpiresTs)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_inventory_detail_basetype_hold_expires")
+                    .table(InventoryDetail::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InventoryDetail::Table)
+                    .drop_column(InventoryDetail::HoldExpiresTs)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum InventoryDetail {
+    Table,
+    Basetype,
+    HoldExpiresTs,
+}
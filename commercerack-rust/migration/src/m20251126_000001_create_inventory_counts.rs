@@ -0,0 +1,201 @@
+use sea_orm_migration::prelude::*;
+
+/// Cycle counts: a session covers one zone or a set of SKUs, and has a line
+/// per SKU and bin (or plain shelf) with the ledger's and the counted
+/// quantity
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(InventoryCounts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InventoryCounts::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key()
+                    )
+                    .col(ColumnDef::new(InventoryCounts::Mid).integer().not_null())
+                    .col(
+                        ColumnDef::new(InventoryCounts::Status)
+                            .string_len(10)
+                            .not_null()
+                            .default("OPEN")
+                    )
+                    .col(ColumnDef::new(InventoryCounts::WmsGeo).string_len(3).null())
+                    .col(ColumnDef::new(InventoryCounts::WmsZone).string_len(3).null())
+                    .col(
+                        ColumnDef::new(InventoryCounts::Skus)
+                            .json_binary()
+                            .not_null()
+                            .default(Expr::cust("'[]'::jsonb"))
+                    )
+                    .col(
+                        ColumnDef::new(InventoryCounts::Note)
+                            .string_len(255)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(InventoryCounts::CreatedBy)
+                            .string_len(10)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(InventoryCounts::CreatedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(InventoryCounts::ClosedBy)
+                            .string_len(10)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(InventoryCounts::ClosedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_inventory_counts_mid")
+                            .from(InventoryCounts::Table, InventoryCounts::Mid)
+                            .to(Zusers::Table, Zusers::Mid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_inventory_counts_mid_status")
+                    .table(InventoryCounts::Table)
+                    .col(InventoryCounts::Mid)
+                    .col(InventoryCounts::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(InventoryCountLines::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InventoryCountLines::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key()
+                    )
+                    .col(ColumnDef::new(InventoryCountLines::CountId).integer().not_null())
+                    .col(ColumnDef::new(InventoryCountLines::Sku).string_len(35).not_null())
+                    .col(
+                        ColumnDef::new(InventoryCountLines::Location)
+                            .string_len(20)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(InventoryCountLines::Expected)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(ColumnDef::new(InventoryCountLines::Counted).integer().null())
+                    .col(
+                        ColumnDef::new(InventoryCountLines::CountedBy)
+                            .string_len(10)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(InventoryCountLines::CountedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(ColumnDef::new(InventoryCountLines::Adjusted).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_inventory_count_lines_count_id")
+                            .from(InventoryCountLines::Table, InventoryCountLines::CountId)
+                            .to(InventoryCounts::Table, InventoryCounts::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_inventory_count_lines_count_sku_location")
+                    .table(InventoryCountLines::Table)
+                    .col(InventoryCountLines::CountId)
+                    .col(InventoryCountLines::Sku)
+                    .col(InventoryCountLines::Location)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(InventoryCountLines::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(InventoryCounts::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum InventoryCounts {
+    Table,
+    Id,
+    Mid,
+    Status,
+    WmsGeo,
+    WmsZone,
+    Skus,
+    Note,
+    CreatedBy,
+    CreatedGmt,
+    ClosedBy,
+    ClosedGmt,
+}
+
+#[derive(DeriveIden)]
+enum InventoryCountLines {
+    Table,
+    Id,
+    CountId,
+    Sku,
+    Location,
+    Expected,
+    Counted,
+    CountedBy,
+    CountedGmt,
+    Adjusted,
+}
+
+#[derive(DeriveIden)]
+enum Zusers {
+    Table,
+    Mid,
+}
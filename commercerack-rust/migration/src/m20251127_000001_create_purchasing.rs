@@ -0,0 +1,317 @@
+use sea_orm_migration::prelude::*;
+
+/// Suppliers and their purchase orders. A PO's status uses the same
+/// `inventory_vendor_status_enum` as the ONORDER rows it puts in the ledger.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Suppliers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Suppliers::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key()
+                    )
+                    .col(ColumnDef::new(Suppliers::Mid).integer().not_null())
+                    .col(ColumnDef::new(Suppliers::Code).string_len(6).not_null())
+                    .col(
+                        ColumnDef::new(Suppliers::Name)
+                            .string_len(100)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(Suppliers::Email)
+                            .string_len(100)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(Suppliers::Phone)
+                            .string_len(30)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(Suppliers::Note)
+                            .string_len(255)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(Suppliers::LeadDays)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(Suppliers::CreatedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(Suppliers::ModifiedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_suppliers_mid")
+                            .from(Suppliers::Table, Suppliers::Mid)
+                            .to(Zusers::Table, Zusers::Mid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_suppliers_mid_code")
+                    .table(Suppliers::Table)
+                    .col(Suppliers::Mid)
+                    .col(Suppliers::Code)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PurchaseOrders::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PurchaseOrders::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key()
+                    )
+                    .col(ColumnDef::new(PurchaseOrders::Mid).integer().not_null())
+                    .col(ColumnDef::new(PurchaseOrders::SupplierId).integer().not_null())
+                    .col(
+                        ColumnDef::new(PurchaseOrders::Status)
+                            .custom(Alias::new("inventory_vendor_status_enum"))
+                            .not_null()
+                            .default(Expr::cust("'NEW'"))
+                    )
+                    .col(
+                        ColumnDef::new(PurchaseOrders::Reference)
+                            .string_len(30)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(PurchaseOrders::Note)
+                            .string_len(255)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(PurchaseOrders::CreatedBy)
+                            .string_len(10)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(
+                        ColumnDef::new(PurchaseOrders::CreatedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(PurchaseOrders::OrderedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(PurchaseOrders::ConfirmedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(PurchaseOrders::ClosedGmt)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_purchase_orders_mid")
+                            .from(PurchaseOrders::Table, PurchaseOrders::Mid)
+                            .to(Zusers::Table, Zusers::Mid)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_purchase_orders_supplier_id")
+                            .from(PurchaseOrders::Table, PurchaseOrders::SupplierId)
+                            .to(Suppliers::Table, Suppliers::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_purchase_orders_mid_status")
+                    .table(PurchaseOrders::Table)
+                    .col(PurchaseOrders::Mid)
+                    .col(PurchaseOrders::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_purchase_orders_supplier_id")
+                    .table(PurchaseOrders::Table)
+                    .col(PurchaseOrders::SupplierId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PurchaseOrderLines::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PurchaseOrderLines::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key()
+                    )
+                    .col(ColumnDef::new(PurchaseOrderLines::PurchaseOrderId).integer().not_null())
+                    .col(ColumnDef::new(PurchaseOrderLines::Sku).string_len(35).not_null())
+                    .col(
+                        ColumnDef::new(PurchaseOrderLines::VendorSku)
+                            .string_len(25)
+                            .not_null()
+                            .default("")
+                    )
+                    .col(ColumnDef::new(PurchaseOrderLines::Qty).integer().not_null())
+                    .col(
+                        ColumnDef::new(PurchaseOrderLines::QtyReceived)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(PurchaseOrderLines::CostI)
+                            .integer()
+                            .not_null()
+                            .default(0)
+                    )
+                    .col(
+                        ColumnDef::new(PurchaseOrderLines::Uuid)
+                            .string_len(36)
+                            .not_null()
+                            .default("")
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_purchase_order_lines_purchase_order_id")
+                            .from(PurchaseOrderLines::Table, PurchaseOrderLines::PurchaseOrderId)
+                            .to(PurchaseOrders::Table, PurchaseOrders::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_purchase_order_lines_order_sku")
+                    .table(PurchaseOrderLines::Table)
+                    .col(PurchaseOrderLines::PurchaseOrderId)
+                    .col(PurchaseOrderLines::Sku)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PurchaseOrderLines::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PurchaseOrders::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Suppliers::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Suppliers {
+    Table,
+    Id,
+    Mid,
+    Code,
+    Name,
+    Email,
+    Phone,
+    Note,
+    LeadDays,
+    CreatedGmt,
+    ModifiedGmt,
+}
+
+#[derive(DeriveIden)]
+enum PurchaseOrders {
+    Table,
+    Id,
+    Mid,
+    SupplierId,
+    Status,
+    Reference,
+    Note,
+    CreatedBy,
+    CreatedGmt,
+    OrderedGmt,
+    ConfirmedGmt,
+    ClosedGmt,
+}
+
+#[derive(DeriveIden)]
+enum PurchaseOrderLines {
+    Table,
+    Id,
+    PurchaseOrderId,
+    Sku,
+    VendorSku,
+    Qty,
+    QtyReceived,
+    CostI,
+    Uuid,
+}
+
+#[derive(DeriveIden)]
+enum Zusers {
+    Table,
+    Mid,
+}
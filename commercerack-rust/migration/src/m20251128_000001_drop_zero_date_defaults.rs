@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+/// The legacy create-table migrations default these columns to MySQL's zero
+/// date, which only MySQL accepts; NULL already means "never", so the
+/// default goes and every backend ends up with the same columns
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ZERO_DATE_COLUMNS: [(&str, &str, &str); 20] = [
+    ("zusers", "password_changed", "0000-00-00 00:00:00"),
+    ("zusers", "last_login", "0000-00-00 00:00:00"),
+    ("zusers", "bill_provisioned", "0000-00-00"),
+    ("zusers", "bill_nextrun", "0000-00-00"),
+    ("zusers", "bill_lastexec", "0000-00-00 00:00:00"),
+    ("zusers", "bill_orderdate", "0000-00-00 00:00:00"),
+    ("inventory_detail", "market_ends_ts", "0000-00-00 00:00:00"),
+    ("inventory_detail", "market_sale_ts", "0000-00-00 00:00:00"),
+    ("inventory_detail", "modified_ts", "0000-00-00 00:00:00"),
+    ("inventory_detail", "verify_ts", "0000-00-00 00:00:00"),
+    ("inventory_detail", "pick_done_ts", "0000-00-00 00:00:00"),
+    ("batch_jobs", "queued_ts", "0000-00-00 00:00:00"),
+    ("batch_jobs", "start_ts", "0000-00-00 00:00:00"),
+    ("batch_jobs", "estdone_ts", "0000-00-00 00:00:00"),
+    ("batch_jobs", "end_ts", "0000-00-00 00:00:00"),
+    ("batch_jobs", "archived_ts", "0000-00-00 00:00:00"),
+    ("batch_jobs", "aborted_ts", "0000-00-00 00:00:00"),
+    ("batch_parameters", "lastrun_ts", "0000-00-00 00:00:00"),
+    ("campaigns", "expires", "0000-00-00 00:00:00"),
+    ("projects", "updated_ts", "0000-00-00 00:00:00"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for (table, column, _) in ZERO_DATE_COLUMNS {
+            db.execute_unprepared(&format!("ALTER TABLE {table} ALTER COLUMN {column} DROP DEFAULT"))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Only MySQL ever had the zero dates to put back
+        if manager.get_database_backend() != DbBackend::MySql {
+            return Ok(());
+        }
+        let db = manager.get_connection();
+        for (table, column, zero) in ZERO_DATE_COLUMNS {
+            db.execute_unprepared(&format!("ALTER TABLE {table} ALTER COLUMN {column} SET DEFAULT '{zero}'"))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -1 +1 @@
-fn main() {}
+//! Storefront server (to be implemented)